@@ -0,0 +1,77 @@
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use faerie::output::OutputSink;
+use faerie::testing::{grid_builder, grid_room};
+use faerie::{GameState, UserName};
+
+// 400x250 rooms and one user every tenth room along the bottom rows.
+const WIDTH: usize = 400;
+const HEIGHT: usize = 250;
+const USERS: usize = 10_000;
+
+struct DiscardSink;
+
+impl OutputSink for DiscardSink {
+    fn send(&mut self, _user_name: &UserName, _text: &str) {}
+}
+
+fn large_map() -> GameState {
+    let mut builder = grid_builder(WIDTH, HEIGHT);
+    for i in 0..USERS {
+        let room = grid_room(i % WIDTH, (i / WIDTH) * 10);
+        builder = builder.user(&format!("user{}", i), &room);
+    }
+    let mut game_state = builder.build().unwrap();
+    game_state.set_output_sink(Box::new(DiscardSink));
+    game_state
+}
+
+// The walker paces back and forth, resting between steps (untimed) so they never tire.
+fn pace<F>(game_state: &mut GameState, iters: u64, mut step: F) -> Duration
+where
+    F: FnMut(&mut GameState, &UserName, bool),
+{
+    let walker = UserName::new("user5000").unwrap();
+    let mut total = Duration::ZERO;
+    for i in 0..iters {
+        let _ = game_state.rest(&walker);
+        let start = Instant::now();
+        step(game_state, &walker, i % 2 == 0);
+        total += start.elapsed();
+    }
+    total
+}
+
+fn movement(c: &mut Criterion) {
+    let mut game_state = large_map();
+    c.bench_function("attempt_move", |b| {
+        b.iter_custom(|iters| {
+            pace(&mut game_state, iters, |game_state, walker, east| {
+                let direction = if east { "east" } else { "west" };
+                game_state.attempt_move(walker, direction).unwrap();
+            })
+        })
+    });
+    c.bench_function("process_input", |b| {
+        b.iter_custom(|iters| {
+            pace(&mut game_state, iters, |game_state, walker, north| {
+                let direction = if north { "north" } else { "south" };
+                game_state.process_input(walker, direction);
+            })
+        })
+    });
+}
+
+fn ticks(c: &mut Criterion) {
+    let mut game_state = large_map();
+    c.bench_function("tick", |b| b.iter(|| game_state.tick()));
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = movement, ticks
+}
+criterion_main!(benches);
@@ -0,0 +1,18 @@
+#![no_main]
+
+// Run with `cargo +nightly fuzz run process_input` from the repository root.
+
+use faerie::testing::{grid_builder, TestWorld};
+use libfuzzer_sys::fuzz_target;
+
+// Each line is input from one of two users in turn, with a tick after every line, so the
+// fuzzer can find sequences of commands as well as single bad ones.
+fuzz_target!(|data: &[u8]| {
+    let mut world = TestWorld::build(grid_builder(3, 3).user("alice", "0,0").user("bob", "1,1"));
+    let text = String::from_utf8_lossy(data);
+    for (i, line) in text.split('\n').take(64).enumerate() {
+        let user = if i % 2 == 0 { "alice" } else { "bob" };
+        world.input(user, line);
+        world.tick_n(1);
+    }
+});
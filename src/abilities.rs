@@ -0,0 +1,293 @@
+use crate::events::GameEvent;
+use crate::lambda::{ActionEvent, ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::UserName;
+use crate::user::{SpecialAttributes, UserType};
+use crate::GameState;
+
+// Mp spent on each use of the abilities that cost mp.
+pub const SWING_MP_COST: i32 = 3;
+pub const INFUSE_MP_COST: i32 = 5;
+
+// Each kind of user has one ability, powered by their SpecialAttributes. Vikings and elf lords
+// spend mp; civilians spend one of their limited chatter charges.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ability {
+    // A viking's heavy blow: twice their usual damage straight away, and a fight from then on.
+    Swing,
+    // An elf lord's healing, on themselves or someone else in the room.
+    Infuse,
+    // A civilian talks at someone until they forget who they were fighting.
+    Chatter,
+}
+
+impl Ability {
+    pub fn from_verb(verb: &str) -> Option<Ability> {
+        match verb {
+            "swing" => Some(Ability::Swing),
+            "infuse" => Some(Ability::Infuse),
+            "chatter" => Some(Ability::Chatter),
+            _ => None,
+        }
+    }
+
+    pub fn verb(self) -> &'static str {
+        match self {
+            Ability::Swing => "swing",
+            Ability::Infuse => "infuse",
+            Ability::Chatter => "chatter",
+        }
+    }
+
+    pub fn user_type(self) -> UserType {
+        match self {
+            Ability::Swing => UserType::Viking,
+            Ability::Infuse => UserType::ElfLord,
+            Ability::Chatter => UserType::Civilian,
+        }
+    }
+}
+
+impl GameState {
+    pub fn use_ability(
+        &mut self,
+        user_name: &UserName,
+        ability: Ability,
+        target_name: Option<&str>,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let user = self.users.get_user(user_name)?;
+        if user.user_type() != ability.user_type() {
+            let args = [
+                ("kind", ability.user_type().display_name()),
+                ("verb", ability.verb()),
+            ];
+            return Err(ability_failure(self.text(
+                user_name,
+                "only_kind_can",
+                &args,
+            )));
+        }
+        match ability {
+            Ability::Swing => self.swing(user_name, target_name),
+            Ability::Infuse => self.infuse(user_name, target_name),
+            Ability::Chatter => self.chatter(user_name, target_name),
+        }
+    }
+
+    fn spend_mp(&mut self, user_name: &UserName, cost: i32) -> Result<(), ActionFailure> {
+        let mp = self.users.get_user(user_name)?.basic_attributes.mp;
+        if mp < cost {
+            let (cost, mp) = (cost.to_string(), mp.to_string());
+            let args = [("cost", cost.as_str()), ("mp", &mp)];
+            return Err(ability_failure(self.text(
+                user_name,
+                "not_enough_mp",
+                &args,
+            )));
+        }
+        self.users.get_user_mut(user_name)?.basic_attributes.mp -= cost;
+        Ok(())
+    }
+
+    fn swing(
+        &mut self,
+        user_name: &UserName,
+        target_name: Option<&str>,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let target_name = target_name
+            .ok_or_else(|| ability_failure(self.text(user_name, "swing_at_whom", &[])))?;
+        // The attack does the checks on the target, so it goes first and is taken back if
+        // there's no mp to pay for the swing.
+        let previous_target = self.combat_targets.get(user_name).cloned();
+        let mut succ = self.attack(user_name, target_name)?;
+        if let Err(failure) = self.spend_mp(user_name, SWING_MP_COST) {
+            match previous_target {
+                Some(previous) => self.combat_targets.insert(*user_name, previous),
+                None => self.combat_targets.remove(user_name),
+            };
+            return Err(failure);
+        }
+
+        let target_name = self.combat_targets[user_name];
+        let attacker = self.users.get_user(user_name)?;
+        let damage = 2 * attacker.attack_damage() + attacker.roll_weapon_dice(&mut self.rng);
+        let target = self.users.get_user_mut(&target_name)?;
+        let damage = target.damage_after_defense(damage);
+        target.basic_attributes.hp -= damage;
+
+        let taken = self.formatter_for(&target_name).format_damage(damage);
+        let args = [("name", user_name.as_str()), ("damage", &taken)];
+        self.tell(&target_name, "swings_at_you", &args);
+        let dealt = self.formatter_for(user_name).format_damage(damage);
+        let args = [("name", target_name.as_str()), ("damage", &dealt)];
+        succ.push_messages(vec![self.text(user_name, "you_swing", &args)]);
+        succ.push_messages(self.publish(GameEvent::Hit {
+            user_name: *user_name,
+            target_name,
+            damage,
+        }));
+        Ok(succ)
+    }
+
+    fn infuse(
+        &mut self,
+        user_name: &UserName,
+        target_name: Option<&str>,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let room_name = self.get_user_location(user_name)?;
+        let target_name = match target_name {
+            None => *user_name,
+            Some(target_name) => match self.users.get_user(target_name) {
+                Ok(target) if target.room_name == room_name && !target.is_dead() => target.name,
+                _ => {
+                    return Err(ability_failure(self.text(
+                        user_name,
+                        "nobody_here",
+                        &[("name", target_name)],
+                    )))
+                }
+            },
+        };
+        let strength = match self.users.get_user(user_name)?.special_attributes {
+            SpecialAttributes::ElfLord { fuck_infusion } => i32::from(fuck_infusion),
+            _ => 0,
+        };
+        self.spend_mp(user_name, INFUSE_MP_COST)?;
+
+        let target = self.users.get_user_mut(&target_name)?;
+        let before = target.basic_attributes.hp;
+        target.basic_attributes.hp = (before + 5 * strength).min(target.max_hp().max(before));
+        let healed = target.basic_attributes.hp - before;
+
+        if &target_name == user_name {
+            return Ok(ActionSuccess::from_events(vec![
+                ActionEvent::Message {
+                    text: self.text(user_name, "you_infuse_yourself", &[]),
+                },
+                ActionEvent::Healed { amount: healed },
+            ]));
+        }
+        let healed = healed.to_string();
+        let args = [("name", user_name.as_str()), ("hp", &healed)];
+        self.tell(&target_name, "infuses_you", &args);
+        let args = [("name", target_name.as_str()), ("hp", &healed)];
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_infuse",
+            &args,
+        )]))
+    }
+
+    fn chatter(
+        &mut self,
+        user_name: &UserName,
+        target_name: Option<&str>,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let target_name = target_name
+            .ok_or_else(|| ability_failure(self.text(user_name, "chatter_at_whom", &[])))?;
+        let room_name = self.get_user_location(user_name)?;
+        let target_name = match self.users.get_user(target_name) {
+            Ok(target) if target.room_name == room_name && &target.name != user_name => target.name,
+            _ => {
+                return Err(ability_failure(self.text(
+                    user_name,
+                    "nobody_here",
+                    &[("name", target_name)],
+                )))
+            }
+        };
+        match &mut self.users.get_user_mut(user_name)?.special_attributes {
+            SpecialAttributes::Civilian { needlessly_chatter } if *needlessly_chatter > 0 => {
+                *needlessly_chatter -= 1;
+            }
+            _ => return Err(ability_failure(self.text(user_name, "out_of_chatter", &[]))),
+        }
+
+        let mut messages = vec![self.text(user_name, "you_chatter", &[("name", &target_name)])];
+        if let Some(was_fighting) = self.combat_targets.remove(&target_name) {
+            let args = [("name", target_name.as_str()), ("target", &was_fighting)];
+            messages.push(self.text(user_name, "forgets_fighting", &args));
+        }
+        self.tell(&target_name, "chatters_at_you", &[("name", user_name)]);
+
+        Ok(ActionSuccess::new(messages))
+    }
+}
+
+fn ability_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::AbilityUnavailable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+
+    fn make_guild_hall() -> GameState {
+        GameStateBuilder::new()
+            .room("hall", "Banners of every class.")
+            .user_of_type("olaf", "hall", UserType::Viking)
+            .user_of_type("eowyn", "hall", UserType::ElfLord)
+            .user("pat", "hall")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn abilities_belong_to_one_class_and_cost_mp() {
+        let mut game_state = make_guild_hall();
+        let olaf = UserName::new("olaf").unwrap();
+        let eowyn = UserName::new("eowyn").unwrap();
+
+        let failure = game_state
+            .process_input_impl(&eowyn, "swing olaf")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["Only vikings can swing."]);
+
+        let succ = game_state.process_input_impl(&olaf, "swing eowyn").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "You attack eowyn!",
+                "You swing hard at eowyn for 12 damage."
+            ]
+        );
+        assert_eq!(game_state.combat_target(&olaf), Some(&eowyn));
+        let user = game_state.users.get_user(&olaf).unwrap();
+        assert_eq!(user.basic_attributes.mp, 6);
+
+        let succ = game_state.process_input_impl(&eowyn, "infuse").unwrap();
+        assert!(succ.events.contains(&ActionEvent::Healed { amount: 12 }));
+        for _ in 0..4 {
+            game_state.process_input_impl(&eowyn, "infuse").unwrap();
+        }
+        let failure = game_state.process_input_impl(&eowyn, "infuse").unwrap_err();
+        assert_eq!(failure.reason, FailReason::AbilityUnavailable);
+        assert_eq!(failure.messages, vec!["You need 5 mp, and have 3."]);
+    }
+
+    #[test]
+    fn chatter_uses_up_charges_and_stops_fights() {
+        let mut game_state = make_guild_hall();
+        let olaf = UserName::new("olaf").unwrap();
+        let pat = UserName::new("pat").unwrap();
+        game_state.attack(&olaf, "eowyn").unwrap();
+
+        let succ = game_state.process_input_impl(&pat, "chatter olaf").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "You talk olaf's ear off.",
+                "olaf forgets all about fighting eowyn."
+            ]
+        );
+        assert_eq!(game_state.combat_target(&olaf), None);
+
+        for _ in 0..19 {
+            game_state.chatter(&pat, Some("olaf")).unwrap();
+        }
+        let failure = game_state
+            .process_input_impl(&pat, "chatter olaf")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["You've run out of things to say."]);
+    }
+}
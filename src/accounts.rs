@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path as FsPath;
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::persistence::write_atomically;
+use crate::type_aliases::{name_key, AccountName, UserName};
+
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+// Checked against when there's no such account, so a missing account takes as long to refuse
+// as a wrong password. Made with the default parameters from a password nobody uses.
+const DUMMY_PASSWORD_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$aDP/A9uwtat/cI+1nQpUgQ$8Msw+IEgMtP5/L+8mgUm+BDBk+7HKsEASI7vNTNNCB8";
+
+// A login, as opposed to a character in the world. One account can own several Users, and
+// the Users are saved with the GameState while accounts are saved on their own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub name: AccountName,
+    // An argon2 PHC string, which carries its own salt and parameters.
+    password_hash: String,
+    pub characters: Vec<UserName>,
+}
+
+impl Account {
+    pub fn owns(&self, user_name: &UserName) -> bool {
+        self.characters.contains(user_name)
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct AccountStore {
+    accounts: BTreeMap<AccountName, Account>,
+}
+
+impl AccountStore {
+    pub fn new() -> AccountStore {
+        AccountStore::default()
+    }
+
+    // Names that fold to one already taken are refused, like users' and rooms'.
+    pub fn register(&mut self, name: &AccountName, password: &str) -> Result<(), FaerieError> {
+        if self.find(name).is_some() {
+            return Err(FaerieError::DuplicateAccount(name.to_string()));
+        }
+        let account = Account {
+            name: *name,
+            password_hash: hash_password(password)?,
+            characters: vec![],
+        };
+        self.accounts.insert(*name, account);
+        Ok(())
+    }
+
+    // Unknown accounts and wrong passwords fail the same way, and take as long, so a client
+    // can't tell which names are taken by guessing passwords.
+    pub fn authenticate(&self, name: &str, password: &str) -> Result<&Account, FaerieError> {
+        let account = self.find(name);
+        let password_hash = account.map_or(DUMMY_PASSWORD_HASH, |account| &account.password_hash);
+        let verified = verify_password(password_hash, password);
+        account
+            .filter(|_| verified)
+            .ok_or(FaerieError::AuthenticationFailed)
+    }
+
+    pub fn change_password(
+        &mut self,
+        name: &str,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<(), FaerieError> {
+        self.authenticate(name, old_password)?;
+        let password_hash = hash_password(new_password)?;
+        self.get_account_mut(name)?.password_hash = password_hash;
+        Ok(())
+    }
+
+    // A character belongs to at most one account.
+    pub fn add_character(&mut self, name: &str, user_name: &UserName) -> Result<(), FaerieError> {
+        if let Some(owner) = self.owner_of(user_name) {
+            return Err(FaerieError::CharacterTaken {
+                user_name: user_name.to_string(),
+                account_name: owner.name.to_string(),
+            });
+        }
+        self.get_account_mut(name)?.characters.push(*user_name);
+        Ok(())
+    }
+
+    pub fn owner_of(&self, user_name: &UserName) -> Option<&Account> {
+        self.accounts.values().find(|a| a.owns(user_name))
+    }
+
+    pub fn get_account(&self, name: &str) -> Result<&Account, FaerieError> {
+        self.find(name)
+            .ok_or_else(|| FaerieError::AccountNotFound(name.to_string()))
+    }
+
+    fn get_account_mut(&mut self, name: &str) -> Result<&mut Account, FaerieError> {
+        let key = name_key(name);
+        self.accounts
+            .values_mut()
+            .find(|account| name_key(&account.name) == key)
+            .ok_or_else(|| FaerieError::AccountNotFound(name.to_string()))
+    }
+
+    // Account names can be typed in any case, though they're usually typed as registered.
+    fn find(&self, name: &str) -> Option<&Account> {
+        if let Some(account) = AccountName::lookup(name).and_then(|name| self.accounts.get(&name)) {
+            return Some(account);
+        }
+        let key = name_key(name);
+        self.accounts
+            .values()
+            .find(|account| name_key(&account.name) == key)
+    }
+
+    pub fn save_to_file<P: AsRef<FsPath>>(&self, file_path: P) -> Result<(), FaerieError> {
+        write_atomically(file_path.as_ref(), &serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn load_from_file<P: AsRef<FsPath>>(file_path: P) -> Result<AccountStore, FaerieError> {
+        let json = fs::read_to_string(file_path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+fn hash_password(password: &str) -> Result<String, FaerieError> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(FaerieError::PasswordTooShort);
+    }
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| FaerieError::PasswordHash(e.to_string()))
+}
+
+fn verify_password(password_hash: &str, password: &str) -> bool {
+    PasswordHash::new(password_hash)
+        .map(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_and_authenticate() {
+        let mut accounts = AccountStore::new();
+        let glenn = AccountName::new("glenn").unwrap();
+        accounts.register(&glenn, "hunter22!").unwrap();
+
+        assert_eq!(
+            accounts.register(&glenn, "something else"),
+            Err(FaerieError::DuplicateAccount("glenn".to_string()))
+        );
+        assert_eq!(
+            accounts.register(&AccountName::new("Glenn").unwrap(), "something else"),
+            Err(FaerieError::DuplicateAccount("Glenn".to_string()))
+        );
+        assert_eq!(
+            accounts.register(&AccountName::new("bob").unwrap(), "short"),
+            Err(FaerieError::PasswordTooShort)
+        );
+
+        assert_eq!(
+            accounts.authenticate("glenn", "hunter22!").unwrap().name,
+            glenn
+        );
+        assert_eq!(
+            accounts.authenticate(" GLENN ", "hunter22!").unwrap().name,
+            glenn
+        );
+        assert_eq!(
+            accounts.authenticate("glenn", "hunter23!").err(),
+            Some(FaerieError::AuthenticationFailed)
+        );
+        assert_eq!(
+            accounts.authenticate("nobody", "hunter22!").err(),
+            Some(FaerieError::AuthenticationFailed)
+        );
+        assert!(!accounts
+            .get_account("glenn")
+            .unwrap()
+            .password_hash
+            .contains("hunter22!"));
+
+        accounts
+            .change_password("glenn", "hunter22!", "correct horse")
+            .unwrap();
+        assert!(accounts.authenticate("glenn", "hunter22!").is_err());
+        assert!(accounts.authenticate("glenn", "correct horse").is_ok());
+    }
+
+    #[test]
+    fn unknown_accounts_pay_for_a_real_hash() {
+        // The dummy has to parse and use the same parameters as real hashes, or checking it
+        // would be quicker than checking a real account's password.
+        let dummy = PasswordHash::new(DUMMY_PASSWORD_HASH).unwrap();
+        let real_hash = hash_password("hunter22!").unwrap();
+        let real = PasswordHash::new(&real_hash).unwrap();
+        assert_eq!(dummy.algorithm, real.algorithm);
+        assert_eq!(dummy.version, real.version);
+        assert_eq!(dummy.params, real.params);
+
+        let accounts = AccountStore::new();
+        assert_eq!(
+            accounts.authenticate("nobody", "not a real password").err(),
+            Some(FaerieError::AuthenticationFailed)
+        );
+    }
+
+    #[test]
+    fn characters_belong_to_one_account() {
+        let mut accounts = AccountStore::new();
+        for name in &["glenn", "bob"] {
+            accounts
+                .register(&AccountName::new(name).unwrap(), "password1")
+                .unwrap();
+        }
+        let viking = UserName::new("Olaf").unwrap();
+        accounts.add_character("glenn", &viking).unwrap();
+
+        assert!(accounts.get_account("glenn").unwrap().owns(&viking));
+        assert_eq!(accounts.owner_of(&viking).unwrap().name, "glenn");
+        assert_eq!(
+            accounts.add_character("bob", &viking),
+            Err(FaerieError::CharacterTaken {
+                user_name: "Olaf".to_string(),
+                account_name: "glenn".to_string(),
+            })
+        );
+    }
+}
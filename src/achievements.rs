@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+
+use crate::events::GameEvent;
+use crate::lambda::{ActionFailure, ActionSuccess};
+use crate::type_aliases::UserName;
+use crate::user::User;
+use crate::GameState;
+
+// Each achievement also earns a title (see profile.rs).
+
+// Rooms a user has to have been to for Wanderer.
+pub const WANDERER_ROOMS: usize = 50;
+// Deaths it takes to earn Undying.
+pub const UNDYING_DEATHS: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Achievement {
+    FirstBlood,
+    Wanderer,
+    Undying,
+}
+
+impl Achievement {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Achievement::FirstBlood => "First Blood",
+            Achievement::Wanderer => "Wanderer",
+            Achievement::Undying => "Undying",
+        }
+    }
+
+    pub fn description(self) -> String {
+        match self {
+            Achievement::FirstBlood => "Defeat someone.".to_string(),
+            Achievement::Wanderer => format!("Visit {} rooms.", WANDERER_ROOMS),
+            Achievement::Undying => format!("Die {} times.", UNDYING_DEATHS),
+        }
+    }
+
+    pub fn all() -> [Achievement; 3] {
+        [
+            Achievement::FirstBlood,
+            Achievement::Wanderer,
+            Achievement::Undying,
+        ]
+    }
+
+    fn is_earned_by(self, user: &User) -> bool {
+        match self {
+            Achievement::FirstBlood => user.kills >= 1,
+            Achievement::Wanderer => user.visited_rooms.len() >= WANDERER_ROOMS,
+            Achievement::Undying => user.deaths >= UNDYING_DEATHS,
+        }
+    }
+}
+
+impl GameState {
+    // Achievements hear every event after quests. Each is unlocked at most once, with a
+    // message for the user and an announcement to everyone else.
+    pub(crate) fn check_achievements_for(&mut self, event: &GameEvent) -> Vec<String> {
+        let user_name = *event.user_name();
+        let user = match self.users.get_user_mut(&user_name) {
+            Ok(user) => user,
+            Err(_) => return vec![],
+        };
+        match event {
+            GameEvent::Killed { .. } => user.kills += 1,
+            GameEvent::Died { .. } => user.deaths += 1,
+            GameEvent::Moved { .. } => {}
+            GameEvent::Spoke { .. }
+            | GameEvent::Hit { .. }
+            | GameEvent::ItemGained { .. }
+            | GameEvent::EffectStarted { .. }
+            | GameEvent::EffectEnded { .. } => return vec![],
+        }
+        let unlocked: Vec<Achievement> = Achievement::all()
+            .iter()
+            .copied()
+            .filter(|a| !user.achievements.contains(a) && a.is_earned_by(user))
+            .collect();
+        user.achievements.extend(&unlocked);
+        user.titles
+            .extend(unlocked.iter().map(|a| a.title().to_string()));
+
+        let mut messages = vec![];
+        for achievement in unlocked {
+            let args = [("achievement", achievement.display_name())];
+            messages.push(self.text(&user_name, "achievement_unlocked", &args));
+            self.announce_achievement(&user_name, achievement);
+        }
+        messages
+    }
+
+    fn announce_achievement(&mut self, user_name: &UserName, achievement: Achievement) {
+        let mut listeners: Vec<UserName> = self
+            .users
+            .users
+            .keys()
+            .filter(|u| *u != user_name)
+            .cloned()
+            .collect();
+        listeners.sort();
+        let args = [
+            ("name", user_name.as_str()),
+            ("achievement", achievement.display_name()),
+        ];
+        for listener in listeners {
+            let announcement = self.text(&listener, "achievement_earned", &args);
+            self.output.send(&listener, &announcement);
+        }
+    }
+
+    pub fn achievements(&self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let user = self.users.get_user(user_name)?;
+        let messages = Achievement::all()
+            .iter()
+            .map(|achievement| {
+                let mark = if user.achievements.contains(achievement) {
+                    "x"
+                } else {
+                    " "
+                };
+                format!(
+                    "[{}] {}: {}",
+                    mark,
+                    achievement.display_name(),
+                    achievement.description()
+                )
+            })
+            .collect();
+        Ok(ActionSuccess::new(messages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+    use crate::testing::TestWorld;
+    use crate::type_aliases::RoomName;
+
+    // alice and bob in an arena, with a pit to the north that kills whoever falls in.
+    fn make_arena() -> TestWorld {
+        let mut world = TestWorld::build(
+            GameStateBuilder::new()
+                .room("arena", "Sand and blood.")
+                .room("pit", "Spikes.")
+                .user("alice", "arena")
+                .user("bob", "arena"),
+        );
+        let arena = RoomName::new("arena").unwrap();
+        let game_state = &mut world.game_state;
+        game_state
+            .add_painful_path(&arena, &RoomName::new("pit").unwrap(), Direction::North)
+            .unwrap();
+        game_state.set_respawn_room(&arena).unwrap();
+        game_state.set_respawn_delay(0);
+        world
+    }
+
+    #[test]
+    fn achievements_unlock_once_and_are_announced() {
+        let mut world = make_arena();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        let messages = world.game_state.publish(GameEvent::Killed {
+            user_name: alice,
+            victim_name: bob,
+        });
+        assert_eq!(messages, vec!["Achievement unlocked: First Blood!"]);
+        assert_eq!(
+            world.messages_for("bob"),
+            vec!["alice has earned the achievement First Blood."]
+        );
+        let messages = world.game_state.publish(GameEvent::Killed {
+            user_name: alice,
+            victim_name: bob,
+        });
+        assert!(messages.is_empty());
+
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "achievements")
+            .unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "[x] First Blood: Defeat someone.",
+                "[ ] Wanderer: Visit 50 rooms.",
+                "[ ] Undying: Die 10 times.",
+            ]
+        );
+    }
+
+    #[test]
+    fn dying_counts_towards_undying() {
+        let mut world = make_arena();
+        let alice = UserName::new("alice").unwrap();
+        for _ in 0..UNDYING_DEATHS {
+            world
+                .game_state
+                .users
+                .get_user_mut(&alice)
+                .unwrap()
+                .basic_attributes
+                .hp = 1;
+            world.game_state.process_input(&alice, "north");
+        }
+        let user = world.game_state.users.get_user(&alice).unwrap();
+        assert_eq!(user.deaths, UNDYING_DEATHS);
+        assert!(user.achievements.contains(&Achievement::Undying));
+        assert!(world
+            .messages_for("alice")
+            .contains(&"Achievement unlocked: Undying!".to_string()));
+    }
+}
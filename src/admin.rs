@@ -0,0 +1,412 @@
+use crate::error::FaerieError;
+use crate::journal::JournalEntry;
+use crate::lambda::{ActionEvent, ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::{RoomName, UserName};
+use crate::user::Role;
+use crate::GameState;
+
+pub(crate) const NEW_ROOM_DESCRIPTION: &str = "An empty room, waiting for a description.";
+
+impl GameState {
+    pub fn set_role(&mut self, user_name: &UserName, role: Role) -> Result<(), FaerieError> {
+        let entry = || JournalEntry::RoleSet {
+            user_name: *user_name,
+            role,
+        };
+        self.journaled_if_ok(entry, |game_state| {
+            game_state.users.get_user_mut(user_name)?.role = role;
+            Ok(())
+        })
+    }
+
+    pub fn role(&self, user_name: &UserName) -> Result<Role, FaerieError> {
+        Ok(self.users.get_user(user_name)?.role)
+    }
+
+    pub(crate) fn require_role(
+        &self,
+        user_name: &UserName,
+        role: Role,
+    ) -> Result<(), ActionFailure> {
+        if self.role(user_name)? < role {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "no_permission", &[])],
+                FailReason::PermissionDenied,
+            ));
+        }
+        Ok(())
+    }
+
+    // Moves the user straight into the room, paths or no paths, telling both rooms. Returns
+    // the room they were in.
+    pub fn teleport_user(
+        &mut self,
+        user_name: &UserName,
+        target_room_name: &RoomName,
+    ) -> Result<RoomName, FaerieError> {
+        let entry = || JournalEntry::UserTeleported {
+            user_name: *user_name,
+            room_name: *target_room_name,
+        };
+        self.journaled_if_ok(entry, |game_state| {
+            game_state.teleport_user_impl(user_name, target_room_name)
+        })
+    }
+
+    fn teleport_user_impl(
+        &mut self,
+        user_name: &UserName,
+        target_room_name: &RoomName,
+    ) -> Result<RoomName, FaerieError> {
+        self.rooms.check_room_exists(target_room_name)?;
+        let room_name = self.get_user_location(user_name)?;
+        self.relocate_user(user_name, target_room_name)?;
+        let args = [("name", user_name.as_str())];
+        self.tell_room_except(&room_name, user_name, "vanishes", &args)?;
+        self.tell_room_except(target_room_name, user_name, "appears", &args)?;
+        Ok(room_name)
+    }
+
+    // Builders can jump straight to any room, paths or no paths.
+    pub fn teleport(
+        &mut self,
+        user_name: &UserName,
+        room_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.require_role(user_name, Role::Builder)?;
+        let target_room_name = self.find_room_named(user_name, room_name)?;
+        let room_name = self.teleport_user(user_name, &target_room_name)?;
+        Ok(ActionSuccess::from_events(vec![ActionEvent::RoomMoved {
+            from: room_name,
+            to: target_room_name,
+        }]))
+    }
+
+    // Admins can send anyone else anywhere. They're shown the room they arrive in.
+    pub fn transfer(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+        room_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.require_role(user_name, Role::Admin)?;
+        let target_name = match self.users.get_user(target_name) {
+            Ok(target) => target.name,
+            Err(_) => {
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "nobody_called", &[("name", target_name)])],
+                    FailReason::InvalidTarget,
+                ))
+            }
+        };
+        let target_room_name = self.find_room_named(user_name, room_name)?;
+        self.teleport_user(&target_name, &target_room_name)?;
+        let args = [("name", user_name.as_str()), ("room", &target_room_name)];
+        self.tell(&target_name, "transfers_you", &args);
+        if let Err(e) = self.print_room(&target_name) {
+            self.send_all(&target_name, &[e.to_string()]);
+        }
+        let args = [("name", target_name.as_str()), ("room", &target_room_name)];
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_transfer",
+            &args,
+        )]))
+    }
+
+    fn find_room_named(
+        &self,
+        user_name: &UserName,
+        room_name: &str,
+    ) -> Result<RoomName, ActionFailure> {
+        match self.rooms.get_room(room_name) {
+            Ok(room) => Ok(room.name),
+            Err(_) => Err(ActionFailure::new(
+                vec![self.text(user_name, "no_such_room", &[("room", room_name)])],
+                FailReason::InvalidTarget,
+            )),
+        }
+    }
+
+    // The new room has no paths; the builder can teleport there to work on it.
+    pub fn create_room_command(
+        &mut self,
+        user_name: &UserName,
+        room_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.require_role(user_name, Role::Builder)?;
+        let room_name = RoomName::new(room_name)?;
+        if self.rooms.get_room(&room_name).is_ok() {
+            return Err(FaerieError::DuplicateRoom(room_name.to_string()).into());
+        }
+        self.build_as(user_name, |game_state| {
+            game_state.create_room(&room_name, NEW_ROOM_DESCRIPTION.to_string())
+        })?;
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "created_room",
+            &[("room", &room_name)],
+        )]))
+    }
+
+    // Takes the user out of the world; whoever is hosting decides what else being kicked
+    // means, e.g. the server drops their connection. See take_kicked_users.
+    pub fn kick(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.require_role(user_name, Role::Admin)?;
+        let target_name = match self.users.get_user(target_name) {
+            Ok(target) if &target.name != user_name => target.name,
+            _ => {
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "cannot_kick", &[("name", target_name)])],
+                    FailReason::InvalidTarget,
+                ))
+            }
+        };
+
+        self.tell(&target_name, "kicked_by", &[("name", user_name)]);
+        let room_name = self.get_user_location(&target_name)?;
+        self.remove_user(&target_name)?;
+        self.tell_room(&room_name, "kicked_out", &[("name", &target_name)])?;
+        self.kicked_users.push(target_name);
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_kick",
+            &[("name", &target_name)],
+        )]))
+    }
+
+    // Reaches everyone in the world but NPCs, wherever they are, in their own formatting.
+    pub fn announce(
+        &mut self,
+        user_name: &UserName,
+        text: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.require_role(user_name, Role::Admin)?;
+        let mut listeners: Vec<UserName> = self
+            .users
+            .users
+            .values()
+            .filter(|u| !u.npc && &u.name != user_name)
+            .map(|u| u.name)
+            .collect();
+        listeners.sort();
+        for listener in listeners {
+            let announcement = self.formatter_for(&listener).format_announcement(text);
+            self.output.send(&listener, &announcement);
+        }
+        Ok(ActionSuccess::new(vec![self
+            .formatter_for(user_name)
+            .format_announcement(text)]))
+    }
+
+    // Users kicked since the last call, oldest first.
+    pub fn take_kicked_users(&mut self) -> Vec<UserName> {
+        self.kicked_users.drain(..).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::format::AnsiFormatter;
+    use crate::lambda::FailReason;
+    use crate::testing::TestWorld;
+    use crate::type_aliases::{RoomName, UserName};
+    use crate::user::Role;
+
+    fn make_admin_world() -> TestWorld {
+        let mut world = TestWorld::build(
+            GameStateBuilder::new()
+                .room("hall", "A long hall.")
+                .user("alice", "hall")
+                .user("bob", "hall")
+                .user("carol", "hall"),
+        );
+        for (name, role) in &[
+            ("alice", Role::Admin),
+            ("bob", Role::Builder),
+            ("carol", Role::Player),
+        ] {
+            world
+                .game_state
+                .set_role(&UserName::new(name).unwrap(), *role)
+                .unwrap();
+        }
+        world
+    }
+
+    #[test]
+    fn commands_are_gated_by_role() {
+        let mut world = make_admin_world();
+        let bob = UserName::new("bob").unwrap();
+        let carol = UserName::new("carol").unwrap();
+
+        for input in &["createroom attic", "goto hall", "kick bob"] {
+            let failure = world
+                .game_state
+                .process_input_impl(&carol, input)
+                .unwrap_err();
+            assert_eq!(failure.reason, FailReason::PermissionDenied);
+            assert_eq!(
+                failure.messages,
+                vec!["You don't have permission to do that."]
+            );
+        }
+        let failure = world
+            .game_state
+            .process_input_impl(&bob, "kick carol")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::PermissionDenied);
+        let failure = world
+            .game_state
+            .process_input_impl(&bob, "transfer carol hall")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::PermissionDenied);
+    }
+
+    #[test]
+    fn builders_can_create_rooms_and_teleport() {
+        let mut world = make_admin_world();
+        let bob = UserName::new("bob").unwrap();
+
+        let succ = world
+            .game_state
+            .process_input_impl(&bob, "createroom attic")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["Created room attic."]);
+        let failure = world
+            .game_state
+            .process_input_impl(&bob, "createroom attic")
+            .unwrap_err();
+        assert_eq!(
+            failure.messages,
+            vec!["Room attic is defined more than once!"]
+        );
+
+        let succ = world
+            .game_state
+            .process_input_impl(&bob, "teleport attic")
+            .unwrap();
+        assert!(succ.was_room_move());
+        assert_eq!(world.game_state.get_user_location(&bob).unwrap(), "attic");
+        assert!(!world
+            .game_state
+            .rooms
+            .get_room("hall")
+            .unwrap()
+            .users
+            .contains(&UserName::new("bob").unwrap()));
+        assert_eq!(world.messages_for("carol"), vec!["bob vanishes."]);
+
+        let failure = world
+            .game_state
+            .process_input_impl(&bob, "teleport cellar")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::InvalidTarget);
+    }
+
+    #[test]
+    fn admins_can_kick() {
+        let mut world = make_admin_world();
+        let alice = UserName::new("alice").unwrap();
+
+        world
+            .game_state
+            .process_input_impl(&alice, "kick carol")
+            .unwrap();
+        assert_eq!(
+            world.messages_for("carol"),
+            vec!["You have been kicked by alice."]
+        );
+        assert_eq!(world.game_state.take_kicked_users(), vec!["carol"]);
+        assert!(world.game_state.take_kicked_users().is_empty());
+        assert!(!world
+            .game_state
+            .rooms
+            .get_room("hall")
+            .unwrap()
+            .users
+            .contains(&UserName::new("carol").unwrap()));
+
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "kick alice")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::InvalidTarget);
+    }
+
+    #[test]
+    fn announcements_reach_everyone() {
+        let mut world = make_admin_world();
+        let alice = UserName::new("alice").unwrap();
+        world.game_state.set_formatter(Box::new(AnsiFormatter));
+        world
+            .game_state
+            .set_color(&UserName::new("carol").unwrap(), false)
+            .unwrap();
+
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "announce Reboot in five minutes.")
+            .unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["\x1b[1;35m[Announcement] Reboot in five minutes.\x1b[0m"]
+        );
+        assert_eq!(
+            world.messages_for("carol"),
+            vec!["[Announcement] Reboot in five minutes."]
+        );
+        assert_eq!(world.messages_for("bob").len(), 1);
+        let failure = world
+            .game_state
+            .process_input_impl(&UserName::new("bob").unwrap(), "announce Hi")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::PermissionDenied);
+    }
+
+    #[test]
+    fn admins_can_transfer_users() {
+        let mut world = make_admin_world();
+        let alice = UserName::new("alice").unwrap();
+        world
+            .game_state
+            .create_room(&RoomName::new("cellar").unwrap(), "Damp.".to_string())
+            .unwrap();
+
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "transfer carol cellar")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You transfer carol to cellar."]);
+        assert_eq!(
+            world
+                .game_state
+                .get_user_location(&UserName::new("carol").unwrap())
+                .unwrap(),
+            "cellar"
+        );
+        assert!(!world
+            .game_state
+            .rooms
+            .get_room("hall")
+            .unwrap()
+            .users
+            .contains(&UserName::new("carol").unwrap()));
+        assert_eq!(world.messages_for("bob"), vec!["carol vanishes."]);
+        assert_eq!(
+            world.messages_for("carol")[0],
+            "alice transfers you to cellar."
+        );
+
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "transfer dave cellar")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["There's nobody called dave."]);
+    }
+}
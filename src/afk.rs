@@ -0,0 +1,179 @@
+use crate::error::FaerieError;
+use crate::type_aliases::{RoomName, UserName};
+use crate::GameState;
+
+pub(crate) const DEFAULT_AFK_AFTER: u64 = 300;
+
+pub(crate) fn default_afk_after() -> Option<u64> {
+    Some(DEFAULT_AFK_AFTER)
+}
+
+// Users who don't send any input for a while are marked AFK, and after longer still can be
+// voided: moved out of the way into a limbo room until they're back. Any input brings them
+// back to where they were. NPCs are never idle.
+impl GameState {
+    // None turns AFK marking off.
+    pub fn set_afk_after(&mut self, ticks: Option<u64>) {
+        self.afk_after = ticks;
+    }
+
+    // Without a limbo room, nobody is voided.
+    pub fn set_void_after(
+        &mut self,
+        ticks: u64,
+        limbo_room_name: Option<&RoomName>,
+    ) -> Result<(), FaerieError> {
+        if let Some(room_name) = limbo_room_name {
+            self.rooms.check_room_exists(room_name)?;
+        }
+        self.void_after = ticks;
+        self.limbo_room_name = limbo_room_name.cloned();
+        Ok(())
+    }
+
+    pub fn is_afk(&self, user_name: &UserName) -> bool {
+        self.afk_users.contains(user_name)
+    }
+
+    pub(crate) fn idle_ticks(&self, user_name: &UserName) -> u64 {
+        let last_input = self.last_input_at.get(user_name).copied().unwrap_or(0);
+        self.tick_count.saturating_sub(last_input)
+    }
+
+    pub(crate) fn check_idle_users(&mut self) {
+        let idle: Vec<(UserName, u64)> = self
+            .users
+            .users
+            .values()
+            .filter(|user| !user.npc && !self.is_awaiting_respawn(&user.name))
+            .map(|user| (user.name, self.idle_ticks(&user.name)))
+            .collect();
+        for (user_name, idle_ticks) in idle {
+            if self.afk_after.is_some_and(|after| idle_ticks >= after)
+                && self.afk_users.insert(user_name)
+            {
+                self.tell(&user_name, "now_afk", &[]);
+            }
+            if idle_ticks >= self.void_after && !self.voided_users.contains_key(&user_name) {
+                self.void_user(&user_name);
+            }
+        }
+    }
+
+    fn void_user(&mut self, user_name: &UserName) {
+        let limbo = match &self.limbo_room_name {
+            Some(limbo) => *limbo,
+            None => return,
+        };
+        let room_name = match self.get_user_location(user_name) {
+            Ok(room_name) if room_name != limbo => room_name,
+            _ => return,
+        };
+        if self.relocate_user(user_name, &limbo).is_err() {
+            return;
+        }
+        let _ = self.tell_room_except(&room_name, user_name, "fades", &[("name", user_name)]);
+        self.voided_users.insert(*user_name, room_name);
+        self.tell(user_name, "you_drift", &[]);
+    }
+
+    // Called on any input. Voided users go back to where they were, or the start room if
+    // that's gone.
+    pub(crate) fn wake_if_idle(&mut self, user_name: &UserName) {
+        if self.afk_users.remove(user_name) {
+            self.tell(user_name, "no_longer_afk", &[]);
+        }
+        let room_name = match self.voided_users.remove(user_name) {
+            Some(room_name) => room_name,
+            None => return,
+        };
+        let room_name = match self.rooms.get_room(&room_name) {
+            Ok(_) => Some(room_name),
+            Err(_) => self.start_room_name,
+        };
+        if let Some(room_name) = room_name {
+            if self.relocate_user(user_name, &room_name).is_ok() {
+                let args = [("name", user_name.as_str())];
+                let _ = self.tell_room_except(&room_name, user_name, "returns", &args);
+            }
+        }
+        if let Err(e) = self.print_room(user_name) {
+            self.output.send(user_name, &e.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::testing::TestWorld;
+
+    // alice and bob in a lounge, with a limbo for the idle.
+    fn make_lounge() -> TestWorld {
+        let mut world = TestWorld::build(
+            GameStateBuilder::new()
+                .room("lounge", "Armchairs.")
+                .room("limbo", "Grey nothing.")
+                .user("alice", "lounge")
+                .user("bob", "lounge"),
+        );
+        let limbo = RoomName::new("limbo").unwrap();
+        world.game_state.set_afk_after(Some(3));
+        world.game_state.set_void_after(5, Some(&limbo)).unwrap();
+        world
+    }
+
+    #[test]
+    fn idle_users_go_afk_and_come_back() {
+        let mut world = make_lounge();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        world.game_state.tick_n(2);
+        world.game_state.process_input_impl(&bob, "look").unwrap();
+        world.game_state.tick();
+        assert!(world.game_state.is_afk(&alice));
+        assert!(!world.game_state.is_afk(&bob));
+        assert_eq!(
+            world.messages_for("alice"),
+            vec!["You've been idle a while, and are now AFK."]
+        );
+        let succ = world.game_state.process_input_impl(&bob, "who").unwrap();
+        assert_eq!(
+            succ.messages()[2],
+            "  alice  civilian  1      -      -     3 (AFK)"
+        );
+
+        world.game_state.process_input_impl(&alice, "look").unwrap();
+        assert!(!world.game_state.is_afk(&alice));
+        assert_eq!(world.messages_for("alice"), vec!["You are no longer AFK."]);
+    }
+
+    #[test]
+    fn long_idle_users_are_voided() {
+        let mut world = make_lounge();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        for _ in 0..5 {
+            world.game_state.process_input_impl(&bob, "look").unwrap();
+            world.game_state.tick();
+        }
+        assert_eq!(
+            world.game_state.users.get_user(&alice).unwrap().room_name,
+            "limbo"
+        );
+        assert!(world
+            .messages_for("bob")
+            .contains(&"alice fades away into the void.".to_string()));
+
+        world.game_state.process_input_impl(&alice, "look").unwrap();
+        assert_eq!(
+            world.game_state.users.get_user(&alice).unwrap().room_name,
+            "lounge"
+        );
+        assert_eq!(
+            world.messages_for("bob"),
+            vec!["alice returns from the void."]
+        );
+    }
+}
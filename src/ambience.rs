@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::type_aliases::{RoomName, UserName};
+use crate::GameState;
+
+// A line shown to everyone in a room, `chance` ticks out of a hundred. In world files:
+//
+//     ambient = [{ text = "A crow caws in the distance.", chance = 2 }]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AmbientMessage {
+    pub text: String,
+    pub chance: u32,
+}
+
+impl AmbientMessage {
+    pub fn new(text: &str, chance: u32) -> AmbientMessage {
+        AmbientMessage {
+            text: text.to_string(),
+            chance,
+        }
+    }
+}
+
+impl GameState {
+    pub fn set_room_ambience(
+        &mut self,
+        room_name: &RoomName,
+        ambient: Vec<AmbientMessage>,
+    ) -> Result<(), FaerieError> {
+        self.rooms.set_ambience(room_name, ambient)
+    }
+
+    // Each message gets its own roll, so a room can show more than one on a tick. Empty rooms
+    // are skipped, so nothing is drawn from the game's rng for them.
+    pub(crate) fn play_room_ambience(&mut self) {
+        let room_names: Vec<RoomName> = self
+            .rooms
+            .ambient_rooms
+            .iter()
+            .filter(|room_name| {
+                self.rooms
+                    .rooms
+                    .get(*room_name)
+                    .is_some_and(|room| !room.users.is_empty())
+            })
+            .copied()
+            .collect();
+        for room_name in room_names {
+            let (ambient, mut listeners): (Vec<AmbientMessage>, Vec<UserName>) =
+                match self.rooms.get_room(&room_name) {
+                    Ok(room) => (room.ambient.clone(), room.users.iter().cloned().collect()),
+                    Err(_) => continue,
+                };
+            listeners.sort();
+            for message in ambient {
+                if !self.rng.chance(message.chance) {
+                    continue;
+                }
+                for listener in &listeners {
+                    self.output.send(listener, &message.text);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::BufferedSink;
+    use crate::user::UserType;
+
+    #[test]
+    fn ambient_messages_play_by_chance() {
+        let mut game_state = GameState::new_with_seed(8);
+        let field = RoomName::new("field").unwrap();
+        game_state
+            .create_room(&field, "Stubble and crows.".to_string())
+            .unwrap();
+        game_state
+            .set_room_ambience(
+                &field,
+                vec![
+                    AmbientMessage::new("A crow caws in the distance.", 100),
+                    AmbientMessage::new("A scarecrow waves at you.", 0),
+                ],
+            )
+            .unwrap();
+        game_state
+            .create_user_in_room(&UserName::new("alice").unwrap(), &field, UserType::Civilian)
+            .unwrap();
+        let output = BufferedSink::new();
+        game_state.set_output_sink(Box::new(output.clone()));
+
+        game_state.tick_n(3);
+        assert_eq!(
+            output.drain_for("alice"),
+            vec!["A crow caws in the distance."; 3]
+        );
+    }
+
+    #[test]
+    fn cleared_ambience_goes_quiet() {
+        let mut game_state = GameState::new_with_seed(8);
+        let cellar = RoomName::new("cellar").unwrap();
+        game_state
+            .create_room(&cellar, "Damp and dark.".to_string())
+            .unwrap();
+        game_state
+            .set_room_ambience(&cellar, vec![AmbientMessage::new("Water drips.", 100)])
+            .unwrap();
+        game_state
+            .create_user_in_room(&UserName::new("bob").unwrap(), &cellar, UserType::Civilian)
+            .unwrap();
+        let output = BufferedSink::new();
+        game_state.set_output_sink(Box::new(output.clone()));
+
+        game_state.tick();
+        assert_eq!(output.drain_for("bob"), vec!["Water drips."]);
+
+        game_state.set_room_ambience(&cellar, Vec::new()).unwrap();
+        game_state.tick_n(3);
+        assert!(output.drain_for("bob").is_empty());
+    }
+}
@@ -0,0 +1,281 @@
+use std::net::TcpListener as StdTcpListener;
+use std::path::PathBuf;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time::{self, Instant};
+
+use crate::accounts::AccountStore;
+use crate::error::FaerieError;
+use crate::server::{ClientId, Connection, ServerEvent, Sessions, TICK_INTERVAL};
+use crate::GameState;
+
+// The game's side of a client task: text for the client goes down the channel, and dropping
+// the channel tells the task to hang up.
+struct ClientChannel(Option<UnboundedSender<String>>);
+
+impl Connection for ClientChannel {
+    fn write_text(&mut self, text: &str) -> bool {
+        match &self.0 {
+            Some(tx) => tx.send(text.to_string()).is_ok(),
+            None => false,
+        }
+    }
+
+    fn close(&mut self) {
+        self.0 = None;
+    }
+}
+
+// Like Server, but every connection is a tokio task, so one game can take hundreds of players
+// without a thread each. The game itself is an actor: the tasks send it lines, a tokio
+// interval sends it ticks, and it answers down each client's channel.
+//
+// Await it, or spawn it onto a runtime like any other task, e.g.
+//
+//     let runtime = tokio::runtime::Runtime::new()?;
+//     runtime.block_on(AsyncServer::new(game_state)?.run("0.0.0.0:4000"))?;
+//
+// Either way the client tasks spread across the runtime's threads.
+pub struct AsyncServer {
+    sessions: Sessions<ClientChannel>,
+}
+
+impl AsyncServer {
+    // New users are placed in the world's start room.
+    pub fn new(game_state: GameState) -> Result<AsyncServer, FaerieError> {
+        Ok(AsyncServer {
+            sessions: Sessions::new(game_state)?,
+        })
+    }
+
+    // Like Server::with_accounts.
+    pub fn with_accounts(
+        mut self,
+        accounts: AccountStore,
+        file_path: Option<PathBuf>,
+    ) -> AsyncServer {
+        self.sessions.set_accounts(accounts, file_path);
+        self
+    }
+
+    pub async fn run<A: ToSocketAddrs>(self, addr: A) -> Result<(), FaerieError> {
+        let listener = TcpListener::bind(addr).await?;
+        self.serve(listener).await
+    }
+
+    // For a listener bound outside the runtime. It must be nonblocking.
+    pub async fn serve_std(self, listener: StdTcpListener) -> Result<(), FaerieError> {
+        self.serve(TcpListener::from_std(listener)?).await
+    }
+
+    pub async fn serve(mut self, listener: TcpListener) -> Result<(), FaerieError> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(accept_connections(listener, tx.clone()));
+        tokio::spawn(send_ticks(tx));
+        while let Some(event) = rx.recv().await {
+            self.sessions.handle_event(event);
+        }
+        Ok(())
+    }
+}
+
+type EventSender = UnboundedSender<ServerEvent<ClientChannel>>;
+
+async fn accept_connections(listener: TcpListener, tx: EventSender) {
+    let mut next_client_id = 0;
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(_) => continue,
+        };
+        let client_id = next_client_id;
+        next_client_id += 1;
+
+        let (client_tx, client_rx) = mpsc::unbounded_channel();
+        let connection = ClientChannel(Some(client_tx));
+        if tx
+            .send(ServerEvent::Connected(client_id, connection))
+            .is_err()
+        {
+            return;
+        }
+        tokio::spawn(run_client(client_id, stream, client_rx, tx.clone()));
+    }
+}
+
+async fn send_ticks(tx: EventSender) {
+    let mut interval = time::interval_at(Instant::now() + TICK_INTERVAL, TICK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if tx.send(ServerEvent::Tick).is_err() {
+            return;
+        }
+    }
+}
+
+// Forwards the client's lines to the game, and the game's text to the client, until one of
+// them hangs up.
+async fn run_client(
+    client_id: ClientId,
+    stream: TcpStream,
+    mut output: UnboundedReceiver<String>,
+    tx: EventSender,
+) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    loop {
+        tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) => {
+                    if tx.send(ServerEvent::Line(client_id, line)).is_err() {
+                        return;
+                    }
+                }
+                _ => break,
+            },
+            text = output.recv() => match text {
+                Some(text) => {
+                    if writer.write_all(text.as_bytes()).await.is_err() {
+                        break;
+                    }
+                }
+                // Kicked.
+                None => return,
+            },
+        }
+    }
+    let _ = tx.send(ServerEvent::Disconnected(client_id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpStream;
+    use std::thread;
+    use std::time::Duration;
+
+    const WORLD: &str = r#"
+        start_room = "Hall"
+
+        [[rooms]]
+        name = "Hall"
+        description = "A long hall."
+
+        [[rooms]]
+        name = "Garden"
+        description = "Roses everywhere."
+
+        [[paths]]
+        from = "Hall"
+        to = "Garden"
+        direction = "north"
+    "#;
+
+    fn start_server() -> std::net::SocketAddr {
+        let listener = StdTcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            let game_state = GameState::from_world_str(WORLD).unwrap();
+            let server = AsyncServer::new(game_state).unwrap();
+            let serving =
+                runtime.block_on(async { tokio::spawn(server.serve_std(listener)).await });
+            serving.unwrap().unwrap();
+        });
+        addr
+    }
+
+    const PASSWORD: &str = "correct horse";
+
+    struct TestClient {
+        stream: TcpStream,
+        reader: BufReader<TcpStream>,
+    }
+
+    impl TestClient {
+        fn open(addr: std::net::SocketAddr) -> TestClient {
+            let stream = TcpStream::connect(addr).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_millis(300)))
+                .unwrap();
+            let reader = BufReader::new(stream.try_clone().unwrap());
+            let mut client = TestClient { stream, reader };
+            assert_eq!(
+                client.read_line(),
+                Some("What is your name? Type `new` to make a new character.".to_string())
+            );
+            client
+        }
+
+        // Registers a new character with PASSWORD.
+        fn connect(addr: std::net::SocketAddr, name: &str) -> TestClient {
+            let mut client = TestClient::open(addr);
+            client.send("new");
+            client.read_line();
+            client.send(name);
+            client.read_line();
+            client.send(PASSWORD);
+            client
+        }
+
+        fn log_in(addr: std::net::SocketAddr, name: &str, password: &str) -> TestClient {
+            let mut client = TestClient::open(addr);
+            client.send(name);
+            assert_eq!(client.read_line(), Some("Password?".to_string()));
+            client.send(password);
+            client
+        }
+
+        fn send(&mut self, line: &str) {
+            write!(self.stream, "{}\r\n", line).unwrap();
+        }
+
+        fn read_line(&mut self) -> Option<String> {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Ok(n) if n > 0 => Some(line.trim_end().to_string()),
+                _ => None,
+            }
+        }
+
+        fn read_all(&mut self) -> Vec<String> {
+            let mut lines = vec![];
+            while let Some(line) = self.read_line() {
+                lines.push(line);
+            }
+            lines
+        }
+    }
+
+    #[test]
+    fn clients_play_together() {
+        let addr = start_server();
+        let mut alice = TestClient::connect(addr, "alice");
+        let mut bob = TestClient::connect(addr, "bob");
+
+        let greeting = alice.read_all();
+        assert_eq!(greeting[0], "Welcome, alice!");
+        assert_eq!(greeting[1], "Hall");
+        bob.read_all();
+
+        alice.send("north");
+        assert_eq!(alice.read_all()[0], "Garden");
+        assert_eq!(bob.read_all(), vec!["alice leaves north."]);
+    }
+
+    #[test]
+    fn disconnecting_frees_the_name() {
+        let addr = start_server();
+        let mut alice = TestClient::connect(addr, "alice");
+        alice.read_all();
+        drop(alice);
+        thread::sleep(Duration::from_millis(100));
+
+        let mut alice = TestClient::log_in(addr, "alice", PASSWORD);
+        assert_eq!(alice.read_line(), Some("Welcome, alice!".to_string()));
+    }
+}
@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::{Path as FsPath, PathBuf};
+
+use crate::error::FaerieError;
+use crate::persistence::write_atomically;
+use crate::GameState;
+
+// Where and how often tick() saves the world, and how many older saves to keep beside it as
+// file.1 (the newest) up to file.N.
+pub(crate) struct Autosave {
+    file_path: PathBuf,
+    every: u64,
+    backups: usize,
+}
+
+impl GameState {
+    // Saves every `every` ticks. Paused ticks don't count.
+    pub fn set_autosave<P: AsRef<FsPath>>(&mut self, file_path: P, every: u64, backups: usize) {
+        self.autosave = Some(Autosave {
+            file_path: file_path.as_ref().to_path_buf(),
+            every: every.max(1),
+            backups,
+        });
+    }
+
+    pub fn disable_autosave(&mut self) {
+        self.autosave = None;
+    }
+
+    // Hosts can call this themselves, say on shutdown. Does nothing without autosave set up.
+    pub fn autosave_now(&self) -> Result<(), FaerieError> {
+        let autosave = match &self.autosave {
+            Some(autosave) => autosave,
+            None => return Ok(()),
+        };
+        let json = self.to_json()?;
+        if autosave.file_path.exists() {
+            rotate_backups(&autosave.file_path, autosave.backups)?;
+        }
+        write_atomically(&autosave.file_path, &json)
+    }
+
+    // tick() can't fail, so a failed save is logged and tried again next time. A journal is
+    // only checkpointed once the save it starts from is on disk.
+    pub(crate) fn autosave_if_due(&mut self) {
+        let due = match &self.autosave {
+            Some(autosave) => self.tick_count.is_multiple_of(autosave.every),
+            None => false,
+        };
+        if due {
+            if let Err(e) = self.autosave_now().and_then(|()| self.checkpoint_journal()) {
+                log::error!("autosave failed: {}", e);
+            }
+        }
+    }
+}
+
+fn backup_path(file_path: &FsPath, n: usize) -> PathBuf {
+    let mut backup_path = file_path.as_os_str().to_owned();
+    backup_path.push(format!(".{}", n));
+    PathBuf::from(backup_path)
+}
+
+// Shifts each backup up one, dropping the oldest, then copies the current save to file.1.
+// The current save is copied rather than moved, so there's always one on disk.
+fn rotate_backups(file_path: &FsPath, backups: usize) -> Result<(), FaerieError> {
+    if backups == 0 {
+        return Ok(());
+    }
+    for n in (1..backups).rev() {
+        let from = backup_path(file_path, n);
+        if from.exists() {
+            fs::rename(&from, backup_path(file_path, n + 1))?;
+        }
+    }
+    fs::copy(file_path, backup_path(file_path, 1))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::type_aliases::RoomName;
+
+    #[test]
+    fn autosaves_rotate_backups() {
+        let dir = std::env::temp_dir().join(format!("faerie_autosave_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("world.json");
+
+        let mut game_state = GameState::new();
+        game_state
+            .create_room(&RoomName::new("den").unwrap(), "Cosy.".to_string())
+            .unwrap();
+        game_state.set_autosave(&file_path, 2, 2);
+        game_state.tick();
+        assert!(!file_path.exists());
+        game_state.tick_n(8);
+
+        let saved_ticks = |path: &FsPath| GameState::load_from_file(path).unwrap().tick_count;
+        assert_eq!(saved_ticks(&file_path), 8);
+        assert_eq!(saved_ticks(&backup_path(&file_path, 1)), 6);
+        assert_eq!(saved_ticks(&backup_path(&file_path, 2)), 4);
+        assert!(!backup_path(&file_path, 3).exists());
+        let mut temp_path = file_path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        assert!(!FsPath::new(&temp_path).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+use crate::catalog;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::room::Direction;
+use crate::type_aliases::{RoomName, UserName};
+use crate::user::Role;
+use crate::GameState;
+
+// A reversible world-building step, recorded while builder mode is on.
+#[derive(Debug, Clone)]
+pub enum BuildOp {
+    CreateRoom(RoomName),
+    AddPath {
+        source_room_name: RoomName,
+        direction: Direction,
+    },
+    SetDescription {
+        room_name: RoomName,
+        old_description: String,
+    },
+    RenameRoom {
+        old_name: RoomName,
+        new_name: RoomName,
+    },
+}
+
+impl GameState {
+    pub fn set_builder_mode(&mut self, enabled: bool) {
+        self.builder_mode = enabled;
+    }
+
+    pub fn is_builder_mode(&self) -> bool {
+        self.builder_mode
+    }
+
+    pub(crate) fn record_build_op(&mut self, op: BuildOp) {
+        if self.builder_mode {
+            self.build_undo_stack.push((self.building_as, op));
+        }
+    }
+
+    // Runs a builder's command, so whatever it changes is theirs to undo.
+    pub(crate) fn build_as<T>(
+        &mut self,
+        user_name: &UserName,
+        build: impl FnOnce(&mut GameState) -> T,
+    ) -> T {
+        let outer = self.building_as.replace(*user_name);
+        let result = build(self);
+        self.building_as = outer;
+        result
+    }
+
+    // Undoes the last change made through the API rather than by a builder.
+    pub fn undo_last_build_op(&mut self) -> Result<ActionSuccess, ActionFailure> {
+        self.undo_build_op(None)
+    }
+
+    // `buildundo`: builders can only take back their own changes.
+    pub(crate) fn undo_build_op_by(
+        &mut self,
+        user_name: &UserName,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.require_role(user_name, Role::Builder)?;
+        let author = self.users.get_user(user_name)?.name;
+        self.undo_build_op(Some(author))
+    }
+
+    fn undo_build_op(&mut self, author: Option<UserName>) -> Result<ActionSuccess, ActionFailure> {
+        let index = self
+            .build_undo_stack
+            .iter()
+            .rposition(|(made_by, _)| *made_by == author);
+        let (author, op) = match index {
+            Some(index) => self.build_undo_stack.remove(index),
+            None => {
+                return Err(ActionFailure::new(
+                    vec![self.undo_text(author, "nothing_to_undo", &[])],
+                    FailReason::NothingToUndo,
+                ))
+            }
+        };
+
+        let result = match &op {
+            BuildOp::CreateRoom(room_name) => self
+                .delete_room(room_name)
+                .map(|()| ("undid_room", vec![("room", room_name.to_string())])),
+            BuildOp::AddPath {
+                source_room_name,
+                direction,
+            } => self
+                .remove_path(source_room_name, direction.clone())
+                .map(|()| {
+                    let exit = Direction::get_path_name(direction.clone());
+                    let room = source_room_name.to_string();
+                    ("undid_path", vec![("exit", exit), ("room", room)])
+                }),
+            BuildOp::SetDescription {
+                room_name,
+                old_description,
+            } => self.rooms.get_room_mut(room_name).map(|room| {
+                room.description = old_description.clone();
+                ("undid_description", vec![("room", room_name.to_string())])
+            }),
+            BuildOp::RenameRoom { old_name, new_name } => {
+                self.rename_room_impl(new_name, old_name).map(|()| {
+                    let args = vec![
+                        ("room", new_name.to_string()),
+                        ("old", old_name.to_string()),
+                    ];
+                    ("undid_rename", args)
+                })
+            }
+        };
+
+        match result {
+            Ok((id, args)) => {
+                let args: Vec<(&str, &str)> = args.iter().map(|(k, v)| (*k, v.as_str())).collect();
+                Ok(ActionSuccess::new(vec![self.undo_text(author, id, &args)]))
+            }
+            Err(e) => {
+                // The op couldn't be undone, so keep it around for another try.
+                self.build_undo_stack.push((author, op));
+                Err(e.into())
+            }
+        }
+    }
+
+    // Changes made through the API have nobody to read about them in their own language.
+    fn undo_text(&self, author: Option<UserName>, id: &str, args: &[(&str, &str)]) -> String {
+        match author {
+            Some(author) => self.text(&author, id, args),
+            None => catalog::lookup(&self.catalog, None, id, args),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+
+use crate::error::FaerieError;
+use crate::room::Direction;
+use crate::type_aliases::{RoomName, UserName};
+use crate::user::UserType;
+use crate::GameState;
+
+// Declares a world in one chain, for worlds made in code and tests:
+//
+//     let game_state = GameStateBuilder::new()
+//         .room("hall", "A long hall.")
+//         .room("garden", "Roses everywhere.")
+//         .path("hall", "garden", Direction::North)
+//         .user("alice", "hall")
+//         .start_room("hall")
+//         .build()?;
+//
+// Nothing is checked until build(), which does rooms, then paths, then users, and stops at
+// the first problem: a bad or repeated name, or a room that doesn't exist.
+#[derive(Default)]
+pub struct GameStateBuilder {
+    rooms: Vec<(String, String)>,
+    paths: Vec<(String, String, Direction)>,
+    users: Vec<(String, String, UserType)>,
+    start_room: Option<String>,
+}
+
+impl GameStateBuilder {
+    pub fn new() -> GameStateBuilder {
+        GameStateBuilder::default()
+    }
+
+    pub fn room(mut self, name: &str, description: &str) -> GameStateBuilder {
+        self.rooms.push((name.to_string(), description.to_string()));
+        self
+    }
+
+    pub fn path(mut self, from: &str, to: &str, direction: Direction) -> GameStateBuilder {
+        self.paths
+            .push((from.to_string(), to.to_string(), direction));
+        self
+    }
+
+    // A civilian.
+    pub fn user(self, name: &str, room: &str) -> GameStateBuilder {
+        self.user_of_type(name, room, UserType::Civilian)
+    }
+
+    pub fn user_of_type(mut self, name: &str, room: &str, user_type: UserType) -> GameStateBuilder {
+        self.users
+            .push((name.to_string(), room.to_string(), user_type));
+        self
+    }
+
+    pub fn start_room(mut self, name: &str) -> GameStateBuilder {
+        self.start_room = Some(name.to_string());
+        self
+    }
+
+    pub fn build(self) -> Result<GameState, FaerieError> {
+        let mut game_state = GameState::new();
+
+        let mut seen_rooms = HashSet::new();
+        for (name, description) in self.rooms {
+            let room_name = RoomName::new(&name)?;
+            if !seen_rooms.insert(room_name) {
+                return Err(FaerieError::DuplicateRoom(name));
+            }
+            game_state.create_room(&room_name, description)?;
+        }
+
+        for (from, to, direction) in self.paths {
+            game_state.add_path(&RoomName::new(&from)?, &RoomName::new(&to)?, direction)?;
+        }
+
+        let mut seen_users = HashSet::new();
+        for (name, room, user_type) in self.users {
+            let user_name = UserName::new(&name)?;
+            if !seen_users.insert(user_name) {
+                return Err(FaerieError::DuplicateUser(name));
+            }
+            game_state.create_user_in_room(&user_name, &RoomName::new(&room)?, user_type)?;
+        }
+
+        if let Some(name) = self.start_room {
+            game_state.set_start_room(&RoomName::new(&name)?)?;
+        }
+        Ok(game_state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_a_playable_world() {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .room("garden", "Roses everywhere.")
+            .path("hall", "garden", Direction::North)
+            .user("alice", "hall")
+            .user_of_type("bjorn", "garden", UserType::Viking)
+            .start_room("hall")
+            .build()
+            .unwrap();
+
+        let alice = UserName::new("alice").unwrap();
+        game_state.process_input_impl(&alice, "north").unwrap();
+        assert_eq!(game_state.get_user_location(&alice).unwrap(), "garden");
+        assert_eq!(game_state.start_room().unwrap(), "hall");
+        assert!(game_state.validate_world().is_clean());
+    }
+
+    #[test]
+    fn build_stops_at_the_first_problem() {
+        let err = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .path("hall", "attic", Direction::North)
+            .build()
+            .err();
+        assert_eq!(err, Some(FaerieError::RoomNotFound("attic".to_string())));
+
+        let err = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .user("alice", "hall")
+            .user("alice", "hall")
+            .build()
+            .err();
+        assert_eq!(err, Some(FaerieError::DuplicateUser("alice".to_string())));
+    }
+}
@@ -0,0 +1,345 @@
+use crate::admin::NEW_ROOM_DESCRIPTION;
+use crate::build_history::BuildOp;
+use crate::error::FaerieError;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::quests::Objective;
+use crate::room::{Direction, Path};
+use crate::type_aliases::{PathName, RoomName, UserName};
+use crate::user::Role;
+use crate::GameState;
+
+// In-game commands for builders. Each one works on the builder's current room.
+impl GameState {
+    // Adds a path from the current room, creating the room at the other end if it doesn't
+    // exist yet. Compass directions get a way back; anything else is one-way.
+    pub fn dig(
+        &mut self,
+        user_name: &UserName,
+        direction: &str,
+        room_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.require_role(user_name, Role::Builder)?;
+        let path_name = Path::match_basic_aliases(direction.to_string());
+        let direction = match Direction::from_name(&path_name) {
+            Some(dir) => dir,
+            None => Direction::CustomOneWay(path_name.clone()),
+        };
+        let here = self.get_user_location(user_name)?;
+        let room_name = RoomName::new(room_name)?;
+
+        let id = if self.rooms.get_room(&room_name).is_ok() {
+            self.build_as(user_name, |game_state| {
+                game_state.add_path(&here, &room_name, direction)
+            })?;
+            "you_open_way"
+        } else {
+            // Checked first so a failed dig doesn't leave an unreachable room behind.
+            self.rooms
+                .get_room(&here)?
+                .check_duplicate_path(&path_name)?;
+            self.build_as(user_name, |game_state| {
+                game_state.create_room_from(
+                    &room_name,
+                    NEW_ROOM_DESCRIPTION.to_string(),
+                    &here,
+                    direction,
+                )
+            })?;
+            "you_dig"
+        };
+        let args = [("exit", path_name.as_str()), ("room", &room_name)];
+        Ok(ActionSuccess::new(vec![self.text(user_name, id, &args)]))
+    }
+
+    pub fn describe_command(
+        &mut self,
+        user_name: &UserName,
+        description: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.require_role(user_name, Role::Builder)?;
+        let here = self.get_user_location(user_name)?;
+        self.build_as(user_name, |game_state| {
+            game_state.set_room_description(&here, description.to_string())
+        })?;
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_describe",
+            &[("room", &here)],
+        )]))
+    }
+
+    pub fn rename_command(
+        &mut self,
+        user_name: &UserName,
+        new_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.require_role(user_name, Role::Builder)?;
+        let here = self.get_user_location(user_name)?;
+        let new_name = RoomName::new(new_name)?;
+        self.build_as(user_name, |game_state| {
+            game_state.rename_room(&here, &new_name)
+        })?;
+        let args = [("room", here.as_str()), ("name", &new_name)];
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "now_called",
+            &args,
+        )]))
+    }
+
+    pub fn delpath(
+        &mut self,
+        user_name: &UserName,
+        path_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.require_role(user_name, Role::Builder)?;
+        let path_name = Path::match_basic_aliases(path_name.to_string());
+        let here = self.get_user_location(user_name)?;
+        let paths = &self.rooms.get_room(&here)?.paths;
+        if !PathName::lookup(&path_name).is_some_and(|name| paths.contains_key(&name)) {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "no_direction", &[("exit", &path_name)])],
+                FailReason::NoSuchPath,
+            ));
+        }
+        let direction = match Direction::from_name(&path_name) {
+            Some(dir) => dir,
+            None => Direction::CustomOneWay(path_name.clone()),
+        };
+        self.remove_path(&here, direction)?;
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_remove_way",
+            &[("exit", &path_name)],
+        )]))
+    }
+
+    // Everything that refers to the room by name follows it: paths, users and the paths
+    // they've found, the start and respawn rooms and quest objectives.
+    pub fn rename_room(
+        &mut self,
+        old_name: &RoomName,
+        new_name: &RoomName,
+    ) -> Result<(), FaerieError> {
+        self.rename_room_impl(old_name, new_name)?;
+        self.record_build_op(BuildOp::RenameRoom {
+            old_name: *old_name,
+            new_name: *new_name,
+        });
+        Ok(())
+    }
+
+    pub(crate) fn rename_room_impl(
+        &mut self,
+        old_name: &RoomName,
+        new_name: &RoomName,
+    ) -> Result<(), FaerieError> {
+        self.rooms.check_room_exists(old_name)?;
+        // Renaming a room to a new casing of its own name is fine.
+        if self
+            .rooms
+            .resolve(new_name)
+            .is_some_and(|name| name != old_name)
+        {
+            return Err(FaerieError::DuplicateRoom(new_name.to_string()));
+        }
+
+        let mut room = self
+            .rooms
+            .remove_room(old_name)
+            .ok_or_else(|| FaerieError::RoomNotFound(old_name.to_string()))?;
+        room.name = *new_name;
+        for user_name in &room.users {
+            self.users.get_user_mut(user_name)?.room_name = *new_name;
+        }
+        for user in self.users.users.values_mut() {
+            user.found_paths = std::mem::take(&mut user.found_paths)
+                .into_iter()
+                .map(|(room_name, path_name)| {
+                    let room_name = if &room_name == old_name {
+                        *new_name
+                    } else {
+                        room_name
+                    };
+                    (room_name, path_name)
+                })
+                .collect();
+            if user.visited_rooms.remove(old_name) {
+                user.visited_rooms.insert(*new_name);
+            }
+        }
+        self.rooms.restore_room(room);
+
+        for room in self.rooms.rooms.values_mut() {
+            for path in room.paths.values_mut() {
+                if &path.target_room_name == old_name {
+                    path.target_room_name = *new_name;
+                }
+            }
+        }
+        if self.start_room_name.as_ref() == Some(old_name) {
+            self.start_room_name = Some(*new_name);
+        }
+        if self.respawn_room_name.as_ref() == Some(old_name) {
+            self.respawn_room_name = Some(*new_name);
+        }
+        if self.limbo_room_name.as_ref() == Some(old_name) {
+            self.limbo_room_name = Some(*new_name);
+        }
+        for room_name in self.voided_users.values_mut() {
+            if room_name == old_name {
+                *room_name = *new_name;
+            }
+        }
+        for quest in self.quests.values_mut() {
+            for objective in quest.objectives.iter_mut() {
+                if *objective == Objective::VisitRoom(*old_name) {
+                    *objective = Objective::VisitRoom(*new_name);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::lambda::FailReason;
+    use crate::type_aliases::{RoomName, UserName};
+    use crate::user::{Role, UserType};
+    use crate::GameState;
+
+    fn make_building_world() -> (GameState, UserName) {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .start_room("hall")
+            .user("bob", "hall")
+            .build()
+            .unwrap();
+        let bob = UserName::new("bob").unwrap();
+        game_state.set_role(&bob, Role::Builder).unwrap();
+        (game_state, bob)
+    }
+
+    #[test]
+    fn dig_describe_and_delpath() {
+        let (mut game_state, bob) = make_building_world();
+
+        let succ = game_state
+            .process_input_impl(&bob, "dig n Rose Garden")
+            .unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["You dig north into a new room, Rose Garden."]
+        );
+        game_state.process_input_impl(&bob, "north").unwrap();
+        game_state
+            .process_input_impl(&bob, "describe Roses,  everywhere.")
+            .unwrap();
+        let garden = game_state.rooms.get_room("Rose Garden").unwrap();
+        assert_eq!(garden.description, "Roses,  everywhere.");
+        assert_eq!(garden.get_path("south").unwrap().target_room_name, "hall");
+
+        // Digging to a room that already exists just links to it.
+        let succ = game_state
+            .process_input_impl(&bob, "dig trapdoor hall")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You open a way trapdoor to hall."]);
+        let failure = game_state
+            .process_input_impl(&bob, "dig s Cellar")
+            .unwrap_err();
+        assert!(game_state.rooms.get_room("Cellar").is_err());
+        assert!(matches!(failure.reason, FailReason::Error(_)));
+
+        game_state.process_input_impl(&bob, "delpath s").unwrap();
+        assert!(game_state.rooms.get_room("hall").unwrap().paths.is_empty());
+        let failure = game_state
+            .process_input_impl(&bob, "delpath s")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::NoSuchPath);
+    }
+
+    #[test]
+    fn rename_follows_references_and_can_be_undone() {
+        let (mut game_state, bob) = make_building_world();
+        game_state.set_builder_mode(true);
+        game_state.process_input_impl(&bob, "dig e attic").unwrap();
+
+        let succ = game_state
+            .process_input_impl(&bob, "rename Great Hall")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["hall is now called Great Hall."]);
+        assert_eq!(game_state.get_user_location(&bob).unwrap(), "Great Hall");
+        assert_eq!(game_state.start_room().unwrap(), "Great Hall");
+        let attic = game_state.rooms.get_room("attic").unwrap();
+        assert_eq!(
+            attic.get_path("west").unwrap().target_room_name,
+            "Great Hall"
+        );
+
+        let failure = game_state
+            .process_input_impl(&bob, "rename attic")
+            .unwrap_err();
+        assert_eq!(
+            failure.messages,
+            vec!["Room attic is defined more than once!"]
+        );
+
+        game_state.process_input_impl(&bob, "buildundo").unwrap();
+        assert_eq!(game_state.get_user_location(&bob).unwrap(), "hall");
+        assert!(game_state.rooms.get_room("Great Hall").is_err());
+    }
+
+    #[test]
+    fn players_cannot_build() {
+        let (mut game_state, bob) = make_building_world();
+        game_state.set_role(&bob, Role::Player).unwrap();
+        let failure = game_state
+            .process_input_impl(&bob, "dig n garden")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::PermissionDenied);
+    }
+
+    #[test]
+    fn builders_only_undo_their_own_changes() {
+        let (mut game_state, bob) = make_building_world();
+        game_state.set_builder_mode(true);
+        let hall = RoomName::new("hall").unwrap();
+        let carol = UserName::new("carol").unwrap();
+        let dave = UserName::new("dave").unwrap();
+        for name in &[&carol, &dave] {
+            game_state
+                .create_user_in_room(name, &hall, UserType::Civilian)
+                .unwrap();
+        }
+        game_state.set_role(&carol, Role::Builder).unwrap();
+
+        game_state.process_input_impl(&bob, "dig n garden").unwrap();
+        game_state
+            .process_input_impl(&carol, "describe Dusty.")
+            .unwrap();
+        let failure = game_state
+            .process_input_impl(&dave, "buildundo")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::PermissionDenied);
+
+        // Bob's dig, its path and then its room, came first, but it's his to undo.
+        game_state.process_input_impl(&bob, "buildundo").unwrap();
+        game_state.process_input_impl(&bob, "buildundo").unwrap();
+        assert!(game_state.rooms.get_room("garden").is_err());
+        assert_eq!(
+            game_state.rooms.get_room("hall").unwrap().description,
+            "Dusty."
+        );
+        let failure = game_state
+            .process_input_impl(&bob, "buildundo")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::NothingToUndo);
+        game_state.process_input_impl(&carol, "buildundo").unwrap();
+        assert_eq!(
+            game_state.rooms.get_room("hall").unwrap().description,
+            "A long hall."
+        );
+    }
+}
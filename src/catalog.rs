@@ -0,0 +1,703 @@
+use std::collections::BTreeMap;
+
+use crate::error::FaerieError;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+// Message IDs and the built-in English for each. `{name}`s are filled in when the message is
+// sent. Worlds translate them into other locales, e.g. in a world file:
+//
+//     [messages.fr]
+//     unknown_input = "Je ne comprends pas « {input} »."
+//
+// Anything not translated is sent in English.
+#[rustfmt::skip]
+const ENGLISH: &[(&str, &str)] = &[
+    ("unknown_input", "I don't understand '{input}'."),
+    ("did_you_mean", "Did you mean {suggestions}?"),
+    ("input_too_long", "That's far too long."),
+    ("world_paused", "The world is paused."),
+    ("path_closed", "The way {exit} is closed."),
+    ("exit_blocked", "You can't go that way."),
+    ("no_such_path", "What? There's no direction {exit} from {room}."),
+    ("path_locked", "The way {exit} is locked. {hint}"),
+    ("try_unlocking", "Try `unlock {exit}`."),
+    ("need_key", "You need the {key}."),
+    ("slowed", "You're carrying too much to move again so soon."),
+    ("exhausted", "You're too tired to go that way. Try `rest`."),
+    ("way_unavailable", "You can't go {exit} right now."),
+    ("dead", "You are dead. You'll be back soon."),
+    ("muted", "You have been muted."),
+    ("room_actors_only", "Only moving, looking and talking work here right now."),
+    ("more", "[Press enter for more.]"),
+    ("no_help", "There's no help on {topic}."),
+    ("help_footer", "Type `help <command>` to find out more."),
+    ("language_is", "Your language is {locale}. There's also {others}."),
+    ("language_is_only", "Your language is {locale}."),
+    ("language_now", "Your language is now {locale}."),
+    ("no_such_language", "There's no {locale} here. There's {locales}."),
+    // Rooms, and moving between them.
+    ("too_dark", "It's too dark to see."),
+    ("blinded", "You can't see a thing."),
+    ("paths_header", "paths: "),
+    ("on_the_ground", "On the ground: {items}."),
+    ("lies_here", "{name} lies here."),
+    ("leaves", "{name} leaves {exit}."),
+    ("leaves_via", "{name} leaves via the {exit}."),
+    ("arrives_from", "{name} arrives from the {exit}."),
+    ("arrives", "{name} arrives."),
+    ("spotted", "You make a noise, and are spotted."),
+    // Talking.
+    ("says", "{name} says, \"{text}\""),
+    ("you_say", "You say, \"{text}\""),
+    ("whispers", "{name} whispers to you, \"{text}\""),
+    ("you_whisper", "You whisper to {name}, \"{text}\""),
+    ("shouts", "{name} shouts, \"{text}\""),
+    ("you_shout", "You shout, \"{text}\""),
+    ("nobody_called", "There's nobody called {name}."),
+    ("nobody_here", "There's nobody called {name} here."),
+    ("no_one_here", "You don't see any {name} here."),
+    ("social_untargeted", "You can't {verb} at anyone in particular."),
+    // Dying.
+    ("you_died", "You have died."),
+    ("dies", "{name} dies."),
+    ("respawned", "{name} appears, looking pale."),
+    ("you_respawn", "You return to life."),
+    // Trading.
+    ("gold_amount", "{gold} gold"),
+    ("nothing", "nothing"),
+    ("already_trading", "You're already trading with {name}."),
+    ("busy_trading", "{name} is busy trading with {partner}."),
+    ("trade_started", "{name} starts trading with you."),
+    ("you_start_trading", "You start trading with {name}."),
+    ("trading_with", "Trading with {name}:"),
+    ("you_offer", "You offer {offer}"),
+    ("they_offer", "{name} offers {offer}"),
+    ("confirmed", "(confirmed)"),
+    ("offer_nothing", "You can't offer nothing."),
+    ("only_have_gold", "You only have {gold} gold."),
+    ("nothing_to_offer", "You don't have {item} to offer."),
+    ("offers", "{name} offers {offer}."),
+    ("you_offer_more", "You offer {offer}."),
+    ("trade_confirmed", "{name} confirms the trade."),
+    ("you_confirm_trade", "You confirm the trade. Waiting for {name}."),
+    ("you_trade", "You trade {gave} to {name} for {got}."),
+    ("trade_cancelled", "{name} cancels the trade."),
+    ("you_cancel_trade", "You cancel the trade."),
+    ("not_trading", "You aren't trading with anyone."),
+    ("not_here_any_more", "{name} isn't here any more."),
+    ("no_longer_has", "{name} no longer has {item}."),
+    ("cannot_hold_gold", "{name} can't hold that much gold."),
+    ("you_cannot_hold_gold", "You can't hold that much more gold."),
+    ("cannot_carry", "{name} can't carry that much."),
+    ("trade_off_left", "{name} has left, so the trade is off."),
+    ("trade_off_died", "{name} has died, so the trade is off."),
+    // Fighting.
+    ("cannot_fight", "You are in no condition to fight."),
+    ("attack_yourself", "You can't attack yourself."),
+    ("already_dead", "{name} is already dead."),
+    ("no_fighting", "You can't fight here."),
+    ("attacks_you", "{name} attacks you!"),
+    ("you_attack", "You attack {name}!"),
+    ("you_hit", "You hit {name} for {damage} damage."),
+    ("hits_you", "{name} hits you for {damage} damage."),
+    // Locks and containers.
+    ("no_direction", "There's no direction {exit} from here."),
+    ("no_door", "There's nothing to lock or unlock to the {exit}."),
+    ("way_already_locked", "The way {exit} is already locked."),
+    ("way_already_unlocked", "The way {exit} is already unlocked."),
+    ("need_key_for", "You need the {key} to do that."),
+    ("you_lock_way", "You lock the way {exit} with the {key}."),
+    ("you_unlock_way", "You unlock the way {exit} with the {key}."),
+    ("container_locked", "The {name} is locked."),
+    ("inside_itself", "You can't put the {name} inside itself."),
+    ("not_carrying", "You don't have {item}."),
+    ("container_full", "The {name} is full."),
+    ("you_put", "You put {item} in the {name}."),
+    ("not_in_container", "There's no {item} in the {name}."),
+    ("you_take_from", "You take {item} from the {name}."),
+    ("no_lock", "The {name} has no lock."),
+    ("already_locked", "The {name} is already locked."),
+    ("already_unlocked", "The {name} is already unlocked."),
+    ("you_lock", "You lock the {name} with the {key}."),
+    ("you_unlock", "You unlock the {name} with the {key}."),
+    ("container_empty", "The {name} is empty."),
+    ("container_holds", "The {name} holds {items}."),
+    // Equipment.
+    ("cannot_wield", "You can't wield {item}."),
+    ("cannot_wear", "You can't wear {item}."),
+    ("you_take_off", "You take off {item}."),
+    ("you_wield", "You wield {item}."),
+    ("you_wear", "You wear {item}."),
+    ("not_using", "You aren't using {item}."),
+    ("you_remove", "You remove {item}."),
+    ("score_title", "{name} the {kind}"),
+    ("score_vitals", "  hp: {hp}  mp: {mp}  stamina: {stamina}"),
+    ("score_load", "  gold: {gold}  carrying: {carrying}"),
+    ("score_fighting", "  attack: {attack}  defense: {defense}"),
+    ("score_zone", "  zone: {zone}"),
+    ("score_slot", "  {slot}: {item}"),
+    // Loot.
+    ("drops", "{name} drops {loot}."),
+    ("crumbles", "{name} crumbles to dust."),
+    ("you_take_gold", "You take {gold} gold."),
+    ("nothing_here", "There's no {item} here."),
+    ("you_take", "You take {item}."),
+    ("no_corpse", "There's no corpse like that here."),
+    ("everything_on", "everything on {name}"),
+    ("nothing_left", "There's nothing left on {name}."),
+    ("you_loot", "You take {loot} from {name}."),
+    // Shops.
+    ("no_shop", "There's no shop here."),
+    ("for_sale", "For sale:"),
+    ("ware", "  {item}: {price} gold"),
+    ("ware_bought", "  {item}: {price} gold (buys for {sell_price})"),
+    ("not_for_sale", "There's no {item} for sale here."),
+    ("too_poor", "You have {gold} gold, and {item} costs {price}."),
+    ("you_buy", "You buy {item} for {price} gold."),
+    ("will_not_buy", "Nobody here will buy {item}."),
+    ("you_sell", "You sell {item} for {price} gold."),
+    // Looking.
+    ("cannot_see_way", "You can't see anything {exit} from here."),
+    ("looking", "Looking {exit}, you see:"),
+    ("too_dark_for_exits", "It's too dark to make out any exits."),
+    ("no_exits", "There are no obvious exits."),
+    ("exits_header", "Exits:"),
+    ("exit_line", "  {exit} - {room} ({notes})"),
+    ("exit_closed", "closed"),
+    ("exit_locked", "locked"),
+    ("exit_open", "open"),
+    ("exit_hidden", "hidden"),
+    ("exit_one_way", "one-way"),
+    ("is_a", "{name} is {kind}."),
+    ("looks_dead", "They look dead."),
+    ("looks_healthy", "They look healthy."),
+    ("looks_wounded", "They look wounded."),
+    // Stamina and weight.
+    ("fighting_too_hard", "You can't rest in the middle of a fight!"),
+    ("not_tired", "You're not tired."),
+    ("rests", "{name} sits down to rest."),
+    ("you_rest", "You sit down and catch your breath."),
+    ("too_heavy", "You can't carry {item} as well."),
+    // Skills.
+    ("skill_improves", "Your {skill} improves to {level}."),
+    ("too_unskilled", "You'd need more skill at {skill} to go {exit}."),
+    ("skill_line", "{skill}: {level}"),
+    ("not_locked", "The way {exit} isn't locked."),
+    ("pick_failed", "You fail to pick the lock."),
+    ("you_pick", "You pick the lock on the way {exit}."),
+    // Travelling.
+    ("you_stop", "You stop in {room} after {taken} of {steps} steps."),
+    ("no_such_room", "There's no room called {room}."),
+    ("already_there", "You're already there."),
+    ("no_route", "You can't find a way to {room}."),
+    // Friends, and people you'd rather not hear from.
+    ("nobody", "nobody"),
+    ("friends_list", "Your friends: {names}."),
+    ("ignoring_list", "You're ignoring: {names}."),
+    ("already_friend", "{name} is already your friend."),
+    ("you_befriend", "You add {name} to your friends."),
+    ("not_friend", "{name} isn't your friend."),
+    ("you_unfriend", "You remove {name} from your friends."),
+    ("already_ignoring", "You're already ignoring {name}."),
+    ("you_ignore", "You ignore {name}."),
+    ("not_ignoring", "You aren't ignoring {name}."),
+    ("you_unignore", "You stop ignoring {name}."),
+    ("logged_in", "{name} has logged in."),
+    ("logged_out", "{name} has logged out."),
+    ("thats_you", "That's you!"),
+    // Channels.
+    ("channels_header", "Channels:"),
+    ("already_on_channel", "You're already on {channel}."),
+    ("you_join", "You join {channel}."),
+    ("not_on_channel", "You aren't on {channel}."),
+    ("you_leave_channel", "You leave {channel}."),
+    ("muted_on_channel", "You have been muted on {channel}."),
+    ("channel_says", "[{channel}] {name}: {text}"),
+    ("you_say_on_channel", "[{channel}] You: {text}"),
+    ("no_channel", "There's no channel {channel}."),
+    ("you_mute_on", "You mute {name} on {channel}."),
+    ("you_unmute_on", "You unmute {name} on {channel}."),
+    // Following, and parties.
+    ("already_following", "{name} is already following you."),
+    ("follows_you", "{name} starts following you."),
+    ("you_follow", "You start following {name}."),
+    ("unfollows_you", "{name} stops following you."),
+    ("you_unfollow", "You stop following {name}."),
+    ("not_following", "You aren't following anyone."),
+    ("no_party", "You aren't in a party."),
+    ("party_header", "Party led by {name}:"),
+    ("party_member", "  {name} (following {leader})"),
+    ("you_follow_them", "You follow {name} {exit}."),
+    ("could_not_follow", "{name} couldn't follow {leader}: {reason}"),
+    // Idling.
+    ("now_afk", "You've been idle a while, and are now AFK."),
+    ("fades", "{name} fades away into the void."),
+    ("you_drift", "You drift into the void."),
+    ("no_longer_afk", "You are no longer AFK."),
+    ("returns", "{name} returns from the void."),
+    // Moderation.
+    ("already_banned", "{name} is already banned."),
+    ("you_ban", "You ban {name}."),
+    ("not_banned", "{name} isn't banned."),
+    ("you_unban", "You unban {name}."),
+    ("you_can_speak", "You can speak again."),
+    ("you_unmute", "You unmute {name}."),
+    ("muted_by", "You have been muted by {name}."),
+    ("you_mute", "You mute {name}."),
+    ("cannot_ban", "You can't ban {name}."),
+    ("cannot_mute", "You can't mute {name}."),
+    // Admins and builders.
+    ("no_permission", "You don't have permission to do that."),
+    ("vanishes", "{name} vanishes."),
+    ("spawns", "{name} appears."),
+    ("appears", "{name} appears out of thin air."),
+    ("transfers_you", "{name} transfers you to {room}."),
+    ("you_transfer", "You transfer {name} to {room}."),
+    ("created_room", "Created room {room}."),
+    ("cannot_kick", "You can't kick {name}."),
+    ("kicked_by", "You have been kicked by {name}."),
+    ("kicked_out", "{name} has been kicked out."),
+    ("you_kick", "You kick {name}."),
+    ("you_open_way", "You open a way {exit} to {room}."),
+    ("you_dig", "You dig {exit} into a new room, {room}."),
+    ("you_describe", "You describe {room}."),
+    ("now_called", "{room} is now called {name}."),
+    ("you_remove_way", "You remove the way {exit}."),
+    ("nothing_to_undo", "Nothing to undo."),
+    ("undid_room", "Removed room {room}."),
+    ("undid_path", "Removed path {exit} from {room}."),
+    ("undid_description", "Restored the description of {room}."),
+    ("undid_rename", "Renamed {room} back to {old}."),
+    // Guilds.
+    ("guild_name", "Guild: {guild}"),
+    ("guild_treasury", "Treasury: {gold} gold"),
+    ("guild_members", "Members:"),
+    ("guild_member", "  {name} ({rank})"),
+    ("already_in_guild", "You're already in {guild}."),
+    ("guild_name_too_long", "Guild names can be at most {max} characters."),
+    ("guild_exists", "There's already a guild called {guild}."),
+    ("you_found_guild", "You found the guild {guild}."),
+    ("in_a_guild", "{name} is already in a guild."),
+    ("invites_you", "{name} invites you to join {guild}. Type `guild join {guild}` to accept."),
+    ("you_invite", "You invite {name} to join {guild}."),
+    ("not_invited", "You haven't been invited to {guild}."),
+    ("joined_guild", "{name} has joined the guild."),
+    ("you_disband", "You leave {guild}, and it's disbanded."),
+    ("you_take_treasury", "You take the {gold} gold left in its treasury."),
+    ("leader_cannot_leave", "You'll have to make someone else leader first."),
+    ("left_guild", "{name} has left the guild."),
+    ("kicked_from_guild", "{name} has kicked you out of {guild}."),
+    ("kicked_out_of_guild", "{name} has been kicked out of the guild."),
+    ("you_kick_from_guild", "You kick {name} out of {guild}."),
+    ("cannot_promote", "You can't promote {name}."),
+    ("cannot_demote", "You can't demote {name}."),
+    ("now_guild_leader", "{name} is now the leader of {guild}."),
+    ("now_guild_officer", "{name} is now an officer of {guild}."),
+    ("cannot_demote_further", "{name} can't be demoted any further."),
+    ("no_longer_officer", "{name} is no longer an officer of {guild}."),
+    ("you_deposit", "You put {gold} gold in the treasury."),
+    ("treasury_only_has", "The treasury only has {gold} gold."),
+    ("treasury_full", "The treasury can't hold that much more gold."),
+    ("you_withdraw", "You take {gold} gold from the treasury."),
+    ("guild_rank_needed", "Only a guild {rank} can do that."),
+    ("not_in_that_guild", "{name} isn't in {guild}."),
+    ("not_an_amount", "{amount} isn't an amount of gold."),
+    ("not_in_guild", "You aren't in a guild."),
+    // Status effects.
+    ("poisoned", "You feel sick."),
+    ("hasted", "You feel yourself speed up."),
+    ("blinded_now", "You can't see!"),
+    ("bruised", "You're bruised."),
+    ("poison_ends", "You feel better."),
+    ("haste_ends", "You feel yourself slow down."),
+    ("blindness_ends", "You can see again."),
+    ("bruises_fade", "Your bruises fade."),
+    ("cannot_see_way_ahead", "You can't see where you're going."),
+    // Abilities.
+    ("only_kind_can", "Only {kind}s can {verb}."),
+    ("not_enough_mp", "You need {cost} mp, and have {mp}."),
+    ("swing_at_whom", "Swing at whom?"),
+    ("swings_at_you", "{name} swings hard, hitting you for {damage} damage."),
+    ("you_swing", "You swing hard at {name} for {damage} damage."),
+    ("you_infuse_yourself", "You infuse yourself with vigor."),
+    ("infuses_you", "{name} infuses you with vigor. You recover {hp} hp."),
+    ("you_infuse", "You infuse {name} with vigor, restoring {hp} hp."),
+    ("chatter_at_whom", "Chatter at whom?"),
+    ("out_of_chatter", "You've run out of things to say."),
+    ("you_chatter", "You talk {name}'s ear off."),
+    ("forgets_fighting", "{name} forgets all about fighting {target}."),
+    ("chatters_at_you", "{name} won't stop talking at you."),
+    // Quests.
+    ("new_quest", "New quest: {quest}"),
+    ("quest_complete", "Quest complete: {quest}!"),
+    ("quest_line", "* {quest} ({done}/{total})"),
+    ("objective_visit", "Visit {name}"),
+    ("objective_collect", "Collect the {name}"),
+    ("objective_defeat", "Defeat {name}"),
+    ("no_active_quests", "You have no active quests."),
+    ("active_quests", "Active quests:"),
+    ("completed_quests", "Completed quests:"),
+    // Titles and descriptions.
+    ("text_too_long", "That's too long; keep it to {max} characters."),
+    ("no_titles", "You haven't earned any titles yet."),
+    ("titles_header", "Your titles:"),
+    ("no_title", "You no longer go by a title."),
+    ("title_not_earned", "You haven't earned the title \"{title}\"."),
+    ("known_as", "You are now known as {name}."),
+    ("description_cleared", "You clear your description."),
+    ("description_updated", "You update your description."),
+    // Weather.
+    ("sky_clears", "The clouds part, and the sky clears."),
+    ("clouds_gather", "Clouds gather overhead."),
+    ("rain_starts", "It starts to rain."),
+    ("storm_breaks", "Thunder rumbles as a storm breaks."),
+    ("fog_rolls_in", "A thick fog rolls in."),
+    ("clouds_drift", "Grey clouds drift by."),
+    ("rain_patters", "Rain patters down around you."),
+    ("lightning", "Lightning splits the sky."),
+    ("shapes_in_fog", "Shapes loom and fade in the fog."),
+    // Mail.
+    ("new_mail", "You have new mail from {name}."),
+    ("you_send_mail", "You send your letter to {name}."),
+    ("mailbox_empty", "Your mailbox is empty."),
+    ("letter_from", "{number}. From {name}:"),
+    ("new_letter_from", "{number}. From {name} (new):"),
+    ("no_letter", "You have no letter {number}."),
+    ("you_delete_mail", "You throw away the letter from {name}."),
+    ("unread_letter", "You have an unread letter. Type `mail read` to read it."),
+    ("unread_letters", "You have {count} unread letters. Type `mail read` to read them."),
+    // Achievements.
+    ("achievement_unlocked", "Achievement unlocked: {achievement}!"),
+    ("achievement_earned", "{name} has earned the achievement {achievement}."),
+    // Conversations and dice.
+    ("no_npc_here", "There's no {name} here."),
+    ("nothing_to_say", "{name} has nothing to say to you."),
+    ("not_talking", "You aren't talking to anyone."),
+    ("no_response", "There's no response {number}."),
+    ("rolls", "{name} rolls {dice}: {total}."),
+    ("you_roll", "You roll {dice}: {rolls} = {total}."),
+    // Where you are, and who else is about.
+    ("you_are_in_levels", "You are in {room}, in {zone} (levels {low}-{high})."),
+    ("you_are_in_zone", "You are in {room}, in {zone}."),
+    ("you_are_in", "You are in {room}."),
+    ("users_online", "Users online:"),
+    // Paging.
+    ("paging_off", "Paging is off."),
+    ("page_size", "Pages are {lines} lines long."),
+    ("page_size_range", "Pages can be 1 to {max} lines long, or off."),
+    ("paging_now_off", "Paging is now off."),
+    ("page_size_now", "Pages are now {lines} lines long."),
+    // Settings.
+    ("prompt_is", "Your prompt is: {prompt}"),
+    ("prompt_tokens", "Tokens: %hp %maxhp %mp %maxmp %sp %maxsp %gold %room %name"),
+    ("prompt_now", "Your prompt is now: {prompt}"),
+    ("color_on", "Color is now on."),
+    ("color_off", "Color is now off."),
+    // What happened to you.
+    ("you_take_damage", "You take {damage} damage."),
+    ("you_recover", "You recover {hp} hp."),
+    ("you_now_have", "You now have {item}."),
+    ("you_find_way", "You find a hidden way {exit}!"),
+    ("found_nothing", "You search, but find nothing."),
+    ("painful_path", "You passed through, but it hurt you."),
+    ("no_bearings", "You can't get your bearings here."),
+    ("which_way", "Which way do you mean, {names}?"),
+    ("which_user", "Who do you mean, {names}?"),
+    ("wrapping_off", "Wrapping is off."),
+    ("screen_width", "Your screen is {columns} columns wide."),
+    ("screen_width_range", "Screens can be {min} to {max} columns wide, or off."),
+    ("wrapping_now_off", "Wrapping is now off."),
+    ("screen_width_now", "Your screen is now {columns} columns wide."),
+    // Questions for commands missing what they act on.
+    ("go_where", "Go where?"),
+    ("attack_whom", "Attack whom?"),
+    ("say_what", "Say what?"),
+    ("emote_what", "Emote what?"),
+    ("follow_whom", "Follow whom?"),
+    ("travel_where", "Travel where?"),
+    ("friend_add_or_remove_whom", "Friend add or remove whom?"),
+    ("ignore_add_or_remove_whom", "Ignore add or remove whom?"),
+    ("which_guild", "Which guild?"),
+    ("how_much_gold", "How much gold?"),
+    ("guild_whom", "Who?"),
+    ("guild_what", "Guild create, invite, join, leave, kick, promote, demote, deposit or withdraw?"),
+    ("mute_whom_on_which_channel", "Mute whom on which channel?"),
+    ("say_what_on_that_channel", "Say what on that channel?"),
+    ("send_what_to_whom", "Send what to whom?"),
+    ("delete_which_letter", "Delete which letter?"),
+    ("mail_send_read_or_delete", "Mail send, read or delete?"),
+    ("pick_the_lock_which_way", "Pick the lock which way?"),
+    ("sneak_where", "Sneak where?"),
+    ("talk_to_whom", "Talk to whom?"),
+    ("whisper_what_to_whom", "Whisper what to whom?"),
+    ("examine_what", "Examine what?"),
+    ("lock_which_way", "Lock which way?"),
+    ("unlock_which_way", "Unlock which way?"),
+    ("wield_what", "Wield what?"),
+    ("wear_what", "Wear what?"),
+    ("remove_what", "Remove what?"),
+    ("buy_what", "Buy what?"),
+    ("sell_what", "Sell what?"),
+    ("offer_what", "Offer what?"),
+    ("at_whom", "At whom?"),
+    ("roll_what", "Roll what?"),
+    ("take_what", "Take what?"),
+    ("put_what_in_what", "Put what in what?"),
+    ("teleport_where", "Teleport where?"),
+    ("create_a_room_called_what", "Create a room called what?"),
+    ("kick_whom", "Kick whom?"),
+    ("transfer_whom_where", "Transfer whom where?"),
+    ("ban_whom", "Ban whom?"),
+    ("unban_whom", "Unban whom?"),
+    ("mute_whom", "Mute whom?"),
+    ("announce_what", "Announce what?"),
+    ("dig_which_way_to_which_room", "Dig which way, to which room?"),
+    ("describe_this_room_how", "Describe this room how?"),
+    ("rename_this_room_to_what", "Rename this room to what?"),
+    ("delete_the_path_which_way", "Delete the path which way?"),
+    ("color_on_or_off", "Color on or off?"),
+    // Logging in.
+    ("what_is_your_name", "What is your name? Type `new` to make a new character."),
+    ("password", "Password?"),
+    ("login_failed", "That name and password don't match. What is your name?"),
+    ("name_unavailable", "That name isn't available. What is your name?"),
+    ("name_banned", "That name is banned. What is your name?"),
+    ("new_name", "What will your character be called?"),
+    ("new_name_unavailable", "That name isn't available. What will your character be called?"),
+    ("new_password", "Choose a password of at least {min} characters."),
+    ("password_too_short", "That's too short. Choose a password of at least {min} characters."),
+    ("welcome", "Welcome, {name}!"),
+];
+
+// Locale, then message ID, then template.
+pub(crate) type Catalog = BTreeMap<String, BTreeMap<String, String>>;
+
+pub(crate) fn default_catalog() -> Catalog {
+    let english = ENGLISH
+        .iter()
+        .map(|(id, text)| (id.to_string(), text.to_string()))
+        .collect();
+    BTreeMap::from([(DEFAULT_LOCALE.to_string(), english)])
+}
+
+// Fills in each `{name}` in the template with its value. It's one pass from left to right,
+// so whatever a value says, even `{room}`, is left as it is. Braces that don't name an
+// argument are kept.
+pub(crate) fn fill_in(template: &str, args: &[(&str, &str)]) -> String {
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let arg = after.find('}').and_then(|end| {
+            let (_, value) = args.iter().find(|(name, _)| *name == &after[..end])?;
+            Some((end, value))
+        });
+        match arg {
+            Some((end, value)) => {
+                text.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                text.push('{');
+                rest = after;
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+// The message in the given language, or in English if it hasn't been translated.
+pub(crate) fn lookup(
+    catalog: &Catalog,
+    locale: Option<&str>,
+    id: &str,
+    args: &[(&str, &str)],
+) -> String {
+    let template = [locale.unwrap_or(DEFAULT_LOCALE), DEFAULT_LOCALE]
+        .iter()
+        .find_map(|locale| catalog.get(*locale)?.get(id));
+    match template {
+        Some(template) => fill_in(template, args),
+        None => id.to_string(),
+    }
+}
+
+impl GameState {
+    // Adds translations for a locale, like "fr", replacing any already given for the same
+    // messages. Every ID must be one of the built-in messages.
+    pub fn add_messages<I>(&mut self, locale: &str, messages: I) -> Result<(), FaerieError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let locale = locale.trim().to_lowercase();
+        let messages: Vec<(String, String)> = messages.into_iter().collect();
+        if let Some((id, _)) = messages
+            .iter()
+            .find(|(id, _)| !ENGLISH.iter().any(|(known, _)| known == id))
+        {
+            return Err(FaerieError::MessageNotFound(id.clone()));
+        }
+        self.catalog.entry(locale).or_default().extend(messages);
+        Ok(())
+    }
+
+    pub fn locales(&self) -> Vec<&str> {
+        self.catalog.keys().map(String::as_str).collect()
+    }
+
+    // Sends the user a message from the catalog, in their language.
+    pub(crate) fn tell(&mut self, user_name: &UserName, id: &str, args: &[(&str, &str)]) {
+        let text = self.text(user_name, id, args);
+        self.send_all(user_name, &[text]);
+    }
+
+    // The message in the user's language, or in English if it hasn't been translated.
+    pub(crate) fn text(&self, user_name: &UserName, id: &str, args: &[(&str, &str)]) -> String {
+        let locale = self
+            .users
+            .get_user(user_name)
+            .ok()
+            .and_then(|user| user.locale.as_deref());
+        lookup(&self.catalog, locale, id, args)
+    }
+
+    // For clients who aren't anyone yet, like those still logging in.
+    pub(crate) fn default_text(&self, id: &str, args: &[(&str, &str)]) -> String {
+        lookup(&self.catalog, None, id, args)
+    }
+
+    pub(crate) fn set_language(
+        &mut self,
+        user_name: &UserName,
+        locale: Option<&str>,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let current = self
+            .users
+            .get_user(user_name)?
+            .locale
+            .clone()
+            .unwrap_or_else(|| DEFAULT_LOCALE.to_string());
+        let locale = match locale {
+            None => {
+                let others: Vec<&str> = self
+                    .locales()
+                    .into_iter()
+                    .filter(|locale| *locale != current)
+                    .collect();
+                let message = if others.is_empty() {
+                    self.text(user_name, "language_is_only", &[("locale", &current)])
+                } else {
+                    self.text(
+                        user_name,
+                        "language_is",
+                        &[("locale", &current), ("others", &others.join(", "))],
+                    )
+                };
+                return Ok(ActionSuccess::new(vec![message]));
+            }
+            Some(locale) => locale.trim().to_lowercase(),
+        };
+        if !self.catalog.contains_key(&locale) {
+            let locales = self.locales().join(", ");
+            return Err(ActionFailure::new(
+                vec![self.text(
+                    user_name,
+                    "no_such_language",
+                    &[("locale", &locale), ("locales", &locales)],
+                )],
+                FailReason::InvalidTarget,
+            ));
+        }
+        self.users.get_user_mut(user_name)?.locale = Some(locale.clone());
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "language_now",
+            &[("locale", &locale)],
+        )]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+
+    fn french() -> Vec<(String, String)> {
+        [
+            ("unknown_input", "Je ne comprends pas « {input} »."),
+            ("language_now", "Votre langue est maintenant {locale}."),
+        ]
+        .iter()
+        .map(|(id, text)| (id.to_string(), text.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn each_user_reads_their_own_language() {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .user("alice", "hall")
+            .user("bob", "hall")
+            .build()
+            .unwrap();
+        game_state.add_messages("FR", french()).unwrap();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+
+        let succ = game_state.process_input_impl(&alice, "language").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["Your language is en. There's also fr."]
+        );
+        let succ = game_state
+            .process_input_impl(&alice, "language fr")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["Votre langue est maintenant fr."]);
+
+        let fail = game_state.process_input_impl(&alice, "xyzzy").unwrap_err();
+        assert_eq!(fail.messages, vec!["Je ne comprends pas « xyzzy »."]);
+        let fail = game_state.process_input_impl(&bob, "xyzzy").unwrap_err();
+        assert_eq!(fail.messages, vec!["I don't understand 'xyzzy'."]);
+        // Untranslated messages fall back to English.
+        let fail = game_state
+            .process_input_impl(&alice, "go west")
+            .unwrap_err();
+        assert_eq!(
+            fail.messages,
+            vec!["What? There's no direction west from hall."]
+        );
+        // What the user typed is never filled in itself.
+        let fail = game_state
+            .process_input_impl(&bob, "go {room}")
+            .unwrap_err();
+        assert_eq!(
+            fail.messages,
+            vec!["What? There's no direction {room} from hall."]
+        );
+    }
+
+    #[test]
+    fn only_known_messages_and_locales_are_accepted() {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .user("alice", "hall")
+            .build()
+            .unwrap();
+        let typo = vec![("unknown_inptu".to_string(), "?".to_string())];
+        assert_eq!(
+            game_state.add_messages("fr", typo),
+            Err(FaerieError::MessageNotFound("unknown_inptu".to_string()))
+        );
+        let alice = UserName::new("alice").unwrap();
+        let fail = game_state
+            .process_input_impl(&alice, "language fr")
+            .unwrap_err();
+        assert_eq!(fail.messages, vec!["There's no fr here. There's en."]);
+    }
+}
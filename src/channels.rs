@@ -0,0 +1,334 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::UserName;
+use crate::user::Role;
+use crate::GameState;
+
+// A named chat line heard by everyone who's joined it, wherever they are. Only users with at
+// least `min_role` can join, and admins can mute anyone on it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Channel {
+    pub name: String,
+    #[serde(default)]
+    pub min_role: Role,
+    #[serde(default)]
+    pub muted: BTreeSet<UserName>,
+}
+
+impl Channel {
+    pub fn new(name: &str, min_role: Role) -> Channel {
+        Channel {
+            name: name.to_lowercase(),
+            min_role,
+            muted: BTreeSet::new(),
+        }
+    }
+}
+
+pub(crate) fn default_channels() -> BTreeMap<String, Channel> {
+    ["ooc", "newbie", "trade"]
+        .iter()
+        .map(|name| (name.to_string(), Channel::new(name, Role::Player)))
+        .collect()
+}
+
+// Words `chat` treats specially, so no channel can be called them. `chat guild` goes to the
+// user's guild (see guilds.rs).
+const CHAT_SUBCOMMANDS: &[&str] = &["join", "leave", "mute", "guild"];
+
+impl GameState {
+    pub fn add_channel(&mut self, channel: Channel) -> Result<(), FaerieError> {
+        let name = channel.name.as_str();
+        if name.is_empty() || name.contains(char::is_whitespace) || CHAT_SUBCOMMANDS.contains(&name)
+        {
+            return Err(FaerieError::InvalidChannelName(channel.name));
+        }
+        self.channels.insert(channel.name.clone(), channel);
+        Ok(())
+    }
+
+    // Every channel the user could join, marking those they have.
+    pub fn list_channels(&self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let user = self.users.get_user(user_name)?;
+        let mut messages = vec![self.text(user_name, "channels_header", &[])];
+        for channel in self.channels.values() {
+            if user.role < channel.min_role {
+                continue;
+            }
+            let mark = if user.channels.contains(&channel.name) {
+                "*"
+            } else {
+                " "
+            };
+            messages.push(format!("{} {}", mark, channel.name));
+        }
+        Ok(ActionSuccess::new(messages))
+    }
+
+    pub fn join_channel(
+        &mut self,
+        user_name: &UserName,
+        channel_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let channel_name = self.find_channel(user_name, channel_name)?;
+        let user = self.users.get_user_mut(user_name)?;
+        if !user.channels.insert(channel_name.clone()) {
+            return Err(channel_failure(self.text(
+                user_name,
+                "already_on_channel",
+                &[("channel", &channel_name)],
+            )));
+        }
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_join",
+            &[("channel", &channel_name)],
+        )]))
+    }
+
+    pub fn leave_channel(
+        &mut self,
+        user_name: &UserName,
+        channel_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let channel_name = channel_name.to_lowercase();
+        let user = self.users.get_user_mut(user_name)?;
+        if !user.channels.remove(&channel_name) {
+            return Err(channel_failure(self.text(
+                user_name,
+                "not_on_channel",
+                &[("channel", &channel_name)],
+            )));
+        }
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_leave_channel",
+            &[("channel", &channel_name)],
+        )]))
+    }
+
+    pub fn chat(
+        &mut self,
+        user_name: &UserName,
+        channel_name: &str,
+        text: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.refuse_if_muted(user_name)?;
+        let channel_name = channel_name.to_lowercase();
+        if channel_name == "guild" {
+            return self.guild_chat(user_name, text);
+        }
+        let on_channel = self
+            .users
+            .get_user(user_name)?
+            .channels
+            .contains(&channel_name);
+        let channel = match self.channels.get(&channel_name) {
+            Some(channel) if on_channel => channel,
+            _ => {
+                return Err(channel_failure(self.text(
+                    user_name,
+                    "not_on_channel",
+                    &[("channel", &channel_name)],
+                )))
+            }
+        };
+        if channel.muted.contains(user_name) {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "muted_on_channel", &[("channel", &channel_name)])],
+                FailReason::PermissionDenied,
+            ));
+        }
+
+        let mut listeners: Vec<UserName> = self
+            .users
+            .users
+            .values()
+            .filter(|u| {
+                &u.name != user_name
+                    && u.channels.contains(&channel_name)
+                    && !u.ignored.contains(user_name)
+            })
+            .map(|u| u.name)
+            .collect();
+        listeners.sort();
+        let args = [
+            ("channel", channel_name.as_str()),
+            ("name", user_name),
+            ("text", text),
+        ];
+        for listener in listeners {
+            let heard = self.text(&listener, "channel_says", &args);
+            self.output.send(&listener, &heard);
+        }
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_say_on_channel",
+            &args,
+        )]))
+    }
+
+    // Admins only; muting someone already muted on the channel unmutes them.
+    pub fn mute_on_channel(
+        &mut self,
+        user_name: &UserName,
+        channel_name: &str,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.require_role(user_name, Role::Admin)?;
+        let channel_name = self.find_channel(user_name, channel_name)?;
+        let target_name = match self.users.get_user(target_name) {
+            Ok(target) => target.name,
+            Err(_) => {
+                return Err(channel_failure(self.text(
+                    user_name,
+                    "nobody_called",
+                    &[("name", target_name)],
+                )))
+            }
+        };
+        let args = [("name", target_name.as_str()), ("channel", &channel_name)];
+        let channel = match self.channels.get_mut(&channel_name) {
+            Some(channel) => channel,
+            None => return Err(channel_failure(self.text(user_name, "no_channel", &args))),
+        };
+        let id = if channel.muted.remove(&target_name) {
+            "you_unmute_on"
+        } else {
+            channel.muted.insert(target_name);
+            "you_mute_on"
+        };
+        Ok(ActionSuccess::new(vec![self.text(user_name, id, &args)]))
+    }
+
+    // Channels the user isn't allowed on look the same as ones that don't exist.
+    fn find_channel(
+        &self,
+        user_name: &UserName,
+        channel_name: &str,
+    ) -> Result<String, ActionFailure> {
+        let role = self.users.get_user(user_name)?.role;
+        match self.channels.get(&channel_name.to_lowercase()) {
+            Some(channel) if role >= channel.min_role => Ok(channel.name.clone()),
+            _ => Err(channel_failure(self.text(
+                user_name,
+                "no_channel",
+                &[("channel", channel_name)],
+            ))),
+        }
+    }
+}
+
+fn channel_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+    use crate::testing::TestWorld;
+
+    // alice and bob in the square, carol far away in the tower, and an admin-only channel.
+    fn make_town() -> TestWorld {
+        let mut world = TestWorld::build(
+            GameStateBuilder::new()
+                .room("square", "Cobbles.")
+                .room("tower", "Drafty.")
+                .path("square", "tower", Direction::North)
+                .user("alice", "square")
+                .user("bob", "square")
+                .user("carol", "tower"),
+        );
+        let game_state = &mut world.game_state;
+        game_state
+            .set_role(&UserName::new("alice").unwrap(), Role::Admin)
+            .unwrap();
+        game_state
+            .add_channel(Channel::new("staff", Role::Admin))
+            .unwrap();
+        world
+    }
+
+    #[test]
+    fn channels_reach_subscribers_anywhere() {
+        let mut world = make_town();
+        let alice = UserName::new("alice").unwrap();
+        let carol = UserName::new("carol").unwrap();
+        world
+            .game_state
+            .process_input_impl(&alice, "chat join trade")
+            .unwrap();
+        world
+            .game_state
+            .process_input_impl(&carol, "chat join Trade")
+            .unwrap();
+
+        let succ = world
+            .game_state
+            .process_input_impl(&carol, "chat trade Selling rope.")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["[trade] You: Selling rope."]);
+        assert_eq!(
+            world.messages_for("alice"),
+            vec!["[trade] carol: Selling rope."]
+        );
+        assert!(world.messages_for("bob").is_empty());
+
+        world
+            .game_state
+            .process_input_impl(&carol, "chat leave trade")
+            .unwrap();
+        let failure = world
+            .game_state
+            .process_input_impl(&carol, "chat trade Anyone?")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["You aren't on trade."]);
+    }
+
+    #[test]
+    fn channels_have_permissions_and_mutes() {
+        let mut world = make_town();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        let failure = world
+            .game_state
+            .process_input_impl(&bob, "chat join staff")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["There's no channel staff."]);
+        let succ = world.game_state.process_input_impl(&bob, "chat").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["Channels:", "  newbie", "  ooc", "  trade"]
+        );
+
+        world
+            .game_state
+            .process_input_impl(&bob, "chat join ooc")
+            .unwrap();
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "chat mute ooc bob")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You mute bob on ooc."]);
+        let failure = world
+            .game_state
+            .process_input_impl(&bob, "chat ooc hello")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::PermissionDenied);
+        world
+            .game_state
+            .process_input_impl(&alice, "chat mute ooc bob")
+            .unwrap();
+        world
+            .game_state
+            .process_input_impl(&bob, "chat ooc hello")
+            .unwrap();
+    }
+}
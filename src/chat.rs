@@ -0,0 +1,172 @@
+use crate::events::GameEvent;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+impl GameState {
+    // Heard by everyone else in the speaker's room.
+    pub fn say(
+        &mut self,
+        user_name: &UserName,
+        text: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.refuse_if_muted(user_name)?;
+        let room_name = self.get_user_location(user_name)?;
+        let args = [("name", user_name.as_str()), ("text", text)];
+        self.tell_room_except(&room_name, user_name, "says", &args)?;
+        let mut succ = ActionSuccess::new(vec![self.text(user_name, "you_say", &args)]);
+        succ.push_messages(self.publish(GameEvent::Spoke {
+            user_name: *user_name,
+            text: text.to_string(),
+        }));
+        Ok(succ)
+    }
+
+    // Heard only by the target, wherever they are.
+    pub fn whisper(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+        text: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.users.get_user(user_name)?;
+        self.refuse_if_muted(user_name)?;
+        let target_name = match self.users.get_user(target_name) {
+            Ok(target) => target.name,
+            Err(_) => {
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "nobody_called", &[("name", target_name)])],
+                    FailReason::InvalidTarget,
+                ))
+            }
+        };
+
+        if !self.is_ignoring(&target_name, user_name) {
+            let args = [("name", user_name.as_str()), ("text", text)];
+            let heard = self.text(&target_name, "whispers", &args);
+            self.send_all(&target_name, &[heard]);
+        }
+        let args = [("name", target_name.as_str()), ("text", text)];
+        let mut succ = ActionSuccess::new(vec![self.text(user_name, "you_whisper", &args)]);
+        succ.push_messages(self.publish(GameEvent::Spoke {
+            user_name: *user_name,
+            text: text.to_string(),
+        }));
+        Ok(succ)
+    }
+
+    // Heard by every other user in the world.
+    pub fn shout(
+        &mut self,
+        user_name: &UserName,
+        text: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.users.get_user(user_name)?;
+        self.refuse_if_muted(user_name)?;
+        let mut listeners: Vec<UserName> = self
+            .users
+            .users
+            .keys()
+            .filter(|u| *u != user_name && !self.is_ignoring(u, user_name))
+            .cloned()
+            .collect();
+        listeners.sort();
+
+        let args = [("name", user_name.as_str()), ("text", text)];
+        for listener in listeners {
+            let heard = self.text(&listener, "shouts", &args);
+            self.output.send(&listener, &heard);
+        }
+        let mut succ = ActionSuccess::new(vec![self.text(user_name, "you_shout", &args)]);
+        succ.push_messages(self.publish(GameEvent::Spoke {
+            user_name: *user_name,
+            text: text.to_string(),
+        }));
+        Ok(succ)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::lambda::FailReason;
+    use crate::room::Direction;
+    use crate::testing::TestWorld;
+    use crate::type_aliases::UserName;
+
+    // alice and bob share the hall; carol is in the garden to the north.
+    fn make_chat_world() -> TestWorld {
+        TestWorld::build(
+            GameStateBuilder::new()
+                .room("hall", "A long hall.")
+                .room("garden", "Roses.")
+                .path("hall", "garden", Direction::North)
+                .user("alice", "hall")
+                .user("bob", "hall")
+                .user("carol", "garden"),
+        )
+    }
+
+    #[test]
+    fn say_reaches_only_the_room() {
+        let mut world = make_chat_world();
+        let alice = UserName::new("alice").unwrap();
+
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "say hello  there")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You say, \"hello  there\""]);
+        assert_eq!(
+            world.messages_for("bob"),
+            vec!["alice says, \"hello  there\""]
+        );
+        assert!(world.messages_for("carol").is_empty());
+        assert!(world.messages_for("alice").is_empty());
+    }
+
+    #[test]
+    fn whisper_reaches_only_the_target() {
+        let mut world = make_chat_world();
+        let alice = UserName::new("alice").unwrap();
+
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "whisper carol meet me north")
+            .unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["You whisper to carol, \"meet me north\""]
+        );
+        assert_eq!(
+            world.take_messages(),
+            vec![crate::output::Message {
+                user_name: UserName::new("carol").unwrap(),
+                text: "alice whispers to you, \"meet me north\"".to_string(),
+            }]
+        );
+
+        let res = world
+            .game_state
+            .process_input_impl(&alice, "whisper dave hi");
+        assert_eq!(res.unwrap_err().reason, FailReason::InvalidTarget);
+        let res = world.game_state.process_input_impl(&alice, "whisper carol");
+        assert_eq!(res.unwrap_err().reason, FailReason::MissingArgument);
+    }
+
+    #[test]
+    fn shout_reaches_everyone_else() {
+        let mut world = make_chat_world();
+
+        world
+            .game_state
+            .process_input_impl(&UserName::new("carol").unwrap(), "shout dinner!")
+            .unwrap();
+        assert_eq!(
+            world.messages_for("alice"),
+            vec!["carol shouts, \"dinner!\""]
+        );
+        assert_eq!(world.messages_for("bob"), vec!["carol shouts, \"dinner!\""]);
+        assert!(world.take_messages().is_empty());
+    }
+}
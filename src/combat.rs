@@ -0,0 +1,229 @@
+use crate::events::GameEvent;
+use crate::flags::RoomFlags;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+// Anyone with a User entry can fight, whether a person or an NPC is driving it. An attacker
+// keeps swinging once per tick until one side dies, they end up in different rooms, or they
+// reach a safe room.
+impl GameState {
+    pub fn attack(
+        &mut self,
+        attacker_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let attacker = self.users.get_user(attacker_name)?;
+        if attacker.is_dead() {
+            return Err(combat_failure(
+                &self.text(attacker_name, "cannot_fight", &[]),
+                FailReason::Dead,
+            ));
+        }
+        if attacker_name == target_name {
+            return Err(combat_failure(
+                &self.text(attacker_name, "attack_yourself", &[]),
+                FailReason::InvalidTarget,
+            ));
+        }
+
+        let target = match self.users.get_user(target_name) {
+            Ok(target) if target.room_name == attacker.room_name => target,
+            _ => match self.match_user_here(attacker_name, target_name)? {
+                Some(target_name) => return self.attack(attacker_name, &target_name),
+                None => {
+                    return Err(combat_failure(
+                        &self.text(attacker_name, "nobody_here", &[("name", target_name)]),
+                        FailReason::InvalidTarget,
+                    ))
+                }
+            },
+        };
+        if target.is_dead() {
+            return Err(combat_failure(
+                &self.text(attacker_name, "already_dead", &[("name", target_name)]),
+                FailReason::InvalidTarget,
+            ));
+        }
+
+        if self.room_has_flag(&target.room_name, RoomFlags::SAFE) {
+            return Err(combat_failure(
+                &self.text(attacker_name, "no_fighting", &[]),
+                FailReason::SafeRoom,
+            ));
+        }
+
+        let target_name = target.name;
+        self.combat_targets.insert(*attacker_name, target_name);
+        self.tell(&target_name, "attacks_you", &[("name", attacker_name)]);
+        Ok(ActionSuccess::new(vec![self.text(
+            attacker_name,
+            "you_attack",
+            &[("name", &target_name)],
+        )]))
+    }
+
+    pub fn combat_target(&self, attacker_name: &UserName) -> Option<&UserName> {
+        self.combat_targets.get(attacker_name)
+    }
+
+    // One swing for every ongoing fight, in attacker name order.
+    pub(crate) fn resolve_combat_round(&mut self) {
+        let fights: Vec<(UserName, UserName)> =
+            self.combat_targets.iter().map(|(a, t)| (*a, *t)).collect();
+
+        for (attacker_name, target_name) in fights {
+            let damage = match (
+                self.users.get_user(&attacker_name),
+                self.users.get_user(&target_name),
+            ) {
+                (Ok(attacker), Ok(target))
+                    if !attacker.is_dead()
+                        && !target.is_dead()
+                        && attacker.room_name == target.room_name
+                        && !self.room_has_flag(&target.room_name, RoomFlags::SAFE) =>
+                {
+                    target.damage_after_defense(
+                        attacker.attack_damage() + attacker.roll_weapon_dice(&mut self.rng),
+                    )
+                }
+                _ => {
+                    self.combat_targets.remove(&attacker_name);
+                    continue;
+                }
+            };
+
+            let died = match self.users.get_user_mut(&target_name) {
+                Ok(target) => {
+                    target.basic_attributes.hp -= damage;
+                    target.is_dead()
+                }
+                Err(_) => continue,
+            };
+
+            let dealt = self.formatter_for(&attacker_name).format_damage(damage);
+            let taken = self.formatter_for(&target_name).format_damage(damage);
+            let args = [("name", target_name.as_str()), ("damage", &dealt)];
+            self.tell(&attacker_name, "you_hit", &args);
+            let args = [("name", attacker_name.as_str()), ("damage", &taken)];
+            self.tell(&target_name, "hits_you", &args);
+
+            let messages = self.publish(GameEvent::Hit {
+                user_name: attacker_name,
+                target_name,
+                damage,
+            });
+            self.send_all(&attacker_name, &messages);
+
+            if died {
+                self.handle_death(&target_name, &attacker_name);
+            }
+        }
+    }
+
+    fn handle_death(&mut self, dead_name: &UserName, killer_name: &UserName) {
+        self.kill_user(dead_name);
+        let messages = self.publish(GameEvent::Killed {
+            user_name: *killer_name,
+            victim_name: *dead_name,
+        });
+        self.send_all(killer_name, &messages);
+    }
+}
+
+fn combat_failure(message: &str, reason: FailReason) -> ActionFailure {
+    ActionFailure::new(vec![message.to_string()], reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::lambda::FailReason;
+    use crate::room::Direction;
+    use crate::testing::TestWorld;
+    use crate::type_aliases::UserName;
+    use crate::user::UserType;
+
+    fn make_arena() -> TestWorld {
+        TestWorld::build(
+            GameStateBuilder::new()
+                .room("arena", "Sand and blood.")
+                .room("exit", "A way out.")
+                .path("arena", "exit", Direction::North)
+                .user_of_type("olaf", "arena", UserType::Viking)
+                .user("pat", "arena"),
+        )
+    }
+
+    #[test]
+    fn rounds_resolve_on_tick_until_death() {
+        let mut world = make_arena();
+        let olaf = UserName::new("olaf").unwrap();
+        let pat = UserName::new("pat").unwrap();
+
+        let succ = world
+            .game_state
+            .process_input_impl(&olaf, "attack pat")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You attack pat!"]);
+        assert_eq!(world.messages_for(&pat), vec!["olaf attacks you!"]);
+
+        world.game_state.tick();
+        assert_eq!(
+            world
+                .game_state
+                .users
+                .get_user(&pat)
+                .unwrap()
+                .basic_attributes
+                .hp,
+            14
+        );
+        assert_eq!(world.messages_for(&olaf), vec!["You hit pat for 6 damage."]);
+        assert_eq!(
+            world.messages_for(&pat),
+            vec!["olaf hits you for 6 damage."]
+        );
+
+        world.game_state.tick_n(3);
+        assert!(world.game_state.users.get_user(&pat).unwrap().is_dead());
+        assert_eq!(world.game_state.combat_target(&olaf), None);
+        assert!(world.messages_for(&olaf).ends_with(&[
+            "pat dies.".to_string(),
+            "Achievement unlocked: First Blood!".to_string(),
+        ]));
+        assert!(world
+            .messages_for(&pat)
+            .contains(&"You have died.".to_string()));
+
+        let res = world.game_state.process_input_impl(&olaf, "attack pat");
+        assert_eq!(res.unwrap_err().reason, FailReason::InvalidTarget);
+        let res = world.game_state.process_input_impl(&pat, "attack olaf");
+        assert_eq!(res.unwrap_err().reason, FailReason::Dead);
+    }
+
+    #[test]
+    fn leaving_the_room_ends_the_fight() {
+        let mut world = make_arena();
+        let olaf = UserName::new("olaf").unwrap();
+        let pat = UserName::new("pat").unwrap();
+
+        world.game_state.attack(&olaf, &pat).unwrap();
+        world.game_state.process_input_impl(&pat, "north").unwrap();
+        world.game_state.tick();
+        assert_eq!(
+            world
+                .game_state
+                .users
+                .get_user(&pat)
+                .unwrap()
+                .basic_attributes
+                .hp,
+            20
+        );
+        assert_eq!(world.game_state.combat_target(&olaf), None);
+
+        let res = world.game_state.attack(&olaf, &pat);
+        assert_eq!(res.unwrap_err().reason, FailReason::InvalidTarget);
+    }
+}
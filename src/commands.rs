@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use crate::error::FaerieError;
+use crate::help::CommandHelp;
+use crate::lambda::{ActionFailure, ActionSuccess};
+use crate::parser;
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+pub type CommandHandler = Box<
+    dyn FnMut(&mut GameState, &UserName, &[String]) -> Result<ActionSuccess, ActionFailure>
+        + Send
+        + Sync,
+>;
+
+// Commands added by whatever is embedding the game, keyed by lowercased verb. Lives on
+// GameState and is consulted by dispatch_input before the input is parsed.
+#[derive(Default)]
+pub(crate) struct CommandRegistry {
+    handlers: BTreeMap<String, CommandHandler>,
+}
+
+impl GameState {
+    // The handler gets the words after the verb, split on whitespace. Verbs are matched
+    // case-insensitively, and can't shadow a built-in verb or another registered command.
+    pub fn register_command<F>(&mut self, verb: &str, handler: F) -> Result<(), FaerieError>
+    where
+        F: 'static
+            + Send
+            + Sync
+            + FnMut(&mut GameState, &UserName, &[String]) -> Result<ActionSuccess, ActionFailure>,
+    {
+        let verb = verb.trim().to_lowercase();
+        if verb.is_empty() || verb.contains(char::is_whitespace) {
+            return Err(FaerieError::InvalidCommandName(verb));
+        }
+        if parser::is_builtin_verb(&verb) || self.commands.handlers.contains_key(&verb) {
+            return Err(FaerieError::DuplicateCommand(verb));
+        }
+        self.set_command_help(CommandHelp::new(&verb, &verb, ""));
+        self.commands.handlers.insert(verb, Box::new(handler));
+        Ok(())
+    }
+
+    // Returns false if no command was registered under that verb.
+    pub fn unregister_command(&mut self, verb: &str) -> bool {
+        let verb = verb.trim().to_lowercase();
+        let removed = self.commands.handlers.remove(&verb).is_some();
+        if removed {
+            self.remove_command_help(&verb);
+        }
+        removed
+    }
+
+    pub fn registered_commands(&self) -> Vec<&str> {
+        self.commands.handlers.keys().map(String::as_str).collect()
+    }
+
+    // None if the input doesn't start with a registered verb.
+    pub(crate) fn run_registered_command(
+        &mut self,
+        user_name: &UserName,
+        user_input: &str,
+    ) -> Option<Result<ActionSuccess, ActionFailure>> {
+        let (verb, rest) = parser::split_verb(user_input)?;
+        // The handler needs &mut self, so it's taken out of the registry while it runs.
+        let mut handler = self.commands.handlers.remove(&verb)?;
+        let args: Vec<String> = rest.split_whitespace().map(str::to_string).collect();
+        let res = handler(self, user_name, &args);
+        // A handler that registered a replacement for itself keeps the replacement.
+        self.commands.handlers.entry(verb).or_insert(handler);
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::error::FaerieError;
+    use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+    use crate::type_aliases::UserName;
+
+    use crate::GameState;
+
+    fn make_command_world() -> GameState {
+        GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .user("alice", "hall")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn registered_commands_are_dispatched_with_their_arguments() {
+        let mut game_state = make_command_world();
+        let alice = UserName::new("alice").unwrap();
+        let mut calls = 0;
+        game_state
+            .register_command("Wave", move |_, user_name, args| {
+                calls += 1;
+                Ok(ActionSuccess::new(vec![format!(
+                    "{} waves at {} (#{}).",
+                    user_name,
+                    args.join(" and "),
+                    calls
+                )]))
+            })
+            .unwrap();
+
+        let succ = game_state
+            .process_input_impl(&alice, "wave  bob carol")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["alice waves at bob and carol (#1)."]);
+        let succ = game_state.process_input_impl(&alice, "WAVE dave").unwrap();
+        assert_eq!(succ.messages(), vec!["alice waves at dave (#2)."]);
+
+        game_state
+            .register_command("sulk", |_, _, _| {
+                Err(ActionFailure::new(
+                    vec!["Nobody notices.".to_string()],
+                    FailReason::InvalidTarget,
+                ))
+            })
+            .unwrap();
+        let failure = game_state.process_input_impl(&alice, "sulk").unwrap_err();
+        assert_eq!(failure.messages, vec!["Nobody notices."]);
+        assert_eq!(
+            game_state.last_failure(&alice).unwrap().messages,
+            failure.messages
+        );
+        assert_eq!(game_state.registered_commands(), vec!["sulk", "wave"]);
+
+        // With the command gone, the built-in wave social answers again.
+        assert!(game_state.unregister_command("wave"));
+        let succ = game_state.process_input_impl(&alice, "wave").unwrap();
+        assert_eq!(succ.messages(), vec!["You wave."]);
+    }
+
+    #[test]
+    fn registered_commands_cannot_shadow_existing_verbs() {
+        let mut game_state = make_command_world();
+        let ok = |_: &mut GameState, _: &UserName, _: &[String]| Ok(ActionSuccess::new(vec![]));
+
+        assert_eq!(
+            game_state.register_command("look", ok),
+            Err(FaerieError::DuplicateCommand("look".to_string()))
+        );
+        assert_eq!(
+            game_state.register_command("list_users", ok),
+            Err(FaerieError::DuplicateCommand("list_users".to_string()))
+        );
+        assert_eq!(
+            game_state.register_command("two words", ok),
+            Err(FaerieError::InvalidCommandName("two words".to_string()))
+        );
+        game_state.register_command("dance", ok).unwrap();
+        assert_eq!(
+            game_state.register_command("DANCE", ok),
+            Err(FaerieError::DuplicateCommand("dance".to_string()))
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::BTreeSet;
+
+use crate::parser;
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+impl GameState {
+    // Tab completion for a line of input. Returns where the word being completed starts and
+    // what it could become: a verb or exit for the first word, or an exit after a verb like
+    // `go` or `look`. Exit names may contain spaces, so the argument is completed as a whole.
+    pub fn complete_input(&self, user_name: &UserName, line: &str) -> (usize, Vec<String>) {
+        let start = line.len() - line.trim_start().len();
+        let exits = self.visible_exits(user_name);
+
+        let (verb, rest) = match parser::split_verb(line) {
+            None => ("".to_string(), ""),
+            Some(split) => split,
+        };
+        let typed_past_verb = line[start..].contains(char::is_whitespace);
+        if !typed_past_verb {
+            let mut words = self.known_verbs();
+            words.extend(exits);
+            return (start, matching(words.into_iter(), &verb));
+        }
+        if parser::takes_path(&verb) {
+            // The argument starts after the verb and the whitespace following it, since rest
+            // has trailing whitespace trimmed too.
+            let after_verb = line[start..]
+                .find(char::is_whitespace)
+                .map_or(line.len(), |i| start + i);
+            let argument_start = line.len() - line[after_verb..].trim_start().len();
+            return (argument_start, matching(exits.into_iter(), rest));
+        }
+        (line.len(), vec![])
+    }
+
+    // Every verb there is: built in, registered, or a social.
+    pub(crate) fn known_verbs(&self) -> Vec<&str> {
+        let mut verbs: Vec<&str> = parser::builtin_verbs().collect();
+        verbs.extend(self.registered_commands());
+        verbs.extend(self.socials.keys().map(|s| s.as_str()));
+        verbs
+    }
+
+    // The ways out of the user's room that they can see.
+    pub(crate) fn visible_exits(&self, user_name: &UserName) -> Vec<&str> {
+        self.get_user_location(user_name)
+            .and_then(|room_name| self.rooms.get_room(&room_name))
+            .map(|room| {
+                room.paths
+                    .values()
+                    .filter(|path| self.can_see_path(user_name, &room.name, path))
+                    .map(|path| path.path_name.as_str())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn matching<'a>(words: impl Iterator<Item = &'a str>, prefix: &str) -> Vec<String> {
+    let prefix = prefix.to_lowercase();
+    words
+        .filter(|w| w.to_lowercase().starts_with(&prefix))
+        .map(str::to_string)
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+    use crate::type_aliases::UserName;
+
+    #[test]
+    fn completes_verbs_and_exits() {
+        let game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .room("north wing", "Dusty.")
+            .room("loft", "Dusty.")
+            .path(
+                "hall",
+                "north wing",
+                Direction::CustomOneWay("north wing".to_string()),
+            )
+            .path(
+                "hall",
+                "loft",
+                Direction::CustomOneWay("ladder".to_string()),
+            )
+            .user("alice", "hall")
+            .build()
+            .unwrap();
+        let alice = UserName::new("alice").unwrap();
+
+        assert_eq!(
+            game_state.complete_input(&alice, "l"),
+            (
+                0,
+                vec![
+                    "l".to_string(),
+                    "ladder".to_string(),
+                    "language".to_string(),
+                    "list".to_string(),
+                    "list_users".to_string(),
+                    "lock".to_string(),
+                    "look".to_string(),
+                    "loot".to_string()
+                ]
+            )
+        );
+        assert_eq!(
+            game_state.complete_input(&alice, "  go NO"),
+            (5, vec!["north wing".to_string()])
+        );
+        assert_eq!(game_state.complete_input(&alice, "say n"), (5, vec![]));
+    }
+
+    #[test]
+    fn completions_start_at_the_argument_whatever_follows_it() {
+        let game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .room("north wing", "Dusty.")
+            .path(
+                "hall",
+                "north wing",
+                Direction::CustomOneWay("north wing".to_string()),
+            )
+            .user("alice", "hall")
+            .build()
+            .unwrap();
+        let alice = UserName::new("alice").unwrap();
+        let wing = vec!["north wing".to_string()];
+
+        assert_eq!(
+            game_state.complete_input(&alice, "go no  "),
+            (3, wing.clone())
+        );
+        assert_eq!(
+            game_state.complete_input(&alice, " look\tnorth \t"),
+            (6, wing.clone())
+        );
+        assert_eq!(
+            game_state.complete_input(&alice, "go north w"),
+            (3, wing.clone())
+        );
+        assert_eq!(game_state.complete_input(&alice, "go "), (3, wing));
+        assert_eq!(game_state.complete_input(&alice, "go zz "), (3, vec![]));
+    }
+}
@@ -0,0 +1,334 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::grammar::with_article;
+use crate::item::Item;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::room::Lock;
+use crate::type_aliases::{RoomName, UserName};
+use crate::GameState;
+
+// What makes an item a container, like a bag or a chest. `capacity` is how many items it holds.
+// One with a lock is locked and unlocked like a door, with its key, and can't be opened while
+// locked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Container {
+    pub capacity: usize,
+    #[serde(default)]
+    pub contents: Vec<Item>,
+    #[serde(default)]
+    pub lock: Option<Lock>,
+}
+
+// Containers can be carried or left on the floor. Carried ones are found first.
+enum Place {
+    Inventory(usize),
+    Floor(RoomName, usize),
+}
+
+impl GameState {
+    fn find_container(&self, user_name: &UserName, name: &str) -> Result<Place, ActionFailure> {
+        let is_container = |i: &Item| i.name == name && i.container.is_some();
+        let user = self.users.get_user(user_name)?;
+        if let Some(index) = user.inventory.iter().position(is_container) {
+            return Ok(Place::Inventory(index));
+        }
+        let room = self.rooms.get_room(&user.room_name)?;
+        match room.items.iter().position(is_container) {
+            Some(index) => Ok(Place::Floor(room.name, index)),
+            None => Err(container_failure(self.text(
+                user_name,
+                "no_one_here",
+                &[("name", name)],
+            ))),
+        }
+    }
+
+    fn container_at(&mut self, user_name: &UserName, place: &Place) -> &mut Container {
+        let item = match place {
+            Place::Inventory(index) => {
+                &mut self.users.get_user_mut(user_name).unwrap().inventory[*index]
+            }
+            Place::Floor(room_name, index) => {
+                &mut self.rooms.get_room_mut(room_name).unwrap().items[*index]
+            }
+        };
+        item.container.as_mut().unwrap()
+    }
+
+    // The container's contents, if it isn't locked.
+    fn open_container(
+        &mut self,
+        user_name: &UserName,
+        name: &str,
+    ) -> Result<&mut Vec<Item>, ActionFailure> {
+        let place = self.find_container(user_name, name)?;
+        let lock = &self.container_at(user_name, &place).lock;
+        if lock.as_ref().is_some_and(|lock| lock.locked) {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "container_locked", &[("name", name)])],
+                FailReason::PathLocked,
+            ));
+        }
+        Ok(&mut self.container_at(user_name, &place).contents)
+    }
+
+    pub fn put_in(
+        &mut self,
+        user_name: &UserName,
+        item_name: &str,
+        container_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        if item_name == container_name {
+            return Err(container_failure(self.text(
+                user_name,
+                "inside_itself",
+                &[("name", item_name)],
+            )));
+        }
+        let capacity = {
+            let place = self.find_container(user_name, container_name)?;
+            self.container_at(user_name, &place).capacity
+        };
+        let inventory = &self.users.get_user(user_name)?.inventory;
+        let index = inventory
+            .iter()
+            .position(|i| i.name == item_name)
+            .ok_or_else(|| {
+                let item = with_article(item_name);
+                container_failure(self.text(user_name, "not_carrying", &[("item", &item)]))
+            })?;
+
+        if self.open_container(user_name, container_name)?.len() >= capacity {
+            return Err(container_failure(self.text(
+                user_name,
+                "container_full",
+                &[("name", container_name)],
+            )));
+        }
+        // Taken out only once it's certain to fit, so it's never lost. The container is looked
+        // up again, since it may have been carried after the item.
+        let item = self.users.get_user_mut(user_name)?.inventory.remove(index);
+        let named = with_article(&item.name);
+        let args = [("item", named.as_str()), ("name", container_name)];
+        let message = self.text(user_name, "you_put", &args);
+        self.open_container(user_name, container_name)?.push(item);
+        Ok(ActionSuccess::new(vec![message]))
+    }
+
+    pub fn take_from(
+        &mut self,
+        user_name: &UserName,
+        item_name: &str,
+        container_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let carried = matches!(
+            self.find_container(user_name, container_name)?,
+            Place::Inventory(_)
+        );
+        let found = self
+            .open_container(user_name, container_name)?
+            .iter()
+            .position(|i| i.name == item_name);
+        let index = match found {
+            Some(index) => index,
+            None => {
+                let args = [("item", item_name), ("name", container_name)];
+                return Err(container_failure(self.text(
+                    user_name,
+                    "not_in_container",
+                    &args,
+                )));
+            }
+        };
+        // Taking from a bag already carried doesn't add any weight.
+        if !carried {
+            let weight = self.open_container(user_name, container_name)?[index].total_weight();
+            self.refuse_if_too_heavy(user_name, weight, &with_article(item_name))?;
+        }
+        let item = self
+            .open_container(user_name, container_name)?
+            .remove(index);
+        let named = with_article(&item.name);
+        let args = [("item", named.as_str()), ("name", container_name)];
+        let message = self.text(user_name, "you_take_from", &args);
+
+        self.give_item(user_name, item)?;
+        Ok(ActionSuccess::new(vec![message]))
+    }
+
+    pub(crate) fn has_container_here(&self, user_name: &UserName, name: &str) -> bool {
+        self.find_container(user_name, name).is_ok()
+    }
+
+    pub(crate) fn set_container_lock_state(
+        &mut self,
+        user_name: &UserName,
+        name: &str,
+        locked: bool,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let place = self.find_container(user_name, name)?;
+        let key_name = match self.container_at(user_name, &place).lock.clone() {
+            None => {
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "no_lock", &[("name", name)])],
+                    FailReason::NotLockable,
+                ))
+            }
+            Some(lock) if lock.locked == locked => {
+                let id = if locked {
+                    "already_locked"
+                } else {
+                    "already_unlocked"
+                };
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, id, &[("name", name)])],
+                    FailReason::NotLockable,
+                ));
+            }
+            Some(lock) => lock.key_name,
+        };
+        if !self.users.get_user(user_name)?.has_item(&key_name) {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "need_key_for", &[("key", &key_name)])],
+                FailReason::MissingKey,
+            ));
+        }
+
+        if let Some(lock) = &mut self.container_at(user_name, &place).lock {
+            lock.locked = locked;
+        }
+        let id = if locked { "you_lock" } else { "you_unlock" };
+        let args = [("name", name), ("key", &key_name)];
+        Ok(ActionSuccess::new(vec![self.text(user_name, id, &args)]))
+    }
+
+    // What's inside, for examine.
+    pub(crate) fn describe_container(
+        &mut self,
+        user_name: &UserName,
+        name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let contents = self.open_container(user_name, name)?;
+        if contents.is_empty() {
+            let message = self.text(user_name, "container_empty", &[("name", name)]);
+            return Ok(ActionSuccess::new(vec![message]));
+        }
+        let names: Vec<String> = contents.iter().map(|i| with_article(&i.name)).collect();
+        let args = [("name", name), ("items", &names.join(", "))];
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "container_holds",
+            &args,
+        )]))
+    }
+
+    // Leaves an item on the floor of a room.
+    pub fn place_item(&mut self, room_name: &RoomName, item: Item) -> Result<(), FaerieError> {
+        self.rooms.get_room_mut(room_name)?.items.push(item);
+        Ok(())
+    }
+}
+
+fn container_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+
+    fn make_storeroom() -> GameState {
+        let mut game_state = GameStateBuilder::new()
+            .room("storeroom", "Shelves, mostly bare.")
+            .user("alice", "storeroom")
+            .build()
+            .unwrap();
+        let storeroom = RoomName::new("storeroom").unwrap();
+        let alice = UserName::new("alice").unwrap();
+        let chest = Item::new("chest".to_string(), "Iron-bound.".to_string())
+            .unwrap()
+            .container(
+                5,
+                Some(Lock {
+                    key_name: "brass key".to_string(),
+                    locked: true,
+                }),
+            );
+        game_state.place_item(&storeroom, chest).unwrap();
+        for name in &["bag", "apple", "pear"] {
+            let mut item = Item::new(name.to_string(), "Plain.".to_string()).unwrap();
+            if *name == "bag" {
+                item = item.container(1, None);
+            }
+            game_state.give_item(&alice, item).unwrap();
+        }
+        game_state
+    }
+
+    #[test]
+    fn bags_hold_items_up_to_their_capacity() {
+        let mut game_state = make_storeroom();
+        let alice = UserName::new("alice").unwrap();
+
+        let succ = game_state
+            .process_input_impl(&alice, "put apple in bag")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You put an apple in the bag."]);
+        let failure = game_state
+            .process_input_impl(&alice, "put pear in bag")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["The bag is full."]);
+        assert!(game_state.users.get_user(&alice).unwrap().has_item("pear"));
+        let failure = game_state
+            .process_input_impl(&alice, "put bag in bag")
+            .unwrap_err();
+        assert_eq!(
+            failure.messages,
+            vec!["You can't put the bag inside itself."]
+        );
+
+        let succ = game_state
+            .process_input_impl(&alice, "examine bag")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["The bag holds an apple."]);
+        let succ = game_state
+            .process_input_impl(&alice, "get apple from bag")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You take an apple from the bag."]);
+        assert!(game_state.users.get_user(&alice).unwrap().has_item("apple"));
+    }
+
+    #[test]
+    fn locked_chests_need_their_key() {
+        let mut game_state = make_storeroom();
+        let alice = UserName::new("alice").unwrap();
+
+        let failure = game_state
+            .process_input_impl(&alice, "put pear in chest")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["The chest is locked."]);
+        let failure = game_state
+            .process_input_impl(&alice, "unlock chest")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::MissingKey);
+
+        let key = Item::new("brass key".to_string(), "Small.".to_string()).unwrap();
+        game_state.give_item(&alice, key).unwrap();
+        let succ = game_state
+            .process_input_impl(&alice, "unlock chest")
+            .unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["You unlock the chest with the brass key."]
+        );
+        game_state
+            .process_input_impl(&alice, "put pear in chest")
+            .unwrap();
+        let failure = game_state
+            .process_input_impl(&alice, "lock bag")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["The bag has no lock."]);
+    }
+}
@@ -0,0 +1,262 @@
+use crate::error::FaerieError;
+use crate::events::GameEvent;
+use crate::lambda::{ActionFailure, FailReason};
+use crate::type_aliases::{RoomName, UserName};
+use crate::user::User;
+use crate::GameState;
+
+pub(crate) const DEFAULT_RESPAWN_DELAY: u64 = 5;
+
+pub(crate) fn default_respawn_delay() -> u64 {
+    DEFAULT_RESPAWN_DELAY
+}
+
+// A user whose hp drops to 0 dies: they can't act until they respawn, a number of ticks later,
+// in the respawn room (or the start room if none is set) with their hp restored, minus any
+// penalty.
+impl GameState {
+    pub fn set_respawn_room(&mut self, room_name: &RoomName) -> Result<(), FaerieError> {
+        self.rooms.check_room_exists(room_name)?;
+        self.respawn_room_name = Some(*room_name);
+        Ok(())
+    }
+
+    pub fn respawn_room(&self) -> Option<&RoomName> {
+        self.respawn_room_name
+            .as_ref()
+            .or(self.start_room_name.as_ref())
+    }
+
+    // Zero respawns the dead immediately.
+    pub fn set_respawn_delay(&mut self, ticks: u64) {
+        self.respawn_delay = ticks;
+    }
+
+    // Hp taken off a respawned user's maximum. They always come back with at least 1.
+    pub fn set_death_penalty(&mut self, hp: i32) {
+        self.death_hp_penalty = hp;
+    }
+
+    pub fn is_awaiting_respawn(&self, user_name: &UserName) -> bool {
+        self.dead_users.contains_key(user_name)
+    }
+
+    pub(crate) fn refuse_if_dead(&self, user_name: &UserName) -> Result<(), ActionFailure> {
+        if self.is_awaiting_respawn(user_name) {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "dead", &[])],
+                FailReason::Dead,
+            ));
+        }
+        Ok(())
+    }
+
+    // Catches deaths from anything that lowers hp: combat, painful paths and scripts.
+    pub(crate) fn check_deaths(&mut self) {
+        let newly_dead: Vec<UserName> = self
+            .users
+            .users
+            .values()
+            .filter(|u| u.is_dead() && !self.dead_users.contains_key(&u.name))
+            .map(|u| u.name)
+            .collect();
+        for user_name in newly_dead {
+            self.kill_user(&user_name);
+        }
+    }
+
+    // After a command, only the room the user ends up in is checked, so a command costs the
+    // same however big the world is. Anyone hurt further afield is caught on the next tick.
+    pub(crate) fn check_deaths_near(&mut self, user_name: &UserName) {
+        let room = match self
+            .get_user_location(user_name)
+            .and_then(|room_name| self.rooms.get_room(&room_name))
+        {
+            Ok(room) => room,
+            Err(_) => return,
+        };
+        let mut newly_dead: Vec<UserName> = room
+            .users
+            .iter()
+            .filter(|name| {
+                self.users.get_user(name).is_ok_and(User::is_dead)
+                    && !self.dead_users.contains_key(*name)
+            })
+            .copied()
+            .collect();
+        newly_dead.sort();
+        for user_name in newly_dead {
+            self.kill_user(&user_name);
+        }
+    }
+
+    pub(crate) fn kill_user(&mut self, user_name: &UserName) {
+        if self.dead_users.contains_key(user_name) {
+            return;
+        }
+        self.combat_targets
+            .retain(|attacker, target| attacker != user_name && target != user_name);
+        self.end_trade(user_name, "trade_off_died");
+        self.dead_users.insert(*user_name, self.tick_count);
+        if let Ok(user) = self.users.get_user_mut(user_name) {
+            user.status_effects.clear();
+        }
+        let message = self.text(user_name, "you_died", &[]);
+        self.send_all(user_name, &[message]);
+        let messages = self.publish(GameEvent::Died {
+            user_name: *user_name,
+        });
+        self.send_all(user_name, &messages);
+        if let Ok(room_name) = self.get_user_location(user_name) {
+            let _ = self.tell_room_except(&room_name, user_name, "dies", &[("name", user_name)]);
+        }
+        let _ = self.drop_loot(user_name);
+        if self.despawn_if_spawned(user_name) {
+            return;
+        }
+        if self.respawn_delay_for(user_name) == 0 {
+            self.respawn(user_name);
+        }
+    }
+
+    // Zones can set their own delay and respawn room for those who die in them.
+    fn respawn_delay_for(&self, user_name: &UserName) -> u64 {
+        self.user_zone(user_name)
+            .and_then(|zone| zone.respawn_delay)
+            .unwrap_or(self.respawn_delay)
+    }
+
+    fn respawn_room_for(&self, user_name: &UserName) -> Option<RoomName> {
+        self.user_zone(user_name)
+            .and_then(|zone| zone.respawn_room.as_ref())
+            .or_else(|| self.respawn_room())
+            .cloned()
+    }
+
+    pub(crate) fn respawn_due_users(&mut self) {
+        let due: Vec<UserName> = self
+            .dead_users
+            .iter()
+            .filter(|(user_name, died_at)| {
+                **died_at + self.respawn_delay_for(user_name) <= self.tick_count
+            })
+            .map(|(user_name, _)| *user_name)
+            .collect();
+        for user_name in due {
+            self.respawn(&user_name);
+        }
+    }
+
+    // Brings a user back right away, whether or not their time is up.
+    pub fn respawn(&mut self, user_name: &UserName) {
+        let respawn_room = self.respawn_room_for(user_name);
+        if self.dead_users.remove(user_name).is_none() {
+            return;
+        }
+        let penalty = self.death_hp_penalty;
+        if let Ok(user) = self.users.get_user_mut(user_name) {
+            user.basic_attributes.hp = (user.max_hp() - penalty).max(1);
+        }
+        if let Some(room_name) = respawn_room {
+            if self.relocate_user(user_name, &room_name).is_ok() {
+                let args = [("name", user_name.as_str())];
+                let _ = self.tell_room_except(&room_name, user_name, "respawned", &args);
+            }
+        }
+        let message = self.text(user_name, "you_respawn", &[]);
+        self.send_all(user_name, &[message]);
+        if let Err(e) = self.print_room(user_name) {
+            self.send_all(user_name, &[e.to_string()]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::lambda::FailReason;
+    use crate::room::Direction;
+    use crate::testing::TestWorld;
+    use crate::type_aliases::{RoomName, UserName};
+
+    fn make_deadly_world() -> TestWorld {
+        let mut world = TestWorld::build(
+            GameStateBuilder::new()
+                .room("temple", "Quiet and safe.")
+                .room("pit", "Sharp rocks.")
+                .user("alice", "pit")
+                .user("bob", "pit"),
+        );
+        let temple = RoomName::new("temple").unwrap();
+        let pit = RoomName::new("pit").unwrap();
+        let game_state = &mut world.game_state;
+        game_state
+            .add_painful_path(&pit, &temple, Direction::North)
+            .unwrap();
+        game_state.set_respawn_room(&temple).unwrap();
+        world
+    }
+
+    #[test]
+    fn dead_users_wait_then_respawn() {
+        let mut world = make_deadly_world();
+        let alice = UserName::new("alice").unwrap();
+        world.game_state.set_respawn_delay(2);
+        world.game_state.set_death_penalty(5);
+        world
+            .game_state
+            .users
+            .get_user_mut(&alice)
+            .unwrap()
+            .basic_attributes
+            .hp = 1;
+
+        world.game_state.process_input(&alice, "north");
+        assert!(world.game_state.is_awaiting_respawn(&alice));
+        assert_eq!(
+            world.messages_for("alice").last().unwrap(),
+            "You have died."
+        );
+        assert_eq!(world.messages_for("bob"), vec!["alice leaves north."]);
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "look")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::Dead);
+
+        world.game_state.tick();
+        assert!(world.game_state.is_awaiting_respawn(&alice));
+        world.game_state.tick();
+        assert!(!world.game_state.is_awaiting_respawn(&alice));
+        let user = world.game_state.users.get_user(&alice).unwrap();
+        assert_eq!(user.basic_attributes.hp, 15);
+        assert_eq!(user.room_name, "temple");
+        assert_eq!(world.messages_for("alice")[0], "You return to life.");
+    }
+
+    #[test]
+    fn without_a_delay_respawn_is_immediate() {
+        let mut world = make_deadly_world();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        world.game_state.set_respawn_delay(0);
+        world.game_state.attack(&bob, "alice").unwrap();
+        world
+            .game_state
+            .users
+            .get_user_mut(&alice)
+            .unwrap()
+            .basic_attributes
+            .hp = 1;
+
+        world.game_state.tick();
+        assert!(!world.game_state.is_awaiting_respawn(&alice));
+        assert_eq!(world.game_state.combat_target(&bob), None);
+        assert!(world
+            .messages_for("bob")
+            .contains(&"alice dies.".to_string()));
+        let user = world.game_state.users.get_user(&alice).unwrap();
+        assert_eq!(user.basic_attributes.hp, 20);
+        assert_eq!(user.room_name, "temple");
+    }
+}
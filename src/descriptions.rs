@@ -0,0 +1,149 @@
+use crate::error::FaerieError;
+use crate::grammar::{join_and, with_article};
+use crate::room::Room;
+use crate::type_aliases::{RoomName, UserName};
+use crate::GameState;
+
+// Ticks in a full day, from morning round to night.
+pub const DAY_LENGTH: u64 = 240;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeOfDay {
+    Morning,
+    Afternoon,
+    Evening,
+    Night,
+}
+
+impl TimeOfDay {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            TimeOfDay::Morning => "morning",
+            TimeOfDay::Afternoon => "afternoon",
+            TimeOfDay::Evening => "evening",
+            TimeOfDay::Night => "night",
+        }
+    }
+}
+
+// Builds a room's description afresh each time someone sees it.
+pub type DescriptionProvider = Option<Box<dyn Fn(&GameState, &UserName) -> String + Send + Sync>>;
+
+impl GameState {
+    // The game starts at daybreak, and each quarter of a day is one time of day.
+    pub fn time_of_day(&self) -> TimeOfDay {
+        match (self.tick_count % DAY_LENGTH) * 4 / DAY_LENGTH {
+            0 => TimeOfDay::Morning,
+            1 => TimeOfDay::Afternoon,
+            2 => TimeOfDay::Evening,
+            _ => TimeOfDay::Night,
+        }
+    }
+
+    // Used instead of the room's own description. Like room hooks, it isn't saved.
+    pub fn set_description_provider<F>(
+        &mut self,
+        room_name: &RoomName,
+        provider: F,
+    ) -> Result<(), FaerieError>
+    where
+        F: 'static + Send + Sync + Fn(&GameState, &UserName) -> String,
+    {
+        self.rooms.get_room_mut(room_name)?.describe = Some(Box::new(provider));
+        Ok(())
+    }
+
+    // The room's description as this user sees it now: from its provider if it has one, or
+    // else its own with any of these filled in:
+    //
+    //     {time}    the time of day, e.g. "evening"
+    //     {weather} the weather in the room's zone, e.g. "raining", or "calm" without any
+    //     {people}  everyone else here, e.g. "alice and bob", or "no one"
+    //     {items}   what's on the ground, e.g. "a lamp", or "nothing"
+    pub(crate) fn room_description_for(&self, user_name: &UserName, room: &Room) -> String {
+        if let Some(provider) = &room.describe {
+            return provider(self, user_name);
+        }
+        if !room.description.contains('{') {
+            return room.description.clone();
+        }
+
+        let mut people: Vec<String> = room
+            .users
+            .iter()
+            .filter(|name| *name != user_name)
+            .map(|name| name.to_string())
+            .collect();
+        people.sort();
+        let weather = match room.zone.as_ref().and_then(|z| self.zones.get(z)?.weather) {
+            Some(weather) => weather.display_name(),
+            None => "calm",
+        };
+        let items: Vec<String> = room.items.iter().map(|i| with_article(&i.name)).collect();
+        let or_else = |words: &[String], none: &str| {
+            if words.is_empty() {
+                none.to_string()
+            } else {
+                join_and(words)
+            }
+        };
+        room.description
+            .replace("{time}", self.time_of_day().display_name())
+            .replace("{weather}", weather)
+            .replace("{people}", &or_else(&people, "no one"))
+            .replace("{items}", &or_else(&items, "nothing"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::item::Item;
+
+    fn make_square() -> GameState {
+        GameStateBuilder::new()
+            .room("square", "It's {time}. You see {people} here, and {items}.")
+            .user("alice", "square")
+            .user("bob", "square")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn placeholders_are_filled_in() {
+        let mut game_state = make_square();
+        let alice = UserName::new("alice").unwrap();
+        let succ = game_state.process_input_impl(&alice, "look").unwrap();
+        assert_eq!(
+            succ.messages()[1],
+            "  It's morning. You see bob here, and nothing."
+        );
+
+        let lamp = Item::new("lamp".to_string(), "Brass.".to_string()).unwrap();
+        game_state
+            .place_item(&RoomName::new("square").unwrap(), lamp)
+            .unwrap();
+        game_state.tick_n(DAY_LENGTH * 3 / 4);
+        let succ = game_state.process_input_impl(&alice, "look").unwrap();
+        assert_eq!(
+            succ.messages()[1],
+            "  It's night. You see bob here, and a lamp."
+        );
+    }
+
+    #[test]
+    fn providers_replace_the_description() {
+        let mut game_state = make_square();
+        let square = RoomName::new("square").unwrap();
+        game_state
+            .set_description_provider(&square, |game_state, user_name| {
+                let crowd = game_state.rooms.get_room("square").unwrap().users.len();
+                format!("{} counts {} people.", user_name, crowd)
+            })
+            .unwrap();
+        let bob = UserName::new("bob").unwrap();
+        let succ = game_state.process_input_impl(&bob, "look").unwrap();
+        assert_eq!(succ.messages()[1], "  bob counts 2 people.");
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::{QuestId, UserName};
+use crate::GameState;
+
+// What an NPC can say when talked to, as nodes of text joined by numbered responses. In world
+// files:
+//
+//     [users.dialogue]
+//     start = "greet"
+//
+//     [users.dialogue.nodes.greet]
+//     text = "Welcome, traveller."
+//     responses = [{ text = "Any work?", next = "work" }, { text = "Goodbye." }]
+//
+//     [users.dialogue.nodes.work]
+//     text = "Rats in the cellar. Interested?"
+//     responses = [{ text = "I'll do it.", quest = "rats" }, { text = "What's for sale?", trade = true }]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Dialogue {
+    pub start: String,
+    pub nodes: BTreeMap<String, DialogueNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DialogueNode {
+    pub text: String,
+    // With none, the conversation ends after the text.
+    #[serde(default)]
+    pub responses: Vec<DialogueResponse>,
+}
+
+// Choosing a response does everything it says, then moves on to `next`, or ends the
+// conversation without it. `trade` shows the NPC's wares, and `script` is the source of a
+// script run for the user, like a room hook; world files give the name of one instead.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DialogueResponse {
+    pub text: String,
+    pub next: Option<String>,
+    pub quest: Option<QuestId>,
+    #[serde(default)]
+    pub trade: bool,
+    pub script: Option<String>,
+}
+
+impl DialogueResponse {
+    pub fn new(text: &str, next: Option<&str>) -> DialogueResponse {
+        DialogueResponse {
+            text: text.to_string(),
+            next: next.map(str::to_string),
+            ..DialogueResponse::default()
+        }
+    }
+}
+
+impl Dialogue {
+    fn check_nodes(&self) -> Result<(), FaerieError> {
+        let nexts = self
+            .nodes
+            .values()
+            .flat_map(|node| &node.responses)
+            .filter_map(|response| response.next.as_ref());
+        for node_name in std::iter::once(&self.start).chain(nexts) {
+            if !self.nodes.contains_key(node_name) {
+                return Err(FaerieError::DialogueNodeNotFound(node_name.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Who a user is talking to, and where they've got to.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Conversation {
+    pub(crate) npc: UserName,
+    node: String,
+}
+
+impl GameState {
+    // Every response has to lead to a node that exists.
+    pub fn set_dialogue(
+        &mut self,
+        user_name: &UserName,
+        dialogue: Option<Dialogue>,
+    ) -> Result<(), FaerieError> {
+        if let Some(dialogue) = &dialogue {
+            dialogue.check_nodes()?;
+        }
+        self.users.get_user_mut(user_name)?.dialogue = dialogue;
+        Ok(())
+    }
+
+    pub fn talk(
+        &mut self,
+        user_name: &UserName,
+        npc_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let room_name = self.get_user_location(user_name)?;
+        let npc = match self.users.get_user(npc_name) {
+            Ok(npc) if npc.room_name == room_name && npc_name != user_name.as_str() => npc,
+            _ => {
+                let message = self.text(user_name, "no_npc_here", &[("name", npc_name)]);
+                return Err(dialogue_failure(message));
+            }
+        };
+        let start = match &npc.dialogue {
+            Some(dialogue) => dialogue.start.clone(),
+            None => {
+                let message = self.text(user_name, "nothing_to_say", &[("name", npc_name)]);
+                return Err(dialogue_failure(message));
+            }
+        };
+
+        let conversation = Conversation {
+            npc: npc.name,
+            node: start,
+        };
+        Ok(ActionSuccess::new(
+            self.enter_dialogue_node(user_name, conversation),
+        ))
+    }
+
+    pub fn respond(
+        &mut self,
+        user_name: &UserName,
+        choice: usize,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let conversation = self
+            .conversations
+            .get(user_name)
+            .cloned()
+            .ok_or_else(|| dialogue_failure(self.text(user_name, "not_talking", &[])))?;
+        let npc_here = self
+            .users
+            .get_user(&conversation.npc)
+            .is_ok_and(|npc| Ok(&npc.room_name) == self.get_user_location(user_name).as_ref());
+        let response = self
+            .users
+            .get_user(&conversation.npc)
+            .ok()
+            .and_then(|npc| npc.dialogue.as_ref()?.nodes.get(&conversation.node))
+            .filter(|_| npc_here)
+            .map(|node| node.responses.get(choice.wrapping_sub(1)).cloned());
+        let response = match response {
+            Some(Some(response)) => response,
+            Some(None) => {
+                let choice = choice.to_string();
+                return Err(dialogue_failure(self.text(
+                    user_name,
+                    "no_response",
+                    &[("number", &choice)],
+                )));
+            }
+            None => {
+                self.conversations.remove(user_name);
+                return Err(dialogue_failure(self.text(user_name, "not_talking", &[])));
+            }
+        };
+
+        let mut messages = vec![self.text(user_name, "you_say", &[("text", &response.text)])];
+
+        if let Some(quest_id) = &response.quest {
+            messages.extend(self.start_quest(user_name, quest_id)?);
+        }
+        if response.trade {
+            match self.list_wares(user_name) {
+                Ok(succ) => messages.extend(succ.messages()),
+                Err(failure) => messages.extend(failure.messages),
+            }
+        }
+        if let Some(source) = &response.script {
+            messages.extend(self.run_dialogue_script(user_name, source));
+        }
+        match response.next {
+            Some(node) => {
+                let conversation = Conversation {
+                    npc: conversation.npc,
+                    node,
+                };
+                messages.extend(self.enter_dialogue_node(user_name, conversation));
+            }
+            None => {
+                self.conversations.remove(user_name);
+            }
+        }
+        Ok(ActionSuccess::new(messages))
+    }
+
+    // The node's text and numbered responses, remembering where the user is if there's
+    // anything to respond to.
+    fn enter_dialogue_node(
+        &mut self,
+        user_name: &UserName,
+        conversation: Conversation,
+    ) -> Vec<String> {
+        let node = match self
+            .users
+            .get_user(&conversation.npc)
+            .ok()
+            .and_then(|npc| npc.dialogue.as_ref()?.nodes.get(&conversation.node))
+        {
+            Some(node) => node.clone(),
+            None => {
+                self.conversations.remove(user_name);
+                return vec![];
+            }
+        };
+        let args = [("name", conversation.npc.as_str()), ("text", &node.text)];
+        let mut messages = vec![self.text(user_name, "says", &args)];
+
+        for (i, response) in node.responses.iter().enumerate() {
+            messages.push(format!("  {}. {}", i + 1, response.text));
+        }
+        if node.responses.is_empty() {
+            self.conversations.remove(user_name);
+        } else {
+            self.conversations.insert(*user_name, conversation);
+        }
+        messages
+    }
+
+    #[cfg(feature = "scripting")]
+    fn run_dialogue_script(&mut self, user_name: &UserName, source: &str) -> Vec<String> {
+        self.run_script_for(user_name, source)
+    }
+
+    #[cfg(not(feature = "scripting"))]
+    fn run_dialogue_script(&mut self, _user_name: &UserName, _source: &str) -> Vec<String> {
+        vec!["faerie was built without the scripting feature".to_string()]
+    }
+}
+
+fn dialogue_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::quests::{Objective, Quest};
+    use crate::room::Direction;
+    use crate::shop::Shop;
+    use crate::type_aliases::RoomName;
+
+    // An elder in the village hall with work and a few things for sale.
+    fn make_village() -> GameState {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "Benches and a hearth.")
+            .room("cellar", "Damp.")
+            .path(
+                "hall",
+                "cellar",
+                Direction::Custom("down".to_string(), "up".to_string()),
+            )
+            .user("alice", "hall")
+            .user("elder", "hall")
+            .build()
+            .unwrap();
+        game_state
+            .register_quest(Quest {
+                id: "rats".to_string(),
+                name: "Rat Catcher".to_string(),
+                description: "Clear the cellar.".to_string(),
+                objectives: vec![Objective::VisitRoom(RoomName::new("cellar").unwrap())],
+            })
+            .unwrap();
+
+        let elder = UserName::new("elder").unwrap();
+        game_state
+            .set_user_shop(&elder, Some(Shop::default().with_ware("candle", 2, 0)))
+            .unwrap();
+        let mut nodes = BTreeMap::new();
+        nodes.insert(
+            "greet".to_string(),
+            DialogueNode {
+                text: "Welcome, traveller.".to_string(),
+                responses: vec![
+                    DialogueResponse::new("Any work?", Some("work")),
+                    DialogueResponse {
+                        trade: true,
+                        ..DialogueResponse::new("What's for sale?", Some("greet"))
+                    },
+                    DialogueResponse::new("Goodbye.", None),
+                ],
+            },
+        );
+        nodes.insert(
+            "work".to_string(),
+            DialogueNode {
+                text: "Rats in the cellar.".to_string(),
+                responses: vec![DialogueResponse {
+                    quest: Some("rats".to_string()),
+                    ..DialogueResponse::new("I'll do it.", Some("thanks"))
+                }],
+            },
+        );
+        nodes.insert(
+            "thanks".to_string(),
+            DialogueNode {
+                text: "Bless you.".to_string(),
+                responses: vec![],
+            },
+        );
+        let dialogue = Dialogue {
+            start: "greet".to_string(),
+            nodes,
+        };
+        game_state.set_dialogue(&elder, Some(dialogue)).unwrap();
+        game_state
+    }
+
+    #[test]
+    fn conversations_follow_responses() {
+        let mut game_state = make_village();
+        let alice = UserName::new("alice").unwrap();
+        let succ = game_state.process_input_impl(&alice, "talk elder").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "elder says, \"Welcome, traveller.\"",
+                "  1. Any work?",
+                "  2. What's for sale?",
+                "  3. Goodbye.",
+            ]
+        );
+        let failure = game_state.process_input_impl(&alice, "4").unwrap_err();
+        assert_eq!(failure.messages, vec!["There's no response 4."]);
+
+        game_state.process_input_impl(&alice, "1").unwrap();
+        let succ = game_state.process_input_impl(&alice, "1").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "You say, \"I'll do it.\"",
+                "New quest: Rat Catcher",
+                "elder says, \"Bless you.\"",
+            ]
+        );
+        assert!(game_state
+            .quest_progress(&alice, &"rats".to_string())
+            .is_some());
+        let failure = game_state.process_input_impl(&alice, "1").unwrap_err();
+        assert_eq!(failure.messages, vec!["You aren't talking to anyone."]);
+    }
+
+    #[test]
+    fn responses_can_open_the_shop_and_walking_off_ends_it() {
+        let mut game_state = make_village();
+        let alice = UserName::new("alice").unwrap();
+        game_state.process_input_impl(&alice, "talk elder").unwrap();
+        let succ = game_state.process_input_impl(&alice, "2").unwrap();
+        assert_eq!(succ.messages()[1..3], ["For sale:", "  candle: 2 gold"]);
+
+        game_state.process_input_impl(&alice, "down").unwrap();
+        let failure = game_state.process_input_impl(&alice, "1").unwrap_err();
+        assert_eq!(failure.messages, vec!["You aren't talking to anyone."]);
+
+        let mut broken = game_state
+            .users
+            .get_user("elder")
+            .unwrap()
+            .dialogue
+            .clone()
+            .unwrap();
+        broken.start = "farewell".to_string();
+        assert_eq!(
+            game_state.set_dialogue(&UserName::new("elder").unwrap(), Some(broken)),
+            Err(FaerieError::DialogueNodeNotFound("farewell".to_string()))
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::rng::GameRng;
+use crate::type_aliases::UserName;
+use crate::user::User;
+use crate::GameState;
+
+pub const MAX_DICE: u32 = 100;
+pub const MAX_SIDES: u32 = 1000;
+
+// Dice in the usual notation: "d20", "2d6", "3d8-2". Saved and written in world files the same
+// way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Dice {
+    pub count: u32,
+    pub sides: u32,
+    pub modifier: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiceRoll {
+    pub rolls: Vec<u32>,
+    pub total: i64,
+}
+
+impl Dice {
+    pub fn roll(&self, rng: &mut GameRng) -> DiceRoll {
+        let rolls: Vec<u32> = (0..self.count)
+            .map(|_| rng.range(1, i64::from(self.sides)) as u32)
+            .collect();
+        let total = rolls.iter().map(|r| i64::from(*r)).sum::<i64>() + i64::from(self.modifier);
+        DiceRoll { rolls, total }
+    }
+}
+
+impl FromStr for Dice {
+    type Err = FaerieError;
+
+    fn from_str(text: &str) -> Result<Dice, FaerieError> {
+        let invalid = || FaerieError::InvalidDice(text.to_string());
+        let notation: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        let (count, rest) = notation
+            .to_lowercase()
+            .split_once('d')
+            .map(|(c, r)| (c.to_string(), r.to_string()))
+            .ok_or_else(invalid)?;
+        let (sides, modifier) = match rest.find(['+', '-']) {
+            Some(i) => (&rest[..i], rest[i..].parse::<i32>().map_err(|_| invalid())?),
+            None => (rest.as_str(), 0),
+        };
+        let count = if count.is_empty() {
+            1
+        } else {
+            count.parse::<u32>().map_err(|_| invalid())?
+        };
+        let sides = sides.parse::<u32>().map_err(|_| invalid())?;
+        if count == 0 || count > MAX_DICE || sides == 0 || sides > MAX_SIDES {
+            return Err(invalid());
+        }
+        Ok(Dice {
+            count,
+            sides,
+            modifier,
+        })
+    }
+}
+
+impl TryFrom<String> for Dice {
+    type Error = FaerieError;
+
+    fn try_from(text: String) -> Result<Dice, FaerieError> {
+        text.parse()
+    }
+}
+
+impl From<Dice> for String {
+    fn from(dice: Dice) -> String {
+        dice.to_string()
+    }
+}
+
+impl fmt::Display for Dice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)?;
+        match self.modifier {
+            0 => Ok(()),
+            m if m > 0 => write!(f, "+{}", m),
+            m => write!(f, "{}", m),
+        }
+    }
+}
+
+impl User {
+    // Extra damage from a weapon's dice, rolled fresh for each hit.
+    pub fn roll_weapon_dice(&self, rng: &mut GameRng) -> i32 {
+        match self.equipment_modifiers().damage_dice {
+            Some(dice) => dice.roll(rng).total as i32,
+            None => 0,
+        }
+    }
+}
+
+impl GameState {
+    // Rolled in the open: the whole room sees the result.
+    pub fn roll(
+        &mut self,
+        user_name: &UserName,
+        notation: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let room_name = self.get_user_location(user_name)?;
+        let dice: Dice = notation.parse().map_err(|e: FaerieError| {
+            ActionFailure::new(vec![e.to_string()], FailReason::InvalidTarget)
+        })?;
+        let roll = dice.roll(&mut self.rng);
+        let mut parts: Vec<String> = roll.rolls.iter().map(|r| r.to_string()).collect();
+        if dice.modifier != 0 {
+            parts.push(dice.modifier.to_string());
+        }
+        let dice = dice.to_string();
+        let total = roll.total.to_string();
+        let sum = parts.join(" + ").replace("+ -", "- ");
+        let args = [
+            ("name", user_name.as_str()),
+            ("dice", &dice),
+            ("rolls", &sum),
+            ("total", &total),
+        ];
+        self.tell_room_except(&room_name, user_name, "rolls", &args)?;
+        Ok(ActionSuccess::new(vec![
+            self.text(user_name, "you_roll", &args)
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equipment::{EquipmentSlot, StatModifiers};
+    use crate::item::Item;
+    use crate::output::BufferedSink;
+    use crate::type_aliases::RoomName;
+    use crate::user::UserType;
+
+    #[test]
+    fn parses_and_rolls_standard_notation() {
+        let dice: Dice = "2d6+3".parse().unwrap();
+        assert_eq!(
+            dice,
+            Dice {
+                count: 2,
+                sides: 6,
+                modifier: 3
+            }
+        );
+        assert_eq!("D20".parse::<Dice>().unwrap().to_string(), "1d20");
+        assert_eq!("3d8 - 2".parse::<Dice>().unwrap().to_string(), "3d8-2");
+        for bad in &["", "d", "2d", "0d6", "2d0", "2x6", "2d6+", "1000d6"] {
+            assert_eq!(
+                bad.parse::<Dice>(),
+                Err(FaerieError::InvalidDice(bad.to_string()))
+            );
+        }
+
+        let mut rng = GameRng::new(1);
+        for _ in 0..50 {
+            let roll = dice.roll(&mut rng);
+            assert_eq!(roll.rolls.len(), 2);
+            assert!((5..=15).contains(&roll.total));
+        }
+    }
+
+    #[test]
+    fn rolls_are_announced_to_the_room() {
+        let mut game_state = GameState::new_with_seed(5);
+        let table = RoomName::new("table").unwrap();
+        game_state
+            .create_room(&table, "Dice and snacks.".to_string())
+            .unwrap();
+        for name in &["alice", "bob"] {
+            game_state
+                .create_user_in_room(&UserName::new(name).unwrap(), &table, UserType::Civilian)
+                .unwrap();
+        }
+        let output = BufferedSink::new();
+        game_state.set_output_sink(Box::new(output.clone()));
+        let alice = UserName::new("alice").unwrap();
+
+        let succ = game_state.process_input_impl(&alice, "roll 1d1+2").unwrap();
+        assert_eq!(succ.messages(), vec!["You roll 1d1+2: 1 + 2 = 3."]);
+        assert_eq!(output.drain_for("bob"), vec!["alice rolls 1d1+2: 3."]);
+        let failure = game_state
+            .process_input_impl(&alice, "roll lots")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::InvalidTarget);
+
+        let club = Item::new("club".to_string(), "Knobbly.".to_string())
+            .unwrap()
+            .equippable(
+                EquipmentSlot::Weapon,
+                StatModifiers {
+                    damage_dice: Some("1d1+4".parse().unwrap()),
+                    ..StatModifiers::default()
+                },
+            );
+        game_state.give_item(&alice, club).unwrap();
+        game_state.process_input_impl(&alice, "wield club").unwrap();
+        let user = game_state.users.get_user(&alice).unwrap();
+        assert_eq!(user.roll_weapon_dice(&mut GameRng::new(1)), 5);
+    }
+}
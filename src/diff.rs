@@ -0,0 +1,250 @@
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::error::FaerieError;
+use crate::type_aliases::{PathName, RoomName, UserName};
+use crate::GameState;
+
+// How one world differs from another, going by what they'd save. A room counts as changed
+// for anything but its paths, which are compared one by one.
+#[derive(Debug, Default, PartialEq)]
+pub struct WorldDiff {
+    pub added_rooms: Vec<RoomName>,
+    pub removed_rooms: Vec<RoomName>,
+    pub changed_rooms: Vec<RoomName>,
+    pub added_paths: Vec<(RoomName, PathName)>,
+    pub removed_paths: Vec<(RoomName, PathName)>,
+    pub changed_paths: Vec<(RoomName, PathName)>,
+    pub added_users: Vec<UserName>,
+    pub removed_users: Vec<UserName>,
+    pub changed_users: Vec<UserName>,
+}
+
+impl WorldDiff {
+    // Compares two saves, as written by GameState::to_json.
+    pub fn between_saves(old_json: &str, new_json: &str) -> Result<WorldDiff, FaerieError> {
+        let old: Value = serde_json::from_str(old_json)?;
+        let new: Value = serde_json::from_str(new_json)?;
+        WorldDiff::between_values(&old, &new)
+    }
+
+    fn between_values(old: &Value, new: &Value) -> Result<WorldDiff, FaerieError> {
+        let mut diff = WorldDiff::default();
+        let old_rooms = entries(old, "rooms");
+        let new_rooms = entries(new, "rooms");
+        let (added, removed, kept) = compare_keys(&old_rooms, &new_rooms);
+        diff.added_rooms = names(&added, RoomName::new)?;
+        diff.removed_rooms = names(&removed, RoomName::new)?;
+        for room_name in kept {
+            let old_room = &old_rooms[&room_name];
+            let new_room = &new_rooms[&room_name];
+            if comparable_room(old_room) != comparable_room(new_room) {
+                diff.changed_rooms.push(RoomName::new(&room_name)?);
+            }
+
+            let old_paths = paths_of(old_room);
+            let new_paths = paths_of(new_room);
+            let (added, removed, kept) = compare_keys(&old_paths, &new_paths);
+            let room_name = RoomName::new(&room_name)?;
+            let in_room = |path_names: &[String]| -> Result<Vec<_>, FaerieError> {
+                Ok(names(path_names, PathName::new)?
+                    .into_iter()
+                    .map(|path_name| (room_name, path_name))
+                    .collect())
+            };
+            diff.added_paths.extend(in_room(&added)?);
+            diff.removed_paths.extend(in_room(&removed)?);
+            let changed: Vec<String> = kept
+                .into_iter()
+                .filter(|path_name| old_paths[path_name] != new_paths[path_name])
+                .collect();
+            diff.changed_paths.extend(in_room(&changed)?);
+        }
+
+        let old_users = entries(old, "users");
+        let new_users = entries(new, "users");
+        let (added, removed, kept) = compare_keys(&old_users, &new_users);
+        diff.added_users = names(&added, UserName::new)?;
+        diff.removed_users = names(&removed, UserName::new)?;
+        let changed: Vec<String> = kept
+            .into_iter()
+            .filter(|user_name| old_users[user_name] != new_users[user_name])
+            .collect();
+        diff.changed_users = names(&changed, UserName::new)?;
+        Ok(diff)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == WorldDiff::default()
+    }
+}
+
+// One line per difference, marked + for added, - for removed and ~ for changed.
+impl fmt::Display for WorldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (mark, room_names) in &[
+            ("+", &self.added_rooms),
+            ("-", &self.removed_rooms),
+            ("~", &self.changed_rooms),
+        ] {
+            for room_name in room_names.iter() {
+                writeln!(f, "{} room {}", mark, room_name)?;
+            }
+        }
+        for (mark, paths) in &[
+            ("+", &self.added_paths),
+            ("-", &self.removed_paths),
+            ("~", &self.changed_paths),
+        ] {
+            for (room_name, path_name) in paths.iter() {
+                writeln!(f, "{} path {} {}", mark, room_name, path_name)?;
+            }
+        }
+        for (mark, user_names) in &[
+            ("+", &self.added_users),
+            ("-", &self.removed_users),
+            ("~", &self.changed_users),
+        ] {
+            for user_name in user_names.iter() {
+                writeln!(f, "{} user {}", mark, user_name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GameState {
+    // What would have to change to turn this world into `other`.
+    pub fn diff(&self, other: &GameState) -> Result<WorldDiff, FaerieError> {
+        let old = serde_json::to_value(self)?;
+        let new = serde_json::to_value(other)?;
+        WorldDiff::between_values(&old, &new)
+    }
+}
+
+// The rooms or users in a save, by name. RoomStore and UserStore each keep theirs in a
+// field named after themselves.
+fn entries(save: &Value, store: &str) -> Map<String, Value> {
+    save.get(store)
+        .and_then(|store_value| store_value.get(store))
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn paths_of(room: &Value) -> Map<String, Value> {
+    room.get("paths")
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default()
+}
+
+// Leaves out the paths, and sorts the users, who are kept in a set with no fixed order.
+fn comparable_room(room: &Value) -> Value {
+    let mut room = room.clone();
+    if let Some(room) = room.as_object_mut() {
+        room.remove("paths");
+        if let Some(Value::Array(users)) = room.get_mut("users") {
+            users.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+        }
+    }
+    room
+}
+
+// Keys only in new, only in old, and in both, each sorted.
+fn compare_keys(
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let mut added: Vec<String> = new
+        .keys()
+        .filter(|k| !old.contains_key(*k))
+        .cloned()
+        .collect();
+    let mut removed: Vec<String> = old
+        .keys()
+        .filter(|k| !new.contains_key(*k))
+        .cloned()
+        .collect();
+    let mut kept: Vec<String> = old
+        .keys()
+        .filter(|k| new.contains_key(*k))
+        .cloned()
+        .collect();
+    added.sort();
+    removed.sort();
+    kept.sort();
+    (added, removed, kept)
+}
+
+fn names<T>(
+    keys: &[String],
+    new: impl Fn(&str) -> Result<T, FaerieError>,
+) -> Result<Vec<T>, FaerieError> {
+    keys.iter().map(|key| new(key)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room::Direction;
+
+    const WORLD: &str = r#"
+        [[rooms]]
+        name = "Hall"
+        description = "A long hall."
+
+        [[rooms]]
+        name = "Garden"
+        description = "Roses everywhere."
+
+        [[paths]]
+        from = "Hall"
+        to = "Garden"
+        direction = "north"
+
+        [[users]]
+        name = "alice"
+        room = "Hall"
+
+        [[users]]
+        name = "bob"
+        room = "Hall"
+    "#;
+
+    #[test]
+    fn diffs_report_rooms_paths_and_users() {
+        let old = GameState::from_world_str(WORLD).unwrap();
+        let mut new = GameState::from_world_str(WORLD).unwrap();
+        let hall = RoomName::new("Hall").unwrap();
+        let garden = RoomName::new("Garden").unwrap();
+        let shed = RoomName::new("Shed").unwrap();
+        new.create_room_from(&shed, "Rakes.".to_string(), &garden, Direction::East)
+            .unwrap();
+        new.set_room_description(&hall, "A swept hall.".to_string())
+            .unwrap();
+        new.remove_user(&UserName::new("bob").unwrap()).unwrap();
+        new.give_gold(&UserName::new("alice").unwrap(), 5).unwrap();
+
+        let diff = old.diff(&new).unwrap();
+        assert_eq!(
+            diff.to_string(),
+            "+ room Shed\n\
+             ~ room Hall\n\
+             + path Garden east\n\
+             - user bob\n\
+             ~ user alice\n"
+        );
+        assert!(new.diff(&new).unwrap().is_empty());
+    }
+
+    #[test]
+    fn saves_round_trip_without_differences() {
+        let game_state = GameState::from_world_str(WORLD).unwrap();
+        let json = game_state.to_json().unwrap();
+        let loaded = GameState::from_json(&json).unwrap();
+        let diff = WorldDiff::between_saves(&json, &loaded.to_json().unwrap()).unwrap();
+        assert!(diff.is_empty(), "{}", diff);
+    }
+}
@@ -0,0 +1,298 @@
+use serde::{Deserialize, Serialize};
+
+use crate::dice::Dice;
+use crate::grammar::with_article;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::UserName;
+use crate::user::User;
+use crate::GameState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum EquipmentSlot {
+    Weapon,
+    Armor,
+    Trinket,
+}
+
+impl EquipmentSlot {
+    pub fn name(self) -> &'static str {
+        match self {
+            EquipmentSlot::Weapon => "weapon",
+            EquipmentSlot::Armor => "armor",
+            EquipmentSlot::Trinket => "trinket",
+        }
+    }
+}
+
+// What an equipped item adds to its wearer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatModifiers {
+    #[serde(default)]
+    pub attack: i32,
+    #[serde(default)]
+    pub defense: i32,
+    // Rolled and added to every hit, on top of `attack`.
+    #[serde(default)]
+    pub damage_dice: Option<Dice>,
+}
+
+impl User {
+    pub fn equipment_modifiers(&self) -> StatModifiers {
+        self.equipment
+            .values()
+            .fold(StatModifiers::default(), |total, item| StatModifiers {
+                attack: total.attack + item.modifiers.attack,
+                defense: total.defense + item.modifiers.defense,
+                damage_dice: total.damage_dice.or(item.modifiers.damage_dice),
+            })
+    }
+
+    // Damage taken from a hit of `damage`, after defence. A hit always does at least 1.
+    pub fn damage_after_defense(&self, damage: i32) -> i32 {
+        (damage - self.equipment_modifiers().defense).max(1)
+    }
+}
+
+// Equipped items leave the inventory, and go back to it when removed.
+impl GameState {
+    pub fn wield(
+        &mut self,
+        user_name: &UserName,
+        item_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.equip(user_name, item_name, true)
+    }
+
+    pub fn wear(
+        &mut self,
+        user_name: &UserName,
+        item_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.equip(user_name, item_name, false)
+    }
+
+    fn equip(
+        &mut self,
+        user_name: &UserName,
+        item_name: &str,
+        wielding: bool,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let named = with_article(item_name);
+        let user = self.users.get_user(user_name)?;
+        let index = match user.inventory.iter().position(|i| i.name == item_name) {
+            Some(index) => index,
+            None => {
+                return Err(equipment_failure(self.text(
+                    user_name,
+                    "not_carrying",
+                    &[("item", &named)],
+                )))
+            }
+        };
+        let slot = match user.inventory[index].slot {
+            Some(EquipmentSlot::Weapon) if wielding => EquipmentSlot::Weapon,
+            Some(slot) if !wielding && slot != EquipmentSlot::Weapon => slot,
+            _ => {
+                let id = if wielding {
+                    "cannot_wield"
+                } else {
+                    "cannot_wear"
+                };
+                return Err(equipment_failure(self.text(
+                    user_name,
+                    id,
+                    &[("item", &named)],
+                )));
+            }
+        };
+
+        let user = self.users.get_user_mut(user_name)?;
+        let item = user.inventory.remove(index);
+        let mut messages = vec![];
+        if let Some(old) = user.equipment.insert(slot, item) {
+            let taken_off = with_article(&old.name);
+            user.inventory.push(old);
+            messages.push(self.text(user_name, "you_take_off", &[("item", &taken_off)]));
+        }
+        let id = if wielding { "you_wield" } else { "you_wear" };
+        messages.push(self.text(user_name, id, &[("item", &named)]));
+        Ok(ActionSuccess::new(messages))
+    }
+
+    pub fn remove_equipment(
+        &mut self,
+        user_name: &UserName,
+        item_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let named = with_article(item_name);
+        let user = self.users.get_user_mut(user_name)?;
+        let slot = match user
+            .equipment
+            .iter()
+            .find(|(_, item)| item.name == item_name)
+        {
+            Some((slot, _)) => *slot,
+            None => {
+                return Err(equipment_failure(self.text(
+                    user_name,
+                    "not_using",
+                    &[("item", &named)],
+                )))
+            }
+        };
+        if let Some(item) = user.equipment.remove(&slot) {
+            user.inventory.push(item);
+        }
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_remove",
+            &[("item", &named)],
+        )]))
+    }
+
+    pub fn score(&self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let user = self.users.get_user(user_name)?;
+        let attributes = &user.basic_attributes;
+        let modifiers = user.equipment_modifiers();
+        let hp = format!("{}/{}", attributes.hp, user.max_hp());
+        let mp = format!("{}/{}", attributes.mp, user.max_mp());
+        let stamina = format!("{}/{}", user.stamina(), user.max_stamina());
+        let gold = attributes.gold.to_string();
+        let carrying = format!("{}/{}", user.carried_weight(), user.carry_capacity());
+        let attack = user.attack_damage().to_string();
+        let defense = modifiers.defense.to_string();
+        let kind = user.user_type().display_name();
+        let mut messages = vec![
+            self.text(
+                user_name,
+                "score_title",
+                &[("name", &user.name), ("kind", kind)],
+            ),
+            self.text(
+                user_name,
+                "score_vitals",
+                &[("hp", &hp), ("mp", &mp), ("stamina", &stamina)],
+            ),
+            self.text(
+                user_name,
+                "score_load",
+                &[("gold", &gold), ("carrying", &carrying)],
+            ),
+            self.text(
+                user_name,
+                "score_fighting",
+                &[("attack", &attack), ("defense", &defense)],
+            ),
+        ];
+        if let Some(zone) = self.zone_of(&user.room_name) {
+            messages.push(self.text(user_name, "score_zone", &[("zone", &zone.name)]));
+        }
+        for (slot, item) in &user.equipment {
+            let args = [("slot", slot.name()), ("item", item.name.as_str())];
+            messages.push(self.text(user_name, "score_slot", &args));
+        }
+        Ok(ActionSuccess::new(messages))
+    }
+}
+
+fn equipment_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::item::Item;
+
+    use crate::user::UserType;
+
+    fn make_armory() -> GameState {
+        let mut game_state = GameStateBuilder::new()
+            .room("armory", "Racks of gear.")
+            .user_of_type("olaf", "armory", UserType::Viking)
+            .user_of_type("pat", "armory", UserType::Viking)
+            .build()
+            .unwrap();
+        let gear = vec![
+            ("axe", EquipmentSlot::Weapon, 4, 0),
+            ("sword", EquipmentSlot::Weapon, 2, 0),
+            ("mail shirt", EquipmentSlot::Armor, 0, 3),
+        ];
+        for (name, slot, attack, defense) in gear {
+            let item = Item::new(name.to_string(), format!("A {}.", name))
+                .unwrap()
+                .equippable(
+                    slot,
+                    StatModifiers {
+                        attack,
+                        defense,
+                        damage_dice: None,
+                    },
+                );
+            game_state
+                .give_item(&UserName::new("olaf").unwrap(), item)
+                .unwrap();
+        }
+        game_state
+    }
+
+    #[test]
+    fn equipping_swaps_items_between_slots_and_inventory() {
+        let mut game_state = make_armory();
+        let olaf = UserName::new("olaf").unwrap();
+
+        let failure = game_state
+            .process_input_impl(&olaf, "wear axe")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["You can't wear an axe."]);
+        game_state.process_input_impl(&olaf, "wield sword").unwrap();
+        let succ = game_state.process_input_impl(&olaf, "wield axe").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["You take off a sword.", "You wield an axe."]
+        );
+        game_state
+            .process_input_impl(&olaf, "wear mail shirt")
+            .unwrap();
+
+        let user = game_state.users.get_user(&olaf).unwrap();
+        assert_eq!(user.inventory.len(), 1);
+        assert_eq!(user.inventory[0].name, "sword");
+        let succ = game_state.process_input_impl(&olaf, "score").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "olaf the viking",
+                "  hp: 220/220  mp: 9/9  stamina: 60/60",
+                "  gold: 0  carrying: 0/65",
+                "  attack: 10  defense: 3",
+                "  weapon: axe",
+                "  armor: mail shirt",
+            ]
+        );
+
+        game_state
+            .process_input_impl(&olaf, "remove mail shirt")
+            .unwrap();
+        let user = game_state.users.get_user(&olaf).unwrap();
+        assert_eq!(user.equipment_modifiers().defense, 0);
+        assert_eq!(user.inventory.len(), 2);
+    }
+
+    #[test]
+    fn equipment_changes_combat_damage() {
+        let mut game_state = make_armory();
+        let olaf = UserName::new("olaf").unwrap();
+        let pat = UserName::new("pat").unwrap();
+        game_state.wield(&olaf, "axe").unwrap();
+        game_state.wear(&olaf, "mail shirt").unwrap();
+        game_state.attack(&olaf, "pat").unwrap();
+        game_state.attack(&pat, "olaf").unwrap();
+
+        game_state.tick();
+        let hp = |name: &str| game_state.users.get_user(name).unwrap().basic_attributes.hp;
+        assert_eq!(hp("pat"), 210);
+        assert_eq!(hp("olaf"), 217);
+    }
+}
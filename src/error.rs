@@ -0,0 +1,170 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::accounts::MIN_PASSWORD_LEN;
+use crate::type_aliases::MAX_NAME_LEN;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FaerieError {
+    RoomNotFound(String),
+    ZoneNotFound(String),
+    UserNotFound(String),
+    EmptyRoomName,
+    EmptyRoomDescription,
+    EmptyUserName,
+    EmptyPathName,
+    EmptyItemName,
+    EmptyAccountName,
+    NameTooLong(String),
+    PathNotFound {
+        room_name: String,
+        path_name: String,
+    },
+    DuplicatePath {
+        room_name: String,
+        path_name: String,
+    },
+    RoomOccupied(String),
+    DuplicateRoom(String),
+    DuplicateUser(String),
+    NoStartRoom,
+    QuestNotFound(String),
+    DuplicateQuest(String),
+    DialogueNodeNotFound(String),
+    InvalidTitle(String),
+    InvalidChannelName(String),
+    InvalidCommandName(String),
+    DuplicateCommand(String),
+    MessageNotFound(String),
+    ScriptNotFound(String),
+    Script(String),
+    InvalidDice(String),
+    TooMuchGold(String),
+    AccountNotFound(String),
+    DuplicateAccount(String),
+    CharacterTaken {
+        user_name: String,
+        account_name: String,
+    },
+    AuthenticationFailed,
+    PasswordTooShort,
+    PasswordHash(String),
+    Io(String),
+    Serialization(String),
+}
+
+impl fmt::Display for FaerieError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FaerieError::RoomNotFound(room_name) => {
+                write!(f, "No room named {} exists!", room_name)
+            }
+            FaerieError::ZoneNotFound(zone_name) => {
+                write!(f, "No zone named {} exists!", zone_name)
+            }
+            FaerieError::UserNotFound(user_name) => {
+                write!(f, "No user named {} exists!", user_name)
+            }
+            FaerieError::EmptyRoomName => write!(f, "Empty room names are not allowed!"),
+            FaerieError::EmptyRoomDescription => {
+                write!(f, "Empty room descriptions are not allowed!")
+            }
+            FaerieError::EmptyUserName => write!(f, "Empty user names are not allowed!"),
+            FaerieError::EmptyPathName => write!(f, "Empty path names are not allowed!"),
+            FaerieError::EmptyItemName => write!(f, "Empty item names are not allowed!"),
+            FaerieError::EmptyAccountName => write!(f, "Empty account names are not allowed!"),
+            FaerieError::NameTooLong(name) => write!(
+                f,
+                "'{}' is too long; names can be at most {} characters.",
+                name, MAX_NAME_LEN
+            ),
+            FaerieError::PathNotFound {
+                room_name,
+                path_name,
+            } => write!(f, "No path '{}' from {} exists!", path_name, room_name),
+            FaerieError::DuplicatePath {
+                room_name,
+                path_name,
+            } => write!(f, "Path '{}' from {} already exists!", path_name, room_name),
+            FaerieError::RoomOccupied(room_name) => {
+                write!(f, "{} still has users in it.", room_name)
+            }
+            FaerieError::DuplicateRoom(room_name) => {
+                write!(f, "Room {} is defined more than once!", room_name)
+            }
+            FaerieError::DuplicateUser(user_name) => {
+                write!(f, "User {} is defined more than once!", user_name)
+            }
+            FaerieError::NoStartRoom => write!(f, "No start room has been set!"),
+            FaerieError::QuestNotFound(quest_id) => {
+                write!(f, "No quest named {} exists!", quest_id)
+            }
+            FaerieError::DuplicateQuest(quest_id) => {
+                write!(f, "Quest {} is defined more than once!", quest_id)
+            }
+            FaerieError::InvalidChannelName(name) => {
+                write!(f, "'{}' can't be used as a channel name.", name)
+            }
+            FaerieError::InvalidTitle(title) => write!(f, "\"{}\" can't be a title!", title),
+            FaerieError::DialogueNodeNotFound(node_name) => {
+                write!(f, "No dialogue node named {} exists!", node_name)
+            }
+            FaerieError::InvalidCommandName(verb) => {
+                write!(f, "'{}' can't be used as a command name.", verb)
+            }
+            FaerieError::DuplicateCommand(verb) => {
+                write!(f, "A command called {} already exists!", verb)
+            }
+            FaerieError::MessageNotFound(id) => write!(f, "No message called {} exists!", id),
+            FaerieError::ScriptNotFound(script_name) => {
+                write!(f, "No script named {} exists!", script_name)
+            }
+            FaerieError::Script(msg) => write!(f, "Script error: {}", msg),
+            FaerieError::InvalidDice(notation) => {
+                write!(f, "'{}' isn't a dice roll, like 2d6+3.", notation)
+            }
+            FaerieError::TooMuchGold(user_name) => {
+                write!(f, "{} can't hold that much gold!", user_name)
+            }
+            FaerieError::AccountNotFound(account_name) => {
+                write!(f, "No account named {} exists!", account_name)
+            }
+            FaerieError::DuplicateAccount(account_name) => {
+                write!(f, "An account named {} already exists!", account_name)
+            }
+            FaerieError::CharacterTaken {
+                user_name,
+                account_name,
+            } => write!(f, "{} already belongs to {}.", user_name, account_name),
+            FaerieError::AuthenticationFailed => write!(f, "Wrong account name or password."),
+            FaerieError::PasswordTooShort => write!(
+                f,
+                "Passwords must be at least {} characters long.",
+                MIN_PASSWORD_LEN
+            ),
+            FaerieError::PasswordHash(msg) => write!(f, "Password hashing failed: {}", msg),
+            FaerieError::Io(msg) => write!(f, "I/O error: {}", msg),
+            FaerieError::Serialization(msg) => write!(f, "Serialization error: {}", msg),
+        }
+    }
+}
+
+impl Error for FaerieError {}
+
+impl From<std::io::Error> for FaerieError {
+    fn from(e: std::io::Error) -> FaerieError {
+        FaerieError::Io(e.to_string())
+    }
+}
+
+impl From<toml::de::Error> for FaerieError {
+    fn from(e: toml::de::Error) -> FaerieError {
+        FaerieError::Serialization(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for FaerieError {
+    fn from(e: serde_json::Error) -> FaerieError {
+        FaerieError::Serialization(e.to_string())
+    }
+}
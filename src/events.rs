@@ -0,0 +1,193 @@
+use crate::status::StatusEffect;
+use crate::type_aliases::{ItemName, PathName, RoomName, UserName};
+use crate::GameState;
+
+// Something that happened in the world, published by the subsystem that made it happen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    Moved {
+        user_name: UserName,
+        from: RoomName,
+        to: RoomName,
+        path_name: PathName,
+    },
+    Spoke {
+        user_name: UserName,
+        text: String,
+    },
+    Hit {
+        user_name: UserName,
+        target_name: UserName,
+        damage: i32,
+    },
+    // user_name is the killer. Deaths with nobody to blame, like falling, aren't published.
+    Killed {
+        user_name: UserName,
+        victim_name: UserName,
+    },
+    ItemGained {
+        user_name: UserName,
+        item_name: ItemName,
+    },
+    EffectStarted {
+        user_name: UserName,
+        effect: StatusEffect,
+    },
+    EffectEnded {
+        user_name: UserName,
+        effect: StatusEffect,
+    },
+    // Published for every death, whatever the cause.
+    Died {
+        user_name: UserName,
+    },
+}
+
+impl GameEvent {
+    // The user who caused the event. Messages returned by listeners are meant for them.
+    pub fn user_name(&self) -> &UserName {
+        match self {
+            GameEvent::Moved { user_name, .. }
+            | GameEvent::Spoke { user_name, .. }
+            | GameEvent::Hit { user_name, .. }
+            | GameEvent::Killed { user_name, .. }
+            | GameEvent::ItemGained { user_name, .. }
+            | GameEvent::EffectStarted { user_name, .. }
+            | GameEvent::EffectEnded { user_name, .. }
+            | GameEvent::Died { user_name } => user_name,
+        }
+    }
+}
+
+pub type ListenerId = u64;
+pub type EventListener = Box<dyn FnMut(&mut GameState, &GameEvent) -> Vec<String> + Send + Sync>;
+
+// Listeners waiting for events. Lives on GameState and, like the scheduler, isn't saved.
+#[derive(Default)]
+pub(crate) struct EventBus {
+    next_id: ListenerId,
+    listeners: Vec<(ListenerId, EventListener)>,
+}
+
+impl GameState {
+    pub fn subscribe<F>(&mut self, listener: F) -> ListenerId
+    where
+        F: 'static + Send + Sync + FnMut(&mut GameState, &GameEvent) -> Vec<String>,
+    {
+        let id = self.event_bus.next_id;
+        self.event_bus.next_id += 1;
+        self.event_bus.listeners.push((id, Box::new(listener)));
+        id
+    }
+
+    // Returns false if there was no such listener. A listener can't unsubscribe itself, or
+    // anyone else, from inside its own callback.
+    pub fn unsubscribe(&mut self, listener_id: ListenerId) -> bool {
+        let listeners = &mut self.event_bus.listeners;
+        let before = listeners.len();
+        listeners.retain(|(id, _)| *id != listener_id);
+        listeners.len() != before
+    }
+
+    // Quests and achievements hear every event first, then listeners in the order they
+    // subscribed. Returns what they have to say to the user who caused the event. Events
+    // published from inside a listener only reach quests and achievements.
+    pub(crate) fn publish(&mut self, event: GameEvent) -> Vec<String> {
+        self.log_event(&event);
+        let mut messages = self.advance_quests_for(&event);
+        messages.extend(self.check_achievements_for(&event));
+        let mut listeners = std::mem::take(&mut self.event_bus.listeners);
+        for (_, listener) in listeners.iter_mut() {
+            messages.extend(listener(self, &event));
+        }
+        // Keep anything subscribed while the listeners ran.
+        listeners.append(&mut self.event_bus.listeners);
+        self.event_bus.listeners = listeners;
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+
+    fn make_event_world() -> GameState {
+        GameStateBuilder::new()
+            .room("square", "A market square.")
+            .room("well", "An old well.")
+            .path("square", "well", Direction::East)
+            .user("alice", "square")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn listeners_hear_published_events() {
+        let mut game_state = make_event_world();
+        let alice = UserName::new("alice").unwrap();
+        let heard = Arc::new(Mutex::new(vec![]));
+        let log = heard.clone();
+        let id = game_state.subscribe(move |_, event| {
+            log.lock().unwrap().push(event.clone());
+            vec![]
+        });
+
+        game_state.process_input_impl(&alice, "east").unwrap();
+        game_state.process_input_impl(&alice, "say hello").unwrap();
+        assert_eq!(
+            *heard.lock().unwrap(),
+            vec![
+                GameEvent::Moved {
+                    user_name: alice,
+                    from: RoomName::new("square").unwrap(),
+                    to: RoomName::new("well").unwrap(),
+                    path_name: PathName::new("east").unwrap(),
+                },
+                GameEvent::Spoke {
+                    user_name: alice,
+                    text: "hello".to_string(),
+                },
+            ]
+        );
+
+        assert!(game_state.unsubscribe(id));
+        assert!(!game_state.unsubscribe(id));
+        game_state.process_input_impl(&alice, "west").unwrap();
+        assert_eq!(heard.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn listener_messages_go_to_the_acting_user() {
+        let mut game_state = make_event_world();
+        let alice = UserName::new("alice").unwrap();
+        game_state.subscribe(|gs, event| match event {
+            GameEvent::Moved { user_name, to, .. } if to == "well" => {
+                gs.users
+                    .get_user_mut(user_name)
+                    .unwrap()
+                    .basic_attributes
+                    .hp -= 1;
+                vec!["The well's chill bites.".to_string()]
+            }
+            _ => vec![],
+        });
+
+        let succ = game_state.process_input_impl(&alice, "east").unwrap();
+        assert!(succ
+            .messages()
+            .contains(&"The well's chill bites.".to_string()));
+        assert_eq!(
+            game_state
+                .users
+                .get_user(&alice)
+                .unwrap()
+                .basic_attributes
+                .hp,
+            19
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::type_aliases::RoomName;
+use crate::GameState;
+
+bitflags! {
+    // In world files and saves these are written like "SAFE | DARK".
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct RoomFlags: u8 {
+        // Nobody can start a fight here, and fights that end up here stop.
+        const SAFE = 1;
+        // The description and paths can't be seen.
+        const DARK = 1 << 1;
+        // NPCs can't walk in.
+        const NO_MOB = 1 << 2;
+        const INDOORS = 1 << 3;
+    }
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct PathFlags: u8 {
+        // Usable, but left out when the room's paths are listed until a user searches it out.
+        const HIDDEN = 1;
+        // There's no way back. Set for paths added without a reverse.
+        const ONE_WAY = 1 << 1;
+        // Nobody can go this way until it's reopened.
+        const CLOSED = 1 << 2;
+    }
+}
+
+impl GameState {
+    pub fn set_room_flags(
+        &mut self,
+        room_name: &RoomName,
+        flags: RoomFlags,
+    ) -> Result<(), FaerieError> {
+        self.rooms.get_room_mut(room_name)?.flags = flags;
+        Ok(())
+    }
+
+    pub fn room_flags(&self, room_name: &RoomName) -> Result<RoomFlags, FaerieError> {
+        Ok(self.rooms.get_room(room_name)?.flags)
+    }
+
+    pub fn set_path_flags(
+        &mut self,
+        room_name: &RoomName,
+        path_name: &str,
+        flags: PathFlags,
+    ) -> Result<(), FaerieError> {
+        self.get_path_mut(room_name, path_name)?.flags = flags;
+        Ok(())
+    }
+
+    pub(crate) fn room_has_flag(&self, room_name: &RoomName, flag: RoomFlags) -> bool {
+        self.rooms
+            .get_room(room_name)
+            .is_ok_and(|room| room.flags.contains(flag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::lambda::FailReason;
+    use crate::room::Direction;
+    use crate::type_aliases::UserName;
+    use crate::user::UserType;
+
+    fn make_flagged_world() -> GameState {
+        GameStateBuilder::new()
+            .room("inn", "A cosy inn.")
+            .room("cellar", "Barrels.")
+            .path("inn", "cellar", Direction::South)
+            .path(
+                "inn",
+                "cellar",
+                Direction::CustomOneWay("chute".to_string()),
+            )
+            .user_of_type("olaf", "inn", UserType::Viking)
+            .user_of_type("pat", "inn", UserType::Viking)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn room_flags_affect_combat_and_sight() {
+        let mut game_state = make_flagged_world();
+        let inn = RoomName::new("inn").unwrap();
+        let olaf = UserName::new("olaf").unwrap();
+        game_state
+            .set_room_flags(&inn, RoomFlags::SAFE | RoomFlags::DARK)
+            .unwrap();
+
+        let failure = game_state
+            .process_input_impl(&olaf, "attack pat")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::SafeRoom);
+        assert_eq!(
+            game_state.describe_room(&olaf).unwrap(),
+            vec!["inn", "  It's too dark to see."]
+        );
+    }
+
+    #[test]
+    fn path_flags_affect_movement_and_listing() {
+        let mut game_state = make_flagged_world();
+        let inn = RoomName::new("inn").unwrap();
+        let olaf = UserName::new("olaf").unwrap();
+        let inn_room = game_state.rooms.get_room(&inn).unwrap();
+        assert_eq!(
+            inn_room.get_path("chute").unwrap().flags,
+            PathFlags::ONE_WAY
+        );
+        assert_eq!(
+            inn_room.get_path("south").unwrap().flags,
+            PathFlags::empty()
+        );
+
+        game_state
+            .set_path_flags(&inn, "chute", PathFlags::ONE_WAY | PathFlags::HIDDEN)
+            .unwrap();
+        game_state
+            .set_path_flags(&inn, "south", PathFlags::CLOSED)
+            .unwrap();
+        let description = game_state.describe_room(&olaf).unwrap();
+        assert_eq!(description.last().unwrap(), "* south");
+
+        let failure = game_state.process_input_impl(&olaf, "south").unwrap_err();
+        assert_eq!(failure.reason, FailReason::PathClosed);
+        game_state.process_input_impl(&olaf, "chute").unwrap();
+        assert_eq!(game_state.get_user_location(&olaf).unwrap(), "cellar");
+    }
+
+    #[test]
+    fn npcs_keep_out_of_no_mob_rooms() {
+        let mut game_state = make_flagged_world();
+        let cellar = RoomName::new("cellar").unwrap();
+        let pat = UserName::new("pat").unwrap();
+        game_state
+            .set_room_flags(&cellar, RoomFlags::NO_MOB)
+            .unwrap();
+        game_state.users.get_user_mut(&pat).unwrap().npc = true;
+
+        let failure = game_state.process_input_impl(&pat, "south").unwrap_err();
+        assert_eq!(failure.reason, FailReason::ExitBlocked);
+        game_state
+            .process_input_impl(&UserName::new("olaf").unwrap(), "south")
+            .unwrap();
+    }
+}
@@ -0,0 +1,199 @@
+use crate::error::FaerieError;
+use crate::grammar::with_article;
+use crate::lambda::{ActionEvent, ActionFailure, ActionSuccess};
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+// Lets a frontend theme the text the engine produces. Every method defaults to plain output.
+pub trait OutputFormatter: Send + Sync {
+    fn format_room_name(&self, room_name: &str) -> String {
+        room_name.to_string()
+    }
+
+    fn format_description(&self, description: &str) -> String {
+        description.to_string()
+    }
+
+    fn format_exit(&self, path_name: &str) -> String {
+        path_name.to_string()
+    }
+
+    fn format_damage(&self, amount: i32) -> String {
+        amount.to_string()
+    }
+
+    fn format_error(&self, text: &str) -> String {
+        text.to_string()
+    }
+
+    fn format_announcement(&self, text: &str) -> String {
+        format!("[Announcement] {}", text)
+    }
+}
+
+pub struct PlainFormatter;
+
+impl OutputFormatter for PlainFormatter {}
+
+pub(crate) fn default_formatter() -> Box<dyn OutputFormatter> {
+    Box::new(PlainFormatter)
+}
+
+pub struct AnsiFormatter;
+
+impl OutputFormatter for AnsiFormatter {
+    fn format_room_name(&self, room_name: &str) -> String {
+        format!("\x1b[1m{}\x1b[0m", room_name)
+    }
+
+    fn format_exit(&self, path_name: &str) -> String {
+        format!("\x1b[36m{}\x1b[0m", path_name)
+    }
+
+    fn format_damage(&self, amount: i32) -> String {
+        format!("\x1b[31m{}\x1b[0m", amount)
+    }
+
+    fn format_error(&self, text: &str) -> String {
+        format!("\x1b[33m{}\x1b[0m", text)
+    }
+
+    fn format_announcement(&self, text: &str) -> String {
+        format!("\x1b[1;35m[Announcement] {}\x1b[0m", text)
+    }
+}
+
+impl GameState {
+    // Users who turned color off get plain text whatever formatter the game is using.
+    pub(crate) fn formatter_for(&self, user_name: &UserName) -> &dyn OutputFormatter {
+        match self.users.get_user(user_name) {
+            Ok(user) if !user.color => &PlainFormatter,
+            _ => self.formatter.as_ref(),
+        }
+    }
+
+    pub fn set_color(&mut self, user_name: &UserName, color: bool) -> Result<(), FaerieError> {
+        self.users.get_user_mut(user_name)?.color = color;
+        Ok(())
+    }
+
+    // `color` on its own flips the setting.
+    pub(crate) fn toggle_color(
+        &mut self,
+        user_name: &UserName,
+        color: Option<bool>,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let user = self.users.get_user_mut(user_name)?;
+        user.color = color.unwrap_or(!user.color);
+        let id = if user.color { "color_on" } else { "color_off" };
+        Ok(ActionSuccess::new(vec![self.text(user_name, id, &[])]))
+    }
+
+    // The text a user sees for the events of one of their actions. Moving shows the room
+    // moved into.
+    pub fn render_events(&self, user_name: &UserName, events: &[ActionEvent]) -> Vec<String> {
+        let fmt = self.formatter_for(user_name);
+        let mut lines = vec![];
+        for event in events {
+            match event {
+                ActionEvent::Message { text } => lines.push(text.clone()),
+                ActionEvent::RoomMoved { to, .. } => match self.describe_room_for(user_name, to) {
+                    Ok(description) => lines.extend(description),
+                    Err(e) => lines.push(e.to_string()),
+                },
+                ActionEvent::Damaged { amount } => {
+                    let damage = fmt.format_damage(*amount);
+                    lines.push(self.text(user_name, "you_take_damage", &[("damage", &damage)]))
+                }
+                ActionEvent::Healed { amount } => {
+                    let hp = amount.to_string();
+                    lines.push(self.text(user_name, "you_recover", &[("hp", &hp)]))
+                }
+                ActionEvent::ItemGained { item_name } => {
+                    let item = with_article(item_name);
+                    lines.push(self.text(user_name, "you_now_have", &[("item", &item)]))
+                }
+            }
+        }
+        self.wrap_for(user_name, lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::format::AnsiFormatter;
+    use crate::lambda::ActionEvent;
+    use crate::room::Direction;
+    use crate::type_aliases::{RoomName, UserName};
+    use crate::user::UserType;
+    use crate::GameState;
+
+    #[test]
+    fn color_can_be_turned_off_per_user() {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .room("garden", "Roses.")
+            .path("hall", "garden", Direction::North)
+            .user("alice", "hall")
+            .user("bob", "hall")
+            .build()
+            .unwrap();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        game_state.set_formatter(Box::new(AnsiFormatter));
+
+        let succ = game_state.process_input_impl(&bob, "color off").unwrap();
+        assert_eq!(succ.messages(), vec!["Color is now off."]);
+        assert_eq!(game_state.describe_room(&bob).unwrap()[0], "hall");
+        assert_eq!(
+            game_state.describe_room(&alice).unwrap()[0],
+            "\x1b[1mhall\x1b[0m"
+        );
+
+        let succ = game_state.process_input_impl(&bob, "color").unwrap();
+        assert_eq!(succ.messages(), vec!["Color is now on."]);
+        assert_eq!(
+            game_state.describe_room(&bob).unwrap()[4],
+            "* \x1b[36mnorth\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn events_are_rendered_as_text() {
+        let mut game_state = GameState::new();
+        let hall = RoomName::new("hall").unwrap();
+        let pit = RoomName::new("pit").unwrap();
+        game_state
+            .create_room(&hall, "A long hall.".to_string())
+            .unwrap();
+        game_state.create_room(&pit, "Spikes.".to_string()).unwrap();
+        game_state
+            .add_painful_path(&hall, &pit, Direction::South)
+            .unwrap();
+        let alice = UserName::new("alice").unwrap();
+        game_state
+            .create_user_in_room(&alice, &hall, UserType::Civilian)
+            .unwrap();
+
+        let mut succ = game_state.process_input_impl(&alice, "south").unwrap();
+        assert_eq!(
+            succ.events[1..],
+            [
+                ActionEvent::Damaged { amount: 1 },
+                ActionEvent::RoomMoved {
+                    from: hall,
+                    to: pit,
+                },
+            ]
+        );
+        succ.push(ActionEvent::ItemGained {
+            item_name: "apple".to_string(),
+        });
+        let lines = game_state.render_events(&alice, &succ.events);
+        assert_eq!(lines[0], "You passed through, but it hurt you.");
+        assert_eq!(lines[1], "You take 1 damage.");
+        assert_eq!(lines[2], "pit");
+        assert_eq!(lines.last().unwrap(), "You now have an apple.");
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::BTreeSet;
+
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+// Each user keeps their own lists. Friends hear when you log in or out; whispers and channel
+// chat from anyone you ignore never reach you, and they aren't told.
+impl GameState {
+    pub fn list_friends(&self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let user = self.users.get_user(user_name)?;
+        Ok(ActionSuccess::new(vec![self.list_names(
+            user_name,
+            "friends_list",
+            &user.friends,
+        )]))
+    }
+
+    pub fn add_friend(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let target_name = self.friend_target(user_name, target_name)?;
+        let user = self.users.get_user_mut(user_name)?;
+        if !user.friends.insert(target_name) {
+            return Err(friend_failure(self.text(
+                user_name,
+                "already_friend",
+                &[("name", &target_name)],
+            )));
+        }
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_befriend",
+            &[("name", &target_name)],
+        )]))
+    }
+
+    pub fn remove_friend(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let user = self.users.get_user_mut(user_name)?;
+        let removed = UserName::lookup(target_name).is_some_and(|name| user.friends.remove(&name));
+        if !removed {
+            return Err(friend_failure(self.text(
+                user_name,
+                "not_friend",
+                &[("name", target_name)],
+            )));
+        }
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_unfriend",
+            &[("name", target_name)],
+        )]))
+    }
+
+    pub fn list_ignored(&self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let user = self.users.get_user(user_name)?;
+        Ok(ActionSuccess::new(vec![self.list_names(
+            user_name,
+            "ignoring_list",
+            &user.ignored,
+        )]))
+    }
+
+    pub fn ignore(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let target_name = self.friend_target(user_name, target_name)?;
+        let user = self.users.get_user_mut(user_name)?;
+        if !user.ignored.insert(target_name) {
+            return Err(friend_failure(self.text(
+                user_name,
+                "already_ignoring",
+                &[("name", &target_name)],
+            )));
+        }
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_ignore",
+            &[("name", &target_name)],
+        )]))
+    }
+
+    pub fn unignore(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let user = self.users.get_user_mut(user_name)?;
+        let removed = UserName::lookup(target_name).is_some_and(|name| user.ignored.remove(&name));
+        if !removed {
+            return Err(friend_failure(self.text(
+                user_name,
+                "not_ignoring",
+                &[("name", target_name)],
+            )));
+        }
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_unignore",
+            &[("name", target_name)],
+        )]))
+    }
+
+    pub fn is_ignoring(&self, listener_name: &str, speaker_name: &str) -> bool {
+        self.users
+            .get_user(listener_name)
+            .map(|listener| {
+                UserName::lookup(speaker_name).is_some_and(|name| listener.ignored.contains(&name))
+            })
+            .unwrap_or(false)
+    }
+
+    // The host calls these as users come and go.
+    pub fn notify_login(&mut self, user_name: &UserName) {
+        self.notify_friends(user_name, "logged_in");
+    }
+
+    pub fn notify_logout(&mut self, user_name: &UserName) {
+        self.notify_friends(user_name, "logged_out");
+    }
+
+    fn notify_friends(&mut self, user_name: &UserName, id: &str) {
+        let mut listeners: Vec<UserName> = self
+            .users
+            .users
+            .values()
+            .filter(|u| u.friends.contains(user_name))
+            .map(|u| u.name)
+            .collect();
+        listeners.sort();
+        for listener in listeners {
+            let text = self.text(&listener, id, &[("name", user_name)]);
+            self.output.send(&listener, &text);
+        }
+    }
+
+    // Anyone but the user themselves, as long as they exist.
+    fn friend_target(
+        &self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<UserName, ActionFailure> {
+        match self.users.get_user(target_name) {
+            Ok(target) if &target.name != user_name => Ok(target.name),
+            Ok(_) => Err(friend_failure(self.text(user_name, "thats_you", &[]))),
+            Err(_) => Err(friend_failure(self.text(
+                user_name,
+                "nobody_called",
+                &[("name", target_name)],
+            ))),
+        }
+    }
+
+    fn list_names(&self, user_name: &UserName, id: &str, names: &BTreeSet<UserName>) -> String {
+        let names = if names.is_empty() {
+            self.text(user_name, "nobody", &[])
+        } else {
+            let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+            names.join(", ")
+        };
+        self.text(user_name, id, &[("names", &names)])
+    }
+}
+
+fn friend_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::testing::TestWorld;
+
+    fn make_tavern() -> TestWorld {
+        TestWorld::build(
+            GameStateBuilder::new()
+                .room("tavern", "Sticky tables.")
+                .user("alice", "tavern")
+                .user("bob", "tavern")
+                .user("carol", "tavern"),
+        )
+    }
+
+    #[test]
+    fn friends_hear_about_logins() {
+        let mut world = make_tavern();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "friend add bob")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You add bob to your friends."]);
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "friend add alice")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["That's you!"]);
+
+        world.game_state.notify_login(&bob);
+        world.game_state.notify_logout(&bob);
+        assert_eq!(
+            world.messages_for("alice"),
+            vec!["bob has logged in.", "bob has logged out."]
+        );
+        assert!(world.messages_for("carol").is_empty());
+
+        world
+            .game_state
+            .process_input_impl(&alice, "friend remove bob")
+            .unwrap();
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "friend")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["Your friends: nobody."]);
+    }
+
+    #[test]
+    fn ignored_users_go_unheard() {
+        let mut world = make_tavern();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        world
+            .game_state
+            .process_input_impl(&alice, "ignore add bob")
+            .unwrap();
+        world
+            .game_state
+            .process_input_impl(&alice, "chat join ooc")
+            .unwrap();
+        world
+            .game_state
+            .process_input_impl(&bob, "chat join ooc")
+            .unwrap();
+
+        let succ = world
+            .game_state
+            .process_input_impl(&bob, "whisper alice psst")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You whisper to alice, \"psst\""]);
+        world
+            .game_state
+            .process_input_impl(&bob, "chat ooc hi")
+            .unwrap();
+        assert!(world.messages_for("alice").is_empty());
+
+        world
+            .game_state
+            .process_input_impl(&alice, "ignore remove bob")
+            .unwrap();
+        world
+            .game_state
+            .process_input_impl(&bob, "chat ooc hi")
+            .unwrap();
+        assert_eq!(world.messages_for("alice"), vec!["[ooc] bob: hi"]);
+    }
+
+    #[test]
+    fn ignored_users_go_unheard_in_the_room_and_beyond() {
+        let mut world = make_tavern();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        world
+            .game_state
+            .process_input_impl(&alice, "ignore add bob")
+            .unwrap();
+
+        for input in &["say hi", "shout HI", "emote waves", "wave", "wave alice"] {
+            world.game_state.process_input_impl(&bob, input).unwrap();
+        }
+        assert!(world.messages_for("alice").is_empty());
+        assert_eq!(
+            world.messages_for("carol"),
+            vec![
+                "bob says, \"hi\"",
+                "bob shouts, \"HI\"",
+                "bob waves",
+                "bob waves.",
+                "bob waves at alice.",
+            ]
+        );
+    }
+
+    #[test]
+    fn ignoring_is_refused_for_yourself_and_strangers_and_ignores_case() {
+        let mut world = make_tavern();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "ignore add alice")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["That's you!"]);
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "ignore add zed")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["There's nobody called zed."]);
+
+        world
+            .game_state
+            .process_input_impl(&alice, "ignore add BOB")
+            .unwrap();
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "ignore add bob")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["You're already ignoring bob."]);
+        assert!(world.game_state.is_ignoring("alice", "bob"));
+        assert!(!world.game_state.is_ignoring("bob", "alice"));
+
+        // Ignoring only silences the speaker for the one who ignores them.
+        world.take_messages();
+        for input in &["say hi", "shout hi", "wave", "emote grins"] {
+            world.game_state.process_input_impl(&bob, input).unwrap();
+        }
+        assert!(world.messages_for("alice").is_empty());
+        assert_eq!(world.messages_for("carol").len(), 4);
+    }
+}
@@ -0,0 +1,98 @@
+pub fn article_for(noun: &str) -> &'static str {
+    match noun.chars().next() {
+        Some(c) if "aeiouAEIOU".contains(c) => "an",
+        _ => "a",
+    }
+}
+
+pub fn with_article(noun: &str) -> String {
+    format!("{} {}", article_for(noun), noun)
+}
+
+pub fn pluralize(noun: &str) -> String {
+    let lower = noun.to_lowercase();
+    let ends_with_consonant_y = lower.ends_with('y')
+        && !lower
+            .chars()
+            .rev()
+            .nth(1)
+            .is_some_and(|c| "aeiou".contains(c));
+
+    if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|end| lower.ends_with(end))
+    {
+        format!("{}es", noun)
+    } else if ends_with_consonant_y {
+        format!("{}ies", &noun[..noun.len() - 1])
+    } else {
+        format!("{}s", noun)
+    }
+}
+
+// Use this when the plural isn't regular, e.g. count_noun_with_plural(3, "mouse", "mice").
+pub fn count_noun_with_plural(count: usize, singular: &str, plural: &str) -> String {
+    if count == 1 {
+        format!("1 {}", singular)
+    } else {
+        format!("{} {}", count, plural)
+    }
+}
+
+pub fn count_noun(count: usize, noun: &str) -> String {
+    count_noun_with_plural(count, noun, &pluralize(noun))
+}
+
+// "a", "a and b", "a, b and c".
+pub fn join_and(words: &[String]) -> String {
+    join_with(words, "and")
+}
+
+// "a", "a or b", "a, b or c".
+pub fn join_or(words: &[String]) -> String {
+    join_with(words, "or")
+}
+
+fn join_with(words: &[String], conjunction: &str) -> String {
+    match words {
+        [] => String::new(),
+        [word] => word.clone(),
+        [init @ .., last] => format!("{} {} {}", init.join(", "), conjunction, last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn articles_follow_initial_vowel() {
+        assert_eq!(with_article("apple"), "an apple");
+        assert_eq!(with_article("banana"), "a banana");
+        assert_eq!(with_article("Emerald"), "an Emerald");
+    }
+
+    #[test]
+    fn counts_use_singular_or_plural() {
+        assert_eq!(count_noun(3, "apple"), "3 apples");
+        assert_eq!(count_noun(1, "coin"), "1 coin");
+        assert_eq!(count_noun(0, "coin"), "0 coins");
+    }
+
+    #[test]
+    fn pluralize_handles_common_endings() {
+        assert_eq!(pluralize("box"), "boxes");
+        assert_eq!(pluralize("torch"), "torches");
+        assert_eq!(pluralize("berry"), "berries");
+        assert_eq!(pluralize("key"), "keys");
+        assert_eq!(count_noun_with_plural(2, "mouse", "mice"), "2 mice");
+    }
+
+    #[test]
+    fn lists_are_joined_with_and() {
+        let words: Vec<String> = vec!["a".into(), "b".into(), "c".into()];
+        assert_eq!(join_and(&words[..1]), "a");
+        assert_eq!(join_and(&words[..2]), "a and b");
+        assert_eq!(join_and(&words), "a, b and c");
+    }
+}
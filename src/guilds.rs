@@ -0,0 +1,749 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+pub const MAX_GUILD_NAME_LEN: usize = 30;
+
+// Members can chat and deposit gold, officers can also invite, kick members and withdraw,
+// and the one leader can promote and demote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GuildRank {
+    Member,
+    Officer,
+    Leader,
+}
+
+impl GuildRank {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            GuildRank::Member => "member",
+            GuildRank::Officer => "officer",
+            GuildRank::Leader => "leader",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Guild {
+    pub name: String,
+    pub members: BTreeMap<UserName, GuildRank>,
+    #[serde(default)]
+    pub treasury: i32,
+    // Users who've been invited and haven't joined yet.
+    #[serde(default)]
+    pub invited: Vec<UserName>,
+}
+
+// Guilds are kept by lowercased name, and saved with the world.
+impl GameState {
+    pub fn guild_of(&self, user_name: &UserName) -> Option<&Guild> {
+        self.guilds
+            .values()
+            .find(|guild| guild.members.contains_key(user_name))
+    }
+
+    pub fn show_guild(&self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        self.users.get_user(user_name)?;
+        let guild = match self.guild_of(user_name) {
+            Some(guild) => guild,
+            None => return Err(self.not_in_guild(user_name)),
+        };
+        let treasury = guild.treasury.to_string();
+        let mut messages = vec![
+            self.text(user_name, "guild_name", &[("guild", &guild.name)]),
+            self.text(user_name, "guild_treasury", &[("gold", &treasury)]),
+            self.text(user_name, "guild_members", &[]),
+        ];
+        let mut members: Vec<(&UserName, &GuildRank)> = guild.members.iter().collect();
+        members.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (member, rank) in members {
+            let args = [("name", member.as_str()), ("rank", rank.display_name())];
+            messages.push(self.text(user_name, "guild_member", &args));
+        }
+        Ok(ActionSuccess::new(messages))
+    }
+
+    pub fn create_guild(
+        &mut self,
+        user_name: &UserName,
+        guild_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.users.get_user(user_name)?;
+        if let Some(guild) = self.guild_of(user_name) {
+            let args = [("guild", guild.name.as_str())];
+            return Err(guild_failure(self.text(
+                user_name,
+                "already_in_guild",
+                &args,
+            )));
+        }
+        let guild_name = guild_name.trim();
+        if guild_name.is_empty() || guild_name.chars().count() > MAX_GUILD_NAME_LEN {
+            let max = MAX_GUILD_NAME_LEN.to_string();
+            return Err(guild_failure(self.text(
+                user_name,
+                "guild_name_too_long",
+                &[("max", &max)],
+            )));
+        }
+        let key = guild_name.to_lowercase();
+        if self.guilds.contains_key(&key) {
+            return Err(guild_failure(self.text(
+                user_name,
+                "guild_exists",
+                &[("guild", guild_name)],
+            )));
+        }
+        let guild = Guild {
+            name: guild_name.to_string(),
+            members: BTreeMap::from([(*user_name, GuildRank::Leader)]),
+            treasury: 0,
+            invited: vec![],
+        };
+        self.guilds.insert(key, guild);
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_found_guild",
+            &[("guild", guild_name)],
+        )]))
+    }
+
+    pub fn invite_to_guild(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let key = self.guild_key_with_rank(user_name, GuildRank::Officer)?;
+        let target_name = match self.users.get_user(target_name) {
+            Ok(target) if !target.npc => target.name,
+            _ => {
+                return Err(guild_failure(self.text(
+                    user_name,
+                    "nobody_called",
+                    &[("name", target_name)],
+                )))
+            }
+        };
+        if self.guild_of(&target_name).is_some() {
+            return Err(guild_failure(self.text(
+                user_name,
+                "in_a_guild",
+                &[("name", &target_name)],
+            )));
+        }
+        let guild = self.guild_mut(user_name, &key)?;
+        if !guild.invited.contains(&target_name) {
+            guild.invited.push(target_name);
+        }
+        let guild_name = guild.name.clone();
+        let args = [("name", user_name.as_str()), ("guild", &guild_name)];
+        self.tell(&target_name, "invites_you", &args);
+        let args = [("name", target_name.as_str()), ("guild", &guild_name)];
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_invite",
+            &args,
+        )]))
+    }
+
+    pub fn join_guild(
+        &mut self,
+        user_name: &UserName,
+        guild_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.users.get_user(user_name)?;
+        if let Some(guild) = self.guild_of(user_name) {
+            let args = [("guild", guild.name.as_str())];
+            return Err(guild_failure(self.text(
+                user_name,
+                "already_in_guild",
+                &args,
+            )));
+        }
+        let key = guild_name.trim().to_lowercase();
+        let invited = self
+            .guilds
+            .get(&key)
+            .is_some_and(|guild| guild.invited.contains(user_name));
+        if !invited {
+            return Err(guild_failure(self.text(
+                user_name,
+                "not_invited",
+                &[("guild", guild_name)],
+            )));
+        }
+        let guild = self.guild_mut(user_name, &key)?;
+        guild.invited.retain(|invited| invited != user_name);
+        guild.members.insert(*user_name, GuildRank::Member);
+        let guild_name = guild.name.clone();
+        self.tell_guild(&key, user_name, "joined_guild", &[("name", user_name)]);
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_join",
+            &[("channel", &guild_name)],
+        )]))
+    }
+
+    // The leader can only leave once they're the last member, which disbands the guild and
+    // leaves them whatever was in the treasury.
+    pub fn leave_guild(&mut self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let key = self.guild_key_with_rank(user_name, GuildRank::Member)?;
+        let guild = &self.guilds[&key];
+        let guild_name = guild.name.clone();
+        if guild.members.len() == 1 {
+            let treasury = guild.treasury;
+            // Refused rather than losing whatever the treasury can't hand over.
+            let gold = match self
+                .users
+                .get_user(user_name)?
+                .basic_attributes
+                .gold
+                .checked_add(treasury)
+            {
+                Some(gold) => gold,
+                None => {
+                    return Err(guild_failure(self.text(
+                        user_name,
+                        "you_cannot_hold_gold",
+                        &[],
+                    )))
+                }
+            };
+            self.guilds.remove(&key);
+            let mut messages = vec![self.text(user_name, "you_disband", &[("guild", &guild_name)])];
+            if treasury > 0 {
+                self.users.get_user_mut(user_name)?.basic_attributes.gold = gold;
+                let gold = treasury.to_string();
+                messages.push(self.text(user_name, "you_take_treasury", &[("gold", &gold)]));
+            }
+            return Ok(ActionSuccess::new(messages));
+        }
+        if guild.members[user_name] == GuildRank::Leader {
+            return Err(guild_failure(self.text(
+                user_name,
+                "leader_cannot_leave",
+                &[],
+            )));
+        }
+        self.guild_mut(user_name, &key)?.members.remove(user_name);
+        self.tell_guild(&key, user_name, "left_guild", &[("name", user_name)]);
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_leave_channel",
+            &[("channel", &guild_name)],
+        )]))
+    }
+
+    // Officers can kick members, and the leader can kick anyone.
+    pub fn kick_from_guild(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let key = self.guild_key_with_rank(user_name, GuildRank::Officer)?;
+        let (target_name, _) =
+            self.outranked_member(&key, user_name, target_name, "cannot_kick")?;
+        let guild = self.guild_mut(user_name, &key)?;
+        guild.members.remove(&target_name);
+        let guild_name = guild.name.clone();
+        let args = [("name", user_name.as_str()), ("guild", &guild_name)];
+        self.tell(&target_name, "kicked_from_guild", &args);
+        let args = [("name", target_name.as_str()), ("guild", &guild_name)];
+        self.tell_guild(&key, user_name, "kicked_out_of_guild", &args);
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_kick_from_guild",
+            &args,
+        )]))
+    }
+
+    // Promoting an officer hands them the leadership, and makes the old leader an officer.
+    pub fn promote_in_guild(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let key = self.guild_key_with_rank(user_name, GuildRank::Leader)?;
+        let (target_name, rank) =
+            self.outranked_member(&key, user_name, target_name, "cannot_promote")?;
+        let guild = self.guild_mut(user_name, &key)?;
+        let id = if rank == GuildRank::Officer {
+            guild.members.insert(target_name, GuildRank::Leader);
+            guild.members.insert(*user_name, GuildRank::Officer);
+            "now_guild_leader"
+        } else {
+            guild.members.insert(target_name, GuildRank::Officer);
+            "now_guild_officer"
+        };
+        let guild_name = guild.name.clone();
+        let args = [("name", target_name.as_str()), ("guild", &guild_name)];
+        self.tell_guild(&key, user_name, id, &args);
+        Ok(ActionSuccess::new(vec![self.text(user_name, id, &args)]))
+    }
+
+    pub fn demote_in_guild(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let key = self.guild_key_with_rank(user_name, GuildRank::Leader)?;
+        let (target_name, rank) =
+            self.outranked_member(&key, user_name, target_name, "cannot_demote")?;
+        if rank == GuildRank::Member {
+            return Err(guild_failure(self.text(
+                user_name,
+                "cannot_demote_further",
+                &[("name", &target_name)],
+            )));
+        }
+        let guild = self.guild_mut(user_name, &key)?;
+        guild.members.insert(target_name, GuildRank::Member);
+        let guild_name = guild.name.clone();
+        let args = [("name", target_name.as_str()), ("guild", &guild_name)];
+        self.tell_guild(&key, user_name, "no_longer_officer", &args);
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "no_longer_officer",
+            &args,
+        )]))
+    }
+
+    pub fn deposit_to_guild(
+        &mut self,
+        user_name: &UserName,
+        amount: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let key = self.guild_key_with_rank(user_name, GuildRank::Member)?;
+        let amount = self.parse_amount(user_name, amount)?;
+        let gold = self.users.get_user(user_name)?.basic_attributes.gold;
+        if gold < amount {
+            let gold = gold.to_string();
+            return Err(guild_failure(self.text(
+                user_name,
+                "only_have_gold",
+                &[("gold", &gold)],
+            )));
+        }
+        let treasury = match self
+            .guild_mut(user_name, &key)?
+            .treasury
+            .checked_add(amount)
+        {
+            Some(treasury) => treasury,
+            None => return Err(guild_failure(self.text(user_name, "treasury_full", &[]))),
+        };
+        self.users.get_user_mut(user_name)?.basic_attributes.gold -= amount;
+        self.guild_mut(user_name, &key)?.treasury = treasury;
+        let amount = amount.to_string();
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_deposit",
+            &[("gold", &amount)],
+        )]))
+    }
+
+    pub fn withdraw_from_guild(
+        &mut self,
+        user_name: &UserName,
+        amount: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let key = self.guild_key_with_rank(user_name, GuildRank::Officer)?;
+        let amount = self.parse_amount(user_name, amount)?;
+        let treasury = self.guild_mut(user_name, &key)?.treasury;
+        if treasury < amount {
+            let treasury = treasury.to_string();
+            return Err(guild_failure(self.text(
+                user_name,
+                "treasury_only_has",
+                &[("gold", &treasury)],
+            )));
+        }
+        let gold = match self
+            .users
+            .get_user(user_name)?
+            .basic_attributes
+            .gold
+            .checked_add(amount)
+        {
+            Some(gold) => gold,
+            None => {
+                return Err(guild_failure(self.text(
+                    user_name,
+                    "you_cannot_hold_gold",
+                    &[],
+                )))
+            }
+        };
+        self.guild_mut(user_name, &key)?.treasury -= amount;
+        self.users.get_user_mut(user_name)?.basic_attributes.gold = gold;
+        let amount = amount.to_string();
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_withdraw",
+            &[("gold", &amount)],
+        )]))
+    }
+
+    // `chat guild <text>`, heard by every other member who isn't ignoring the speaker.
+    pub fn guild_chat(
+        &mut self,
+        user_name: &UserName,
+        text: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.refuse_if_muted(user_name)?;
+        let key = self.guild_key_with_rank(user_name, GuildRank::Member)?;
+        let guild_name = self.guilds[&key].name.clone();
+        let listeners: Vec<UserName> = self.guilds[&key]
+            .members
+            .keys()
+            .filter(|member| *member != user_name && !self.is_ignoring(member, user_name))
+            .cloned()
+            .collect();
+        let args = [
+            ("channel", guild_name.as_str()),
+            ("name", user_name),
+            ("text", text),
+        ];
+        for listener in listeners {
+            let heard = self.text(&listener, "channel_says", &args);
+            self.output.send(&listener, &heard);
+        }
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_say_on_channel",
+            &args,
+        )]))
+    }
+
+    fn guild_mut(&mut self, user_name: &UserName, key: &str) -> Result<&mut Guild, ActionFailure> {
+        let not_in_guild = self.not_in_guild(user_name);
+        self.guilds.get_mut(key).ok_or(not_in_guild)
+    }
+
+    fn tell_guild(
+        &mut self,
+        key: &str,
+        except_user_name: &UserName,
+        id: &str,
+        args: &[(&str, &str)],
+    ) {
+        let listeners: Vec<UserName> = match self.guilds.get(key) {
+            Some(guild) => guild
+                .members
+                .keys()
+                .filter(|member| *member != except_user_name)
+                .cloned()
+                .collect(),
+            None => return,
+        };
+        for listener in listeners {
+            self.tell(&listener, id, args);
+        }
+    }
+
+    // The key of the user's guild, as long as they're at least the given rank in it.
+    fn guild_key_with_rank(
+        &self,
+        user_name: &UserName,
+        min_rank: GuildRank,
+    ) -> Result<String, ActionFailure> {
+        self.users.get_user(user_name)?;
+        let guild = match self.guild_of(user_name) {
+            Some(guild) => guild,
+            None => return Err(self.not_in_guild(user_name)),
+        };
+        if guild.members[user_name] < min_rank {
+            return Err(ActionFailure::new(
+                vec![self.text(
+                    user_name,
+                    "guild_rank_needed",
+                    &[("rank", min_rank.display_name())],
+                )],
+                FailReason::PermissionDenied,
+            ));
+        }
+        Ok(guild.name.to_lowercase())
+    }
+
+    // `refusal` is the message for a member who isn't outranked.
+    fn outranked_member(
+        &self,
+        key: &str,
+        user_name: &UserName,
+        target_name: &str,
+        refusal: &str,
+    ) -> Result<(UserName, GuildRank), ActionFailure> {
+        let guild = &self.guilds[key];
+        let (target_name, rank) = match guild
+            .members
+            .iter()
+            .find(|(member, _)| member.as_str() == target_name)
+        {
+            Some((member, rank)) => (*member, *rank),
+            None => {
+                let args = [("name", target_name), ("guild", &guild.name)];
+                return Err(guild_failure(self.text(
+                    user_name,
+                    "not_in_that_guild",
+                    &args,
+                )));
+            }
+        };
+        if rank >= guild.members[user_name] {
+            return Err(guild_failure(self.text(
+                user_name,
+                refusal,
+                &[("name", &target_name)],
+            )));
+        }
+        Ok((target_name, rank))
+    }
+
+    fn parse_amount(&self, user_name: &UserName, amount: &str) -> Result<i32, ActionFailure> {
+        match amount.trim().parse::<i32>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(guild_failure(self.text(
+                user_name,
+                "not_an_amount",
+                &[("amount", amount)],
+            ))),
+        }
+    }
+
+    fn not_in_guild(&self, user_name: &UserName) -> ActionFailure {
+        guild_failure(self.text(user_name, "not_in_guild", &[]))
+    }
+}
+
+fn guild_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+    use crate::testing::TestWorld;
+
+    // alice, bob and carol in the hall, dave out in the yard.
+    fn make_guild_hall() -> TestWorld {
+        TestWorld::build(
+            GameStateBuilder::new()
+                .room("hall", "Banners.")
+                .room("yard", "Mud.")
+                .path("hall", "yard", Direction::South)
+                .user("alice", "hall")
+                .user("bob", "hall")
+                .user("carol", "hall")
+                .user("dave", "yard"),
+        )
+    }
+
+    #[test]
+    fn guilds_invite_chat_and_share_gold() {
+        let mut world = make_guild_hall();
+        let alice = UserName::new("alice").unwrap();
+        let dave = UserName::new("dave").unwrap();
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "guild create The Foxes")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You found the guild The Foxes."]);
+        let failure = world
+            .game_state
+            .process_input_impl(&dave, "guild join the foxes")
+            .unwrap_err();
+        assert_eq!(
+            failure.messages,
+            vec!["You haven't been invited to the foxes."]
+        );
+
+        world
+            .game_state
+            .process_input_impl(&alice, "guild invite dave")
+            .unwrap();
+        assert_eq!(
+            world.messages_for("dave"),
+            vec!["alice invites you to join The Foxes. Type `guild join The Foxes` to accept."]
+        );
+        world
+            .game_state
+            .process_input_impl(&dave, "guild join the foxes")
+            .unwrap();
+        assert_eq!(
+            world.messages_for("alice"),
+            vec!["dave has joined the guild."]
+        );
+
+        let succ = world
+            .game_state
+            .process_input_impl(&dave, "chat guild Hello!")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["[The Foxes] You: Hello!"]);
+        assert_eq!(
+            world.messages_for("alice"),
+            vec!["[The Foxes] dave: Hello!"]
+        );
+        assert!(world.messages_for("bob").is_empty());
+
+        world.game_state.give_gold(&dave, 10).unwrap();
+        world
+            .game_state
+            .process_input_impl(&dave, "guild deposit 10")
+            .unwrap();
+        let failure = world
+            .game_state
+            .process_input_impl(&dave, "guild withdraw 5")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::PermissionDenied);
+        let succ = world.game_state.process_input_impl(&dave, "guild").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "Guild: The Foxes",
+                "Treasury: 10 gold",
+                "Members:",
+                "  alice (leader)",
+                "  dave (member)",
+            ]
+        );
+    }
+
+    #[test]
+    fn guild_ranks_limit_kicks_and_leaving() {
+        let mut world = make_guild_hall();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        let carol = UserName::new("carol").unwrap();
+        world.game_state.create_guild(&alice, "Owls").unwrap();
+        for user_name in &[&bob, &carol] {
+            world.game_state.invite_to_guild(&alice, user_name).unwrap();
+            world.game_state.join_guild(user_name, "owls").unwrap();
+        }
+        world
+            .game_state
+            .process_input_impl(&alice, "guild promote bob")
+            .unwrap();
+        let failure = world
+            .game_state
+            .process_input_impl(&bob, "guild kick alice")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["You can't kick alice."]);
+        world.take_messages();
+        world
+            .game_state
+            .process_input_impl(&bob, "guild kick carol")
+            .unwrap();
+        assert_eq!(
+            world.messages_for("carol"),
+            vec!["bob has kicked you out of Owls."]
+        );
+        assert!(world.game_state.guild_of(&carol).is_none());
+
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "guild leave")
+            .unwrap_err();
+        assert_eq!(
+            failure.messages,
+            vec!["You'll have to make someone else leader first."]
+        );
+        world
+            .game_state
+            .process_input_impl(&alice, "guild promote bob")
+            .unwrap();
+        world
+            .game_state
+            .process_input_impl(&alice, "guild leave")
+            .unwrap();
+        let succ = world
+            .game_state
+            .process_input_impl(&bob, "guild leave")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You leave Owls, and it's disbanded."]);
+        assert!(world.game_state.guilds.is_empty());
+    }
+
+    #[test]
+    fn treasury_gold_never_overflows_or_goes_missing() {
+        let mut world = make_guild_hall();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        let carol = UserName::new("carol").unwrap();
+        for input in &["guild create The Foxes", "guild invite bob"] {
+            world.game_state.process_input_impl(&alice, input).unwrap();
+        }
+        world
+            .game_state
+            .process_input_impl(&bob, "guild join the foxes")
+            .unwrap();
+        world.game_state.give_gold(&alice, i32::MAX).unwrap();
+        world.game_state.give_gold(&bob, 1).unwrap();
+        world
+            .game_state
+            .process_input_impl(&alice, &format!("guild deposit {}", i32::MAX))
+            .unwrap();
+
+        let failure = world
+            .game_state
+            .process_input_impl(&bob, "guild deposit 1")
+            .unwrap_err();
+        assert_eq!(
+            failure.messages,
+            vec!["The treasury can't hold that much more gold."]
+        );
+        let gold_of = |world: &TestWorld, user_name: &UserName| {
+            world
+                .game_state
+                .users
+                .get_user(user_name)
+                .unwrap()
+                .basic_attributes
+                .gold
+        };
+        assert_eq!(gold_of(&world, &bob), 1);
+
+        world.game_state.give_gold(&alice, 1).unwrap();
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, &format!("guild withdraw {}", i32::MAX))
+            .unwrap_err();
+        assert_eq!(
+            failure.messages,
+            vec!["You can't hold that much more gold."]
+        );
+        assert_eq!(gold_of(&world, &alice), 1);
+
+        // A last member who can't take the treasury doesn't disband and lose it.
+        world
+            .game_state
+            .process_input_impl(&carol, "guild create The Hermits")
+            .unwrap();
+        world.game_state.give_gold(&carol, 5).unwrap();
+        world
+            .game_state
+            .process_input_impl(&carol, "guild deposit 5")
+            .unwrap();
+        world.game_state.give_gold(&carol, i32::MAX).unwrap();
+        let failure = world
+            .game_state
+            .process_input_impl(&carol, "guild leave")
+            .unwrap_err();
+        assert_eq!(
+            failure.messages,
+            vec!["You can't hold that much more gold."]
+        );
+        let succ = world
+            .game_state
+            .process_input_impl(&carol, "guild")
+            .unwrap();
+        assert!(succ.messages().contains(&"Treasury: 5 gold".to_string()));
+    }
+}
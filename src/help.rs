@@ -0,0 +1,250 @@
+use std::collections::BTreeMap;
+
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::{name_key, UserName};
+use crate::user::Role;
+use crate::GameState;
+
+// What `help` says about a command. Users only see help for commands their role allows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandHelp {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub syntax: String,
+    pub description: String,
+    pub min_role: Role,
+}
+
+impl CommandHelp {
+    pub fn new(name: &str, syntax: &str, description: &str) -> CommandHelp {
+        CommandHelp {
+            name: name.to_lowercase(),
+            aliases: vec![],
+            syntax: syntax.to_string(),
+            description: description.to_string(),
+            min_role: Role::Player,
+        }
+    }
+
+    pub fn with_aliases(mut self, aliases: &[&str]) -> CommandHelp {
+        self.aliases = aliases.iter().map(|alias| alias.to_lowercase()).collect();
+        self
+    }
+
+    pub fn with_min_role(mut self, min_role: Role) -> CommandHelp {
+        self.min_role = min_role;
+        self
+    }
+}
+
+// Name, aliases, syntax, description and who may use it, for each built-in verb.
+#[rustfmt::skip]
+const BUILTIN_HELP: &[(&str, &[&str], &str, &str, Role)] = &[
+    ("achievements", &[], "achievements", "Lists the achievements you've earned.", Role::Player),
+    ("announce", &[], "announce <text>", "Tells everyone in the world something.", Role::Admin),
+    ("attack", &["kill"], "attack <someone>", "Starts a fight with someone in the room. You keep swinging every tick until one of you falls or leaves.", Role::Player),
+    ("ban", &[], "ban <someone>", "Kicks someone and stops them coming back.", Role::Admin),
+    ("bio", &[], "bio <text>", "Sets what others see when they examine you.", Role::Player),
+    ("buildundo", &[], "buildundo", "Undoes your last change to the world's rooms and paths.", Role::Builder),
+    ("buy", &[], "buy <item>", "Buys something from the shop here.", Role::Player),
+    ("chat", &[], "chat [join <channel> | leave <channel> | <channel> <text>]", "Talks on a chat channel. On its own, lists the channels.", Role::Player),
+    ("chatter", &[], "chatter <someone>", "A civilian's ability: talks at someone until they forget who they were fighting.", Role::Player),
+    ("color", &["colour"], "color on|off", "Turns colored output on or off.", Role::Player),
+    ("confirm", &[], "confirm", "Agrees to the trade as it stands.", Role::Player),
+    ("createroom", &[], "createroom <room>", "Makes a new room with no paths.", Role::Builder),
+    ("delpath", &[], "delpath <exit>", "Removes a way out of this room, and the way back.", Role::Builder),
+    ("describe", &[], "describe <text>", "Sets this room's description.", Role::Builder),
+    ("dig", &[], "dig <direction> <room>", "Makes a path to a room, making the room if needed.", Role::Builder),
+    ("emote", &["me"], "emote <action>", "Shows the room you doing something, like `emote waves.`", Role::Player),
+    ("examine", &["x"], "examine <someone or something>", "Looks closely at someone or something here.", Role::Player),
+    ("exits", &[], "exits", "Lists the ways out of the room.", Role::Player),
+    ("follow", &[], "follow <someone>", "Follows someone wherever they go.", Role::Player),
+    ("friend", &[], "friend [add <someone> | remove <someone>]", "Lists your friends, or adds or removes one. You hear when friends log in and out.", Role::Player),
+    ("go", &[], "go <exit>", "Leaves by the named exit. Typing the exit's name on its own works too.", Role::Player),
+    ("guild", &[], "guild [create|invite|join|leave|kick|promote|demote|deposit|withdraw ...]", "Shows your guild, or runs it.", Role::Player),
+    ("help", &[], "help [<command>]", "Lists commands, or explains one.", Role::Player),
+    ("ignore", &[], "ignore [add <someone> | remove <someone>]", "Lists who you ignore, or adds or removes someone.", Role::Player),
+    ("infuse", &[], "infuse [<someone>]", "An elf lord's ability: heals you or someone here.", Role::Player),
+    ("journal", &[], "journal", "Lists your quests, finished ones too.", Role::Player),
+    ("kick", &[], "kick <someone>", "Takes someone out of the world.", Role::Admin),
+    ("language", &[], "language [<locale>]", "Shows or sets the language you read the game in, if the world has been translated.", Role::Player),
+    ("list", &[], "list", "Lists what the shop here sells.", Role::Player),
+    ("list_users", &[], "list_users", "Lists who's online.", Role::Player),
+    ("lock", &[], "lock <exit or container>", "Locks a door or container, if you have the key.", Role::Player),
+    ("look", &["l"], "look [<exit or someone>]", "Describes the room, what lies in a direction, or someone here.", Role::Player),
+    ("loot", &[], "loot [<corpse>]", "Takes everything from a corpse.", Role::Player),
+    ("mail", &[], "mail [send <someone> <text> | read | delete <number>]", "Sends and reads letters.", Role::Player),
+    ("map", &[], "map", "Draws the rooms around you.", Role::Player),
+    ("mute", &[], "mute <someone>", "Stops someone speaking, or lets them speak again.", Role::Admin),
+    ("offer", &[], "offer <item>", "Adds something to your side of a trade.", Role::Player),
+    ("pagesize", &[], "pagesize [<lines> | off]", "Shows or sets how many lines of a long reply you see before pressing enter for more.", Role::Player),
+    ("party", &[], "party", "Shows who's in your party.", Role::Player),
+    ("pick", &[], "pick <exit>", "Tries to pick the lock on a door.", Role::Player),
+    ("prompt", &[], "prompt [<template> | default]", "Shows or sets your prompt. %hp, %maxhp, %mp, %maxmp, %sp, %maxsp, %gold, %room and %name are filled in.", Role::Player),
+    ("put", &[], "put <item> in <container>", "Puts something in a container.", Role::Player),
+    ("quests", &[], "quests", "Lists the quests you're on.", Role::Player),
+    ("remove", &[], "remove <item>", "Takes off something you're wielding or wearing.", Role::Player),
+    ("rename", &[], "rename <room>", "Renames this room.", Role::Builder),
+    ("rest", &[], "rest", "Rests to get your stamina back.", Role::Player),
+    ("roll", &[], "roll <dice>", "Rolls dice like 2d6+1 for the room to see.", Role::Player),
+    ("say", &[], "say <text>", "Says something to everyone in the room.", Role::Player),
+    ("score", &[], "score", "Shows your stats.", Role::Player),
+    ("search", &[], "search", "Searches the room for hidden exits.", Role::Player),
+    ("sell", &[], "sell <item>", "Sells something to the shop here.", Role::Player),
+    ("shout", &[], "shout <text>", "Says something everyone in the world hears.", Role::Player),
+    ("skills", &[], "skills", "Lists your skills.", Role::Player),
+    ("sneak", &[], "sneak <exit>", "Leaves without being seen, unless someone spots you.", Role::Player),
+    ("swing", &[], "swing <someone>", "A viking's ability: a heavy blow that starts a fight.", Role::Player),
+    ("take", &["get"], "take <item> [from <container>]", "Picks something up, or takes it out of a container.", Role::Player),
+    ("talk", &[], "talk [to] <someone>", "Starts a conversation.", Role::Player),
+    ("teleport", &["goto"], "teleport <room>", "Jumps straight to a room.", Role::Builder),
+    ("title", &[], "title [<title>]", "Shows or sets the title after your name.", Role::Player),
+    ("trade", &[], "trade [<someone> | cancel]", "Starts a trade with someone here, or shows or cancels yours.", Role::Player),
+    ("transfer", &[], "transfer <someone> <room>", "Sends someone else to a room.", Role::Admin),
+    ("travel", &[], "travel <room>", "Walks the shortest way to a room.", Role::Player),
+    ("unban", &[], "unban <someone>", "Lets a banned user back in.", Role::Admin),
+    ("unfollow", &[], "unfollow", "Stops following whoever you're following.", Role::Player),
+    ("unlock", &[], "unlock <exit or container>", "Unlocks a door or container, if you have the key.", Role::Player),
+    ("wear", &[], "wear <item>", "Puts on armor or clothing.", Role::Player),
+    ("where", &[], "where", "Says which zone you're in.", Role::Player),
+    ("whisper", &[], "whisper <someone> <text>", "Says something only they hear.", Role::Player),
+    ("who", &[], "who", "Lists everyone online.", Role::Player),
+    ("width", &[], "width [<columns> | off]", "Shows or sets how wide your screen is. Long lines are wrapped to fit.", Role::Player),
+    ("wield", &[], "wield <item>", "Takes up a weapon.", Role::Player),
+];
+
+pub(crate) fn builtin_help() -> BTreeMap<String, CommandHelp> {
+    BUILTIN_HELP
+        .iter()
+        .map(|(name, aliases, syntax, description, min_role)| {
+            let help = CommandHelp::new(name, syntax, description)
+                .with_aliases(aliases)
+                .with_min_role(*min_role);
+            (help.name.clone(), help)
+        })
+        .collect()
+}
+
+impl GameState {
+    // Adds or replaces the help for a command. Registered commands get a bare entry of their
+    // own, which this can fill in.
+    pub fn set_command_help(&mut self, help: CommandHelp) {
+        self.help.insert(help.name.clone(), help);
+    }
+
+    pub(crate) fn remove_command_help(&mut self, name: &str) {
+        self.help.remove(&name_key(name));
+    }
+
+    // Found by name or alias.
+    pub fn command_help(&self, name: &str) -> Option<&CommandHelp> {
+        let key = name_key(name);
+        self.help
+            .get(&key)
+            .or_else(|| self.help.values().find(|help| help.aliases.contains(&key)))
+    }
+
+    pub fn help(
+        &self,
+        user_name: &UserName,
+        topic: Option<&str>,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let role = self.role(user_name)?;
+        let topic = match topic {
+            Some(topic) => topic,
+            None => {
+                let names: Vec<&str> = self
+                    .help
+                    .values()
+                    .filter(|help| help.min_role <= role)
+                    .map(|help| help.name.as_str())
+                    .collect();
+                let socials: Vec<&str> = self.socials.keys().map(String::as_str).collect();
+                let mut messages = vec![format!("Commands: {}", names.join(", "))];
+                if !socials.is_empty() {
+                    messages.push(format!("Socials: {}", socials.join(", ")));
+                }
+                messages.push(self.text(user_name, "help_footer", &[]));
+                return Ok(ActionSuccess::new(messages));
+            }
+        };
+
+        let help = match self.command_help(topic) {
+            Some(help) if help.min_role <= role => help,
+            _ => {
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "no_help", &[("topic", topic)])],
+                    FailReason::InvalidTarget,
+                ))
+            }
+        };
+        let mut messages = vec![help.syntax.clone()];
+        if !help.description.is_empty() {
+            messages.push(format!("  {}", help.description));
+        }
+        if !help.aliases.is_empty() {
+            messages.push(format!("  Also: {}", help.aliases.join(", ")));
+        }
+        match help.min_role {
+            Role::Player => {}
+            Role::Builder => messages.push("  Builders only.".to_string()),
+            Role::Admin => messages.push("  Admins only.".to_string()),
+        }
+        Ok(ActionSuccess::new(messages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::parser;
+
+    #[test]
+    fn every_builtin_verb_has_help() {
+        let game_state = GameState::new();
+        for verb in parser::builtin_verbs() {
+            assert!(game_state.command_help(verb).is_some(), "{}", verb);
+        }
+    }
+
+    #[test]
+    fn help_pages_follow_roles_and_registration() {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .user("alice", "hall")
+            .build()
+            .unwrap();
+        let alice = UserName::new("alice").unwrap();
+
+        let succ = game_state.process_input_impl(&alice, "help x").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "examine <someone or something>",
+                "  Looks closely at someone or something here.",
+                "  Also: x",
+            ]
+        );
+        let list = game_state.process_input_impl(&alice, "help").unwrap();
+        assert!(!list.messages()[0].contains("ban"));
+        let fail = game_state
+            .process_input_impl(&alice, "help ban")
+            .unwrap_err();
+        assert_eq!(fail.messages, vec!["There's no help on ban."]);
+        game_state.set_role(&alice, Role::Admin).unwrap();
+        let succ = game_state.process_input_impl(&alice, "help ban").unwrap();
+        assert_eq!(succ.messages()[2], "  Admins only.");
+
+        game_state
+            .register_command("dance", |_, _, _| Ok(ActionSuccess::default()))
+            .unwrap();
+        let succ = game_state.process_input_impl(&alice, "help dance").unwrap();
+        assert_eq!(succ.messages(), vec!["dance"]);
+        game_state.set_command_help(CommandHelp::new("dance", "dance", "Dances a jig."));
+        let succ = game_state.process_input_impl(&alice, "help dance").unwrap();
+        assert_eq!(succ.messages(), vec!["dance", "  Dances a jig."]);
+        game_state.unregister_command("dance");
+        assert!(game_state.command_help("dance").is_none());
+    }
+}
@@ -0,0 +1,197 @@
+use crate::error::FaerieError;
+use crate::lambda::{mk_action_callback, ActionFailure, ActionSuccess};
+use crate::type_aliases::{RoomName, UserName};
+use crate::user::User;
+use crate::GameState;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum RoomHookKind {
+    Enter,
+    Exit,
+}
+
+// Like exit conditions, none of these survive a save; they have to be set again after loading.
+impl GameState {
+    // Decides whether a user may come through this path into the room on the other side. A
+    // failure keeps them where they are, with the failure's messages as the explanation.
+    pub fn set_entry_cond<F>(
+        &mut self,
+        room_name: &RoomName,
+        path_name: &str,
+        cond: F,
+    ) -> Result<(), FaerieError>
+    where
+        F: 'static + Send + Sync + FnMut(&mut User) -> Result<ActionSuccess, ActionFailure>,
+    {
+        self.get_path_mut(room_name, path_name)?.entry_cond = mk_action_callback(cond);
+        Ok(())
+    }
+
+    pub fn set_on_enter<F>(&mut self, room_name: &RoomName, hook: F) -> Result<(), FaerieError>
+    where
+        F: 'static + Send + Sync + FnMut(&mut GameState, &UserName) -> Vec<String>,
+    {
+        self.rooms.get_room_mut(room_name)?.on_enter = Some(Box::new(hook));
+        Ok(())
+    }
+
+    pub fn set_on_exit<F>(&mut self, room_name: &RoomName, hook: F) -> Result<(), FaerieError>
+    where
+        F: 'static + Send + Sync + FnMut(&mut GameState, &UserName) -> Vec<String>,
+    {
+        self.rooms.get_room_mut(room_name)?.on_exit = Some(Box::new(hook));
+        Ok(())
+    }
+
+    pub(crate) fn run_room_hook(
+        &mut self,
+        room_name: &RoomName,
+        user_name: &UserName,
+        kind: RoomHookKind,
+    ) -> Result<Vec<String>, FaerieError> {
+        // The hook needs &mut self, so it's taken out of the room while it runs.
+        let room = self.rooms.get_room_mut(room_name)?;
+        let slot = match kind {
+            RoomHookKind::Enter => &mut room.on_enter,
+            RoomHookKind::Exit => &mut room.on_exit,
+        };
+        let mut hook = match slot.take() {
+            Some(hook) => hook,
+            None => return Ok(vec![]),
+        };
+        let messages = hook(self, user_name);
+
+        // The hook may have deleted its room, or replaced itself; either way, leave it be.
+        if let Ok(room) = self.rooms.get_room_mut(room_name) {
+            let slot = match kind {
+                RoomHookKind::Enter => &mut room.on_enter,
+                RoomHookKind::Exit => &mut room.on_exit,
+            };
+            if slot.is_none() {
+                *slot = Some(hook);
+            }
+        }
+        Ok(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::item::Item;
+    use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+    use crate::room::Direction;
+    use crate::testing::TestWorld;
+    use crate::type_aliases::{RoomName, UserName};
+
+    // The shrine is north of the hall, behind a path that wants an amulet.
+    fn make_shrine_world() -> TestWorld {
+        let mut world = TestWorld::build(
+            GameStateBuilder::new()
+                .room("hall", "A long hall.")
+                .room("shrine", "Candles.")
+                .path("hall", "shrine", Direction::North)
+                .user("alice", "hall"),
+        );
+        world
+            .game_state
+            .set_entry_cond(&RoomName::new("hall").unwrap(), "north", |user| {
+                if user.has_item("amulet") {
+                    Ok(ActionSuccess::new(vec!["The amulet glows.".to_string()]))
+                } else {
+                    Err(ActionFailure::new(
+                        vec!["You need the amulet to enter.".to_string()],
+                        FailReason::ExitBlocked,
+                    ))
+                }
+            })
+            .unwrap();
+        world
+    }
+
+    #[test]
+    fn entry_cond_gates_the_path() {
+        let mut world = make_shrine_world();
+        let alice = UserName::new("alice").unwrap();
+
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "north")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::ExitBlocked);
+        assert_eq!(failure.messages, vec!["You need the amulet to enter."]);
+        assert_eq!(world.game_state.get_user_location(&alice).unwrap(), "hall");
+
+        let amulet = Item::new("amulet".to_string(), "Warm.".to_string()).unwrap();
+        world.game_state.give_item(&alice, amulet).unwrap();
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "north")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["The amulet glows."]);
+        assert_eq!(
+            world.game_state.get_user_location(&alice).unwrap(),
+            "shrine"
+        );
+
+        // The way back has no condition.
+        world
+            .game_state
+            .process_input_impl(&alice, "south")
+            .unwrap();
+    }
+
+    #[test]
+    fn room_hooks_run_on_arrival_and_departure() {
+        let mut world = make_shrine_world();
+        let alice = UserName::new("alice").unwrap();
+        let hall = RoomName::new("hall").unwrap();
+        let shrine = RoomName::new("shrine").unwrap();
+        let amulet = Item::new("amulet".to_string(), "Warm.".to_string()).unwrap();
+        world.game_state.give_item(&alice, amulet).unwrap();
+
+        let mut visits = 0;
+        world
+            .game_state
+            .set_on_enter(&shrine, move |game_state, user_name| {
+                visits += 1;
+                let pendant = Item::new("pendant".to_string(), "Blessed.".to_string()).unwrap();
+                game_state.give_item(user_name, pendant).unwrap();
+                vec![format!("A bell rings {} time(s).", visits)]
+            })
+            .unwrap();
+        world
+            .game_state
+            .set_on_exit(&hall, |_, user_name| vec![format!("Bye, {}.", user_name)])
+            .unwrap();
+
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "north")
+            .unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "The amulet glows.",
+                "Bye, alice.",
+                "A bell rings 1 time(s)."
+            ]
+        );
+        assert!(world
+            .game_state
+            .users
+            .get_user("alice")
+            .unwrap()
+            .has_item("pendant"));
+
+        world
+            .game_state
+            .process_input_impl(&alice, "south")
+            .unwrap();
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "north")
+            .unwrap();
+        assert_eq!(succ.messages().last().unwrap(), "A bell rings 2 time(s).");
+    }
+}
@@ -0,0 +1,110 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{OnceLock, PoisonError, RwLock};
+
+// A name, stored once for the life of the process. Copying one costs nothing, and comparing or
+// hashing one only looks at its id, so they make cheap map keys. The text is kept alongside the
+// id, so showing a name never has to go back to the interner.
+#[derive(Clone, Copy)]
+pub(crate) struct Symbol {
+    id: u32,
+    text: &'static str,
+}
+
+impl Symbol {
+    pub(crate) fn as_str(self) -> &'static str {
+        self.text
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Symbol) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+// Alphabetical, so sorted names still read in order. Equal text always means the same id.
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Symbol) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Symbol) -> Ordering {
+        self.text.cmp(other.text)
+    }
+}
+
+#[derive(Default)]
+struct Interner {
+    ids: HashMap<&'static str, u32>,
+}
+
+fn interner() -> &'static RwLock<Interner> {
+    static INTERNER: OnceLock<RwLock<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(Default::default)
+}
+
+// The text's symbol, adding it if it's new. Interned text is never freed, so only validated
+// names are interned; look up anything else with `lookup`.
+pub(crate) fn intern(text: &str) -> Symbol {
+    if let Some(symbol) = lookup(text) {
+        return symbol;
+    }
+    let mut interner = interner().write().unwrap_or_else(PoisonError::into_inner);
+    // Someone else may have added it since we looked.
+    if let Some((text, id)) = interner.ids.get_key_value(text) {
+        return Symbol { id: *id, text };
+    }
+    let id = interner.ids.len() as u32;
+    let text: &'static str = Box::leak(text.to_string().into_boxed_str());
+    interner.ids.insert(text, id);
+    Symbol { id, text }
+}
+
+// The text's symbol, if it's ever been interned. Nothing is added, so this is safe to call with
+// whatever a player types.
+pub(crate) fn lookup(text: &str) -> Option<Symbol> {
+    let interner = interner().read().unwrap_or_else(PoisonError::into_inner);
+    interner
+        .ids
+        .get_key_value(text)
+        .map(|(text, id)| Symbol { id: *id, text })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_text_is_the_same_symbol() {
+        let hall = intern("interned hall");
+        assert!(hall == intern("interned hall"));
+        assert!(hall != intern("interned garden"));
+        assert_eq!(hall.as_str(), "interned hall");
+        assert!(lookup("interned hall") == Some(hall));
+        assert!(lookup("never interned").is_none());
+    }
+
+    #[test]
+    fn symbols_sort_by_their_text() {
+        let mut symbols = [
+            intern("zz sorted"),
+            intern("aa sorted"),
+            intern("mm sorted"),
+        ];
+        symbols.sort();
+        let texts: Vec<&str> = symbols.iter().map(|symbol| symbol.as_str()).collect();
+        assert_eq!(texts, vec!["aa sorted", "mm sorted", "zz sorted"]);
+    }
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+use crate::containers::Container;
+use crate::equipment::{EquipmentSlot, StatModifiers};
+use crate::error::FaerieError;
+use crate::room::Lock;
+use crate::type_aliases::ItemName;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Item {
+    pub name: ItemName,
+    pub description: String,
+    // Where the item goes when equipped. Items without a slot can only be carried.
+    #[serde(default)]
+    pub slot: Option<EquipmentSlot>,
+    #[serde(default)]
+    pub modifiers: StatModifiers,
+    #[serde(default)]
+    pub container: Option<Container>,
+    #[serde(default)]
+    pub weight: u32,
+}
+
+impl Item {
+    pub fn new(name: ItemName, description: String) -> Result<Item, FaerieError> {
+        if name.is_empty() {
+            return Err(FaerieError::EmptyItemName);
+        }
+        Ok(Item {
+            name,
+            description,
+            slot: None,
+            modifiers: StatModifiers::default(),
+            container: None,
+            weight: 0,
+        })
+    }
+
+    pub fn equippable(mut self, slot: EquipmentSlot, modifiers: StatModifiers) -> Item {
+        self.slot = Some(slot);
+        self.modifiers = modifiers;
+        self
+    }
+
+    pub fn container(mut self, capacity: usize, lock: Option<Lock>) -> Item {
+        self.container = Some(Container {
+            capacity,
+            contents: vec![],
+            lock,
+        });
+        self
+    }
+}
@@ -0,0 +1,418 @@
+use std::fs;
+use std::path::Path as FsPath;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::item::Item;
+use crate::output::OutputSink;
+use crate::persistence::write_atomically;
+use crate::room::Direction;
+use crate::type_aliases::{RoomName, UserName};
+use crate::user::{Role, UserType};
+use crate::GameState;
+
+// Something a host did to the world from outside. Everything else that happens follows from
+// these, the saved world they started from, and its seeded rng.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JournalEntry {
+    Input {
+        user_name: UserName,
+        input: String,
+    },
+    Tick,
+    UserCreated {
+        user_name: UserName,
+        room_name: RoomName,
+        user_type: UserType,
+    },
+    UserRemoved {
+        user_name: UserName,
+    },
+    RoomCreated {
+        room_name: RoomName,
+        description: String,
+    },
+    RoomDescribed {
+        room_name: RoomName,
+        description: String,
+    },
+    RoomDeleted {
+        room_name: RoomName,
+    },
+    PathAdded {
+        source_room_name: RoomName,
+        target_room_name: RoomName,
+        direction: Direction,
+        painful: bool,
+    },
+    PathRemoved {
+        source_room_name: RoomName,
+        direction: Direction,
+    },
+    StartRoomSet {
+        room_name: RoomName,
+    },
+    PausedSet {
+        paused: bool,
+    },
+    PausedInputSet {
+        reject: bool,
+    },
+    UserTeleported {
+        user_name: UserName,
+        room_name: RoomName,
+    },
+    RoleSet {
+        user_name: UserName,
+        role: Role,
+    },
+    GoldGiven {
+        user_name: UserName,
+        amount: i32,
+    },
+    ItemGiven {
+        user_name: UserName,
+        item: Item,
+    },
+    SeedSet {
+        seed: u64,
+    },
+}
+
+// The world as it was saved when journaling started, then every entry since, oldest first.
+// Entries are only ever added.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Journal {
+    start: String,
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    // The journal only lives in memory until this is called, so hosts should save it as often
+    // as they can bear to lose play, say after every tick, and again on shutdown.
+    pub fn save_to_file<P: AsRef<FsPath>>(&self, file_path: P) -> Result<(), FaerieError> {
+        write_atomically(file_path.as_ref(), &serde_json::to_string(self)?)
+    }
+
+    pub fn load_from_file<P: AsRef<FsPath>>(file_path: P) -> Result<Journal, FaerieError> {
+        let json = fs::read_to_string(file_path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+// Replays drop everything they'd have sent.
+struct DiscardSink;
+
+impl OutputSink for DiscardSink {
+    fn send(&mut self, _user_name: &UserName, _text: &str) {}
+}
+
+// Only the top-level calls a host makes are recorded, one JournalEntry each: process_input,
+// tick, create_user_in_room, remove_user, create_room, set_room_description, delete_room,
+// add_path, add_painful_path, remove_path, set_start_room, set_paused,
+// set_reject_input_while_paused, teleport_user, set_role, give_gold, give_item and set_seed.
+// Calls built from these, like create_room_from, are recorded as the calls they make. Calls
+// that fail are left out, since they changed nothing. Whatever those calls do in turn, like
+// kicks or spawns, isn't recorded either, since replaying the call does it again.
+//
+// Every other public setter, like set_room_flags, set_path_lock or register_quest, is left
+// out, so make those before start_journal or replay won't see them. State the save leaves out
+// (see persistence.rs) isn't in the starting snapshot either, so start journaling right after
+// loading a world for an exact replay.
+impl GameState {
+    pub fn start_journal(&mut self) -> Result<(), FaerieError> {
+        self.journal = Some(Journal {
+            start: self.to_json()?,
+            entries: vec![],
+        });
+        Ok(())
+    }
+
+    // Starts the journal over from the world as it is now, dropping the entries that led here.
+    // Autosave does this after every save, so the journal only covers play since then. Does
+    // nothing unless journaling.
+    pub fn checkpoint_journal(&mut self) -> Result<(), FaerieError> {
+        if self.journal.is_some() {
+            self.start_journal()?;
+        }
+        Ok(())
+    }
+
+    pub fn journal(&self) -> Option<&Journal> {
+        self.journal.as_ref()
+    }
+
+    // Stops journaling and hands back what was recorded.
+    pub fn take_journal(&mut self) -> Option<Journal> {
+        self.journal.take()
+    }
+
+    // Rebuilds the world the journal was recorded from. Output during the replay goes
+    // nowhere, so set an output sink on the result before using it.
+    pub fn replay(journal: &Journal) -> Result<GameState, FaerieError> {
+        let mut game_state = GameState::from_json(&journal.start)?;
+        game_state.set_output_sink(Box::new(DiscardSink));
+        for entry in &journal.entries {
+            match entry {
+                JournalEntry::Input { user_name, input } => {
+                    game_state.process_input(user_name, input)
+                }
+                JournalEntry::Tick => game_state.tick(),
+                JournalEntry::UserCreated {
+                    user_name,
+                    room_name,
+                    user_type,
+                } => game_state.create_user_in_room(user_name, room_name, *user_type)?,
+                JournalEntry::UserRemoved { user_name } => game_state.remove_user(user_name)?,
+                JournalEntry::RoomCreated {
+                    room_name,
+                    description,
+                } => game_state.create_room(room_name, description.clone())?,
+                JournalEntry::RoomDescribed {
+                    room_name,
+                    description,
+                } => game_state.set_room_description(room_name, description.clone())?,
+                JournalEntry::RoomDeleted { room_name } => game_state.delete_room(room_name)?,
+                JournalEntry::PathAdded {
+                    source_room_name,
+                    target_room_name,
+                    direction,
+                    painful: false,
+                } => game_state.add_path(source_room_name, target_room_name, direction.clone())?,
+                JournalEntry::PathAdded {
+                    source_room_name,
+                    target_room_name,
+                    direction,
+                    painful: true,
+                } => game_state.add_painful_path(
+                    source_room_name,
+                    target_room_name,
+                    direction.clone(),
+                )?,
+                JournalEntry::PathRemoved {
+                    source_room_name,
+                    direction,
+                } => game_state.remove_path(source_room_name, direction.clone())?,
+                JournalEntry::StartRoomSet { room_name } => game_state.set_start_room(room_name)?,
+                JournalEntry::PausedSet { paused } => game_state.set_paused(*paused),
+                JournalEntry::PausedInputSet { reject } => {
+                    game_state.set_reject_input_while_paused(*reject)
+                }
+                JournalEntry::UserTeleported {
+                    user_name,
+                    room_name,
+                } => {
+                    game_state.teleport_user(user_name, room_name)?;
+                }
+                JournalEntry::RoleSet { user_name, role } => {
+                    game_state.set_role(user_name, *role)?
+                }
+                JournalEntry::GoldGiven { user_name, amount } => {
+                    game_state.give_gold(user_name, *amount)?
+                }
+                JournalEntry::ItemGiven { user_name, item } => {
+                    game_state.give_item(user_name, item.clone())?
+                }
+                JournalEntry::SeedSet { seed } => game_state.set_seed(*seed),
+            }
+        }
+        Ok(game_state)
+    }
+
+    // Records the entry if this is a top-level call, then runs it.
+    pub(crate) fn journaled<T>(
+        &mut self,
+        entry: impl FnOnce() -> JournalEntry,
+        run: impl FnOnce(&mut GameState) -> T,
+    ) -> T {
+        if self.journal_depth == 0 {
+            if let Some(journal) = &mut self.journal {
+                journal.entries.push(entry());
+            }
+        }
+        self.journal_depth += 1;
+        let result = run(self);
+        self.journal_depth -= 1;
+        result
+    }
+
+    // Like journaled, but for calls that can fail, which are only recorded once they succeed.
+    pub(crate) fn journaled_if_ok<T, E>(
+        &mut self,
+        entry: impl FnOnce() -> JournalEntry,
+        run: impl FnOnce(&mut GameState) -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.journal_depth += 1;
+        let result = run(self);
+        self.journal_depth -= 1;
+        if result.is_ok() && self.journal_depth == 0 {
+            if let Some(journal) = &mut self.journal {
+                journal.entries.push(entry());
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room::Direction;
+
+    // A snapshot of the empty rooms, then two users fighting and wandering about.
+    fn play_journaled_game() -> GameState {
+        let mut game_state = GameState::new();
+        let arena = RoomName::new("arena").unwrap();
+        game_state.create_room(&arena, "Sand.".to_string()).unwrap();
+        game_state
+            .create_room_from(
+                &RoomName::new("stands").unwrap(),
+                "Benches.".to_string(),
+                &arena,
+                Direction::North,
+            )
+            .unwrap();
+        game_state.set_output_sink(Box::new(DiscardSink));
+        game_state.start_journal().unwrap();
+
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        game_state
+            .create_user_in_room(&alice, &arena, UserType::Viking)
+            .unwrap();
+        game_state
+            .create_user_in_room(&bob, &arena, UserType::Civilian)
+            .unwrap();
+        game_state.process_input(&alice, "attack bob");
+        game_state.tick_n(3);
+        game_state.process_input(&alice, "north");
+        game_state.process_input(&alice, "say Too easy.");
+        game_state.tick();
+        game_state
+    }
+
+    // Rooms and users are kept in hash maps, so compare the saves as values, with each room's
+    // users sorted since they're saved from a hash set.
+    fn save(game_state: &GameState) -> serde_json::Value {
+        let mut save: serde_json::Value =
+            serde_json::from_str(&game_state.to_json().unwrap()).unwrap();
+        for room in save["rooms"]["rooms"].as_object_mut().unwrap().values_mut() {
+            room["users"]
+                .as_array_mut()
+                .unwrap()
+                .sort_by_key(|user| user.to_string());
+        }
+        save
+    }
+
+    #[test]
+    fn replays_match_the_original() {
+        let mut game_state = play_journaled_game();
+        let journal = game_state.take_journal().unwrap();
+        assert_eq!(journal.entries().len(), 9);
+        assert_eq!(
+            journal.entries()[2],
+            JournalEntry::Input {
+                user_name: UserName::new("alice").unwrap(),
+                input: "attack bob".to_string(),
+            }
+        );
+
+        let replayed = GameState::replay(&journal).unwrap();
+        assert_eq!(save(&replayed), save(&game_state));
+    }
+
+    #[test]
+    fn only_top_level_calls_are_recorded() {
+        let mut game_state = play_journaled_game();
+        let alice = UserName::new("alice").unwrap();
+        game_state.set_role(&alice, Role::Admin).unwrap();
+        let before = game_state.journal().unwrap().entries().len();
+        // Kicking removes bob from inside the input, so only the input is recorded.
+        game_state.process_input(&alice, "kick bob");
+        let journal = game_state.take_journal().unwrap();
+        assert_eq!(journal.entries().len(), before + 1);
+        assert!(game_state.journal().is_none());
+    }
+
+    #[test]
+    fn host_edits_are_replayed_and_failed_ones_left_out() {
+        let mut game_state = play_journaled_game();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        let arena = RoomName::new("arena").unwrap();
+        let stands = RoomName::new("stands").unwrap();
+        let pit = RoomName::new("pit").unwrap();
+        let before = game_state.journal().unwrap().entries().len();
+
+        game_state
+            .create_room_from(&pit, "Spikes.".to_string(), &arena, Direction::South)
+            .unwrap();
+        game_state
+            .add_painful_path(&stands, &pit, Direction::CustomOneWay("chute".to_string()))
+            .unwrap();
+        game_state
+            .set_room_description(&arena, "Bloody sand.".to_string())
+            .unwrap();
+        game_state.teleport_user(&bob, &pit).unwrap();
+        game_state.set_role(&alice, Role::Builder).unwrap();
+        game_state.give_gold(&bob, 12).unwrap();
+        let lamp = Item::new("lamp".to_string(), "Bright.".to_string()).unwrap();
+        game_state.give_item(&bob, lamp).unwrap();
+        game_state.set_start_room(&pit).unwrap();
+        game_state.set_seed(7);
+        game_state.set_paused(true);
+        game_state.set_reject_input_while_paused(true);
+        game_state.process_input(&alice, "south");
+        game_state.set_paused(false);
+        game_state.remove_path(&arena, Direction::North).unwrap();
+        let entries = game_state.journal().unwrap().entries().len();
+        assert_eq!(entries, before + 15);
+        assert_eq!(
+            game_state.journal().unwrap().entries()[before],
+            JournalEntry::RoomCreated {
+                room_name: pit,
+                description: "Spikes.".to_string(),
+            }
+        );
+
+        assert!(game_state.delete_room(&pit).is_err());
+        assert!(game_state.give_gold(&bob, i32::MAX).is_err());
+        assert_eq!(game_state.journal().unwrap().entries().len(), entries);
+
+        let journal = game_state.take_journal().unwrap();
+        let replayed = GameState::replay(&journal).unwrap();
+        assert_eq!(save(&replayed), save(&game_state));
+        assert_eq!(replayed.start_room(), Some(&pit));
+    }
+
+    #[test]
+    fn autosaves_checkpoint_the_journal() {
+        let dir = std::env::temp_dir().join(format!("faerie_journal_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut game_state = play_journaled_game();
+        let alice = UserName::new("alice").unwrap();
+        game_state.set_autosave(dir.join("world.json"), 10, 0);
+        game_state.tick_n(10 - game_state.tick_count() % 10);
+        assert!(game_state.journal().unwrap().entries().is_empty());
+
+        game_state.process_input(&alice, "south");
+        let journal_path = dir.join("journal.json");
+        game_state
+            .journal()
+            .unwrap()
+            .save_to_file(&journal_path)
+            .unwrap();
+        let journal = Journal::load_from_file(&journal_path).unwrap();
+        assert_eq!(journal.entries().len(), 1);
+        let replayed = GameState::replay(&journal).unwrap();
+        assert_eq!(save(&replayed), save(&game_state));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,36 +1,122 @@
-#[derive(Debug)]
+use crate::error::FaerieError;
+use crate::type_aliases::{ItemName, RoomName};
+
+// Something that happened to the acting user. Frontends can react to these directly; the CLI
+// and server turn them into text with GameState::render_events.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionEvent {
+    Message { text: String },
+    RoomMoved { from: RoomName, to: RoomName },
+    Damaged { amount: i32 },
+    Healed { amount: i32 },
+    ItemGained { item_name: ItemName },
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct ActionSuccess {
-    pub messages: Vec<String>,
-    room_move: bool,
+    pub events: Vec<ActionEvent>,
 }
 
 impl ActionSuccess {
     pub fn new(messages: Vec<String>) -> ActionSuccess {
         ActionSuccess {
-            messages,
-            room_move: false,
+            events: messages
+                .into_iter()
+                .map(|text| ActionEvent::Message { text })
+                .collect(),
         }
     }
 
-    pub fn set_was_room_move(&mut self) {
-        self.room_move = true;
+    pub fn from_events(events: Vec<ActionEvent>) -> ActionSuccess {
+        ActionSuccess { events }
+    }
+
+    pub fn push(&mut self, event: ActionEvent) {
+        self.events.push(event);
+    }
+
+    pub fn push_messages(&mut self, messages: Vec<String>) {
+        self.events.extend(
+            messages
+                .into_iter()
+                .map(|text| ActionEvent::Message { text }),
+        );
+    }
+
+    pub fn append(&mut self, other: &mut ActionSuccess) {
+        self.events.append(&mut other.events);
+    }
+
+    // Just the text of the Message events, in order.
+    pub fn messages(&self) -> Vec<String> {
+        self.events
+            .iter()
+            .filter_map(|event| match event {
+                ActionEvent::Message { text } => Some(text.clone()),
+                _ => None,
+            })
+            .collect()
     }
 
     pub fn was_room_move(&self) -> bool {
-        self.room_move
+        self.events
+            .iter()
+            .any(|event| matches!(event, ActionEvent::RoomMoved { .. }))
     }
+}
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailReason {
+    EmptyInput,
+    MissingArgument,
+    NoSuchPath,
+    ExitBlocked,
+    PathLocked,
+    MissingKey,
+    NotLockable,
+    WorldPaused,
+    NothingToUndo,
+    InvalidTarget,
+    Dead,
+    PermissionDenied,
+    SafeRoom,
+    PathClosed,
+    // The user isn't the right kind to use it, or can't pay its cost.
+    AbilityUnavailable,
+    // Carrying too much.
+    Encumbered,
+    // Out of stamina.
+    Exhausted,
+    // Longer than parser::MAX_INPUT_LEN.
+    InputTooLong,
+    // Neither a command nor a way out.
+    UnknownCommand,
+    Error(FaerieError),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ActionFailure {
     pub messages: Vec<String>,
+    pub reason: FailReason,
+}
+
+impl ActionFailure {
+    pub fn new(messages: Vec<String>, reason: FailReason) -> ActionFailure {
+        ActionFailure { messages, reason }
+    }
+}
+
+impl From<FaerieError> for ActionFailure {
+    fn from(e: FaerieError) -> ActionFailure {
+        ActionFailure::new(vec![e.to_string()], FailReason::Error(e))
+    }
 }
 
-pub type ActionFunc<T> = Option<Box<dyn FnMut(&mut T) -> Result<ActionSuccess, ActionFailure>>>;
-pub fn mk_action_callback<F: 'static, T>(f: F) -> ActionFunc<T>
+pub type ActionFunc<T> =
+    Option<Box<dyn FnMut(&mut T) -> Result<ActionSuccess, ActionFailure> + Send + Sync>>;
+pub fn mk_action_callback<F, T>(f: F) -> ActionFunc<T>
 where
-    F: FnMut(&mut T) -> Result<ActionSuccess, ActionFailure>,
+    F: 'static + Send + Sync + FnMut(&mut T) -> Result<ActionSuccess, ActionFailure>,
 {
     Some(Box::new(f))
 }
@@ -1,109 +1,525 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
 
 mod user;
-use user::{User, UserType};
+use user::User;
+pub use user::{Role, UserType};
 
+mod interner;
 mod type_aliases;
-use type_aliases::{RoomName, UserName};
+use type_aliases::{name_key, QuestId};
+pub use type_aliases::{AccountName, PathName, RoomName, UserName};
 
 mod lambda;
-use lambda::{ActionFailure, ActionSuccess};
+pub use lambda::{ActionEvent, ActionFailure, ActionSuccess, FailReason};
 
-pub mod room;
-use room::{Direction, Path, Room};
+mod error;
+pub use error::FaerieError;
+
+pub mod grammar;
+use grammar::with_article;
+
+pub mod format;
+use format::OutputFormatter;
+
+pub mod output;
+use output::OutputSink;
 
+pub mod room;
+use room::{Direction, Path, PathKind, PathType, Room};
+
+mod build_history;
+pub mod builder;
+use build_history::BuildOp;
+pub use builder::GameStateBuilder;
+
+mod autosave;
+mod persistence;
+use autosave::Autosave;
+
+pub mod diff;
+pub mod reload;
+pub use diff::WorldDiff;
+mod world_file;
+
+#[cfg(feature = "async-server")]
+pub mod async_server;
+pub mod server;
+
+mod parser;
+use parser::{Command, ParseError};
+
+mod combat;
+
+mod chat;
+
+mod look;
+mod matching;
+
+pub mod item;
+
+mod locks;
+
+mod validate;
+pub use validate::WorldReport;
+
+pub mod scheduler;
+use scheduler::Scheduler;
+pub mod events;
+use events::{EventBus, GameEvent};
+pub mod journal;
+pub mod logging;
+pub mod testing;
+use journal::{Journal, JournalEntry};
+
+pub mod quests;
+use quests::Quest;
+
+pub mod commands;
+use commands::CommandRegistry;
+
+mod hooks;
+use hooks::RoomHookKind;
+
+mod completion;
+pub mod help;
+pub use help::CommandHelp;
+pub mod catalog;
+pub mod pager;
+pub mod prompt;
+pub mod wrap;
+
+pub mod accounts;
+
+mod admin;
+
+mod building;
+mod death;
+mod party;
+mod speedwalk;
+mod suggestions;
+
+mod flags;
+pub use flags::{PathFlags, RoomFlags};
+pub mod equipment;
+mod regen;
+mod search;
+pub mod status;
+pub use equipment::{EquipmentSlot, StatModifiers};
+pub mod shop;
+use search::SearchCheck;
+pub use shop::{Shop, Ware};
+pub mod abilities;
+mod trade;
+pub use abilities::Ability;
+pub mod rng;
+use rng::GameRng;
+pub mod dice;
+pub use dice::Dice;
+pub mod spawn;
+pub use spawn::{NpcTemplate, SpawnPoint};
+pub mod loot;
+pub use loot::{Corpse, LootDrop, LootTable};
+pub mod containers;
+pub use containers::Container;
+mod weight;
+pub mod zones;
+pub use status::StatusEffect;
+use trade::TradeSide;
+pub use zones::Zone;
+pub mod map;
+pub mod room_actors;
+pub use map::Coords;
+pub mod descriptions;
+pub use descriptions::TimeOfDay;
+pub mod weather;
+pub use weather::Weather;
+pub mod ambience;
+pub use ambience::AmbientMessage;
+pub mod stamina;
+pub use stamina::Terrain;
+pub mod skills;
+pub use skills::{Skill, SkillCheck};
+mod sneak;
+
+pub mod dialogue;
+use dialogue::Conversation;
+pub use dialogue::{Dialogue, DialogueNode, DialogueResponse};
+pub mod achievements;
+pub use achievements::Achievement;
+pub mod profile;
+use profile::TextValidator;
+mod afk;
+pub mod mail;
+mod moderation;
+pub use mail::Mail;
+pub mod channels;
+pub use channels::Channel;
+mod friends;
+pub mod guilds;
+pub use guilds::{Guild, GuildRank};
+mod who;
+
+pub mod socials;
+use socials::Social;
+
+#[cfg(feature = "scripting")]
+mod scripting;
+#[cfg(feature = "scripting")]
+use scripting::ScriptEngine;
+
+// Rooms and users are found by name_key as well as their exact name, so "Hall " finds "hall".
+// The keys aren't saved; GameState::from_json rebuilds them.
+#[derive(Serialize, Deserialize)]
 struct RoomStore {
     rooms: HashMap<RoomName, Room>,
+    #[serde(skip)]
+    keys: HashMap<String, RoomName>,
+    // Rooms that may have corpses in them, so decaying them doesn't visit every room. Rooms
+    // whose corpses are gone drop out the next time they're decayed.
+    #[serde(skip)]
+    corpse_rooms: BTreeSet<RoomName>,
+    // Rooms with ambient messages, which are few even in a big world.
+    #[serde(skip)]
+    ambient_rooms: BTreeSet<RoomName>,
 }
 
 impl RoomStore {
     fn new() -> RoomStore {
         RoomStore {
             rooms: HashMap::new(),
+            keys: HashMap::new(),
+            corpse_rooms: BTreeSet::new(),
+            ambient_rooms: BTreeSet::new(),
         }
     }
 
-    fn get_room(&self, room_name: &RoomName) -> &Room {
-        let room = self
+    // Refuses a room whose name folds to the same name_key as one already here, so "Hall"
+    // can't hide "hall".
+    fn insert_room(&mut self, room: Room) -> Result<(), FaerieError> {
+        if self.keys.contains_key(&name_key(&room.name)) {
+            return Err(FaerieError::DuplicateRoom(room.name.to_string()));
+        }
+        self.restore_room(room);
+        Ok(())
+    }
+
+    // Puts back a room that came out of this store, without checking its name again.
+    fn restore_room(&mut self, room: Room) {
+        self.keys.insert(name_key(&room.name), room.name);
+        if !room.corpses.is_empty() {
+            self.corpse_rooms.insert(room.name);
+        }
+        if !room.ambient.is_empty() {
+            self.ambient_rooms.insert(room.name);
+        }
+        self.rooms.insert(room.name, room);
+    }
+
+    fn remove_room(&mut self, room_name: &str) -> Option<Room> {
+        let room = self.rooms.remove(&RoomName::lookup(room_name)?)?;
+        // Old saves can hold rooms whose names fold together, and then the key may be the
+        // other room's.
+        let key = name_key(room_name);
+        if self.keys.get(&key) == Some(&room.name) {
+            self.keys.remove(&key);
+        }
+        self.corpse_rooms.remove(&room.name);
+        self.ambient_rooms.remove(&room.name);
+        Some(room)
+    }
+
+    fn reindex(&mut self) {
+        self.keys = self
+            .rooms
+            .keys()
+            .map(|room_name| (name_key(room_name), *room_name))
+            .collect();
+        self.corpse_rooms = self
             .rooms
-            .get(room_name)
-            .expect(&format!("Failed to find room named {}!", room_name));
-        room
+            .values()
+            .filter(|room| !room.corpses.is_empty())
+            .map(|room| room.name)
+            .collect();
+        self.ambient_rooms = self
+            .rooms
+            .values()
+            .filter(|room| !room.ambient.is_empty())
+            .map(|room| room.name)
+            .collect();
+    }
+
+    fn set_ambience(
+        &mut self,
+        room_name: &str,
+        ambient: Vec<AmbientMessage>,
+    ) -> Result<(), FaerieError> {
+        let room = self.get_room_mut(room_name)?;
+        room.ambient = ambient;
+        let (room_name, has_ambience) = (room.name, !room.ambient.is_empty());
+        if has_ambience {
+            self.ambient_rooms.insert(room_name);
+        } else {
+            self.ambient_rooms.remove(&room_name);
+        }
+        Ok(())
     }
 
-    fn get_room_mut(&mut self, room_name: &RoomName) -> &mut Room {
-        let room = self.rooms.get_mut(room_name).expect(&format!(
-            "Failed to find room named {} for mutation!",
-            room_name
-        ));
-        room
+    fn add_corpse(&mut self, room_name: &str, corpse: Corpse) -> Result<(), FaerieError> {
+        let room = self.get_room_mut(room_name)?;
+        room.corpses.push(corpse);
+        let room_name = room.name;
+        self.corpse_rooms.insert(room_name);
+        Ok(())
     }
 
-    fn check_room_exists(&self, room_name: &RoomName) {
-        assert!(
-            self.rooms.contains_key(room_name),
-            format!("No room named {} exists!", room_name)
-        );
+    // The room's name as it's stored.
+    fn resolve(&self, room_name: &str) -> Option<&RoomName> {
+        let exact = RoomName::lookup(room_name).and_then(|name| self.rooms.get_key_value(&name));
+        match exact {
+            Some((room_name, _)) => Some(room_name),
+            None => self.keys.get(&name_key(room_name)),
+        }
+    }
+
+    fn get_room(&self, room_name: &str) -> Result<&Room, FaerieError> {
+        self.resolve(room_name)
+            .and_then(|key| self.rooms.get(key))
+            .ok_or_else(|| FaerieError::RoomNotFound(room_name.to_string()))
+    }
+
+    fn get_room_mut(&mut self, room_name: &str) -> Result<&mut Room, FaerieError> {
+        let key = self
+            .resolve(room_name)
+            .cloned()
+            .ok_or_else(|| FaerieError::RoomNotFound(room_name.to_string()))?;
+        self.rooms
+            .get_mut(&key)
+            .ok_or_else(|| FaerieError::RoomNotFound(room_name.to_string()))
+    }
+
+    fn check_room_exists(&self, room_name: &str) -> Result<(), FaerieError> {
+        self.get_room(room_name).map(|_| ())
+    }
+
+    fn rooms_in_zone(&self, zone_name: &str) -> impl Iterator<Item = &Room> + '_ {
+        let zone_name = zone_name.to_string();
+        self.rooms
+            .values()
+            .filter(move |room| room.in_zone(&zone_name))
     }
 }
 
+#[derive(Serialize, Deserialize)]
 struct UserStore {
     users: HashMap<UserName, User>,
+    #[serde(skip)]
+    keys: HashMap<String, UserName>,
 }
 
 impl UserStore {
     fn new() -> UserStore {
         UserStore {
             users: HashMap::new(),
+            keys: HashMap::new(),
         }
     }
 
-    fn get_user(&self, user_name: &UserName) -> &User {
-        let user = self
+    // Names that fold to one already taken are refused, like rooms'.
+    fn insert_user(&mut self, user: User) -> Result<(), FaerieError> {
+        if self.keys.contains_key(&name_key(&user.name)) {
+            return Err(FaerieError::DuplicateUser(user.name.to_string()));
+        }
+        self.restore_user(user);
+        Ok(())
+    }
+
+    // Puts back a user that came out of this store, without checking their name again.
+    fn restore_user(&mut self, user: User) {
+        self.keys.insert(name_key(&user.name), user.name);
+        self.users.insert(user.name, user);
+    }
+
+    fn remove_user(&mut self, user_name: &str) -> Option<User> {
+        let user = self.users.remove(&UserName::lookup(user_name)?)?;
+        self.keys.remove(&name_key(user_name));
+        Some(user)
+    }
+
+    fn reindex(&mut self) {
+        self.keys = self
             .users
-            .get(user_name)
-            .expect(&format!("Failed to find user named {}!", user_name));
-        user
+            .keys()
+            .map(|user_name| (name_key(user_name), *user_name))
+            .collect();
     }
 
-    fn get_user_mut(&mut self, user_name: &UserName) -> &mut User {
-        let user = self.users.get_mut(user_name).expect(&format!(
-            "Failed to find user named {} for mutation!",
-            user_name
-        ));
-        user
+    // The user's name as it's stored.
+    fn resolve(&self, user_name: &str) -> Option<&UserName> {
+        let exact = UserName::lookup(user_name).and_then(|name| self.users.get_key_value(&name));
+        match exact {
+            Some((user_name, _)) => Some(user_name),
+            None => self.keys.get(&name_key(user_name)),
+        }
     }
 
-    fn check_user_exists(&self, user_name: &UserName) {
-        assert!(
-            self.users.contains_key(user_name),
-            format!("No user named {} exists!", user_name)
-        );
+    fn get_user(&self, user_name: &str) -> Result<&User, FaerieError> {
+        self.resolve(user_name)
+            .and_then(|key| self.users.get(key))
+            .ok_or_else(|| FaerieError::UserNotFound(user_name.to_string()))
+    }
+
+    fn get_user_mut(&mut self, user_name: &str) -> Result<&mut User, FaerieError> {
+        let key = self
+            .resolve(user_name)
+            .cloned()
+            .ok_or_else(|| FaerieError::UserNotFound(user_name.to_string()))?;
+        self.users
+            .get_mut(&key)
+            .ok_or_else(|| FaerieError::UserNotFound(user_name.to_string()))
     }
 }
 
+#[derive(Debug, PartialEq)]
 enum GlobalActions {
     ListOnlineUsers,
+    BuildUndo,
 }
 
 impl GlobalActions {
-    fn from_text(input: &String) -> Option<GlobalActions> {
-        match input.as_ref() {
+    const NAMES: &'static [&'static str] = &["list_users", "buildundo"];
+
+    fn from_text(input: &str) -> Option<GlobalActions> {
+        match input {
             "list_users" => Some(GlobalActions::ListOnlineUsers),
+            "buildundo" => Some(GlobalActions::BuildUndo),
             _ => None,
         }
     }
 
+    #[cfg(test)]
     fn from_enum(input: GlobalActions) -> String {
         match input {
             GlobalActions::ListOnlineUsers => "list_users".to_string(),
+            GlobalActions::BuildUndo => "buildundo".to_string(),
         }
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct GameState {
     rooms: RoomStore,
     users: UserStore,
+    #[serde(skip)]
+    last_failures: HashMap<UserName, ActionFailure>,
+    tick_count: u64,
+    missed_ticks: u64,
+    paused: bool,
+    reject_input_while_paused: bool,
+    #[serde(skip)]
+    builder_mode: bool,
+    // Each step with whoever made it; None for changes made through the API.
+    #[serde(skip)]
+    build_undo_stack: Vec<(Option<UserName>, BuildOp)>,
+    // The builder whose command is running, if any. See build_as.
+    #[serde(skip)]
+    building_as: Option<UserName>,
+    #[serde(skip, default = "format::default_formatter")]
+    formatter: Box<dyn OutputFormatter>,
+    start_room_name: Option<RoomName>,
+    #[serde(default)]
+    respawn_room_name: Option<RoomName>,
+    #[serde(default = "death::default_respawn_delay")]
+    respawn_delay: u64,
+    #[serde(default)]
+    death_hp_penalty: i32,
+    // Users waiting to respawn, with the tick they died on.
+    #[serde(default)]
+    dead_users: BTreeMap<UserName, u64>,
+    #[serde(skip, default = "output::default_sink")]
+    output: Box<dyn OutputSink>,
+    #[serde(skip)]
+    combat_targets: BTreeMap<UserName, UserName>,
+    // Each follower's leader.
+    #[serde(skip)]
+    leaders: BTreeMap<UserName, UserName>,
+    #[serde(skip)]
+    search_check: Option<SearchCheck>,
+    // Both sides of every trade in progress, by trader.
+    #[serde(skip)]
+    trades: BTreeMap<UserName, TradeSide>,
+    #[serde(skip)]
+    conversations: BTreeMap<UserName, Conversation>,
+    #[serde(skip)]
+    scheduler: Scheduler,
+    #[serde(skip)]
+    event_bus: EventBus,
+    #[serde(default)]
+    quests: BTreeMap<QuestId, Quest>,
+    #[serde(default)]
+    rng: GameRng,
+    #[serde(default)]
+    spawn_points: Vec<SpawnPoint>,
+    #[serde(default)]
+    zones: BTreeMap<String, Zone>,
+    // The tick each slowed user can next move on.
+    #[serde(skip)]
+    move_ready_at: BTreeMap<UserName, u64>,
+    #[serde(skip)]
+    commands: CommandRegistry,
+    #[serde(skip)]
+    kicked_users: Vec<UserName>,
+    #[serde(default)]
+    banned_users: BTreeSet<UserName>,
+    #[serde(default)]
+    muted_users: BTreeSet<UserName>,
+    #[serde(default = "channels::default_channels")]
+    channels: BTreeMap<String, Channel>,
+    #[serde(default)]
+    guilds: BTreeMap<String, Guild>,
+    #[serde(skip, default = "socials::default_socials")]
+    socials: BTreeMap<String, Social>,
+    #[serde(skip, default = "help::builtin_help")]
+    help: BTreeMap<String, CommandHelp>,
+    // The rest of each user's long reply, waiting for them to press enter (see pager.rs).
+    #[serde(skip)]
+    pages: HashMap<UserName, Vec<String>>,
+    // Messages by locale, then ID (see catalog.rs).
+    #[serde(skip, default = "catalog::default_catalog")]
+    catalog: catalog::Catalog,
+    #[serde(skip)]
+    text_validators: Vec<TextValidator>,
+    // The tick each user last sent input on.
+    #[serde(skip)]
+    last_input_at: HashMap<UserName, u64>,
+    #[serde(default = "afk::default_afk_after")]
+    afk_after: Option<u64>,
+    #[serde(default)]
+    void_after: u64,
+    #[serde(default)]
+    limbo_room_name: Option<RoomName>,
+    #[serde(skip)]
+    afk_users: HashSet<UserName>,
+    // Where each voided user was before they went to limbo.
+    #[serde(default)]
+    voided_users: BTreeMap<UserName, RoomName>,
+    #[serde(skip)]
+    autosave: Option<Autosave>,
+    #[serde(skip)]
+    journal: Option<Journal>,
+    // How deep in journaled calls we are, so only the outermost is recorded.
+    #[serde(skip)]
+    journal_depth: u32,
+    #[cfg(feature = "scripting")]
+    #[serde(skip)]
+    scripts: ScriptEngine,
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GameState {
@@ -111,15 +527,121 @@ impl GameState {
         GameState {
             rooms: RoomStore::new(),
             users: UserStore::new(),
+            last_failures: HashMap::new(),
+            tick_count: 0,
+            missed_ticks: 0,
+            paused: false,
+            reject_input_while_paused: false,
+            builder_mode: false,
+            build_undo_stack: vec![],
+            building_as: None,
+            formatter: format::default_formatter(),
+            start_room_name: None,
+            respawn_room_name: None,
+            respawn_delay: death::DEFAULT_RESPAWN_DELAY,
+            death_hp_penalty: 0,
+            dead_users: BTreeMap::new(),
+            output: output::default_sink(),
+            combat_targets: BTreeMap::new(),
+            leaders: BTreeMap::new(),
+            search_check: None,
+            trades: BTreeMap::new(),
+            conversations: BTreeMap::new(),
+            scheduler: Scheduler::default(),
+            event_bus: EventBus::default(),
+            quests: BTreeMap::new(),
+            rng: GameRng::default(),
+            spawn_points: vec![],
+            zones: BTreeMap::new(),
+            move_ready_at: BTreeMap::new(),
+            commands: CommandRegistry::default(),
+            kicked_users: vec![],
+            banned_users: BTreeSet::new(),
+            muted_users: BTreeSet::new(),
+            channels: channels::default_channels(),
+            guilds: BTreeMap::new(),
+            socials: socials::default_socials(),
+            help: help::builtin_help(),
+            pages: HashMap::new(),
+            catalog: catalog::default_catalog(),
+            text_validators: vec![],
+            last_input_at: HashMap::new(),
+            afk_after: afk::default_afk_after(),
+            void_after: 0,
+            limbo_room_name: None,
+            afk_users: HashSet::new(),
+            voided_users: BTreeMap::new(),
+            autosave: None,
+            journal: None,
+            journal_depth: 0,
+            #[cfg(feature = "scripting")]
+            scripts: ScriptEngine::default(),
         }
     }
 
+    pub fn tick(&mut self) {
+        self.journaled(|| JournalEntry::Tick, GameState::tick_impl)
+    }
+
+    fn tick_impl(&mut self) {
+        if self.paused {
+            self.missed_ticks += 1;
+            return;
+        }
+        self.tick_count += 1;
+        self.run_scheduled_tasks();
+        self.tick_status_effects();
+        self.resolve_combat_round();
+        self.check_deaths();
+        self.regenerate();
+        self.recover_stamina();
+        self.respawn_due_users();
+        self.decay_corpses();
+        self.play_zone_ambience();
+        self.play_room_ambience();
+        self.change_weather();
+        self.run_spawn_points();
+        self.check_idle_users();
+        self.autosave_if_due();
+    }
+
+    pub fn tick_n(&mut self, n: u64) {
+        for _ in 0..n {
+            self.tick();
+        }
+    }
+
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    // Ticks that arrived while the world was paused and were dropped.
+    pub fn missed_ticks(&self) -> u64 {
+        self.missed_ticks
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        let entry = || JournalEntry::PausedSet { paused };
+        self.journaled(entry, |game_state| game_state.paused = paused)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_reject_input_while_paused(&mut self, reject: bool) {
+        let entry = || JournalEntry::PausedInputSet { reject };
+        self.journaled(entry, |game_state| {
+            game_state.reject_input_while_paused = reject
+        })
+    }
+
     pub fn print_debug_map(&self) {
         println!("Rooms:");
-        for (_roomname, room) in &self.rooms.rooms {
+        for room in self.rooms.rooms.values() {
             println!("  {}: ", room.name);
             println!("    paths:");
-            for (_pathname, path) in &room.paths {
+            for path in room.paths.values() {
                 println!("      * {} -> {}", path.path_name, path.target_room_name);
             }
             println!("    users:");
@@ -132,29 +654,322 @@ impl GameState {
         println!();
         println!("Users:");
 
-        for (_username, user) in &self.users.users {
+        for user in self.users.users.values() {
             println!(" {}", user.name);
         }
     }
 
-    pub fn print_room(&self, username: &UserName) {
-        let user = self.users.get_user(username);
-        let room = self.rooms.get_room(&user.room_name);
-        let desc = room.description.clone();
+    // The room graph in Graphviz DOT format, e.g. for `dot -Tsvg`. Painful paths are dashed.
+    pub fn export_dot(&self) -> String {
+        let mut room_names: Vec<&RoomName> = self.rooms.rooms.keys().collect();
+        room_names.sort();
 
-        println!("{}", &user.room_name);
-        println!("  {}", &desc);
-        println!();
-        println!("paths: ");
-        for (_path_name, exit) in &room.paths {
-            println!("* {}", exit.path_name);
+        let mut lines = vec!["digraph world {".to_string()];
+        for room_name in &room_names {
+            lines.push(format!("    \"{}\";", escape_dot(room_name)));
+        }
+        for room_name in &room_names {
+            let room = &self.rooms.rooms[*room_name];
+            let mut paths: Vec<&Path> = room.paths.values().collect();
+            paths.sort_by_key(|a| a.path_name);
+            for path in paths {
+                let style = if path.kind == PathKind::Painful {
+                    ", style=dashed"
+                } else {
+                    ""
+                };
+                lines.push(format!(
+                    "    \"{}\" -> \"{}\" [label=\"{}\"{}];",
+                    escape_dot(room_name),
+                    escape_dot(&path.target_room_name),
+                    escape_dot(&path.path_name),
+                    style
+                ));
+            }
+        }
+        lines.push("}".to_string());
+        lines.join("\n") + "\n"
+    }
+
+    pub fn print_room(&mut self, username: &UserName) -> Result<(), FaerieError> {
+        let lines = self.wrap_for(username, self.describe_room(username)?);
+        self.send_paged(username, &lines);
+        Ok(())
+    }
+
+    pub fn set_output_sink(&mut self, output: Box<dyn OutputSink>) {
+        self.output = output;
+    }
+
+    fn send_all(&mut self, user_name: &UserName, messages: &[String]) {
+        for m in messages {
+            self.output.send(user_name, m);
+        }
+    }
+
+    // Sends a message to everyone in the room, each in their own language.
+    fn tell_room(
+        &mut self,
+        room_name: &RoomName,
+        id: &str,
+        args: &[(&str, &str)],
+    ) -> Result<(), FaerieError> {
+        let mut listeners: Vec<UserName> = self
+            .rooms
+            .get_room(room_name)?
+            .users
+            .iter()
+            .cloned()
+            .collect();
+        listeners.sort();
+        for listener in listeners {
+            let text = self.text(&listener, id, args);
+            self.output.send(&listener, &text);
+        }
+        Ok(())
+    }
+
+    // Sends text to everyone in the room except one user, usually whoever caused it.
+    fn send_to_room_except(
+        &mut self,
+        room_name: &RoomName,
+        except_user_name: &UserName,
+        text: &str,
+    ) -> Result<(), FaerieError> {
+        for listener in self.room_listeners(room_name, except_user_name)? {
+            self.output.send(&listener, text);
+        }
+        Ok(())
+    }
+
+    // Like send_to_room_except, but each listener reads the message in their own language.
+    fn tell_room_except(
+        &mut self,
+        room_name: &RoomName,
+        except_user_name: &UserName,
+        id: &str,
+        args: &[(&str, &str)],
+    ) -> Result<(), FaerieError> {
+        for listener in self.room_listeners(room_name, except_user_name)? {
+            let text = self.text(&listener, id, args);
+            self.output.send(&listener, &text);
         }
+        Ok(())
+    }
+
+    // Everyone else in the room, less anyone ignoring the user the message is about.
+    fn room_listeners(
+        &self,
+        room_name: &RoomName,
+        except_user_name: &UserName,
+    ) -> Result<Vec<UserName>, FaerieError> {
+        let mut listeners: Vec<UserName> = self
+            .rooms
+            .get_room(room_name)?
+            .users
+            .iter()
+            .filter(|u| *u != except_user_name && !self.is_ignoring(u, except_user_name))
+            .cloned()
+            .collect();
+
+        listeners.sort();
+        Ok(listeners)
     }
 
-    pub fn create_room(&mut self, name: &RoomName, desc: String) {
-        let room = Room::new(name.clone(), desc);
+    pub fn describe_room(&self, username: &UserName) -> Result<Vec<String>, FaerieError> {
+        let user = self.users.get_user(username)?;
+        self.describe_room_for(username, &user.room_name)
+    }
+
+    // A room as the given user would see it, whether or not they're in it.
+    pub(crate) fn describe_room_for(
+        &self,
+        username: &UserName,
+        room_name: &RoomName,
+    ) -> Result<Vec<String>, FaerieError> {
+        let room = self.rooms.get_room(room_name)?;
+        let fmt = self.formatter_for(username);
+        if room.flags.contains(RoomFlags::DARK) {
+            return Ok(vec![
+                fmt.format_room_name(&room.name),
+                format!("  {}", self.text(username, "too_dark", &[])),
+            ]);
+        }
+        if self
+            .users
+            .get_user(username)?
+            .has_effect(StatusEffect::Blinded)
+        {
+            return Ok(vec![
+                fmt.format_room_name(&room.name),
+                format!("  {}", self.text(username, "blinded", &[])),
+            ]);
+        }
+
+        let mut lines = vec![fmt.format_room_name(&room.name)];
+        for paragraph in wrap::paragraphs(&self.room_description_for(username, room)) {
+            lines.push(format!("  {}", fmt.format_description(&paragraph)));
+        }
+        lines.push("".to_string());
+        lines.push(self.text(username, "paths_header", &[]));
+
+        let mut path_names: Vec<&PathName> = room
+            .paths
+            .iter()
+            .filter(|(_, path)| self.can_see_path(username, room_name, path))
+            .map(|(name, _)| name)
+            .collect();
+        path_names.sort();
+        for path_name in path_names {
+            lines.push(format!("* {}", fmt.format_exit(path_name)));
+        }
+
+        let mut on_floor: Vec<String> = room.items.iter().map(|i| with_article(&i.name)).collect();
+        if room.gold > 0 {
+            let gold = room.gold.to_string();
+            on_floor.push(self.text(username, "gold_amount", &[("gold", &gold)]));
+        }
+        if !on_floor.is_empty() {
+            let items = on_floor.join(", ");
+            lines.push(self.text(username, "on_the_ground", &[("items", &items)]));
+        }
+        for corpse in &room.corpses {
+            lines.push(self.text(username, "lies_here", &[("name", &corpse.name())]));
+        }
+        Ok(lines)
+    }
+
+    // Where newly arriving users are placed, e.g. by the server.
+    pub fn set_start_room(&mut self, room_name: &RoomName) -> Result<(), FaerieError> {
+        let entry = || JournalEntry::StartRoomSet {
+            room_name: *room_name,
+        };
+        self.journaled_if_ok(entry, |game_state| {
+            game_state.rooms.check_room_exists(room_name)?;
+            game_state.start_room_name = Some(*room_name);
+            Ok(())
+        })
+    }
+
+    pub fn start_room(&self) -> Option<&RoomName> {
+        self.start_room_name.as_ref()
+    }
+
+    pub fn set_formatter(&mut self, formatter: Box<dyn OutputFormatter>) {
+        self.formatter = formatter;
+    }
+
+    pub fn create_room(&mut self, name: &RoomName, desc: String) -> Result<(), FaerieError> {
+        let entry = || JournalEntry::RoomCreated {
+            room_name: *name,
+            description: desc.clone(),
+        };
+        self.journaled_if_ok(entry, |game_state| {
+            game_state.create_room_impl(name, desc.clone())
+        })
+    }
+
+    fn create_room_impl(&mut self, name: &RoomName, desc: String) -> Result<(), FaerieError> {
+        let room = Room::new(*name, desc)?;
         // TODO: make this an action on the roomcollection directly?
-        self.rooms.rooms.insert(name.clone(), room);
+        self.rooms.insert_room(room)?;
+
+        self.record_build_op(BuildOp::CreateRoom(*name));
+        Ok(())
+    }
+
+    pub fn set_room_description(
+        &mut self,
+        room_name: &RoomName,
+        desc: String,
+    ) -> Result<(), FaerieError> {
+        let entry = || JournalEntry::RoomDescribed {
+            room_name: *room_name,
+            description: desc.clone(),
+        };
+        self.journaled_if_ok(entry, |game_state| {
+            game_state.set_room_description_impl(room_name, desc.clone())
+        })
+    }
+
+    fn set_room_description_impl(
+        &mut self,
+        room_name: &RoomName,
+        desc: String,
+    ) -> Result<(), FaerieError> {
+        if desc.is_empty() {
+            return Err(FaerieError::EmptyRoomDescription);
+        }
+        let room = self.rooms.get_room_mut(room_name)?;
+        let old_description = std::mem::replace(&mut room.description, desc);
+        self.record_build_op(BuildOp::SetDescription {
+            room_name: *room_name,
+            old_description,
+        });
+        Ok(())
+    }
+
+    // Removes the room and every path leading into it. Occupied rooms can't be deleted.
+    pub fn delete_room(&mut self, room_name: &RoomName) -> Result<(), FaerieError> {
+        let entry = || JournalEntry::RoomDeleted {
+            room_name: *room_name,
+        };
+        self.journaled_if_ok(entry, |game_state| game_state.delete_room_impl(room_name))
+    }
+
+    fn delete_room_impl(&mut self, room_name: &RoomName) -> Result<(), FaerieError> {
+        let room = self.rooms.get_room(room_name)?;
+        if !room.users.is_empty() {
+            return Err(FaerieError::RoomOccupied(room_name.to_string()));
+        }
+
+        self.rooms.remove_room(room_name);
+        for room in self.rooms.rooms.values_mut() {
+            room.paths
+                .retain(|_path_name, path| &path.target_room_name != room_name);
+        }
+        Ok(())
+    }
+
+    // Removes the path in the given direction, along with its reverse if it has one.
+    pub fn remove_path(
+        &mut self,
+        source_room_name: &RoomName,
+        direction: Direction,
+    ) -> Result<(), FaerieError> {
+        let entry = || JournalEntry::PathRemoved {
+            source_room_name: *source_room_name,
+            direction: direction.clone(),
+        };
+        self.journaled_if_ok(entry, |game_state| {
+            game_state.remove_path_impl(source_room_name, direction.clone())
+        })
+    }
+
+    fn remove_path_impl(
+        &mut self,
+        source_room_name: &RoomName,
+        direction: Direction,
+    ) -> Result<(), FaerieError> {
+        let path_name = Direction::get_path_name(direction.clone());
+        let source_room = self.rooms.get_room_mut(source_room_name)?;
+        let removed = match source_room.remove_path(&path_name) {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        if let Some(d) = Direction::get_reverse(direction) {
+            let reverse_name = Direction::get_path_name(d);
+            if let Ok(target_room) = self.rooms.get_room_mut(&removed.target_room_name) {
+                let leads_back = PathName::lookup(&reverse_name)
+                    .and_then(|name| target_room.paths.get(&name))
+                    .is_some_and(|p| &p.target_room_name == source_room_name);
+                if leads_back {
+                    target_room.remove_path(&reverse_name);
+                }
+            }
+        }
+        Ok(())
     }
 
     pub fn create_room_from(
@@ -163,9 +978,10 @@ impl GameState {
         this_desc: String,
         other_room_name: &RoomName,
         direction: Direction,
-    ) {
-        self.create_room(this_name, this_desc);
-        self.add_path(other_room_name, this_name, direction);
+    ) -> Result<(), FaerieError> {
+        self.rooms.check_room_exists(other_room_name)?;
+        self.create_room(this_name, this_desc)?;
+        self.add_path(other_room_name, this_name, direction)
     }
 
     pub fn add_path(
@@ -173,12 +989,94 @@ impl GameState {
         source_room_name: &RoomName,
         target_room_name: &RoomName,
         direction: Direction,
-    ) {
-        self.add_path_impl(source_room_name, target_room_name, direction.clone());
+    ) -> Result<(), FaerieError> {
+        self.add_paths_of_type(source_room_name, target_room_name, direction, false)
+    }
+
+    pub fn add_painful_path(
+        &mut self,
+        source_room_name: &RoomName,
+        target_room_name: &RoomName,
+        direction: Direction,
+    ) -> Result<(), FaerieError> {
+        self.add_paths_of_type(source_room_name, target_room_name, direction, true)
+    }
+
+    fn add_paths_of_type(
+        &mut self,
+        source_room_name: &RoomName,
+        target_room_name: &RoomName,
+        direction: Direction,
+        painful: bool,
+    ) -> Result<(), FaerieError> {
+        let entry = || JournalEntry::PathAdded {
+            source_room_name: *source_room_name,
+            target_room_name: *target_room_name,
+            direction: direction.clone(),
+            painful,
+        };
+        let path_type: fn() -> PathType = if painful {
+            || PathType::Painful
+        } else {
+            || PathType::Normal
+        };
+        self.journaled_if_ok(entry, |game_state| {
+            game_state.add_paths(
+                source_room_name,
+                target_room_name,
+                direction.clone(),
+                path_type,
+            )
+        })
+    }
+
+    // Both directions are validated before either is added, so a failure leaves no half-made path.
+    fn add_paths(
+        &mut self,
+        source_room_name: &RoomName,
+        target_room_name: &RoomName,
+        direction: Direction,
+        path_type: fn() -> PathType,
+    ) -> Result<(), FaerieError> {
+        let reverse = Direction::get_reverse(direction.clone());
+        self.check_path_addable(source_room_name, target_room_name, direction.clone())?;
+        if let Some(d) = reverse.clone() {
+            self.check_path_addable(target_room_name, source_room_name, d)?;
+        }
 
-        if let Some(d) = Direction::get_reverse(direction.clone()) {
-            self.add_path_impl(target_room_name, source_room_name, d);
+        self.add_path_impl(
+            source_room_name,
+            target_room_name,
+            direction.clone(),
+            path_type(),
+        )?;
+        match reverse {
+            Some(d) => self.add_path_impl(target_room_name, source_room_name, d, path_type())?,
+            None => {
+                let path_name = Direction::get_path_name(direction.clone());
+                self.get_path_mut(source_room_name, &path_name)?
+                    .flags
+                    .insert(PathFlags::ONE_WAY);
+            }
         }
+
+        self.record_build_op(BuildOp::AddPath {
+            source_room_name: *source_room_name,
+            direction,
+        });
+        Ok(())
+    }
+
+    fn check_path_addable(
+        &self,
+        source_room_name: &RoomName,
+        target_room_name: &RoomName,
+        direction: Direction,
+    ) -> Result<(), FaerieError> {
+        let path_name = PathName::new(&Direction::get_path_name(direction))?;
+        self.rooms.check_room_exists(target_room_name)?;
+        let source_room = self.rooms.get_room(source_room_name)?;
+        source_room.check_duplicate_path(&path_name)
     }
 
     fn add_path_impl(
@@ -186,17 +1084,35 @@ impl GameState {
         source_room_name: &RoomName,
         target_room_name: &RoomName,
         direction: Direction,
-    ) {
-        self.rooms.check_room_exists(target_room_name);
-        let source_room = self.rooms.get_room_mut(source_room_name);
-        let path_name = Direction::get_path_name(direction.clone());
-        source_room.add_path(target_room_name, &path_name);
+        path_type: PathType,
+    ) -> Result<(), FaerieError> {
+        self.rooms.check_room_exists(target_room_name)?;
+        let source_room = self.rooms.get_room_mut(source_room_name)?;
+        let path_name = PathName::new(&Direction::get_path_name(direction))?;
+        source_room.check_duplicate_path(&path_name)?;
+        source_room.add_path_special(target_room_name, &path_name, path_type)
     }
 
-    fn get_user_location(&self, user_name: &UserName) -> RoomName {
-        let user = self.users.get_user(user_name);
-        self.rooms.check_room_exists(&user.room_name);
-        user.room_name.clone()
+    // Every room with someone in it, in no particular order. A big world has far more rooms
+    // than users, so they're found by where the users are.
+    fn occupied_rooms(&self) -> Vec<&Room> {
+        let room_names: HashSet<&RoomName> = self
+            .users
+            .users
+            .values()
+            .map(|user| &user.room_name)
+            .collect();
+        room_names
+            .into_iter()
+            .filter_map(|room_name| self.rooms.rooms.get(room_name))
+            .filter(|room| !room.users.is_empty())
+            .collect()
+    }
+
+    fn get_user_location(&self, user_name: &UserName) -> Result<RoomName, FaerieError> {
+        let user = self.users.get_user(user_name)?;
+        self.rooms.check_room_exists(&user.room_name)?;
+        Ok(user.room_name)
     }
 
     pub fn create_user_in_room(
@@ -204,447 +1120,1064 @@ impl GameState {
         user_name: &UserName,
         room_name: &RoomName,
         user_type: UserType,
-    ) {
-        let user = User::new(user_name.clone(), room_name.clone(), user_type);
-        self.users.users.insert(user_name.clone(), user);
+    ) -> Result<(), FaerieError> {
+        let entry = || JournalEntry::UserCreated {
+            user_name: *user_name,
+            room_name: *room_name,
+            user_type,
+        };
+        self.journaled_if_ok(entry, |game_state| {
+            game_state.create_user_in_room_impl(user_name, room_name, user_type)
+        })
+    }
 
-        let room = self.rooms.get_room_mut(room_name);
-        room.users.insert(user_name.clone());
+    fn create_user_in_room_impl(
+        &mut self,
+        user_name: &UserName,
+        room_name: &RoomName,
+        user_type: UserType,
+    ) -> Result<(), FaerieError> {
+        self.rooms.check_room_exists(room_name)?;
+        let user = User::new(*user_name, *room_name, user_type);
+        self.users.insert_user(user)?;
+        self.last_input_at.insert(*user_name, self.tick_count);
+
+        let room = self.rooms.get_room_mut(room_name)?;
+        room.users.insert(*user_name);
+        Ok(())
     }
 
-    pub fn create_basic_user_in_room(&mut self, user_name: &UserName, room_name: &RoomName) {
-        let user_type = UserType::Civilian;
-        let user = User::new(user_name.clone(), room_name.clone(), user_type);
-        self.users.users.insert(user_name.clone(), user);
+    // Takes a user out of the world altogether, along with any fight, party or trade they're in.
+    pub fn remove_user(&mut self, user_name: &UserName) -> Result<(), FaerieError> {
+        let entry = || JournalEntry::UserRemoved {
+            user_name: *user_name,
+        };
+        self.journaled_if_ok(entry, |game_state| game_state.remove_user_impl(user_name))
+    }
 
-        let room = self.rooms.get_room_mut(room_name);
-        room.users.insert(user_name.clone());
+    fn remove_user_impl(&mut self, user_name: &UserName) -> Result<(), FaerieError> {
+        let room_name = self.get_user_location(user_name)?;
+        self.end_trade(user_name, "trade_off_left");
+        self.rooms.get_room_mut(&room_name)?.users.remove(user_name);
+        self.users.remove_user(user_name);
+        self.combat_targets
+            .retain(|attacker, target| attacker != user_name && target != user_name);
+        self.leaders
+            .retain(|follower, leader| follower != user_name && leader != user_name);
+        self.conversations
+            .retain(|talker, conversation| talker != user_name && conversation.npc != *user_name);
+        self.dead_users.remove(user_name);
+        self.last_failures.remove(user_name);
+        self.last_input_at.remove(user_name);
+        self.afk_users.remove(user_name);
+        self.voided_users.remove(user_name);
+        Ok(())
     }
 
-    pub fn process_input(&mut self, user_name: &UserName, user_input: &String) {
+    // Moves a user between rooms without any checks or announcements.
+    pub(crate) fn relocate_user(
+        &mut self,
+        user_name: &UserName,
+        target_room_name: &RoomName,
+    ) -> Result<(), FaerieError> {
+        let room_name = self.get_user_location(user_name)?;
+        self.rooms.check_room_exists(target_room_name)?;
+        let user = self.users.get_user_mut(user_name)?;
+        user.room_name = *target_room_name;
+        user.visited_rooms.insert(*target_room_name);
+        // Rooms hold the name as it's stored, whatever case it was given in.
+        let user_name = user.name;
+        self.rooms
+            .get_room_mut(target_room_name)?
+            .users
+            .insert(user_name);
+        self.rooms
+            .get_room_mut(&room_name)?
+            .users
+            .remove(&user_name);
+        Ok(())
+    }
+
+    pub fn create_basic_user_in_room(
+        &mut self,
+        user_name: &UserName,
+        room_name: &RoomName,
+    ) -> Result<(), FaerieError> {
+        self.create_user_in_room(user_name, room_name, UserType::Civilian)
+    }
+
+    pub fn process_input(&mut self, user_name: &UserName, user_input: &str) {
+        let entry = || JournalEntry::Input {
+            user_name: *user_name,
+            input: user_input.to_string(),
+        };
+        self.journaled(entry, |game_state| {
+            game_state.process_input_and_send(user_name, user_input)
+        })
+    }
+
+    fn process_input_and_send(&mut self, user_name: &UserName, user_input: &str) {
+        let user_name = &self.canonical_user_name(user_name);
+        // Pressing enter while reading a long reply shows the next page of it.
+        if user_input.trim().is_empty() && self.send_next_page(user_name) {
+            return;
+        }
+        self.pages.remove(user_name);
         let attempt = self.process_input_impl(user_name, user_input);
         match attempt {
             Ok(succ) => {
-                for m in &succ.messages {
-                    println!("{}", m);
-                }
-
-                if succ.was_room_move() {
-                    self.print_room(user_name);
-                }
+                let messages = self.render_events(user_name, &succ.events);
+                self.send_paged(user_name, &messages);
+                self.check_deaths_near(user_name);
             }
             Err(unsucc) => {
-                for m in &unsucc.messages {
-                    println!("{}", m);
-                }
+                let fmt = self.formatter_for(user_name);
+                let messages: Vec<String> = unsucc
+                    .messages
+                    .iter()
+                    .map(|m| fmt.format_error(m))
+                    .collect();
+                let messages = self.wrap_for(user_name, messages);
+                self.send_all(user_name, &messages);
             }
         }
     }
 
+    // Hosts may give the name in any case; from here on it's used as stored.
+    fn canonical_user_name(&self, user_name: &UserName) -> UserName {
+        self.users.resolve(user_name).cloned().unwrap_or(*user_name)
+    }
+
     pub fn process_input_impl(
         &mut self,
         user_name: &UserName,
-        user_input: &String,
+        user_input: &str,
     ) -> Result<ActionSuccess, ActionFailure> {
-        // TODO: add a did_move to action success, or just special case it here for print room
-        let global_action_attempt = self.attempt_global_action(user_name, user_input);
-        if let Some(glob_succ) = global_action_attempt {
-            return glob_succ;
+        let user_name = &self.canonical_user_name(user_name);
+        self.last_input_at.insert(*user_name, self.tick_count);
+        self.wake_if_idle(user_name);
+        // Any input at all is handled, however long or strange.
+        let user_input = &parser::sanitize_input(user_input);
+        let attempt = self.dispatch_input(user_name, user_input);
+        self.log_command(user_name, user_input, &attempt);
+        match &attempt {
+            Ok(_) => {
+                self.last_failures.remove(user_name);
+            }
+            Err(unsucc) => {
+                self.last_failures.insert(*user_name, unsucc.clone());
+            }
         }
+        attempt
+    }
 
-        self.attempt_move(user_name, user_input)
+    fn dispatch_input(
+        &mut self,
+        user_name: &UserName,
+        user_input: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        if self.paused && self.reject_input_while_paused {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "world_paused", &[])],
+                FailReason::WorldPaused,
+            ));
+        }
+        if user_input.chars().count() > parser::MAX_INPUT_LEN {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "input_too_long", &[])],
+                FailReason::InputTooLong,
+            ));
+        }
+        self.refuse_if_dead(user_name)?;
+
+        if let Some(res) = self.run_registered_command(user_name, user_input) {
+            return res;
+        }
+        if let Some(res) = self.run_social(user_name, user_input) {
+            return res;
+        }
+        if let Some(steps) = self.speedwalk_steps(user_name, user_input) {
+            return self.speedwalk(user_name, &steps);
+        }
+
+        match parser::parse(user_input) {
+            Ok(Command::Global(action)) => self.run_global_action(user_name, action),
+            Ok(Command::Go(path_name)) => self.attempt_move(user_name, &path_name),
+            Ok(Command::Bare(input)) => match self.attempt_move(user_name, &input) {
+                Err(failure) if failure.reason == FailReason::NoSuchPath => {
+                    Err(self.unknown_command(user_name, &input))
+                }
+                res => res,
+            },
+            Ok(Command::Sneak(path_name)) => self.sneak(user_name, &path_name),
+            Ok(Command::Talk(npc_name)) => self.talk(user_name, &npc_name),
+            Ok(Command::Respond(choice)) => self.respond(user_name, choice),
+            Ok(Command::Attack(target_name)) => self.attack(user_name, &target_name),
+            Ok(Command::Say(text)) => self.say(user_name, &text),
+            Ok(Command::Whisper { target, text }) => self.whisper(user_name, &target, &text),
+            Ok(Command::Shout(text)) => self.shout(user_name, &text),
+            Ok(Command::Emote(text)) => self.emote(user_name, &text),
+            Ok(Command::Follow(target_name)) => self.follow(user_name, &target_name),
+            Ok(Command::Unfollow) => self.unfollow(user_name),
+            Ok(Command::Travel(room_name)) => self.travel(user_name, &room_name),
+            Ok(Command::Search) => self.search(user_name),
+            Ok(Command::Exits) => self.exits(user_name),
+            Ok(Command::Party) => self.show_party(user_name),
+            Ok(Command::Look(target)) => self.look(user_name, target.as_deref()),
+            Ok(Command::Examine(target_name)) => self.examine(user_name, &target_name),
+            Ok(Command::Lock(path_name)) => self.set_lock_state(user_name, &path_name, true),
+            Ok(Command::Unlock(path_name)) => self.set_lock_state(user_name, &path_name, false),
+            Ok(Command::Quests) => self.list_quests(user_name, false),
+            Ok(Command::Journal) => self.list_quests(user_name, true),
+            Ok(Command::Wield(item_name)) => self.wield(user_name, &item_name),
+            Ok(Command::Wear(item_name)) => self.wear(user_name, &item_name),
+            Ok(Command::Remove(item_name)) => self.remove_equipment(user_name, &item_name),
+            Ok(Command::Score) => self.score(user_name),
+            Ok(Command::List) => self.list_wares(user_name),
+            Ok(Command::Buy(item_name)) => self.buy(user_name, &item_name),
+            Ok(Command::Sell(item_name)) => self.sell(user_name, &item_name),
+            Ok(Command::Trade(target_name)) => self.start_trade(user_name, &target_name),
+            Ok(Command::ShowTrade) => self.show_trade(user_name),
+            Ok(Command::CancelTrade) => self.cancel_trade(user_name),
+            Ok(Command::Offer(offered)) => self.offer(user_name, &offered),
+            Ok(Command::ConfirmTrade) => self.confirm_trade(user_name),
+            Ok(Command::Roll(notation)) => self.roll(user_name, &notation),
+            Ok(Command::Where) => self.where_am_i(user_name),
+            Ok(Command::Help(topic)) => self.help(user_name, topic.as_deref()),
+            Ok(Command::Prompt(template)) => self.set_prompt(user_name, template.as_deref()),
+            Ok(Command::PageSize(lines)) => self.set_page_size(user_name, lines.as_deref()),
+            Ok(Command::Width(columns)) => self.set_width(user_name, columns.as_deref()),
+            Ok(Command::Language(locale)) => self.set_language(user_name, locale.as_deref()),
+            Ok(Command::Map) => self.map(user_name),
+            Ok(Command::Rest) => self.rest(user_name),
+            Ok(Command::Skills) => self.skills(user_name),
+            Ok(Command::Achievements) => self.achievements(user_name),
+            Ok(Command::Who) => self.who(user_name),
+            Ok(Command::MailSend {
+                recipient_name,
+                text,
+            }) => self.send_mail(user_name, &recipient_name, &text),
+            Ok(Command::MailRead) => self.read_mail(user_name),
+            Ok(Command::Friends) => self.list_friends(user_name),
+            Ok(Command::FriendAdd(target_name)) => self.add_friend(user_name, &target_name),
+            Ok(Command::FriendRemove(target_name)) => self.remove_friend(user_name, &target_name),
+            Ok(Command::Ignored) => self.list_ignored(user_name),
+            Ok(Command::IgnoreAdd(target_name)) => self.ignore(user_name, &target_name),
+            Ok(Command::IgnoreRemove(target_name)) => self.unignore(user_name, &target_name),
+            Ok(Command::GuildInfo) => self.show_guild(user_name),
+            Ok(Command::GuildCreate(guild_name)) => self.create_guild(user_name, &guild_name),
+            Ok(Command::GuildInvite(target_name)) => self.invite_to_guild(user_name, &target_name),
+            Ok(Command::GuildJoin(guild_name)) => self.join_guild(user_name, &guild_name),
+            Ok(Command::GuildLeave) => self.leave_guild(user_name),
+            Ok(Command::GuildKick(target_name)) => self.kick_from_guild(user_name, &target_name),
+            Ok(Command::GuildPromote(target_name)) => {
+                self.promote_in_guild(user_name, &target_name)
+            }
+            Ok(Command::GuildDemote(target_name)) => self.demote_in_guild(user_name, &target_name),
+            Ok(Command::GuildDeposit(amount)) => self.deposit_to_guild(user_name, &amount),
+            Ok(Command::GuildWithdraw(amount)) => self.withdraw_from_guild(user_name, &amount),
+            Ok(Command::Channels) => self.list_channels(user_name),
+            Ok(Command::JoinChannel(channel_name)) => self.join_channel(user_name, &channel_name),
+            Ok(Command::LeaveChannel(channel_name)) => self.leave_channel(user_name, &channel_name),
+            Ok(Command::MuteOnChannel {
+                channel_name,
+                target_name,
+            }) => self.mute_on_channel(user_name, &channel_name, &target_name),
+            Ok(Command::Chat { channel_name, text }) => self.chat(user_name, &channel_name, &text),
+            Ok(Command::MailDelete(number)) => self.delete_mail(user_name, &number),
+            Ok(Command::Title(title)) => self.choose_title(user_name, title.as_deref()),
+            Ok(Command::Bio(description)) => self.set_user_description(user_name, &description),
+            Ok(Command::Pick(path_name)) => self.pick_lock(user_name, &path_name),
+            Ok(Command::Take(item_name)) => self.take(user_name, &item_name),
+            Ok(Command::TakeFrom {
+                item_name,
+                container_name,
+            }) => self.take_from(user_name, &item_name, &container_name),
+            Ok(Command::Put {
+                item_name,
+                container_name,
+            }) => self.put_in(user_name, &item_name, &container_name),
+            Ok(Command::Loot(owner_name)) => self.loot(user_name, owner_name.as_deref()),
+            Ok(Command::UseAbility(ability, target_name)) => {
+                self.use_ability(user_name, ability, target_name.as_deref())
+            }
+            Ok(Command::Color(color)) => self.toggle_color(user_name, color),
+            Ok(Command::Teleport(room_name)) => self.teleport(user_name, &room_name),
+            Ok(Command::Transfer {
+                user_name: target_name,
+                room_name,
+            }) => self.transfer(user_name, &target_name, &room_name),
+            Ok(Command::CreateRoom(room_name)) => self.create_room_command(user_name, &room_name),
+            Ok(Command::Kick(target_name)) => self.kick(user_name, &target_name),
+            Ok(Command::Ban(target_name)) => self.ban(user_name, &target_name),
+            Ok(Command::Announce(text)) => self.announce(user_name, &text),
+            Ok(Command::Unban(target_name)) => self.unban(user_name, &target_name),
+            Ok(Command::Mute(target_name)) => self.mute(user_name, &target_name),
+            Ok(Command::Dig {
+                direction,
+                room_name,
+            }) => self.dig(user_name, &direction, &room_name),
+            Ok(Command::Describe(text)) => self.describe_command(user_name, &text),
+            Ok(Command::Rename(room_name)) => self.rename_command(user_name, &room_name),
+            Ok(Command::DelPath(path_name)) => self.delpath(user_name, &path_name),
+            Err(ParseError::Empty) => Err(ActionFailure::new(vec![], FailReason::EmptyInput)),
+            Err(ParseError::MissingArgument { prompt, .. }) => Err(ActionFailure::new(
+                vec![self.text(user_name, prompt, &[])],
+                FailReason::MissingArgument,
+            )),
+        }
+    }
+
+    /// The most recent failure from process_input for this user, cleared by the next success.
+    pub fn last_failure(&self, user_name: &UserName) -> Option<&ActionFailure> {
+        self.last_failures.get(user_name)
     }
 
     pub fn attempt_global_action(
         &mut self,
         user_name: &UserName,
-        possible_action_name: &String,
+        possible_action_name: &str,
     ) -> Option<Result<ActionSuccess, ActionFailure>> {
-        let action = GlobalActions::from_text(possible_action_name);
-        if let Some(act) = action {
-            match act {
-                GlobalActions::ListOnlineUsers => {
-                    let messages = self.get_online_users_message();
-                    return Some(Ok(ActionSuccess::new(messages)));
-                }
-            }
-        } else {
-            None
+        GlobalActions::from_text(possible_action_name)
+            .map(|action| self.run_global_action(user_name, action))
+    }
+
+    fn run_global_action(
+        &mut self,
+        user_name: &UserName,
+        action: GlobalActions,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        match action {
+            GlobalActions::ListOnlineUsers => self.who(user_name),
+            GlobalActions::BuildUndo => self.undo_build_op_by(user_name),
         }
     }
 
     pub fn attempt_move(
         &mut self,
         user_name: &UserName,
-        possible_path_name: &String,
+        possible_path_name: &str,
     ) -> Result<ActionSuccess, ActionFailure> {
-        let possible_path_name = Path::match_basic_aliases(possible_path_name.clone());
+        self.attempt_move_impl(user_name, possible_path_name, false)
+    }
 
-        // TODO: should this be a none? i think this whole function should return None if 
-        if possible_path_name == "" {
-            return Err(ActionFailure { messages: vec![] });
-        }
+    // Sneaking users are only announced to those who spot them; see sneak.rs.
+    pub(crate) fn attempt_move_impl(
+        &mut self,
+        user_name: &UserName,
+        possible_path_name: &str,
+        sneaking: bool,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let possible_path_name = Path::match_basic_aliases(possible_path_name.to_string());
 
-        let mut messages = vec![];
+        // TODO: should this be a none? i think this whole function should return None if
+        if possible_path_name.is_empty() {
+            return Err(ActionFailure::new(vec![], FailReason::EmptyInput));
+        }
 
-        let room_name = self.get_user_location(user_name);
-        let room = self.rooms.get_room_mut(&room_name);
+        let mut succ = ActionSuccess::default();
+
+        self.refuse_if_slowed(user_name)?;
+        let room_name = self.get_user_location(user_name)?;
+        let possible_path_name = self
+            .match_exit(user_name, &room_name, &possible_path_name)?
+            .unwrap_or(possible_path_name);
+        let mut stamina_cost = 0;
+        let mut skill_used = None;
+        let mut hurt = None;
+        if let Some(path) = self
+            .rooms
+            .get_room(&room_name)?
+            .get_path(&possible_path_name)
+        {
+            if path.flags.contains(PathFlags::CLOSED) {
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "path_closed", &[("exit", &possible_path_name)])],
+                    FailReason::PathClosed,
+                ));
+            }
+            if self.users.get_user(user_name)?.npc
+                && self.room_has_flag(&path.target_room_name, RoomFlags::NO_MOB)
+            {
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "exit_blocked", &[])],
+                    FailReason::ExitBlocked,
+                ));
+            }
+            self.refuse_if_unskilled(user_name, path)?;
+            skill_used = path.required_skill.map(|check| check.skill);
+            stamina_cost = self.refuse_if_exhausted(user_name, &path.target_room_name)?;
+            if path.kind == PathKind::Painful {
+                hurt = Some(self.text(user_name, "painful_path", &[]));
+            }
+        }
+        let room = self.rooms.get_room_mut(&room_name)?;
 
         // TODO: make a pathcollection on each room, make a convenience function which does this?
         // TODO: move this out and/or give a global "I do not understand"
-        let path = match room.paths.get_mut(&possible_path_name) {
-            Some(p) => Ok(p),
-            None => Err(ActionFailure {
-                messages: vec![format!(
-                    // TODO: better message
-                    "What? There's no direction {} from {}.",
-                    possible_path_name, room_name
-                )],
-            }),
-        }?;
+        let path = match room.get_path_mut(&possible_path_name) {
+            Some(p) => p,
+            None => {
+                // TODO: better message
+                let args = [("exit", possible_path_name.as_str()), ("room", &room_name)];
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "no_such_path", &args)],
+                    FailReason::NoSuchPath,
+                ));
+            }
+        };
+
+        if let Some(lock) = path.lock.as_ref().filter(|l| l.locked) {
+            let key_name = lock.key_name.to_string();
+            let hint = if self.users.get_user(user_name)?.has_item(&lock.key_name) {
+                self.text(user_name, "try_unlocking", &[("exit", &possible_path_name)])
+            } else {
+                self.text(user_name, "need_key", &[("key", &key_name)])
+            };
+            let args = [("exit", possible_path_name.as_str()), ("hint", &hint)];
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "path_locked", &args)],
+                FailReason::PathLocked,
+            ));
+        }
+
+        if let Some(ref mut entry_lambda) = path.entry_cond {
+            let user = self.users.get_user_mut(user_name)?;
+            let mut action_succ = entry_lambda(user)?;
+            succ.append(&mut action_succ);
+        }
 
         // TODO: make this a method somewhere
-        // TODO: pass actionsuccess/failure messages through as a single thing?
         if let Some(ref mut exit_lambda) = path.exit_cond {
-            let user = self.users.get_user_mut(user_name);
+            if let Some(text) = hurt {
+                succ.push(ActionEvent::Message { text });
+            }
+            let user = self.users.get_user_mut(user_name)?;
+
             let exit_lambda_result = exit_lambda(user);
             match exit_lambda_result {
                 Ok(mut action_succ) => {
-                    messages.append(&mut action_succ.messages);
+                    succ.append(&mut action_succ);
                     Ok(true)
                 }
                 Err(action_fail) => Err(action_fail),
             }?;
         }
 
-        let target_room_name = path.target_room_name.clone();
-        let target_room = self.rooms.get_room_mut(&target_room_name);
-        target_room.users.insert(user_name.clone());
+        let target_room_name = path.target_room_name;
+        let path_name = path.path_name;
+        self.relocate_user(user_name, &target_room_name)?;
+        self.spend_stamina(user_name, stamina_cost);
+        if let Some(skill) = skill_used {
+            succ.push_messages(self.practice_skill(user_name, skill));
+        }
 
-        let user = self.users.get_user_mut(user_name);
-        user.room_name = target_room_name.clone();
+        let spotted_by = if sneaking {
+            Some(self.spot_sneaker(user_name, &[&room_name, &target_room_name])?)
+        } else {
+            None
+        };
+        let leave_id = match Direction::from_name(&path_name) {
+            Some(_) => "leaves",
+            None => "leaves_via",
+        };
+        let args = [("name", user_name.as_str()), ("exit", path_name.as_str())];
+        self.announce_movement(&room_name, user_name, leave_id, &args, spotted_by.as_ref())?;
+        match self.compass_path_between(&target_room_name, &room_name)? {
+            Some(from) => {
+                let args = [("name", user_name.as_str()), ("exit", from.as_str())];
+                self.announce_movement(
+                    &target_room_name,
+                    user_name,
+                    "arrives_from",
+                    &args,
+                    spotted_by.as_ref(),
+                )?;
+            }
+            None => {
+                let args = [("name", user_name.as_str())];
+                self.announce_movement(
+                    &target_room_name,
+                    user_name,
+                    "arrives",
+                    &args,
+                    spotted_by.as_ref(),
+                )?;
+            }
+        }
+        if let Some(spotted_by) = &spotted_by {
+            succ.push_messages(self.finish_sneaking(user_name, spotted_by));
+        }
+        succ.push_messages(self.run_room_hook(&room_name, user_name, RoomHookKind::Exit)?);
+        succ.push_messages(self.run_room_hook(
+            &target_room_name,
+            user_name,
+            RoomHookKind::Enter,
+        )?);
+        succ.push_messages(self.publish(GameEvent::Moved {
+            user_name: *user_name,
+            from: room_name,
+            to: target_room_name,
+            path_name,
+        }));
+        self.note_move(user_name);
+        self.move_followers(user_name, &room_name, &path_name);
+        succ.push(ActionEvent::RoomMoved {
+            from: room_name,
+            to: target_room_name,
+        });
+        Ok(succ)
+    }
 
-        let room = self.rooms.get_room_mut(&room_name);
-        room.users.take(user_name);
+    // The compass direction leading from one room to another, if any, for arrival messages.
+    fn compass_path_between(
+        &self,
+        source_room_name: &RoomName,
+        target_room_name: &RoomName,
+    ) -> Result<Option<PathName>, FaerieError> {
+        let room = self.rooms.get_room(source_room_name)?;
+        let mut compass_paths: Vec<&PathName> = room
+            .paths
+            .iter()
+            .filter(|(name, path)| {
+                &path.target_room_name == target_room_name && Direction::from_name(name).is_some()
+            })
+            .map(|(name, _)| name)
+            .collect();
+        compass_paths.sort();
+        Ok(compass_paths.first().map(|name| *(*name)))
+    }
 
-        let mut succ = ActionSuccess::new(messages);
-        succ.set_was_room_move();
-        Ok(succ)
+    // Rooms that can be reached from start_room_name, but only by taking at least one painful path.
+    pub fn find_rooms_only_reachable_painfully(
+        &self,
+        start_room_name: &RoomName,
+    ) -> Result<Vec<RoomName>, FaerieError> {
+        let reachable = self.reachable_rooms(start_room_name, true)?;
+        let safely_reachable = self.reachable_rooms(start_room_name, false)?;
+
+        let mut painful_only: Vec<RoomName> =
+            reachable.difference(&safely_reachable).cloned().collect();
+        painful_only.sort();
+        Ok(painful_only)
     }
 
-    fn get_online_users_message(&self) -> Vec<String> {
-        let users: Vec<&String> = self.users.users.keys().collect();
-        format_user_list(users)
+    fn reachable_rooms(
+        &self,
+        start_room_name: &RoomName,
+        allow_painful: bool,
+    ) -> Result<HashSet<RoomName>, FaerieError> {
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        self.rooms.check_room_exists(start_room_name)?;
+        seen.insert(*start_room_name);
+        queue.push_back(*start_room_name);
+
+        while let Some(room_name) = queue.pop_front() {
+            let room = self.rooms.get_room(&room_name)?;
+            for path in room.paths.values() {
+                if !allow_painful && path.kind == PathKind::Painful {
+                    continue;
+                }
+                // Dangling paths are validate_world's business, not a reason to give up here.
+                if self
+                    .rooms
+                    .check_room_exists(&path.target_room_name)
+                    .is_err()
+                {
+                    continue;
+                }
+                if seen.insert(path.target_room_name) {
+                    queue.push_back(path.target_room_name);
+                }
+            }
+        }
+        Ok(seen)
     }
 
-}
+    // The shortest sequence of path names leading from one room to another, following paths
+    // only in the direction they go. Ties are broken by path name, so routes are stable.
+    pub fn find_path(&self, from: &RoomName, to: &RoomName) -> Option<Vec<PathName>> {
+        self.rooms.get_room(to).ok()?;
+        let mut came_from: HashMap<RoomName, (RoomName, PathName)> = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(*from);
+
+        while let Some(room_name) = queue.pop_front() {
+            if &room_name == to {
+                let mut route = vec![];
+                let mut current = room_name;
+                while let Some((previous, path_name)) = came_from.remove(&current) {
+                    route.push(path_name);
+                    current = previous;
+                }
+                route.reverse();
+                return Some(route);
+            }
 
-fn format_user_list(users: Vec<&String>) -> Vec<String> {
-    let mut messages = vec!["Users online:".to_string()];
-    for username in users {
-        messages.push(format!("* {}", username.to_string()));
+            let room = match self.rooms.get_room(&room_name) {
+                Ok(room) => room,
+                Err(_) => continue,
+            };
+            let mut paths: Vec<&Path> = room.paths.values().collect();
+            paths.sort_by_key(|a| a.path_name);
+            for path in paths {
+                let target = &path.target_room_name;
+                if target != from && !came_from.contains_key(target) {
+                    came_from.insert(*target, (room_name, path.path_name));
+                    queue.push_back(*target);
+                }
+            }
+        }
+        None
     }
-    messages
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn make_simple_2_room_north_map() -> (GameState, UserName, RoomName, RoomName) {
-        let mut game_state = GameState::new();
-        let room1name = "room1".to_string();
-        let room2name = "room2".to_string();
-
-        game_state.create_room(&room1name, "description".to_string());
-        game_state.create_room_from(
-            &room2name,
-            "description2".to_string(),
-            &room1name,
-            Direction::North,
-        );
+    fn make_simple_2_room_north_map() -> (GameState, UserName, RoomName, RoomName) {
+        let game_state = GameStateBuilder::new()
+            .room("room1", "description")
+            .room("room2", "description2")
+            .path("room1", "room2", Direction::North)
+            .user("user1", "room1")
+            .build()
+            .unwrap();
+        (
+            game_state,
+            UserName::new("user1").unwrap(),
+            RoomName::new("room1").unwrap(),
+            RoomName::new("room2").unwrap(),
+        )
+    }
+
+    #[test]
+    fn strange_and_overlong_input_is_handled() {
+        let (mut game_state, user1name, _, room2name) = make_simple_2_room_north_map();
+        for input in &["", "\u{0}", "\u{1b}[2J", "\t\r\n", "\u{202e}"] {
+            assert!(game_state.process_input_impl(&user1name, input).is_err());
+        }
+        game_state
+            .process_input_impl(&user1name, "\u{7f}nor\u{0}th\r\n")
+            .unwrap();
+        assert_eq!(game_state.get_user_location(&user1name).unwrap(), room2name);
+
+        let long = format!("say {}", "a".repeat(parser::MAX_INPUT_LEN * 100));
+        let fail = game_state
+            .process_input_impl(&user1name, &long)
+            .unwrap_err();
+        assert!(matches!(fail.reason, FailReason::InputTooLong));
+    }
 
-        let user1name = "user1".to_string();
-        game_state.create_user_in_room(&user1name, &room1name, UserType::Civilian);
-        (game_state, user1name, room1name, room2name)
+    #[test]
+    fn lookups_ignore_case_and_spacing() {
+        let game_state = GameStateBuilder::new()
+            .room("Great Hall", "Banners.")
+            .room("Garden", "Roses.")
+            .path("Great Hall", "Garden", Direction::North)
+            .user("Alice", "Great Hall")
+            .build()
+            .unwrap();
+        let mut game_state = GameState::from_json(&game_state.to_json().unwrap()).unwrap();
+        assert_eq!(
+            game_state.rooms.get_room(" great hall").unwrap().name,
+            "Great Hall"
+        );
+        assert_eq!(game_state.users.get_user("ALICE ").unwrap().name, "Alice");
+        assert!(game_state.rooms.get_room("great").is_err());
+
+        let alice = UserName::new("alice").unwrap();
+        game_state.process_input_impl(&alice, "NORTH").unwrap();
+        assert_eq!(game_state.get_user_location(&alice).unwrap(), "Garden");
+        game_state.process_input_impl(&alice, "S").unwrap();
+        let hall = game_state.rooms.get_room("Great Hall").unwrap();
+        assert_eq!(hall.users.iter().collect::<Vec<_>>(), vec!["Alice"]);
+        assert!(hall.check_duplicate_path("North").is_err());
     }
 
     #[test]
     fn move_double_norf() {
         let mut game_state = GameState::new();
 
-        let room1name = "room1".to_string();
-        let room2name = "room2".to_string();
-        let room3name = "room3".to_string();
-        game_state.create_room(&room1name, "yeet".to_string());
-        game_state.create_room(&room2name, "dang".to_string());
-        game_state.create_room(&room3name, "where am i".to_string());
-
-        game_state.add_path(&room1name, &room2name, Direction::North);
-        game_state.add_path(&room2name, &room3name, Direction::North);
-
-        let user1name = "user1".to_string();
-        game_state.create_user_in_room(&user1name, &room1name, UserType::Civilian);
+        let room1name = RoomName::new("room1").unwrap();
+        let room2name = RoomName::new("room2").unwrap();
+        let room3name = RoomName::new("room3").unwrap();
+        game_state
+            .create_room(&room1name, "yeet".to_string())
+            .unwrap();
+        game_state
+            .create_room(&room2name, "dang".to_string())
+            .unwrap();
+        game_state
+            .create_room(&room3name, "where am i".to_string())
+            .unwrap();
 
         game_state
-            .attempt_move(&user1name, &"north".to_string())
+            .add_path(&room1name, &room2name, Direction::North)
+            .unwrap();
+        game_state
+            .add_path(&room2name, &room3name, Direction::North)
             .unwrap();
+
+        let user1name = UserName::new("user1").unwrap();
         game_state
-            .attempt_move(&user1name, &"n".to_string())
+            .create_user_in_room(&user1name, &room1name, UserType::Civilian)
             .unwrap();
 
-        let user = game_state.users.get_user(&user1name);
+        game_state.attempt_move(&user1name, "north").unwrap();
+        game_state.attempt_move(&user1name, "n").unwrap();
+
+        let user = game_state.users.get_user(&user1name).unwrap();
         assert_eq!(user.room_name, room3name);
 
-        let room1 = game_state.rooms.get_room(&room1name);
-        let room2 = game_state.rooms.get_room(&room2name);
-        let room3 = game_state.rooms.get_room(&room3name);
+        let room1 = game_state.rooms.get_room(&room1name).unwrap();
+        let room2 = game_state.rooms.get_room(&room2name).unwrap();
+        let room3 = game_state.rooms.get_room(&room3name).unwrap();
         let is_user_in_room3 = room3.users.contains(&user1name);
-        assert_eq!(is_user_in_room3, true);
+        assert!(is_user_in_room3);
 
-        let user = game_state.users.get_user(&user1name);
+        let user = game_state.users.get_user(&user1name).unwrap();
         assert_eq!(user.room_name, room3name);
 
         let is_user_in_room1 = room1.users.contains(&user1name);
         let is_user_in_room2 = room2.users.contains(&user1name);
-        assert_eq!(is_user_in_room1, false);
-        assert_eq!(is_user_in_room2, false);
+        assert!(!is_user_in_room1);
+        assert!(!is_user_in_room2);
     }
 
     #[test]
     fn move_up_and_back() {
         let (mut game_state, user1name, room1name, room2name) = make_simple_2_room_north_map();
 
-        game_state
-            .attempt_move(&user1name, &"n".to_string())
-            .unwrap();
-        game_state
-            .attempt_move(&user1name, &"s".to_string())
-            .unwrap();
+        game_state.attempt_move(&user1name, "n").unwrap();
+        game_state.attempt_move(&user1name, "s").unwrap();
 
-        let user = game_state.users.get_user(&user1name);
+        let user = game_state.users.get_user(&user1name).unwrap();
         assert_eq!(user.room_name, room1name);
 
-        let room1 = game_state.rooms.get_room(&room1name);
-        let room2 = game_state.rooms.get_room(&room2name);
+        let room1 = game_state.rooms.get_room(&room1name).unwrap();
+        let room2 = game_state.rooms.get_room(&room2name).unwrap();
         let is_user_in_room1 = room1.users.contains(&user1name);
-        assert_eq!(is_user_in_room1, true);
+        assert!(is_user_in_room1);
 
         let is_user_in_room2 = room2.users.contains(&user1name);
-        assert_eq!(is_user_in_room2, false);
+        assert!(!is_user_in_room2);
     }
 
     #[test]
     fn move_invalid_direction() {
         let (mut game_state, user1name, room1name, _) = make_simple_2_room_north_map();
 
-        let user = game_state.users.get_user(&user1name);
+        let user = game_state.users.get_user(&user1name).unwrap();
         assert_eq!(user.room_name, room1name);
 
-        let room1 = game_state.rooms.get_room(&room1name);
+        let room1 = game_state.rooms.get_room(&room1name).unwrap();
         let is_user_in_room1 = room1.users.contains(&user1name);
-        assert_eq!(is_user_in_room1, true);
+        assert!(is_user_in_room1);
 
-        let res = game_state.attempt_move(&user1name, &"NORF".to_string());
-        assert_eq!(
-            res.is_ok(),
-            false,
+        let res = game_state.attempt_move(&user1name, "NORF");
+        assert!(
+            res.is_err(),
             "Move somehow succeeded in moving a fake direction."
         );
     }
 
     #[test]
-    #[should_panic(expected = "Empty room names are not allowed!")]
     fn attempt_empty_room_name_creation() {
-        let mut game_state = GameState::new();
-
-        game_state.create_room(&"".to_string(), "The Land Of Dook".to_string());
+        let err = RoomName::new("").unwrap_err();
+        assert_eq!(err, FaerieError::EmptyRoomName);
+        assert_eq!(err.to_string(), "Empty room names are not allowed!");
     }
 
     #[test]
-    #[should_panic(expected = "Empty room descriptions are not allowed!")]
     fn attempt_empty_room_description_creation() {
         let mut game_state = GameState::new();
 
-        game_state.create_room(&"Mang0".to_string(), "".to_string());
+        let err = game_state
+            .create_room(&RoomName::new("Mang0").unwrap(), "".to_string())
+            .unwrap_err();
+        assert_eq!(err, FaerieError::EmptyRoomDescription);
+        assert_eq!(err.to_string(), "Empty room descriptions are not allowed!");
     }
 
     #[test]
-    #[should_panic(expected = "Empty user names are not allowed!")]
-    fn attempt_empty_user_name_creation() {
+    fn attempt_duplicate_room_creation() {
         let mut game_state = GameState::new();
+        let hall = RoomName::new("hall").unwrap();
+        game_state
+            .create_room(&hall, "A long hall.".to_string())
+            .unwrap();
 
-        game_state.create_room(
-            &"Dooklandia".to_string(),
-            "Big ol' dook in front of you".to_string(),
+        for name in &["hall", "Hall", " HALL "] {
+            let err = game_state
+                .create_room(&RoomName::new(name).unwrap(), "Another.".to_string())
+                .unwrap_err();
+            assert!(matches!(err, FaerieError::DuplicateRoom(_)));
+        }
+        assert_eq!(game_state.rooms.get_room("HALL").unwrap().name, hall);
+        assert_eq!(
+            game_state.rooms.get_room("hall").unwrap().description,
+            "A long hall."
         );
+    }
 
-        game_state.create_user_in_room(
-            &"".to_string(),
-            &"Dooklandia".to_string(),
-            UserType::Civilian,
-        );
+    #[test]
+    fn attempt_duplicate_user_creation() {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .room("garden", "Roses.")
+            .user("alice", "hall")
+            .build()
+            .unwrap();
+        let alice = UserName::new("alice").unwrap();
+        let hall = RoomName::new("hall").unwrap();
+        let garden = RoomName::new("garden").unwrap();
+
+        for name in &["alice", "Alice", " ALICE "] {
+            let err = game_state
+                .create_basic_user_in_room(&UserName::new(name).unwrap(), &garden)
+                .unwrap_err();
+            assert!(matches!(err, FaerieError::DuplicateUser(_)));
+        }
+        assert_eq!(game_state.get_user_location(&alice).unwrap(), hall);
+        assert!(game_state.rooms.get_room(&garden).unwrap().users.is_empty());
+        game_state.remove_user(&alice).unwrap();
+        game_state.delete_room(&hall).unwrap();
+    }
+
+    #[test]
+    fn removing_a_room_keeps_the_key_of_another_that_folds_the_same() {
+        let mut game_state = GameState::new();
+        let upper = RoomName::new("Hall").unwrap();
+        let lower = RoomName::new("hall").unwrap();
+        game_state
+            .create_room(&upper, "The old hall.".to_string())
+            .unwrap();
+        // As an old save might have had it.
+        game_state
+            .rooms
+            .restore_room(Room::new(lower, "The new hall.".to_string()).unwrap());
+
+        game_state.rooms.remove_room("Hall");
+        assert_eq!(game_state.rooms.get_room("HALL").unwrap().name, lower);
+    }
+
+    #[test]
+    fn attempt_empty_user_name_creation() {
+        let err = UserName::new("").unwrap_err();
+        assert_eq!(err, FaerieError::EmptyUserName);
+        assert_eq!(err.to_string(), "Empty user names are not allowed!");
     }
 
     #[test]
-    #[should_panic(expected = "Failed to find room named FAKEFRIENDS for mutation!")]
     fn attempt_incorrect_room_user_creation() {
         let mut game_state = GameState::new();
 
-        let room1name = "Dooklandia".to_string();
+        let room1name = RoomName::new("Dooklandia").unwrap();
 
-        game_state.create_room(&room1name, "Big ol' dook in front of you".to_string());
+        game_state
+            .create_room(&room1name, "Big ol' dook in front of you".to_string())
+            .unwrap();
 
-        game_state.create_user_in_room(
-            &"Freddie".to_string(),
-            &"FAKEFRIENDS".to_string(),
-            UserType::Civilian,
-        );
+        let err = game_state
+            .create_user_in_room(
+                &UserName::new("Freddie").unwrap(),
+                &RoomName::new("FAKEFRIENDS").unwrap(),
+                UserType::Civilian,
+            )
+            .unwrap_err();
+        assert_eq!(err, FaerieError::RoomNotFound("FAKEFRIENDS".to_string()));
+        assert_eq!(err.to_string(), "No room named FAKEFRIENDS exists!");
     }
 
     #[test]
-    #[should_panic(expected = "Empty path names are not allowed!")]
     fn attempt_empty_oneway_path_name_creation() {
         let mut game_state = GameState::new();
 
-        let room1name = "room1".to_string();
-        let room2name = "room2".to_string();
+        let room1name = RoomName::new("room1").unwrap();
+        let room2name = RoomName::new("room2").unwrap();
 
-        game_state.create_room(&room1name, "The Land Of Dook".to_string());
-        game_state.create_room(&room2name, "The Land Of Dook, 2".to_string());
+        game_state
+            .create_room(&room1name, "The Land Of Dook".to_string())
+            .unwrap();
+        game_state
+            .create_room(&room2name, "The Land Of Dook, 2".to_string())
+            .unwrap();
 
-        game_state.add_path(
-            &room1name,
-            &room2name,
-            Direction::CustomOneWay("".to_string()),
-        );
+        let err = game_state
+            .add_path(
+                &room1name,
+                &room2name,
+                Direction::CustomOneWay("".to_string()),
+            )
+            .unwrap_err();
+        assert_eq!(err, FaerieError::EmptyPathName);
+        assert_eq!(err.to_string(), "Empty path names are not allowed!");
     }
 
     #[test]
-    #[should_panic(expected = "Empty path names are not allowed!")]
     fn attempt_empty_twoway_path_name_creation() {
         let mut game_state = GameState::new();
 
-        let room1name = "room1".to_string();
-        let room2name = "room2".to_string();
+        let room1name = RoomName::new("room1").unwrap();
+        let room2name = RoomName::new("room2").unwrap();
 
-        game_state.create_room(&room1name, "The Land Of Dook".to_string());
-        game_state.create_room(&room2name, "The Land Of Dook, 2".to_string());
+        game_state
+            .create_room(&room1name, "The Land Of Dook".to_string())
+            .unwrap();
+        game_state
+            .create_room(&room2name, "The Land Of Dook, 2".to_string())
+            .unwrap();
 
-        game_state.add_path(
-            &room1name,
-            &room2name,
-            Direction::Custom("mkay".to_string(), "".to_string()),
-        );
+        let err = game_state
+            .add_path(
+                &room1name,
+                &room2name,
+                Direction::Custom("mkay".to_string(), "".to_string()),
+            )
+            .unwrap_err();
+        assert_eq!(err, FaerieError::EmptyPathName);
+        assert_eq!(err.to_string(), "Empty path names are not allowed!");
     }
 
     #[test]
-    #[should_panic(expected = "No room named FAKENEWS exists!")]
     fn attempt_path_to_invalid_room() {
         let mut game_state = GameState::new();
 
-        let room1name = "room1".to_string();
-        let room2name = "room2".to_string();
+        let room1name = RoomName::new("room1").unwrap();
+        let room2name = RoomName::new("room2").unwrap();
 
-        game_state.create_room(&room1name, "The Land Of Dook".to_string());
-        game_state.create_room(&room2name, "The Land Of Dook, 2".to_string());
+        game_state
+            .create_room(&room1name, "The Land Of Dook".to_string())
+            .unwrap();
+        game_state
+            .create_room(&room2name, "The Land Of Dook, 2".to_string())
+            .unwrap();
 
-        game_state.add_path(
-            &room1name,
-            &"FAKENEWS".to_string(),
-            Direction::Custom("mkay".to_string(), "jkll".to_string()),
-        );
+        let err = game_state
+            .add_path(
+                &room1name,
+                &RoomName::new("FAKENEWS").unwrap(),
+                Direction::Custom("mkay".to_string(), "jkll".to_string()),
+            )
+            .unwrap_err();
+        assert_eq!(err, FaerieError::RoomNotFound("FAKENEWS".to_string()));
+        assert_eq!(err.to_string(), "No room named FAKENEWS exists!");
     }
 
     #[test]
-    #[should_panic(expected = "Failed to find room named FAKENEWS for mutation!")]
     fn attempt_path_from_invalid_room() {
         let mut game_state = GameState::new();
 
-        let room1name = "room1".to_string();
-        let room2name = "room2".to_string();
+        let room1name = RoomName::new("room1").unwrap();
+        let room2name = RoomName::new("room2").unwrap();
 
-        game_state.create_room(&room1name, "The Land Of Dook".to_string());
-        game_state.create_room(&room2name, "The Land Of Dook, 2".to_string());
+        game_state
+            .create_room(&room1name, "The Land Of Dook".to_string())
+            .unwrap();
+        game_state
+            .create_room(&room2name, "The Land Of Dook, 2".to_string())
+            .unwrap();
 
-        game_state.add_path(
-            &"FAKENEWS".to_string(),
-            &room2name,
-            Direction::Custom("mkay".to_string(), "jkll".to_string()),
-        );
+        let err = game_state
+            .add_path(
+                &RoomName::new("FAKENEWS").unwrap(),
+                &room2name,
+                Direction::Custom("mkay".to_string(), "jkll".to_string()),
+            )
+            .unwrap_err();
+        assert_eq!(err, FaerieError::RoomNotFound("FAKENEWS".to_string()));
+        assert_eq!(err.to_string(), "No room named FAKENEWS exists!");
     }
 
     #[test]
     fn create_room_from_other_room() {
         let mut game_state = GameState::new();
 
-        let room1name = "room1".to_string();
-        let room2name = "room2".to_string();
-
-        game_state.create_room(&room1name, "description".to_string());
-        game_state.create_room_from(
-            &room2name,
-            "description2".to_string(),
-            &room1name,
-            Direction::North,
-        );
-
-        let user1name = "user1".to_string();
-        game_state.create_user_in_room(&user1name, &room1name, UserType::Civilian);
+        let room1name = RoomName::new("room1").unwrap();
+        let room2name = RoomName::new("room2").unwrap();
 
         game_state
-            .attempt_move(&user1name, &"north".to_string())
+            .create_room(&room1name, "description".to_string())
+            .unwrap();
+        game_state
+            .create_room_from(
+                &room2name,
+                "description2".to_string(),
+                &room1name,
+                Direction::North,
+            )
             .unwrap();
+
+        let user1name = UserName::new("user1").unwrap();
         game_state
-            .attempt_move(&user1name, &"south".to_string())
+            .create_user_in_room(&user1name, &room1name, UserType::Civilian)
             .unwrap();
 
-        let user = game_state.users.get_user(&user1name);
+        game_state.attempt_move(&user1name, "north").unwrap();
+        game_state.attempt_move(&user1name, "south").unwrap();
+
+        let user = game_state.users.get_user(&user1name).unwrap();
         assert_eq!(user.room_name, room1name);
 
-        let room1 = game_state.rooms.get_room(&room1name);
-        let room2 = game_state.rooms.get_room(&room2name);
+        let room1 = game_state.rooms.get_room(&room1name).unwrap();
+        let room2 = game_state.rooms.get_room(&room2name).unwrap();
         let is_user_in_room1 = room1.users.contains(&user1name);
         let is_user_in_room2 = room2.users.contains(&user1name);
-        assert_eq!(is_user_in_room1, true);
-        assert_eq!(is_user_in_room2, false);
+        assert!(is_user_in_room1);
+        assert!(!is_user_in_room2);
     }
 
     #[test]
-    #[should_panic(expected = "Path 'north' from room1 already exists!")]
-    fn test_duplicate_path_creation_panics() {
+    fn test_duplicate_path_creation_fails() {
         let (mut game_state, _, room1name, room2name) = make_simple_2_room_north_map();
 
-        game_state.add_path(&room1name, &room2name, Direction::North);
+        let err = game_state
+            .add_path(&room1name, &room2name, Direction::North)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            FaerieError::DuplicatePath {
+                room_name: room1name.to_string(),
+                path_name: "north".to_string(),
+            }
+        );
+        assert_eq!(err.to_string(), "Path 'north' from room1 already exists!");
     }
 
     #[test]
     fn test_process_movement_input() {
         let (mut game_state, user1name, room1name, room2name) = make_simple_2_room_north_map();
 
-        game_state.process_input(&user1name, &"north".to_string());
+        game_state.process_input(&user1name, "north");
 
-        let user = game_state.users.get_user(&user1name);
+        let user = game_state.users.get_user(&user1name).unwrap();
         assert_eq!(user.room_name, room2name);
 
-        let room1 = game_state.rooms.get_room(&room1name);
+        let room1 = game_state.rooms.get_room(&room1name).unwrap();
         let is_user_in_room1 = room1.users.contains(&user1name);
-        assert_eq!(is_user_in_room1, false);
+        assert!(!is_user_in_room1);
 
-        let room2 = game_state.rooms.get_room(&room2name);
+        let room2 = game_state.rooms.get_room(&room2name).unwrap();
         let is_user_in_room2 = room2.users.contains(&user1name);
-        assert_eq!(is_user_in_room2, true);
+        assert!(is_user_in_room2);
     }
 
     #[test]
     fn test_was_movement_input_marked() {
         let (mut game_state, user1name, _, _) = make_simple_2_room_north_map();
 
-        let res = game_state.process_input_impl(&user1name, &"north".to_string());
+        let res = game_state.process_input_impl(&user1name, "north");
         if let Ok(succ) = res {
-            assert_eq!(succ.was_room_move(), true);
+            assert!(succ.was_room_move());
         } else {
-            assert!(false, "Room move failed!");
+            panic!("Room move failed!");
         }
     }
 
@@ -652,11 +2185,11 @@ mod tests {
     fn test_was_non_movement_input_not_marked() {
         let (mut game_state, user1name, _, _) = make_simple_2_room_north_map();
 
-        let res = game_state.process_input_impl(&user1name, &"list_users".to_string());
+        let res = game_state.process_input_impl(&user1name, "list_users");
         if let Ok(succ) = res {
-            assert_eq!(succ.was_room_move(), false);
+            assert!(!succ.was_room_move());
         } else {
-            assert!(false, "Basic global action failed!");
+            panic!("Basic global action failed!");
         }
     }
 
@@ -671,14 +2204,15 @@ mod tests {
 
         match valid_action_attempt {
             Some(x) => {
-                if let Ok(ActionSuccess{ messages, .. }) = x {
-                    assert_eq!(messages, format_user_list(vec![&user1name]));
+                if let Ok(succ) = x {
+                    assert_eq!(succ.messages()[0], "Users online:");
+                    assert!(succ.messages()[2].starts_with("  user1"));
                 } else {
-                    assert!(false, "Listing users attempt failed!");
+                    panic!("Listing users attempt failed!");
                 }
             }
             None => {
-                assert!(false, "Got no result from global action attempt!");
+                panic!("Got no result from global action attempt!");
             }
         }
     }
@@ -687,13 +2221,424 @@ mod tests {
     fn test_attempt_invalid_global_action() {
         let (mut game_state, user1name, _, _) = make_simple_2_room_north_map();
 
-        let invalid_action_attempt = game_state.attempt_global_action(
-            &user1name,
-            &"DOODOOBUTT".to_string(),
+        let invalid_action_attempt = game_state.attempt_global_action(&user1name, "DOODOOBUTT");
+
+        if invalid_action_attempt.is_some() {
+            panic!("Got a result for an invalid action!");
+        }
+    }
+
+    fn block_north_exit(game_state: &mut GameState, room_name: &RoomName, target: &RoomName) {
+        let blocker = lambda::mk_action_callback(|_user: &mut User| {
+            Err(ActionFailure::new(
+                vec!["A wall of thorns blocks your way.".to_string()],
+                FailReason::ExitBlocked,
+            ))
+        });
+        let room = game_state.rooms.get_room_mut(room_name).unwrap();
+        room.add_path_special(
+            target,
+            &PathName::new("north").unwrap(),
+            room::PathType::Custom(blocker),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_last_failure_records_blocked_move() {
+        let (mut game_state, user1name, room1name, room2name) = make_simple_2_room_north_map();
+        block_north_exit(&mut game_state, &room1name, &room2name);
+
+        assert!(game_state.last_failure(&user1name).is_none());
+
+        let res = game_state.process_input_impl(&user1name, "north");
+        assert!(res.is_err());
+
+        let failure = game_state.last_failure(&user1name).unwrap();
+        assert_eq!(failure.reason, FailReason::ExitBlocked);
+
+        game_state
+            .process_input_impl(&user1name, "NORF")
+            .unwrap_err();
+        let failure = game_state.last_failure(&user1name).unwrap();
+        assert_eq!(failure.reason, FailReason::UnknownCommand);
+    }
+
+    #[test]
+    fn test_last_failure_cleared_on_success() {
+        let (mut game_state, user1name, room1name, room2name) = make_simple_2_room_north_map();
+        block_north_exit(&mut game_state, &room1name, &room2name);
+
+        game_state
+            .process_input_impl(&user1name, "north")
+            .unwrap_err();
+        assert!(game_state.last_failure(&user1name).is_some());
+
+        game_state
+            .process_input_impl(&user1name, "list_users")
+            .unwrap();
+        assert!(game_state.last_failure(&user1name).is_none());
+    }
+
+    #[test]
+    fn test_room_behind_painful_path_is_flagged() {
+        let (mut game_state, _, room1name, room2name) = make_simple_2_room_north_map();
+        let room3name = RoomName::new("room3").unwrap();
+        game_state
+            .create_room(&room3name, "description3".to_string())
+            .unwrap();
+
+        let room2 = game_state.rooms.get_room_mut(&room2name).unwrap();
+        room2
+            .add_path_special(
+                &room3name,
+                &PathName::new("thorns").unwrap(),
+                room::PathType::Painful,
+            )
+            .unwrap();
+
+        let flagged = game_state
+            .find_rooms_only_reachable_painfully(&room1name)
+            .unwrap();
+        assert_eq!(flagged, vec![room3name]);
+    }
+
+    #[test]
+    fn test_room_with_safe_alternative_is_not_flagged() {
+        let (mut game_state, _, room1name, room2name) = make_simple_2_room_north_map();
+        let room3name = RoomName::new("room3").unwrap();
+        game_state
+            .create_room(&room3name, "description3".to_string())
+            .unwrap();
+
+        let room2 = game_state.rooms.get_room_mut(&room2name).unwrap();
+        room2
+            .add_path_special(
+                &room3name,
+                &PathName::new("thorns").unwrap(),
+                room::PathType::Painful,
+            )
+            .unwrap();
+        game_state
+            .add_path(&room1name, &room3name, Direction::East)
+            .unwrap();
+
+        let flagged = game_state
+            .find_rooms_only_reachable_painfully(&room1name)
+            .unwrap();
+        assert!(flagged.is_empty());
+    }
+
+    #[test]
+    fn test_find_path_takes_shortest_route() {
+        let (mut game_state, _, room1name, room2name) = make_simple_2_room_north_map();
+        let room3name = RoomName::new("room3").unwrap();
+        game_state
+            .create_room_from(
+                &room3name,
+                "description3".to_string(),
+                &room2name,
+                Direction::East,
+            )
+            .unwrap();
+
+        assert_eq!(
+            game_state.find_path(&room1name, &room3name).unwrap(),
+            vec!["north", "east"]
+        );
+        assert_eq!(
+            game_state.find_path(&room3name, &room1name).unwrap(),
+            vec!["west", "south"]
+        );
+        assert_eq!(game_state.find_path(&room1name, &room1name), Some(vec![]));
+        assert_eq!(
+            game_state.find_path(&room1name, &RoomName::new("nowhere").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_path_respects_one_way_paths() {
+        let (mut game_state, _, room1name, _) = make_simple_2_room_north_map();
+        let room3name = RoomName::new("room3").unwrap();
+        game_state
+            .create_room(&room3name, "description3".to_string())
+            .unwrap();
+        game_state
+            .add_path(
+                &room1name,
+                &room3name,
+                Direction::CustomOneWay("slide".to_string()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            game_state.find_path(&room1name, &room3name).unwrap(),
+            vec!["slide"]
         );
+        assert_eq!(game_state.find_path(&room3name, &room1name), None);
+    }
+
+    #[test]
+    fn test_tick_does_not_advance_while_paused() {
+        let (mut game_state, _, _, _) = make_simple_2_room_north_map();
+
+        game_state.tick();
+        assert_eq!(game_state.tick_count(), 1);
+
+        game_state.set_paused(true);
+        game_state.tick_n(3);
+        assert_eq!(game_state.tick_count(), 1);
+        assert_eq!(game_state.missed_ticks(), 3);
+
+        game_state.set_paused(false);
+        game_state.tick_n(2);
+        assert_eq!(game_state.tick_count(), 3);
+        assert_eq!(game_state.missed_ticks(), 3);
+    }
+
+    #[test]
+    fn test_input_while_paused_follows_policy() {
+        let (mut game_state, user1name, _, room2name) = make_simple_2_room_north_map();
+        game_state.set_paused(true);
+        game_state.set_reject_input_while_paused(true);
+
+        let res = game_state.process_input_impl(&user1name, "north");
+        assert_eq!(res.unwrap_err().reason, FailReason::WorldPaused);
+
+        game_state.set_reject_input_while_paused(false);
+        game_state.process_input_impl(&user1name, "north").unwrap();
+        let user = game_state.users.get_user(&user1name).unwrap();
+        assert_eq!(user.room_name, room2name);
+    }
+
+    #[test]
+    fn test_buildundo_removes_created_room() {
+        let (mut game_state, _, _, _) = make_simple_2_room_north_map();
+        game_state.set_builder_mode(true);
+
+        let room3name = RoomName::new("room3").unwrap();
+        game_state
+            .create_room(&room3name, "description3".to_string())
+            .unwrap();
+        assert!(game_state.rooms.rooms.contains_key(&room3name));
+
+        game_state.undo_last_build_op().unwrap();
+        assert!(!game_state.rooms.rooms.contains_key(&room3name));
+    }
+
+    #[test]
+    fn test_buildundo_removes_both_path_directions() {
+        let (mut game_state, _, room1name, room2name) = make_simple_2_room_north_map();
+        game_state.set_builder_mode(true);
+
+        game_state
+            .add_path(&room1name, &room2name, Direction::East)
+            .unwrap();
+        game_state.undo_last_build_op().unwrap();
+
+        let room1 = game_state.rooms.get_room(&room1name).unwrap();
+        let room2 = game_state.rooms.get_room(&room2name).unwrap();
+        assert!(room1.get_path("east").is_none());
+        assert!(room2.get_path("west").is_none());
+        assert!(room1.get_path("north").is_some());
+        assert!(room2.get_path("south").is_some());
+    }
+
+    #[test]
+    fn test_buildundo_restores_description() {
+        let (mut game_state, _, room1name, _) = make_simple_2_room_north_map();
+        game_state.set_builder_mode(true);
+
+        game_state
+            .set_room_description(&room1name, "A new coat of paint.".to_string())
+            .unwrap();
+        game_state.undo_last_build_op().unwrap();
+
+        let room1 = game_state.rooms.get_room(&room1name).unwrap();
+        assert_eq!(room1.description, "description");
+    }
+
+    #[test]
+    fn test_buildundo_with_empty_stack() {
+        let (mut game_state, user1name, _, _) = make_simple_2_room_north_map();
+        game_state.set_role(&user1name, Role::Builder).unwrap();
+
+        let res = game_state.process_input_impl(&user1name, "buildundo");
+        let failure = res.unwrap_err();
+        assert_eq!(failure.messages, vec!["Nothing to undo.".to_string()]);
+        assert_eq!(failure.reason, FailReason::NothingToUndo);
+    }
 
-        if let Some(_) = invalid_action_attempt {
-            assert!(false, "Got a result for an invalid action!");
+    struct TaggingFormatter;
+
+    impl OutputFormatter for TaggingFormatter {
+        fn format_room_name(&self, room_name: &str) -> String {
+            format!("<room>{}</room>", room_name)
+        }
+
+        fn format_exit(&self, path_name: &str) -> String {
+            format!("<exit>{}</exit>", path_name)
         }
     }
+
+    #[test]
+    fn test_describe_room_uses_custom_formatter() {
+        let (mut game_state, user1name, _, _) = make_simple_2_room_north_map();
+        game_state.set_formatter(Box::new(TaggingFormatter));
+
+        let lines = game_state.describe_room(&user1name).unwrap();
+        assert_eq!(lines[0], "<room>room1</room>");
+        assert_eq!(lines[1], "  description");
+        assert!(lines.contains(&"* <exit>north</exit>".to_string()));
+    }
+
+    #[test]
+    fn test_describe_room_plain_by_default() {
+        let (game_state, user1name, _, _) = make_simple_2_room_north_map();
+
+        let lines = game_state.describe_room(&user1name).unwrap();
+        assert_eq!(
+            lines,
+            vec!["room1", "  description", "", "paths: ", "* north"]
+        );
+    }
+
+    #[test]
+    fn test_failed_add_path_leaves_no_half_path() {
+        let (mut game_state, _, room1name, room2name) = make_simple_2_room_north_map();
+        let room3name = RoomName::new("room3").unwrap();
+        game_state
+            .create_room(&room3name, "description3".to_string())
+            .unwrap();
+
+        // room2 already has a "south" path, so the reverse of this one can't be added.
+        let err = game_state
+            .add_path(&room3name, &room2name, Direction::North)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            FaerieError::DuplicatePath {
+                room_name: room2name.to_string(),
+                path_name: "south".to_string(),
+            }
+        );
+
+        let room3 = game_state.rooms.get_room(&room3name).unwrap();
+        assert!(room3.paths.is_empty());
+        let room2 = game_state.rooms.get_room(&room2name).unwrap();
+        assert_eq!(room2.get_path("south").unwrap().target_room_name, room1name);
+    }
+
+    #[test]
+    fn test_input_from_unknown_user_is_an_error() {
+        let (mut game_state, _, _, _) = make_simple_2_room_north_map();
+
+        let res = game_state.process_input_impl(&UserName::new("ghost").unwrap(), "north");
+        let failure = res.unwrap_err();
+        assert_eq!(
+            failure.reason,
+            FailReason::Error(FaerieError::UserNotFound("ghost".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_game_state_can_be_shared_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<GameState>();
+
+        let (game_state, user1name, _, room2name) = make_simple_2_room_north_map();
+        let game_state = std::sync::Arc::new(std::sync::RwLock::new(game_state));
+        let mover = {
+            let game_state = game_state.clone();
+            std::thread::spawn(move || {
+                game_state
+                    .write()
+                    .unwrap()
+                    .process_input_impl(&user1name, "north")
+                    .unwrap();
+            })
+        };
+        mover.join().unwrap();
+        assert_eq!(
+            game_state
+                .read()
+                .unwrap()
+                .get_user_location(&user1name)
+                .unwrap(),
+            room2name
+        );
+    }
+
+    #[test]
+    fn test_process_input_writes_to_output_sink() {
+        let (mut game_state, user1name, _, _) = make_simple_2_room_north_map();
+        let output = output::BufferedSink::new();
+        game_state.set_output_sink(Box::new(output.clone()));
+
+        game_state.process_input(&user1name, "north");
+        let messages = output.drain_for(&user1name);
+        assert_eq!(messages[0], "room2");
+        assert_eq!(messages[1], "  description2");
+
+        game_state.process_input(&user1name, "NORF");
+        let messages = output.drain();
+        assert_eq!(
+            messages,
+            vec![output::Message {
+                user_name: user1name,
+                text: "I don't understand 'NORF'.".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_room_moves_are_announced_to_both_rooms() {
+        let (mut game_state, user1name, room1name, room2name) = make_simple_2_room_north_map();
+        let watcher1 = UserName::new("watcher1").unwrap();
+        let watcher2 = UserName::new("watcher2").unwrap();
+        game_state
+            .create_basic_user_in_room(&watcher1, &room1name)
+            .unwrap();
+        game_state
+            .create_basic_user_in_room(&watcher2, &room2name)
+            .unwrap();
+        let output = output::BufferedSink::new();
+        game_state.set_output_sink(Box::new(output.clone()));
+
+        game_state.process_input_impl(&user1name, "north").unwrap();
+        assert_eq!(output.drain_for(&watcher1), vec!["user1 leaves north."]);
+        assert_eq!(
+            output.drain_for(&watcher2),
+            vec!["user1 arrives from the south."]
+        );
+        assert!(output.drain_for(&user1name).is_empty());
+    }
+
+    #[test]
+    fn test_export_dot() {
+        let (mut game_state, _, room1name, _) = make_simple_2_room_north_map();
+        let quoted = RoomName::new("the \"pit\"").unwrap();
+        game_state
+            .create_room(&quoted, "description3".to_string())
+            .unwrap();
+        game_state
+            .add_painful_path(
+                &room1name,
+                &quoted,
+                Direction::CustomOneWay("down".to_string()),
+            )
+            .unwrap();
+
+        let expected = r#"digraph world {
+    "room1";
+    "room2";
+    "the \"pit\"";
+    "room1" -> "the \"pit\"" [label="down", style=dashed];
+    "room1" -> "room2" [label="north"];
+    "room2" -> "room1" [label="south"];
+}
+"#;
+        assert_eq!(game_state.export_dot(), expected);
+    }
 }
@@ -0,0 +1,233 @@
+use crate::error::FaerieError;
+use crate::events::GameEvent;
+use crate::item::Item;
+use crate::journal::JournalEntry;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::room::{Lock, Path};
+use crate::type_aliases::{ItemName, PathName, RoomName, UserName};
+use crate::GameState;
+
+impl GameState {
+    // Puts a lock on a path, or takes it off with None. The path leading straight back, if
+    // there is exactly one, shares the same door and gets the same lock.
+    pub fn set_path_lock(
+        &mut self,
+        room_name: &RoomName,
+        path_name: &str,
+        key_name: Option<ItemName>,
+        locked: bool,
+    ) -> Result<(), FaerieError> {
+        let lock = key_name.map(|key_name| Lock { key_name, locked });
+        let reverse = self.reverse_path(room_name, path_name)?;
+        self.get_path_mut(room_name, path_name)?.lock = lock.clone();
+        if let Some((target_room_name, reverse_path_name)) = reverse {
+            self.get_path_mut(&target_room_name, &reverse_path_name)?
+                .lock = lock;
+        }
+        Ok(())
+    }
+
+    pub fn give_item(&mut self, user_name: &UserName, item: Item) -> Result<(), FaerieError> {
+        let entry = || JournalEntry::ItemGiven {
+            user_name: *user_name,
+            item: item.clone(),
+        };
+        self.journaled_if_ok(entry, |game_state| {
+            game_state.give_item_impl(user_name, item.clone())
+        })
+    }
+
+    fn give_item_impl(&mut self, user_name: &UserName, item: Item) -> Result<(), FaerieError> {
+        let event = GameEvent::ItemGained {
+            user_name: *user_name,
+            item_name: item.name.clone(),
+        };
+        self.users.get_user_mut(user_name)?.inventory.push(item);
+        let messages = self.publish(event);
+        self.send_all(user_name, &messages);
+        Ok(())
+    }
+
+    pub fn set_lock_state(
+        &mut self,
+        user_name: &UserName,
+        possible_path_name: &str,
+        locked: bool,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let path_name = Path::match_basic_aliases(possible_path_name.to_string());
+        let room_name = self.get_user_location(user_name)?;
+
+        let lock = match self.rooms.get_room(&room_name)?.get_path(&path_name) {
+            None if self.has_container_here(user_name, possible_path_name) => {
+                return self.set_container_lock_state(user_name, possible_path_name, locked)
+            }
+            None => {
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "no_direction", &[("exit", &path_name)])],
+                    FailReason::NoSuchPath,
+                ))
+            }
+            Some(path) => match &path.lock {
+                None => {
+                    return Err(ActionFailure::new(
+                        vec![self.text(user_name, "no_door", &[("exit", &path_name)])],
+                        FailReason::NotLockable,
+                    ))
+                }
+                Some(lock) => lock.clone(),
+            },
+        };
+
+        if lock.locked == locked {
+            let id = if locked {
+                "way_already_locked"
+            } else {
+                "way_already_unlocked"
+            };
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, id, &[("exit", &path_name)])],
+                FailReason::NotLockable,
+            ));
+        }
+        if !self.users.get_user(user_name)?.has_item(&lock.key_name) {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "need_key_for", &[("key", &lock.key_name)])],
+                FailReason::MissingKey,
+            ));
+        }
+
+        self.set_path_lock(&room_name, &path_name, Some(lock.key_name.clone()), locked)?;
+        let id = if locked {
+            "you_lock_way"
+        } else {
+            "you_unlock_way"
+        };
+        let args = [("exit", path_name.as_str()), ("key", &lock.key_name)];
+        Ok(ActionSuccess::new(vec![self.text(user_name, id, &args)]))
+    }
+
+    pub(crate) fn get_path_mut(
+        &mut self,
+        room_name: &RoomName,
+        path_name: &str,
+    ) -> Result<&mut Path, FaerieError> {
+        self.rooms
+            .get_room_mut(room_name)?
+            .get_path_mut(path_name)
+            .ok_or_else(|| FaerieError::PathNotFound {
+                room_name: room_name.to_string(),
+                path_name: path_name.to_string(),
+            })
+    }
+
+    fn reverse_path(
+        &self,
+        room_name: &RoomName,
+        path_name: &str,
+    ) -> Result<Option<(RoomName, PathName)>, FaerieError> {
+        let room = self.rooms.get_room(room_name)?;
+        let target_room_name = match room.get_path(path_name) {
+            Some(path) => &path.target_room_name,
+            None => {
+                return Err(FaerieError::PathNotFound {
+                    room_name: room_name.to_string(),
+                    path_name: path_name.to_string(),
+                })
+            }
+        };
+        let target_room = self.rooms.get_room(target_room_name)?;
+        let mut back: Vec<&PathName> = target_room
+            .paths
+            .iter()
+            .filter(|(_, p)| &p.target_room_name == room_name)
+            .map(|(name, _)| name)
+            .collect();
+        if back.len() == 1 {
+            Ok(back.pop().map(|name| (*target_room_name, *name)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::item::Item;
+    use crate::lambda::FailReason;
+    use crate::room::Direction;
+    use crate::type_aliases::{RoomName, UserName};
+
+    use crate::GameState;
+
+    fn make_locked_world() -> GameState {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .room("vault", "Gold!")
+            .path("hall", "vault", Direction::North)
+            .user("alice", "hall")
+            .build()
+            .unwrap();
+        game_state
+            .set_path_lock(
+                &RoomName::new("hall").unwrap(),
+                "north",
+                Some("brass key".to_string()),
+                true,
+            )
+            .unwrap();
+        game_state
+    }
+
+    #[test]
+    fn locked_path_blocks_movement_and_names_the_key() {
+        let mut game_state = make_locked_world();
+        let alice = UserName::new("alice").unwrap();
+
+        let failure = game_state.process_input_impl(&alice, "north").unwrap_err();
+        assert_eq!(failure.reason, FailReason::PathLocked);
+        assert_eq!(
+            failure.messages,
+            vec!["The way north is locked. You need the brass key."]
+        );
+
+        let failure = game_state
+            .process_input_impl(&alice, "unlock n")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::MissingKey);
+    }
+
+    #[test]
+    fn key_holder_can_unlock_and_relock_both_sides() {
+        let mut game_state = make_locked_world();
+        let alice = UserName::new("alice").unwrap();
+        let key = Item::new("brass key".to_string(), "Small and shiny.".to_string()).unwrap();
+        game_state.give_item(&alice, key).unwrap();
+
+        let failure = game_state.process_input_impl(&alice, "north").unwrap_err();
+        assert_eq!(
+            failure.messages,
+            vec!["The way north is locked. Try `unlock north`."]
+        );
+
+        let succ = game_state
+            .process_input_impl(&alice, "unlock north")
+            .unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["You unlock the way north with the brass key."]
+        );
+        game_state.process_input_impl(&alice, "north").unwrap();
+
+        game_state.process_input_impl(&alice, "lock south").unwrap();
+        let vault = game_state.rooms.get_room("vault").unwrap();
+        assert!(vault.get_path("south").unwrap().is_locked());
+        let hall = game_state.rooms.get_room("hall").unwrap();
+        assert!(hall.get_path("north").unwrap().is_locked());
+
+        let failure = game_state
+            .process_input_impl(&alice, "lock south")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::NotLockable);
+    }
+}
@@ -0,0 +1,158 @@
+use std::io::Write;
+
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+use crate::events::GameEvent;
+use crate::lambda::{ActionFailure, ActionSuccess};
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+// Every command and game event goes through the `log` facade, so hosts can send them wherever
+// they like. Commands are logged under the "faerie::command" target and events under
+// "faerie::event". Commands, movement and combat are info; everything else is debug.
+impl GameState {
+    pub(crate) fn log_command(
+        &self,
+        user_name: &UserName,
+        user_input: &str,
+        attempt: &Result<ActionSuccess, ActionFailure>,
+    ) {
+        let context = self.log_context(user_name);
+        match attempt {
+            Ok(_) => log::info!(target: "faerie::command", "{}: {}", context, user_input),
+            Err(failure) => log::info!(
+                target: "faerie::command",
+                "{}: {} (failed: {})",
+                context,
+                user_input,
+                failure.messages.join(" ")
+            ),
+        }
+    }
+
+    pub(crate) fn log_event(&self, event: &GameEvent) {
+        let level = match event {
+            GameEvent::Moved { .. }
+            | GameEvent::Hit { .. }
+            | GameEvent::Killed { .. }
+            | GameEvent::Died { .. } => Level::Info,
+            _ => Level::Debug,
+        };
+        if log::log_enabled!(target: "faerie::event", level) {
+            log::log!(target: "faerie::event", level, "{}", self.describe_event(event));
+        }
+    }
+
+    fn describe_event(&self, event: &GameEvent) -> String {
+        let context = self.log_context(event.user_name());
+        match event {
+            GameEvent::Moved {
+                from, path_name, ..
+            } => format!("{} moved {} from {}", context, path_name, from),
+            GameEvent::Spoke { text, .. } => format!("{} spoke: {}", context, text),
+            GameEvent::Hit {
+                target_name,
+                damage,
+                ..
+            } => format!("{} hit {} for {}", context, target_name, damage),
+            GameEvent::Killed { victim_name, .. } => format!("{} killed {}", context, victim_name),
+            GameEvent::ItemGained { item_name, .. } => format!("{} gained {}", context, item_name),
+            GameEvent::EffectStarted { effect, .. } => {
+                format!("{} is now {}", context, effect.name())
+            }
+            GameEvent::EffectEnded { effect, .. } => {
+                format!("{} is no longer {}", context, effect.name())
+            }
+            GameEvent::Died { .. } => format!("{} died", context),
+        }
+    }
+
+    // "alice@hall [tick 12]", or without the room if they aren't in one.
+    fn log_context(&self, user_name: &UserName) -> String {
+        match self.get_user_location(user_name) {
+            Ok(room_name) => format!("{}@{} [tick {}]", user_name, room_name, self.tick_count),
+            Err(_) => format!("{} [tick {}]", user_name, self.tick_count),
+        }
+    }
+}
+
+// For hosts that don't bring their own logger: everything at `level` and above is written to
+// stderr, one record per line.
+pub struct StderrLogger {
+    level: LevelFilter,
+}
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let _ = writeln!(
+                std::io::stderr(),
+                "{:<5} {}: {}",
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
+
+// Fails if a logger has already been installed.
+pub fn init_stderr_logger(level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_boxed_logger(Box::new(StderrLogger { level }))?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+    use crate::type_aliases::{PathName, RoomName};
+
+    #[test]
+    fn events_are_described_with_context() {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .room("garden", "Roses.")
+            .path("hall", "garden", Direction::North)
+            .user("alice", "hall")
+            .user("bob", "hall")
+            .build()
+            .unwrap();
+        let hall = RoomName::new("hall").unwrap();
+        let garden = RoomName::new("garden").unwrap();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        game_state.tick_n(2);
+
+        let hit = GameEvent::Hit {
+            user_name: alice,
+            target_name: bob,
+            damage: 3,
+        };
+        assert_eq!(
+            game_state.describe_event(&hit),
+            "alice@hall [tick 2] hit bob for 3"
+        );
+        game_state.process_input_impl(&alice, "north").unwrap();
+        let moved = GameEvent::Moved {
+            user_name: alice,
+            from: hall,
+            to: garden,
+            path_name: PathName::new("north").unwrap(),
+        };
+        assert_eq!(
+            game_state.describe_event(&moved),
+            "alice@garden [tick 2] moved north from hall"
+        );
+    }
+}
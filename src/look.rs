@@ -0,0 +1,257 @@
+use crate::flags::{PathFlags, RoomFlags};
+use crate::grammar::with_article;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::room::Path;
+use crate::status::StatusEffect;
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+impl GameState {
+    // With no target this reprints the current room. With a path name it peeks at the room
+    // on the other side without going there, and with someone's name it examines them.
+    pub fn look(
+        &mut self,
+        user_name: &UserName,
+        target: Option<&str>,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let (target, given) = match target {
+            None => return Ok(ActionSuccess::new(self.describe_room(user_name)?)),
+            Some(target) => (Path::match_basic_aliases(target.to_string()), target),
+        };
+
+        let room_name = self.get_user_location(user_name)?;
+        let target_room_name = match self.rooms.get_room(&room_name)?.get_path(&target) {
+            Some(path) => path.target_room_name,
+            None if self.is_user_here(user_name, given) => return self.examine(user_name, given),
+            None => {
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "cannot_see_way", &[("exit", &target)])],
+                    FailReason::NoSuchPath,
+                ))
+            }
+        };
+        let target_room = self.rooms.get_room(&target_room_name)?;
+        let fmt = self.formatter_for(user_name);
+        Ok(ActionSuccess::new(vec![
+            self.text(user_name, "looking", &[("exit", &target)]),
+            fmt.format_room_name(&target_room_name),
+            format!(
+                "  {}",
+                fmt.format_description(&self.room_description_for(user_name, target_room))
+            ),
+        ]))
+    }
+
+    // Every path the user can see, with where it leads and anything stopping them using it.
+    pub fn exits(&self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let room_name = self.get_user_location(user_name)?;
+        let room = self.rooms.get_room(&room_name)?;
+        if room.flags.contains(RoomFlags::DARK)
+            || self
+                .users
+                .get_user(user_name)?
+                .has_effect(StatusEffect::Blinded)
+        {
+            return Ok(ActionSuccess::new(vec![self.text(
+                user_name,
+                "too_dark_for_exits",
+                &[],
+            )]));
+        }
+
+        let mut paths: Vec<&Path> = room
+            .paths
+            .values()
+            .filter(|path| self.can_see_path(user_name, &room_name, path))
+            .collect();
+        if paths.is_empty() {
+            return Ok(ActionSuccess::new(vec![self.text(
+                user_name,
+                "no_exits",
+                &[],
+            )]));
+        }
+        paths.sort_by_key(|a| a.path_name);
+
+        let fmt = self.formatter_for(user_name);
+        let mut messages = vec![self.text(user_name, "exits_header", &[])];
+        for path in paths {
+            let mut notes = vec![];
+            if path.flags.contains(PathFlags::CLOSED) {
+                notes.push("exit_closed");
+            } else if path.is_locked() {
+                notes.push("exit_locked");
+            } else {
+                notes.push("exit_open");
+            }
+            if path.flags.contains(PathFlags::HIDDEN) {
+                notes.push("exit_hidden");
+            }
+            if path.flags.contains(PathFlags::ONE_WAY) {
+                notes.push("exit_one_way");
+            }
+            let notes: Vec<String> = notes
+                .into_iter()
+                .map(|id| self.text(user_name, id, &[]))
+                .collect();
+            let exit = fmt.format_exit(&path.path_name);
+            let notes = notes.join(", ");
+            let args = [
+                ("exit", exit.as_str()),
+                ("room", &path.target_room_name),
+                ("notes", &notes),
+            ];
+            messages.push(self.text(user_name, "exit_line", &args));
+        }
+        Ok(ActionSuccess::new(messages))
+    }
+
+    pub fn examine(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let room_name = self.get_user_location(user_name)?;
+        let target = match self.users.get_user(target_name) {
+            Ok(target) if target.room_name == room_name => target,
+            _ if self.has_container_here(user_name, target_name) => {
+                return self.describe_container(user_name, target_name)
+            }
+            _ => match self.match_user_here(user_name, target_name)? {
+                Some(target_name) => return self.examine(user_name, &target_name),
+                None => {
+                    return Err(ActionFailure::new(
+                        vec![self.text(user_name, "no_one_here", &[("name", target_name)])],
+                        FailReason::InvalidTarget,
+                    ))
+                }
+            },
+        };
+
+        let condition = if target.is_dead() {
+            "looks_dead"
+        } else if target.basic_attributes.hp * 2 >= target.max_hp() {
+            "looks_healthy"
+        } else {
+            "looks_wounded"
+        };
+        let name = target.titled_name();
+        let kind = with_article(target.user_type().display_name());
+        let mut messages = vec![self.text(user_name, "is_a", &[("name", &name), ("kind", &kind)])];
+
+        messages.extend(target.description.clone());
+        messages.push(self.text(user_name, condition, &[]));
+
+        Ok(ActionSuccess::new(messages))
+    }
+
+    fn is_user_here(&self, user_name: &UserName, target_name: &str) -> bool {
+        let here = self.get_user_location(user_name).ok();
+        self.users
+            .get_user(target_name)
+            .is_ok_and(|target| Some(&target.room_name) == here.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::flags::PathFlags;
+    use crate::lambda::FailReason;
+    use crate::room::Direction;
+    use crate::type_aliases::{RoomName, UserName};
+    use crate::user::UserType;
+    use crate::GameState;
+
+    fn make_world() -> GameState {
+        GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .room("garden", "Roses.")
+            .path("hall", "garden", Direction::North)
+            .user("alice", "hall")
+            .user_of_type("eowyn", "hall", UserType::ElfLord)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn look_reprints_room_or_peeks_through_a_path() {
+        let mut game_state = make_world();
+        let alice = UserName::new("alice").unwrap();
+
+        let succ = game_state.process_input_impl(&alice, "look").unwrap();
+        assert_eq!(succ.messages(), game_state.describe_room(&alice).unwrap());
+        assert!(!succ.was_room_move());
+
+        let succ = game_state.process_input_impl(&alice, "look n").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["Looking north, you see:", "garden", "  Roses."]
+        );
+
+        let res = game_state.process_input_impl(&alice, "look west");
+        assert_eq!(res.unwrap_err().reason, FailReason::NoSuchPath);
+    }
+
+    #[test]
+    fn examine_describes_users_in_the_room() {
+        let mut game_state = make_world();
+        let alice = UserName::new("alice").unwrap();
+
+        let succ = game_state
+            .process_input_impl(&alice, "examine eowyn")
+            .unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["eowyn is an elf lord.", "They look healthy."]
+        );
+
+        game_state
+            .users
+            .get_user_mut("eowyn")
+            .unwrap()
+            .basic_attributes
+            .hp = 10;
+        let succ = game_state
+            .process_input_impl(&alice, "examine eowyn")
+            .unwrap();
+        assert_eq!(succ.messages()[1], "They look wounded.");
+
+        let res = game_state.process_input_impl(&alice, "examine sword");
+        assert_eq!(res.unwrap_err().reason, FailReason::InvalidTarget);
+    }
+
+    #[test]
+    fn exits_show_where_paths_lead() {
+        let mut game_state = make_world();
+        let hall = RoomName::new("hall").unwrap();
+        let alice = UserName::new("alice").unwrap();
+        game_state
+            .add_path(
+                &hall,
+                &RoomName::new("garden").unwrap(),
+                Direction::CustomOneWay("hedge gap".to_string()),
+            )
+            .unwrap();
+        game_state
+            .set_path_flags(&hall, "hedge gap", PathFlags::HIDDEN | PathFlags::ONE_WAY)
+            .unwrap();
+        game_state
+            .set_path_lock(&hall, "north", Some("gate key".to_string()), true)
+            .unwrap();
+
+        let succ = game_state.process_input_impl(&alice, "exits").unwrap();
+        assert_eq!(succ.messages(), vec!["Exits:", "  north - garden (locked)"]);
+
+        game_state.search(&alice).unwrap();
+        let succ = game_state.process_input_impl(&alice, "exits").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "Exits:",
+                "  hedge gap - garden (open, hidden, one-way)",
+                "  north - garden (locked)"
+            ]
+        );
+    }
+}
@@ -0,0 +1,358 @@
+use serde::{Deserialize, Serialize};
+
+use crate::dice::Dice;
+use crate::error::FaerieError;
+use crate::grammar::with_article;
+use crate::item::Item;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::{ItemName, RoomName, UserName};
+use crate::GameState;
+
+fn always() -> u32 {
+    100
+}
+
+// One item a loot table may drop, `chance` times out of a hundred.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LootDrop {
+    pub item: ItemName,
+    #[serde(default = "always")]
+    pub chance: u32,
+    pub description: Option<String>,
+}
+
+// What an NPC leaves behind when it dies. Gold is rolled, e.g. "2d6". With `corpse_ticks` set,
+// the loot is left in a corpse that decays after that many ticks, along with anything still in
+// it; otherwise it's dropped on the floor. In world files:
+//
+//     [users.loot]
+//     gold = "1d6"
+//     corpse_ticks = 30
+//     drops = [{ item = "club" }, { item = "ruby", chance = 5 }]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LootTable {
+    #[serde(default)]
+    pub drops: Vec<LootDrop>,
+    pub gold: Option<Dice>,
+    pub corpse_ticks: Option<u64>,
+}
+
+impl LootTable {
+    pub fn with_drop(mut self, item_name: &str, chance: u32) -> LootTable {
+        self.drops.push(LootDrop {
+            item: item_name.to_string(),
+            chance,
+            description: None,
+        });
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Corpse {
+    pub owner: UserName,
+    pub items: Vec<Item>,
+    pub gold: i32,
+    pub decays_at: u64,
+}
+
+impl Corpse {
+    pub fn name(&self) -> String {
+        format!("{}'s corpse", self.owner)
+    }
+}
+
+impl GameState {
+    fn describe_loot(&self, reader: &UserName, items: &[Item], gold: i32) -> String {
+        let mut parts: Vec<String> = items.iter().map(|i| with_article(&i.name)).collect();
+        if gold > 0 {
+            parts.push(self.text(reader, "gold_amount", &[("gold", &gold.to_string())]));
+        }
+        parts.join(", ")
+    }
+
+    // Only used when the user is an NPC.
+    pub fn set_loot_table(
+        &mut self,
+        user_name: &UserName,
+        loot: Option<LootTable>,
+    ) -> Result<(), FaerieError> {
+        self.users.get_user_mut(user_name)?.loot = loot;
+        Ok(())
+    }
+
+    pub(crate) fn drop_loot(&mut self, user_name: &UserName) -> Result<(), FaerieError> {
+        let user = self.users.get_user(user_name)?;
+        let table = match &user.loot {
+            Some(table) if user.npc => table.clone(),
+            _ => return Ok(()),
+        };
+        let room_name = user.room_name;
+
+        let mut items = vec![];
+        for drop in &table.drops {
+            if self.rng.chance(drop.chance) {
+                let description = drop
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| format!("It's {}.", with_article(&drop.item)));
+                items.push(Item::new(drop.item.clone(), description)?);
+            }
+        }
+        let gold = match table.gold {
+            Some(dice) => (dice.roll(&mut self.rng).total as i32).max(0),
+            None => 0,
+        };
+
+        if let Some(ticks) = table.corpse_ticks {
+            let corpse = Corpse {
+                owner: *user_name,
+                items,
+                gold,
+                decays_at: self.tick_count + ticks,
+            };
+            self.rooms.add_corpse(&room_name, corpse)?;
+        } else if !items.is_empty() || gold > 0 {
+            for listener in self.room_listeners(&room_name, user_name)? {
+                let args = [
+                    ("name", user_name.as_str()),
+                    ("loot", &self.describe_loot(&listener, &items, gold)),
+                ];
+                let message = self.text(&listener, "drops", &args);
+                self.output.send(&listener, &message);
+            }
+            let room = self.rooms.get_room_mut(&room_name)?;
+            room.items.extend(items);
+            room.gold = room.gold.saturating_add(gold);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn decay_corpses(&mut self) {
+        let now = self.tick_count;
+        let mut decayed: Vec<(RoomName, String)> = vec![];
+        let rooms = &mut self.rooms.rooms;
+        self.rooms.corpse_rooms.retain(|room_name| {
+            let room = match rooms.get_mut(room_name) {
+                Some(room) => room,
+                None => return false,
+            };
+            room.corpses.retain(|corpse| {
+                let keep = corpse.decays_at > now;
+                if !keep {
+                    decayed.push((*room_name, corpse.name()));
+                }
+                keep
+            });
+            !room.corpses.is_empty()
+        });
+        for (room_name, corpse_name) in decayed {
+            let _ = self.tell_room(&room_name, "crumbles", &[("name", &corpse_name)]);
+        }
+    }
+
+    // Picks something up off the floor: an item, or "gold" for all the gold there.
+    pub fn take(
+        &mut self,
+        user_name: &UserName,
+        item_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let room_name = self.get_user_location(user_name)?;
+        let room = self.rooms.get_room_mut(&room_name)?;
+        if item_name == "gold" && room.gold > 0 {
+            // The gold stays on the floor if it can't all be carried.
+            let gold = room.gold;
+            self.give_gold(user_name, gold)?;
+            self.rooms.get_room_mut(&room_name)?.gold = 0;
+            let gold = gold.to_string();
+            let message = self.text(user_name, "you_take_gold", &[("gold", &gold)]);
+            return Ok(ActionSuccess::new(vec![message]));
+        }
+        let room = self.rooms.get_room(&room_name)?;
+        let index = room
+            .items
+            .iter()
+            .position(|i| i.name == item_name)
+            .ok_or_else(|| {
+                loot_failure(self.text(user_name, "nothing_here", &[("item", item_name)]))
+            })?;
+        let weight = room.items[index].total_weight();
+        self.refuse_if_too_heavy(user_name, weight, &with_article(item_name))?;
+        let item = self.rooms.get_room_mut(&room_name)?.items.remove(index);
+        let named = with_article(&item.name);
+        let message = self.text(user_name, "you_take", &[("item", &named)]);
+        self.give_item(user_name, item)?;
+        Ok(ActionSuccess::new(vec![message]))
+    }
+
+    // Takes everything from a corpse, given by whose it was. Without a name, the first one here.
+    pub fn loot(
+        &mut self,
+        user_name: &UserName,
+        owner_name: Option<&str>,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let room_name = self.get_user_location(user_name)?;
+        let room = self.rooms.get_room(&room_name)?;
+        let corpse = room
+            .corpses
+            .iter()
+            .find(|c| owner_name.is_none_or(|name| *c.owner == *name || c.name() == name))
+            .ok_or_else(|| loot_failure(self.text(user_name, "no_corpse", &[])))?;
+        let corpse_name = corpse.name();
+        let weight = corpse.items.iter().map(Item::total_weight).sum();
+        let everything = self.text(user_name, "everything_on", &[("name", &corpse_name)]);
+        self.refuse_if_too_heavy(user_name, weight, &everything)?;
+        let corpse = self
+            .rooms
+            .get_room_mut(&room_name)?
+            .corpses
+            .iter_mut()
+            .find(|c| c.name() == corpse_name)
+            .unwrap();
+        let items = std::mem::take(&mut corpse.items);
+        let gold = std::mem::take(&mut corpse.gold);
+        if items.is_empty() && gold == 0 {
+            return Err(loot_failure(self.text(
+                user_name,
+                "nothing_left",
+                &[("name", &corpse_name)],
+            )));
+        }
+
+        let loot = self.describe_loot(user_name, &items, gold);
+        let args = [("loot", loot.as_str()), ("name", &corpse_name)];
+        let message = self.text(user_name, "you_loot", &args);
+
+        for item in items {
+            self.give_item(user_name, item)?;
+        }
+        self.give_gold(user_name, gold)?;
+        Ok(ActionSuccess::new(vec![message]))
+    }
+}
+
+fn loot_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::testing::TestWorld;
+    use crate::user::UserType;
+
+    fn make_battlefield(loot: LootTable) -> TestWorld {
+        let mut world = TestWorld::build(
+            GameStateBuilder::new()
+                .room("field", "Trampled grass.")
+                .user_of_type("alice", "field", UserType::Viking)
+                .user_of_type("orc", "field", UserType::Viking),
+        );
+        let orc = UserName::new("orc").unwrap();
+        let game_state = &mut world.game_state;
+        game_state.set_seed(3);
+        game_state.users.get_user_mut(&orc).unwrap().npc = true;
+        game_state.set_loot_table(&orc, Some(loot)).unwrap();
+        game_state.set_respawn_delay(100);
+        world
+    }
+
+    fn kill_orc(game_state: &mut GameState) {
+        let orc = UserName::new("orc").unwrap();
+        game_state
+            .users
+            .get_user_mut(&orc)
+            .unwrap()
+            .basic_attributes
+            .hp = 0;
+        game_state.tick();
+    }
+
+    #[test]
+    fn loot_drops_to_the_floor() {
+        let table = LootTable {
+            gold: Some("1d1+3".parse().unwrap()),
+            ..LootTable::default()
+        };
+        let mut world = make_battlefield(table.with_drop("axe", 100).with_drop("crown", 0));
+        let alice = UserName::new("alice").unwrap();
+        kill_orc(&mut world.game_state);
+        assert!(world
+            .messages_for("alice")
+            .contains(&"orc drops an axe, 4 gold.".to_string()));
+
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "take gold")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You take 4 gold."]);
+        world
+            .game_state
+            .process_input_impl(&alice, "get axe")
+            .unwrap();
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "take crown")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["There's no crown here."]);
+        let user = world.game_state.users.get_user(&alice).unwrap();
+        assert!(user.has_item("axe"));
+        assert_eq!(user.basic_attributes.gold, 4);
+    }
+
+    #[test]
+    fn corpses_hold_loot_until_they_decay() {
+        let table = LootTable {
+            corpse_ticks: Some(3),
+            ..LootTable::default()
+        };
+        let mut world = make_battlefield(table.with_drop("tusk", 100));
+        let alice = UserName::new("alice").unwrap();
+        kill_orc(&mut world.game_state);
+
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "loot orc")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You take a tusk from orc's corpse."]);
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "loot")
+            .unwrap_err();
+        assert_eq!(
+            failure.messages,
+            vec!["There's nothing left on orc's corpse."]
+        );
+
+        world.take_messages();
+        world.game_state.tick_n(3);
+        assert_eq!(
+            world.messages_for("alice"),
+            vec!["orc's corpse crumbles to dust."]
+        );
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "loot")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::InvalidTarget);
+    }
+
+    #[test]
+    fn gold_stays_on_the_floor_when_it_cannot_be_carried() {
+        let mut world = make_battlefield(LootTable::default());
+        let alice = UserName::new("alice").unwrap();
+        world.game_state.rooms.get_room_mut("field").unwrap().gold = 7;
+        world.game_state.give_gold(&alice, i32::MAX - 3).unwrap();
+
+        world
+            .game_state
+            .process_input_impl(&alice, "take gold")
+            .unwrap_err();
+        assert_eq!(world.game_state.rooms.get_room("field").unwrap().gold, 7);
+        let user = world.game_state.users.get_user(&alice).unwrap();
+        assert_eq!(user.basic_attributes.gold, i32::MAX - 3);
+    }
+}
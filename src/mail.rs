@@ -0,0 +1,212 @@
+use serde::{Deserialize, Serialize};
+
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+// A message left for a user, kept in their mailbox until they delete it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mail {
+    pub from: UserName,
+    pub text: String,
+    // The tick it was sent on.
+    pub sent_at: u64,
+    #[serde(default)]
+    pub read: bool,
+}
+
+impl GameState {
+    // The recipient hears about it straight away if they're around, and otherwise at login.
+    pub fn send_mail(
+        &mut self,
+        user_name: &UserName,
+        recipient_name: &str,
+        text: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.users.get_user(user_name)?;
+        self.refuse_if_muted(user_name)?;
+        let mail = Mail {
+            from: *user_name,
+            text: text.to_string(),
+            sent_at: self.tick_count,
+            read: false,
+        };
+        let recipient = match self.users.get_user_mut(recipient_name) {
+            Ok(recipient) => recipient,
+            Err(_) => {
+                return Err(mail_failure(self.text(
+                    user_name,
+                    "nobody_called",
+                    &[("name", recipient_name)],
+                )))
+            }
+        };
+        recipient.mailbox.push(mail);
+        let recipient_name = recipient.name;
+        self.tell(&recipient_name, "new_mail", &[("name", user_name)]);
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_send_mail",
+            &[("name", &recipient_name)],
+        )]))
+    }
+
+    // Shows every letter, numbered for `mail delete`, and marks them all read.
+    pub fn read_mail(&mut self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let mailbox = &self.users.get_user(user_name)?.mailbox;
+        if mailbox.is_empty() {
+            return Ok(ActionSuccess::new(vec![self.text(
+                user_name,
+                "mailbox_empty",
+                &[],
+            )]));
+        }
+        let mut messages = vec![];
+        for (i, mail) in mailbox.iter().enumerate() {
+            let number = (i + 1).to_string();
+            let args = [("number", number.as_str()), ("name", &mail.from)];
+            let id = if mail.read {
+                "letter_from"
+            } else {
+                "new_letter_from"
+            };
+            messages.push(self.text(user_name, id, &args));
+            messages.push(format!("  {}", mail.text));
+        }
+        for mail in self.users.get_user_mut(user_name)?.mailbox.iter_mut() {
+            mail.read = true;
+        }
+        Ok(ActionSuccess::new(messages))
+    }
+
+    pub fn delete_mail(
+        &mut self,
+        user_name: &UserName,
+        number: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let user = self.users.get_user_mut(user_name)?;
+        let index = match number.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= user.mailbox.len() => n - 1,
+            _ => {
+                return Err(mail_failure(self.text(
+                    user_name,
+                    "no_letter",
+                    &[("number", number)],
+                )))
+            }
+        };
+        let mail = user.mailbox.remove(index);
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_delete_mail",
+            &[("name", &mail.from)],
+        )]))
+    }
+
+    // What a user should be told as they log in.
+    pub fn login_notices(&self, user_name: &UserName) -> Vec<String> {
+        let unread = match self.users.get_user(user_name) {
+            Ok(user) => user.mailbox.iter().filter(|mail| !mail.read).count(),
+            Err(_) => return vec![],
+        };
+        match unread {
+            0 => vec![],
+            1 => vec![self.text(user_name, "unread_letter", &[])],
+            n => vec![self.text(user_name, "unread_letters", &[("count", &n.to_string())])],
+        }
+    }
+}
+
+fn mail_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::testing::TestWorld;
+
+    fn make_post_office() -> TestWorld {
+        TestWorld::build(
+            GameStateBuilder::new()
+                .room("post office", "Pigeonholes.")
+                .user("alice", "post office")
+                .user("bob", "post office"),
+        )
+    }
+
+    #[test]
+    fn mail_is_delivered_and_read() {
+        let mut world = make_post_office();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "mail send bob Meet me at the well.")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You send your letter to bob."]);
+        assert_eq!(
+            world.messages_for("bob"),
+            vec!["You have new mail from alice."]
+        );
+        world
+            .game_state
+            .process_input_impl(&alice, "mail send bob Bring a rope.")
+            .unwrap();
+        assert_eq!(
+            world.game_state.login_notices(&bob),
+            vec!["You have 2 unread letters. Type `mail read` to read them."]
+        );
+
+        let succ = world
+            .game_state
+            .process_input_impl(&bob, "mail read")
+            .unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "1. From alice (new):",
+                "  Meet me at the well.",
+                "2. From alice (new):",
+                "  Bring a rope.",
+            ]
+        );
+        assert!(world.game_state.login_notices(&bob).is_empty());
+    }
+
+    #[test]
+    fn mail_can_be_deleted() {
+        let mut world = make_post_office();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "mail send carol Hello")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["There's nobody called carol."]);
+        world
+            .game_state
+            .process_input_impl(&alice, "mail send bob Hello")
+            .unwrap();
+
+        let failure = world
+            .game_state
+            .process_input_impl(&bob, "mail delete 2")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["You have no letter 2."]);
+        let succ = world
+            .game_state
+            .process_input_impl(&bob, "mail delete 1")
+            .unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["You throw away the letter from alice."]
+        );
+        let succ = world
+            .game_state
+            .process_input_impl(&bob, "mail read")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["Your mailbox is empty."]);
+    }
+}
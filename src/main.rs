@@ -1,76 +1,160 @@
-use std::io;
-use std::io::Write;
+use std::cell::RefCell;
+use std::env;
+use std::path::PathBuf;
+use std::rc::Rc;
 
-use faerie::GameState;
-use faerie::room::Direction;
+use faerie::accounts::AccountStore;
+use faerie::{FaerieError, GameState, UserName};
+
+use log::LevelFilter;
+
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
 
 use std::process::Command;
 
-fn main() {
+const STARTER_WORLD: &str = include_str!("../worlds/starter.toml");
+
+// Usage: faerie [--serve ADDR] [WORLD_FILE]
+// Without a world file, the built-in starter world is used.
+fn main() -> Result<(), FaerieError> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (serve_addr, world_file_path) = match args.as_slice() {
+        [flag, addr, rest @ ..] if flag == "--serve" => (Some(addr.clone()), rest.first()),
+        [flag] if flag == "--serve" => {
+            eprintln!("Usage: faerie [--serve ADDR] [WORLD_FILE]");
+            std::process::exit(1);
+        }
+        rest => (None, rest.first()),
+    };
+
+    let game_state = match world_file_path {
+        Some(world_file_path) => GameState::from_world_file(world_file_path)?,
+        None => GameState::from_world_str(STARTER_WORLD)?,
+    };
+
+    if let Some(addr) = serve_addr {
+        // FAERIE_LOG sets how much is logged to stderr: off, error, warn, info (the default),
+        // debug or trace.
+        let log_level = env::var("FAERIE_LOG")
+            .ok()
+            .and_then(|level| level.parse().ok())
+            .unwrap_or(LevelFilter::Info);
+        let _ = faerie::logging::init_stderr_logger(log_level);
+        // FAERIE_ACCOUNTS is where players' accounts are kept, accounts.json by default.
+        let accounts_path = PathBuf::from(
+            env::var("FAERIE_ACCOUNTS").unwrap_or_else(|_| "accounts.json".to_string()),
+        );
+        let accounts = if accounts_path.exists() {
+            AccountStore::load_from_file(&accounts_path)?
+        } else {
+            AccountStore::new()
+        };
+        println!("Listening on {}", addr);
+        return serve(game_state, accounts, accounts_path, addr);
+    }
+
+    let user1name = UserName::new("glenn")?;
+    let game_state = Rc::new(RefCell::new(game_state));
+
+    let mut editor = Editor::new().map_err(readline_error)?;
+    editor.set_helper(Some(ReplHelper {
+        game_state: game_state.clone(),
+        user_name: user1name,
+    }));
+
     Command::new("clear").status().unwrap();
-    let user1name = "glenn".to_string();
-    let mut game_state = create_basic_game_state(user1name.clone());
-    game_state.print_room(&user1name);
+    game_state.borrow_mut().print_room(&user1name)?;
 
     loop {
-        // TODO: move all of this into dedicated lib functionality
-        print!(">>> ");
-        io::stdout().flush().unwrap();
-        let mut buf = String::new();
-        io::stdin().read_line(&mut buf).unwrap();
-        let lastchar = buf.pop();
-
-        // You don't want this forever, but for now breaking with Ctrl-D is nice
-        if lastchar.is_none() {
-            break;
+        let prompt = game_state
+            .borrow()
+            .render_prompt(&user1name)
+            .unwrap_or_else(|_| format!("{} ", faerie::prompt::DEFAULT_PROMPT));
+        let line = match editor.readline(&prompt) {
+            Ok(line) => line,
+            // Ctrl-C abandons the current line; Ctrl-D quits.
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(readline_error(e)),
+        };
+        if !line.trim().is_empty() {
+            editor
+                .add_history_entry(line.as_str())
+                .map_err(readline_error)?;
         }
 
-        {
-        // TODO: have commands, which override directions
-        // This is where you want a better entry point
-        game_state.process_input(&user1name, &buf);
-        }
+        let mut game_state = game_state.borrow_mut();
+        game_state.process_input(&user1name, &line);
+
+        // Locally, time only moves when the player acts.
+        game_state.tick();
     }
+    Ok(())
+}
+
+// Built with the async-server feature, clients are tokio tasks instead of threads.
+#[cfg(feature = "async-server")]
+fn serve(
+    game_state: GameState,
+    accounts: AccountStore,
+    accounts_path: PathBuf,
+    addr: String,
+) -> Result<(), FaerieError> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    let server = faerie::async_server::AsyncServer::new(game_state)?
+        .with_accounts(accounts, Some(accounts_path));
+    runtime.block_on(server.run(addr))
 }
 
-fn create_basic_game_state(user1name: String) -> GameState {
-    let mut game_state = GameState::new();
-
-    let room1name = "Starting Point".to_string();
-    let room2name = "North of Start".to_string();
-    let room3name = "More North".to_string();
-    let room4name = "Over West".to_string();
-    let room5name = "The Odd Little Woods".to_string();
-    game_state.create_room(
-        &room1name,
-        "This seems like a nice place to start an adventure.".to_string(),
-    );
-    game_state.create_room_from(
-        &room2name,
-        "You're on a grassy plain. It's windy, but not uncomfortably so.".to_string(),
-        &room1name,
-        Direction::North,
-    );
-    game_state.create_room(
-        &room3name,
-        "A large swamp spreads out before you. It smells like sulfur farts.".to_string(),
-    );
-    game_state.create_room(
-        &room4name,
-        "The secret glen doesn't seem all that secret, but the amber sunlight filtering "
-            .to_string()
-            + "through the trees really speaks to your soul. Maybe you should take a nap here.",
-    );
-    game_state.create_room(
-        &room5name,
-        "Ah, the real secret of this little township of the woods.".to_string(),
-    );
-
-    //game_state.add_path(&room1name, &room2name, Direction::North);
-    game_state.add_path(&room2name, &room3name, Direction::North);
-    game_state.add_path(&room3name, &room4name, Direction::West);
-    game_state.add_path(&room4name, &room5name, Direction::NorthWest);
-
-    game_state.create_basic_user_in_room(&user1name, &room1name);
-    game_state
+#[cfg(not(feature = "async-server"))]
+fn serve(
+    game_state: GameState,
+    accounts: AccountStore,
+    accounts_path: PathBuf,
+    addr: String,
+) -> Result<(), FaerieError> {
+    faerie::server::Server::new(game_state)?
+        .with_accounts(accounts, Some(accounts_path))
+        .run(addr)
 }
+
+fn readline_error(e: ReadlineError) -> FaerieError {
+    FaerieError::Io(e.to_string())
+}
+
+// Tab-completes verbs and the exits of the player's current room.
+struct ReplHelper {
+    game_state: Rc<RefCell<GameState>>,
+    user_name: UserName,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self
+            .game_state
+            .borrow()
+            .complete_input(&self.user_name, &line[..pos]))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+
+use crate::error::FaerieError;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::{RoomName, UserName};
+use crate::GameState;
+
+// Where a room sits: x grows to the east, y to the north and z upwards.
+pub type Coords = (i32, i32, i32);
+
+// How many rooms the map shows in each direction from the user.
+pub const MAP_RADIUS: i32 = 2;
+
+// The step a compass path takes, as (east, north).
+pub(crate) fn compass_offset(path_name: &str) -> Option<(i32, i32)> {
+    match path_name {
+        "north" => Some((0, 1)),
+        "south" => Some((0, -1)),
+        "east" => Some((1, 0)),
+        "west" => Some((-1, 0)),
+        "northeast" => Some((1, 1)),
+        "southeast" => Some((1, -1)),
+        "southwest" => Some((-1, -1)),
+        "northwest" => Some((-1, 1)),
+        _ => None,
+    }
+}
+
+// A compass path fits the coordinates if it stays on one level and heads the way it says,
+// however far it goes.
+pub(crate) fn path_fits(from: Coords, to: Coords, (east, north): (i32, i32)) -> bool {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let steps = if east != 0 { dx * east } else { dy * north };
+    to.2 == from.2 && steps > 0 && dx == steps * east && dy == steps * north
+}
+
+impl GameState {
+    pub fn set_room_coords(
+        &mut self,
+        room_name: &RoomName,
+        coords: Option<Coords>,
+    ) -> Result<(), FaerieError> {
+        self.rooms.get_room_mut(room_name)?.coords = coords;
+        Ok(())
+    }
+
+    // Rooms the user has visited around them on their level, with the compass paths between
+    // neighbours:
+    //
+    //     [ ]-[ ]
+    //      |
+    //     [*]
+    pub fn map(&self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let room_name = self.get_user_location(user_name)?;
+        let here = self.rooms.get_room(&room_name)?.coords.ok_or_else(|| {
+            ActionFailure::new(
+                vec![self.text(user_name, "no_bearings", &[])],
+                FailReason::InvalidTarget,
+            )
+        })?;
+
+        let user = self.users.get_user(user_name)?;
+        let nearby: HashMap<Coords, &RoomName> = self
+            .rooms
+            .rooms
+            .values()
+            .filter(|room| user.has_visited(&room.name))
+            .filter_map(|room| Some((room.coords?, &room.name)))
+            .filter(|((x, y, z), _)| {
+                *z == here.2 && (x - here.0).abs() <= MAP_RADIUS && (y - here.1).abs() <= MAP_RADIUS
+            })
+            .collect();
+
+        let size = (4 * MAP_RADIUS + 1) as usize;
+        let mut grid = vec![vec![' '; 2 * size + 1]; size];
+        for (coords, nearby_room_name) in &nearby {
+            let row = (2 * (here.1 - coords.1 + MAP_RADIUS)) as usize;
+            let col = (4 * (coords.0 - here.0 + MAP_RADIUS)) as usize;
+            let mark = if coords == &here { '*' } else { ' ' };
+            grid[row][col..col + 3].copy_from_slice(&['[', mark, ']']);
+
+            for path in self.rooms.get_room(nearby_room_name)?.paths.values() {
+                let (east, north) = match compass_offset(&path.path_name) {
+                    Some(offset) => offset,
+                    None => continue,
+                };
+                let neighbour = (coords.0 + east, coords.1 + north, coords.2);
+                if nearby.get(&neighbour) != Some(&&path.target_room_name)
+                    || !self.can_see_path(user_name, nearby_room_name, path)
+                {
+                    continue;
+                }
+                let link = match (east, north) {
+                    (0, _) => '|',
+                    (_, 0) => '-',
+                    (e, n) if e == n => '/',
+                    _ => '\\',
+                };
+                let link_row = (row as i32 - north) as usize;
+                let link_col = (col as i32 + 1 + 2 * east) as usize;
+                grid[link_row][link_col] = link;
+            }
+        }
+
+        let mut lines: Vec<String> = grid
+            .iter()
+            .map(|row| row.iter().collect::<String>().trim_end().to_string())
+            .collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        let first = lines.iter().position(|line| !line.is_empty()).unwrap_or(0);
+        let indent = lines
+            .iter()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        Ok(ActionSuccess::new(
+            lines[first..]
+                .iter()
+                .map(|line| line.get(indent..).unwrap_or("").to_string())
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+    use crate::type_aliases::PathName;
+
+    // A crossroads, with a field to the northeast of the north road that only connects back
+    // to it.
+    fn make_crossroads() -> GameState {
+        let mut game_state = GameStateBuilder::new()
+            .room("cross", "Four ways.")
+            .room("north road", "A road.")
+            .room("east road", "A road.")
+            .room("south road", "A road.")
+            .room("field", "Wheat.")
+            .path("cross", "north road", Direction::North)
+            .path("cross", "east road", Direction::East)
+            .path("cross", "south road", Direction::South)
+            .path("north road", "field", Direction::NorthEast)
+            .user("alice", "cross")
+            .build()
+            .unwrap();
+        for (name, coords) in &[
+            ("cross", (0, 0, 0)),
+            ("north road", (0, 1, 0)),
+            ("east road", (1, 0, 0)),
+            ("south road", (0, -1, 0)),
+            ("field", (1, 2, 0)),
+        ] {
+            game_state
+                .set_room_coords(&RoomName::new(name).unwrap(), Some(*coords))
+                .unwrap();
+        }
+        game_state
+    }
+
+    #[test]
+    fn the_map_shows_visited_rooms_and_paths_nearby() {
+        let mut game_state = make_crossroads();
+        let alice = UserName::new("alice").unwrap();
+        let succ = game_state.process_input_impl(&alice, "map").unwrap();
+        assert_eq!(succ.messages(), vec!["[*]"]);
+
+        for path in &["north", "northeast", "southwest", "south", "east", "west"] {
+            game_state.process_input_impl(&alice, path).unwrap();
+        }
+        let succ = game_state.process_input_impl(&alice, "map").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["    [ ]", "   /", "[ ]", " |", "[*]-[ ]"]
+        );
+        let user = game_state.users.get_user(&alice).unwrap();
+        assert!(user.has_visited("field"));
+        assert!(!user.has_visited("south road"));
+
+        let nowhere = RoomName::new("nowhere").unwrap();
+        game_state
+            .create_room(&nowhere, "Formless.".to_string())
+            .unwrap();
+        game_state.users.get_user_mut(&alice).unwrap().room_name = nowhere;
+        let failure = game_state.map(&alice).unwrap_err();
+        assert_eq!(failure.messages, vec!["You can't get your bearings here."]);
+    }
+
+    #[test]
+    fn validation_catches_paths_that_contradict_coordinates() {
+        let mut game_state = make_crossroads();
+        assert!(game_state.validate_world().misplaced_exits.is_empty());
+        game_state
+            .set_room_coords(&RoomName::new("east road").unwrap(), Some((1, 0, 1)))
+            .unwrap();
+        assert_eq!(
+            game_state.validate_world().misplaced_exits,
+            vec![
+                (
+                    RoomName::new("cross").unwrap(),
+                    PathName::new("east").unwrap()
+                ),
+                (
+                    RoomName::new("east road").unwrap(),
+                    PathName::new("west").unwrap()
+                ),
+            ]
+        );
+        assert!(path_fits((0, 0, 0), (3, 3, 0), (1, 1)));
+        assert!(!path_fits((0, 0, 0), (-2, 0, 0), (1, 0)));
+    }
+}
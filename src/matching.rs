@@ -0,0 +1,141 @@
+use crate::grammar::join_or;
+use crate::lambda::{ActionFailure, FailReason};
+use crate::type_aliases::{name_key, RoomName, UserName};
+use crate::GameState;
+
+// How a name someone typed matched the names on offer.
+#[derive(Debug, PartialEq)]
+pub(crate) enum NameMatch<'a> {
+    Nothing,
+    One(&'a str),
+    // Sorted, for listing back to the user.
+    Ambiguous(Vec<&'a str>),
+}
+
+// A candidate whose name_key is the given name's wins outright. Otherwise the given name may
+// be the start of a candidate's name, as long as it's the start of only one.
+pub(crate) fn match_name<'a>(
+    given: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> NameMatch<'a> {
+    let given = name_key(given);
+    if given.is_empty() {
+        return NameMatch::Nothing;
+    }
+    let mut starting_with = vec![];
+    for candidate in candidates {
+        let key = name_key(candidate);
+        if key == given {
+            return NameMatch::One(candidate);
+        }
+        if key.starts_with(&given) {
+            starting_with.push(candidate);
+        }
+    }
+    starting_with.sort_unstable();
+    starting_with.dedup();
+    match starting_with.as_slice() {
+        [] => NameMatch::Nothing,
+        [candidate] => NameMatch::One(candidate),
+        _ => NameMatch::Ambiguous(starting_with),
+    }
+}
+
+impl GameState {
+    // Turns a match into the name it settled on, or a failure asking which was meant.
+    fn settle(
+        &self,
+        user_name: &UserName,
+        name_match: NameMatch,
+        question_id: &str,
+    ) -> Result<Option<String>, ActionFailure> {
+        match name_match {
+            NameMatch::Nothing => Ok(None),
+            NameMatch::One(name) => Ok(Some(name.to_string())),
+            NameMatch::Ambiguous(names) => {
+                let names: Vec<String> = names.into_iter().map(str::to_string).collect();
+                let names = join_or(&names);
+                Err(ActionFailure::new(
+                    vec![self.text(user_name, question_id, &[("names", &names)])],
+                    FailReason::InvalidTarget,
+                ))
+            }
+        }
+    }
+
+    // The way out of the room the user means, so `nor` can find north. Hidden paths they
+    // haven't found aren't offered.
+    pub(crate) fn match_exit(
+        &self,
+        user_name: &UserName,
+        room_name: &RoomName,
+        given: &str,
+    ) -> Result<Option<String>, ActionFailure> {
+        let room = self.rooms.get_room(room_name)?;
+        let visible = room
+            .paths
+            .values()
+            .filter(|path| self.can_see_path(user_name, room_name, path))
+            .map(|path| path.path_name.as_str());
+        self.settle(user_name, match_name(given, visible), "which_way")
+    }
+
+    // Whoever in the user's room they mean, so `guard` can find guard1.
+    pub(crate) fn match_user_here(
+        &self,
+        user_name: &UserName,
+        given: &str,
+    ) -> Result<Option<String>, ActionFailure> {
+        let room_name = self.get_user_location(user_name)?;
+        let here = self.rooms.get_room(&room_name)?.users.iter();
+        let here = here.map(|name| name.as_str());
+        self.settle(user_name, match_name(given, here), "which_user")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+
+    #[test]
+    fn exact_names_beat_prefixes() {
+        let names = ["north", "northeast", "east"];
+        assert_eq!(match_name("North", names), NameMatch::One("north"));
+        assert_eq!(match_name("ea", names), NameMatch::One("east"));
+        assert_eq!(
+            match_name("nor", names),
+            NameMatch::Ambiguous(vec!["north", "northeast"])
+        );
+        assert_eq!(match_name("west", names), NameMatch::Nothing);
+        assert_eq!(match_name(" ", names), NameMatch::Nothing);
+    }
+
+    #[test]
+    fn moves_and_targets_accept_prefixes() {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .room("garden", "Roses everywhere.")
+            .path("hall", "garden", Direction::North)
+            .user("alice", "hall")
+            .user("guard1", "garden")
+            .user("guard2", "garden")
+            .build()
+            .unwrap();
+        let alice = UserName::new("alice").unwrap();
+
+        game_state.process_input_impl(&alice, "nor").unwrap();
+        assert_eq!(game_state.get_user_location(&alice).unwrap(), "garden");
+
+        let fail = game_state
+            .process_input_impl(&alice, "exa guard")
+            .unwrap_err();
+        assert_eq!(fail.messages, vec!["Who do you mean, guard1 or guard2?"]);
+        game_state
+            .remove_user(&UserName::new("guard2").unwrap())
+            .unwrap();
+        let succ = game_state.process_input_impl(&alice, "exa guard").unwrap();
+        assert_eq!(succ.messages()[0], "guard1 is a civilian.");
+    }
+}
@@ -0,0 +1,282 @@
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::{name_key, UserName};
+use crate::user::Role;
+use crate::GameState;
+
+// Bans and mutes are saved with the world. Whoever is hosting decides what a ban means at
+// login; the server refuses the name.
+impl GameState {
+    // Banned and muted names are matched by name_key, since they may be for users who don't
+    // exist yet.
+    pub fn is_banned(&self, user_name: &str) -> bool {
+        let key = name_key(user_name);
+        self.banned_users.iter().any(|name| name_key(name) == key)
+    }
+
+    pub fn is_muted(&self, user_name: &str) -> bool {
+        let key = name_key(user_name);
+        self.muted_users.iter().any(|name| name_key(name) == key)
+    }
+
+    // Banning someone in the world kicks them too.
+    pub fn ban(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.require_role(user_name, Role::Admin)?;
+        let target_name = self.moderation_target(user_name, target_name, "cannot_ban")?;
+        if self.is_banned(&target_name) || !self.banned_users.insert(target_name) {
+            return Err(moderation_failure(self.text(
+                user_name,
+                "already_banned",
+                &[("name", &target_name)],
+            )));
+        }
+        if self.users.get_user(&target_name).is_ok() {
+            self.kick(user_name, &target_name)?;
+        }
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_ban",
+            &[("name", &target_name)],
+        )]))
+    }
+
+    pub fn unban(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.require_role(user_name, Role::Admin)?;
+        let key = name_key(target_name);
+        let before = self.banned_users.len();
+        self.banned_users.retain(|name| name_key(name) != key);
+        if self.banned_users.len() == before {
+            return Err(moderation_failure(self.text(
+                user_name,
+                "not_banned",
+                &[("name", target_name)],
+            )));
+        }
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_unban",
+            &[("name", target_name)],
+        )]))
+    }
+
+    // Muted users can't say, whisper or shout anything; mute again to unmute.
+    pub fn mute(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.require_role(user_name, Role::Admin)?;
+        let target_name = self.moderation_target(user_name, target_name, "cannot_mute")?;
+        if self.muted_users.remove(&target_name) {
+            self.tell(&target_name, "you_can_speak", &[]);
+            return Ok(ActionSuccess::new(vec![self.text(
+                user_name,
+                "you_unmute",
+                &[("name", &target_name)],
+            )]));
+        }
+        self.muted_users.insert(target_name);
+        self.tell(&target_name, "muted_by", &[("name", user_name)]);
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_mute",
+            &[("name", &target_name)],
+        )]))
+    }
+
+    pub(crate) fn refuse_if_muted(&self, user_name: &UserName) -> Result<(), ActionFailure> {
+        if self.is_muted(user_name) {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "muted", &[])],
+                FailReason::PermissionDenied,
+            ));
+        }
+        Ok(())
+    }
+
+    // Anyone but the admin themselves, whether or not they're in the world. Names nobody has
+    // ever had aren't interned just to be refused.
+    fn moderation_target(
+        &self,
+        user_name: &UserName,
+        target_name: &str,
+        refusal: &str,
+    ) -> Result<UserName, ActionFailure> {
+        let resolved = self
+            .users
+            .resolve(target_name)
+            .copied()
+            .or_else(|| UserName::lookup(target_name));
+        match resolved {
+            Some(target_name) if &target_name != user_name => Ok(target_name),
+            _ => Err(moderation_failure(self.text(
+                user_name,
+                refusal,
+                &[("name", target_name)],
+            ))),
+        }
+    }
+}
+
+fn moderation_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::testing::TestWorld;
+    use crate::type_aliases::RoomName;
+
+    fn make_moderated_world() -> TestWorld {
+        let mut world = TestWorld::build(
+            GameStateBuilder::new()
+                .room("hall", "A long hall.")
+                .user("alice", "hall")
+                .user("carol", "hall")
+                .user("dave", "hall"),
+        );
+        world
+            .game_state
+            .set_role(&UserName::new("alice").unwrap(), Role::Admin)
+            .unwrap();
+        world
+    }
+
+    #[test]
+    fn banned_users_are_kicked_and_stay_banned() {
+        let mut world = make_moderated_world();
+        let alice = UserName::new("alice").unwrap();
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "ban carol")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You ban carol."]);
+        assert!(world.game_state.is_banned("carol"));
+        assert_eq!(world.game_state.take_kicked_users(), vec!["carol"]);
+        assert!(world.game_state.users.get_user("carol").is_err());
+        assert_eq!(
+            world.messages_for("dave"),
+            vec!["carol has been kicked out."]
+        );
+
+        let failure = world
+            .game_state
+            .process_input_impl(&UserName::new("dave").unwrap(), "unban carol")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::PermissionDenied);
+        world
+            .game_state
+            .process_input_impl(&alice, "unban carol")
+            .unwrap();
+        assert!(!world.game_state.is_banned("carol"));
+    }
+
+    #[test]
+    fn muted_users_cannot_talk() {
+        let mut world = make_moderated_world();
+        let alice = UserName::new("alice").unwrap();
+        let dave = UserName::new("dave").unwrap();
+        world
+            .game_state
+            .process_input_impl(&alice, "mute dave")
+            .unwrap();
+        assert_eq!(
+            world.messages_for("dave"),
+            vec!["You have been muted by alice."]
+        );
+        let speaking = [
+            "say hello",
+            "shout hello",
+            "whisper alice hello",
+            "emote waves",
+            "wave",
+            "wave alice",
+        ];
+        for input in &speaking {
+            let failure = world
+                .game_state
+                .process_input_impl(&dave, input)
+                .unwrap_err();
+            assert_eq!(failure.messages, vec!["You have been muted."]);
+        }
+        assert!(world.messages_for("alice").is_empty());
+        assert!(world.messages_for("carol").is_empty());
+
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "mute dave")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You unmute dave."]);
+        world
+            .game_state
+            .process_input_impl(&dave, "say hello")
+            .unwrap();
+    }
+
+    #[test]
+    fn names_nobody_has_had_are_refused_without_being_interned() {
+        let mut world = make_moderated_world();
+        let alice = UserName::new("alice").unwrap();
+        for input in &["ban zed the unseen", "travel zed's hideout"] {
+            assert!(world.game_state.process_input_impl(&alice, input).is_err());
+        }
+        assert!(UserName::lookup("zed the unseen").is_none());
+        assert!(RoomName::lookup("zed's hideout").is_none());
+    }
+
+    #[test]
+    fn only_admins_mute_and_muting_covers_mail_channels_and_guilds() {
+        let mut world = make_moderated_world();
+        let alice = UserName::new("alice").unwrap();
+        let carol = UserName::new("carol").unwrap();
+        let dave = UserName::new("dave").unwrap();
+        for user_name in &[&alice, &dave] {
+            world
+                .game_state
+                .process_input_impl(user_name, "chat join ooc")
+                .unwrap();
+        }
+        world
+            .game_state
+            .process_input_impl(&dave, "guild create The Loud")
+            .unwrap();
+
+        let failure = world
+            .game_state
+            .process_input_impl(&carol, "mute dave")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::PermissionDenied);
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "mute alice")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["You can't mute alice."]);
+        assert!(!world.game_state.is_muted("dave"));
+
+        world
+            .game_state
+            .process_input_impl(&alice, "mute DAVE")
+            .unwrap();
+        assert!(world.game_state.is_muted("Dave"));
+        world.take_messages();
+        for input in &["chat ooc hi", "chat guild hi", "mail send alice hi"] {
+            let failure = world
+                .game_state
+                .process_input_impl(&dave, input)
+                .unwrap_err();
+            assert_eq!(failure.messages, vec!["You have been muted."]);
+        }
+        assert!(world.take_messages().is_empty());
+        let alice_user = world.game_state.users.get_user(&alice).unwrap();
+        assert!(alice_user.mailbox.is_empty());
+    }
+}
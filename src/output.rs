@@ -0,0 +1,87 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::type_aliases::UserName;
+
+// Where GameState delivers text meant for a user. The host decides what "delivering" means.
+pub trait OutputSink: Send + Sync {
+    fn send(&mut self, user_name: &UserName, text: &str);
+
+    // The user is about to be asked for input. Unlike sent text, a prompt isn't a line of its
+    // own: their input goes after it. Hosts that show prompts some other way can ignore this.
+    fn send_prompt(&mut self, _user_name: &UserName, _prompt: &str) {}
+}
+
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn send(&mut self, _user_name: &UserName, text: &str) {
+        println!("{}", text);
+    }
+
+    fn send_prompt(&mut self, _user_name: &UserName, prompt: &str) {
+        print!("{}", prompt);
+        let _ = io::stdout().flush();
+    }
+}
+
+pub(crate) fn default_sink() -> Box<dyn OutputSink> {
+    Box::new(StdoutSink)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub user_name: UserName,
+    pub text: String,
+}
+
+// A sink shared with a thread that panicked still has every message sent before the panic.
+fn lock(messages: &Mutex<Vec<Message>>) -> MutexGuard<'_, Vec<Message>> {
+    messages.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// Collects messages until the host drains them. Clones share the same buffer, so keep one
+// handle and give a clone to GameState::set_output_sink.
+#[derive(Clone, Default)]
+pub struct BufferedSink {
+    buffer: Arc<Mutex<Vec<Message>>>,
+    prompts: Arc<Mutex<Vec<Message>>>,
+}
+
+impl BufferedSink {
+    pub fn new() -> BufferedSink {
+        BufferedSink::default()
+    }
+
+    pub fn drain(&self) -> Vec<Message> {
+        lock(&self.buffer).drain(..).collect()
+    }
+
+    pub fn drain_for(&self, user_name: &str) -> Vec<String> {
+        let mut buffer = lock(&self.buffer);
+        let (theirs, others) = buffer.drain(..).partition(|m| m.user_name == user_name);
+        *buffer = others;
+        theirs.into_iter().map(|m: Message| m.text).collect()
+    }
+
+    // Prompts are kept apart from messages, since they aren't lines.
+    pub fn drain_prompts(&self) -> Vec<Message> {
+        lock(&self.prompts).drain(..).collect()
+    }
+}
+
+impl OutputSink for BufferedSink {
+    fn send(&mut self, user_name: &UserName, text: &str) {
+        lock(&self.buffer).push(Message {
+            user_name: *user_name,
+            text: text.to_string(),
+        });
+    }
+
+    fn send_prompt(&mut self, user_name: &UserName, prompt: &str) {
+        lock(&self.prompts).push(Message {
+            user_name: *user_name,
+            text: prompt.to_string(),
+        });
+    }
+}
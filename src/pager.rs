@@ -0,0 +1,149 @@
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+pub const MAX_PAGE_SIZE: usize = 200;
+
+impl GameState {
+    fn page_size(&self, user_name: &UserName) -> usize {
+        self.users
+            .get_user(user_name)
+            .ok()
+            .and_then(|user| user.page_size)
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+    }
+
+    // Sends a reply to the user a page at a time. What doesn't fit waits until they press
+    // enter, and anything else they type drops it.
+    pub(crate) fn send_paged(&mut self, user_name: &UserName, lines: &[String]) {
+        self.pages.remove(user_name);
+        let page_size = self.page_size(user_name);
+        if page_size == 0 || lines.len() <= page_size {
+            self.send_all(user_name, lines);
+            return;
+        }
+        let (page, rest) = lines.split_at(page_size);
+        self.send_all(user_name, page);
+        let more = self.text(user_name, "more", &[]);
+        self.output.send(user_name, &more);
+        self.pages.insert(*user_name, rest.to_vec());
+    }
+
+    // Sends the next page of whatever the user is reading, if they're reading anything.
+    pub(crate) fn send_next_page(&mut self, user_name: &UserName) -> bool {
+        match self.pages.remove(user_name) {
+            Some(lines) => {
+                self.send_paged(user_name, &lines);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn has_more_pages(&self, user_name: &UserName) -> bool {
+        self.pages.contains_key(user_name)
+    }
+
+    pub(crate) fn set_page_size(
+        &mut self,
+        user_name: &UserName,
+        page_size: Option<&str>,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let page_size = match page_size {
+            None => {
+                let message = match self.page_size(user_name) {
+                    0 => self.text(user_name, "paging_off", &[]),
+                    n => self.text(user_name, "page_size", &[("lines", &n.to_string())]),
+                };
+                return Ok(ActionSuccess::new(vec![message]));
+            }
+            Some(off) if off.eq_ignore_ascii_case("off") => 0,
+            Some(lines) => match lines.parse::<usize>() {
+                Ok(n) if (1..=MAX_PAGE_SIZE).contains(&n) => n,
+                _ => {
+                    let max = MAX_PAGE_SIZE.to_string();
+                    return Err(pager_failure(self.text(
+                        user_name,
+                        "page_size_range",
+                        &[("max", &max)],
+                    )));
+                }
+            },
+        };
+        self.users.get_user_mut(user_name)?.page_size = Some(page_size);
+        let message = match page_size {
+            0 => self.text(user_name, "paging_now_off", &[]),
+            n => self.text(user_name, "page_size_now", &[("lines", &n.to_string())]),
+        };
+
+        Ok(ActionSuccess::new(vec![message]))
+    }
+}
+
+fn pager_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{grid_builder, TestWorld};
+
+    // Alice walks into the middle of a 3x3 grid, whose description is eight lines long.
+    fn make_grid() -> TestWorld {
+        let mut world = TestWorld::build(grid_builder(3, 3).user("alice", "0,1"));
+        world.input("alice", "pagesize 3");
+        world.messages_for("alice");
+        world
+    }
+
+    #[test]
+    fn long_replies_wait_for_enter() {
+        let mut world = make_grid();
+        world.input("alice", "east");
+        assert_eq!(
+            world.messages_for("alice"),
+            vec![
+                "1,1",
+                "  Room 1,1 of a 3x3 grid.",
+                "",
+                "[Press enter for more.]"
+            ]
+        );
+        world.input("alice", "");
+        assert_eq!(
+            world.messages_for("alice"),
+            vec!["paths: ", "* east", "* north", "[Press enter for more.]"]
+        );
+        world.input("alice", " ");
+        assert_eq!(world.messages_for("alice"), vec!["* south", "* west"]);
+        assert!(!world
+            .game_state
+            .has_more_pages(&UserName::new("alice").unwrap()));
+    }
+
+    #[test]
+    fn other_input_drops_the_rest() {
+        let mut world = make_grid();
+        let alice = UserName::new("alice").unwrap();
+        world.input("alice", "east");
+        assert!(world.game_state.has_more_pages(&alice));
+        world.messages_for("alice");
+
+        world.input("alice", "pagesize off");
+        assert!(!world.game_state.has_more_pages(&alice));
+        assert_eq!(world.messages_for("alice"), vec!["Paging is now off."]);
+        world.input("alice", "look");
+        assert_eq!(world.messages_for("alice").len(), 8);
+
+        let fail = world
+            .game_state
+            .process_input_impl(&alice, "pagesize lots")
+            .unwrap_err();
+        assert_eq!(
+            fail.messages,
+            vec!["Pages can be 1 to 200 lines long, or off."]
+        );
+    }
+}
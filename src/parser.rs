@@ -0,0 +1,780 @@
+use crate::abilities::Ability;
+use crate::matching::{match_name, NameMatch};
+use crate::GlobalActions;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Command {
+    Global(GlobalActions),
+    Go(String),
+    // Input that doesn't start with a verb, taken as a path name.
+    Bare(String),
+    Attack(String),
+    Say(String),
+    Whisper {
+        target: String,
+        text: String,
+    },
+    Shout(String),
+    Emote(String),
+    Follow(String),
+    Unfollow,
+    Party,
+    Travel(String),
+    Search,
+    Exits,
+    Look(Option<String>),
+    Examine(String),
+    Lock(String),
+    Unlock(String),
+    Quests,
+    Journal,
+    Wield(String),
+    Wear(String),
+    Remove(String),
+    Score,
+    List,
+    Buy(String),
+    Sell(String),
+    Trade(String),
+    ShowTrade,
+    CancelTrade,
+    Offer(String),
+    ConfirmTrade,
+    UseAbility(Ability, Option<String>),
+    Roll(String),
+    Where,
+    Help(Option<String>),
+    Prompt(Option<String>),
+    PageSize(Option<String>),
+    Width(Option<String>),
+    Language(Option<String>),
+    Map,
+    Rest,
+    Skills,
+    Achievements,
+    Who,
+    MailSend {
+        recipient_name: String,
+        text: String,
+    },
+    MailRead,
+    Friends,
+    FriendAdd(String),
+    FriendRemove(String),
+    Ignored,
+    IgnoreAdd(String),
+    IgnoreRemove(String),
+    GuildInfo,
+    GuildCreate(String),
+    GuildInvite(String),
+    GuildJoin(String),
+    GuildLeave,
+    GuildKick(String),
+    GuildPromote(String),
+    GuildDemote(String),
+    GuildDeposit(String),
+    GuildWithdraw(String),
+    Channels,
+    JoinChannel(String),
+    LeaveChannel(String),
+    MuteOnChannel {
+        channel_name: String,
+        target_name: String,
+    },
+    Chat {
+        channel_name: String,
+        text: String,
+    },
+    MailDelete(String),
+    Title(Option<String>),
+    Bio(String),
+    Pick(String),
+    Sneak(String),
+    Talk(String),
+    // A numbered response in a conversation.
+    Respond(usize),
+    Take(String),
+    TakeFrom {
+        item_name: String,
+        container_name: String,
+    },
+    Put {
+        item_name: String,
+        container_name: String,
+    },
+    Loot(Option<String>),
+    Color(Option<bool>),
+    Teleport(String),
+    Transfer {
+        user_name: String,
+        room_name: String,
+    },
+    CreateRoom(String),
+    Kick(String),
+    Ban(String),
+    Announce(String),
+    Unban(String),
+    Mute(String),
+    Dig {
+        direction: String,
+        room_name: String,
+    },
+    Describe(String),
+    Rename(String),
+    DelPath(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum ParseError {
+    Empty,
+    // The prompt is the ID of the catalog message asking for what's missing.
+    MissingArgument { verb: String, prompt: &'static str },
+}
+
+// Input longer than this many characters is refused without being parsed.
+pub const MAX_INPUT_LEN: usize = 1000;
+
+// Makes raw input safe to parse: whitespace of any kind becomes a plain space, other control
+// characters are dropped, and anything past MAX_INPUT_LEN is cut off. Control characters are
+// dropped before counting, and one character more than the limit is kept, so overlong input
+// can still be told apart and refused.
+pub(crate) fn sanitize_input(input: &str) -> String {
+    input
+        .chars()
+        .filter_map(|c| {
+            if c.is_whitespace() {
+                Some(' ')
+            } else if c.is_control() {
+                None
+            } else {
+                Some(c)
+            }
+        })
+        .take(MAX_INPUT_LEN + 1)
+        .collect()
+}
+
+// The first word of the input, lowercased, plus everything after it with outer whitespace
+// trimmed. Inner whitespace in the rest is kept as typed, so text like "say  hi" survives.
+pub(crate) fn split_verb(input: &str) -> Option<(String, &str)> {
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+    let (verb, rest) = match input.find(char::is_whitespace) {
+        Some(i) => (&input[..i], input[i..].trim_start()),
+        None => (input, ""),
+    };
+    Some((verb.to_lowercase(), rest))
+}
+
+const BUILTIN_VERBS: &[&str] = &[
+    "go",
+    "attack",
+    "kill",
+    "say",
+    "shout",
+    "whisper",
+    "emote",
+    "me",
+    "follow",
+    "unfollow",
+    "party",
+    "travel",
+    "search",
+    "exits",
+    "where",
+    "help",
+    "prompt",
+    "pagesize",
+    "width",
+    "language",
+    "map",
+    "rest",
+    "skills",
+    "achievements",
+    "who",
+    "mail",
+    "chat",
+    "friend",
+    "ignore",
+    "guild",
+    "title",
+    "bio",
+    "pick",
+    "sneak",
+    "talk",
+    "look",
+    "l",
+    "examine",
+    "x",
+    "lock",
+    "unlock",
+    "quests",
+    "journal",
+    "wield",
+    "wear",
+    "remove",
+    "score",
+    "list",
+    "buy",
+    "sell",
+    "trade",
+    "offer",
+    "confirm",
+    "swing",
+    "infuse",
+    "chatter",
+    "roll",
+    "take",
+    "get",
+    "loot",
+    "put",
+    "color",
+    "colour",
+    "teleport",
+    "goto",
+    "transfer",
+    "createroom",
+    "kick",
+    "ban",
+    "announce",
+    "unban",
+    "mute",
+    "dig",
+    "describe",
+    "rename",
+    "delpath",
+];
+
+// Verbs parse() already gives a meaning to, which registered commands may not take over.
+pub(crate) fn is_builtin_verb(verb: &str) -> bool {
+    BUILTIN_VERBS.contains(&verb) || GlobalActions::from_text(verb).is_some()
+}
+
+pub(crate) fn builtin_verbs() -> impl Iterator<Item = &'static str> {
+    BUILTIN_VERBS.iter().chain(GlobalActions::NAMES).copied()
+}
+
+// Verbs whose argument is a path name.
+pub(crate) fn takes_path(verb: &str) -> bool {
+    matches!(
+        verb,
+        "go" | "sneak" | "look" | "l" | "lock" | "unlock" | "pick"
+    )
+}
+
+// Verbs given an argument may be shortened, like `exa guard`, as long as the abbreviation
+// is at least this long and only fits one verb. Lone words are left alone, since they may be
+// path names.
+const MIN_VERB_ABBREVIATION: usize = 3;
+
+fn expand_verb(verb: String, rest: &str) -> String {
+    if rest.is_empty() || verb.chars().count() < MIN_VERB_ABBREVIATION || is_builtin_verb(&verb) {
+        return verb;
+    }
+    match match_name(&verb, BUILTIN_VERBS.iter().copied()) {
+        NameMatch::One(full) => full.to_string(),
+        _ => verb,
+    }
+}
+
+pub(crate) fn parse(input: &str) -> Result<Command, ParseError> {
+    let (verb, rest) = split_verb(input).ok_or(ParseError::Empty)?;
+    let verb = expand_verb(verb, rest);
+
+    if rest.is_empty() {
+        if let Some(action) = GlobalActions::from_text(&verb) {
+            return Ok(Command::Global(action));
+        }
+        if let Ok(choice) = verb.parse::<usize>() {
+            return Ok(Command::Respond(choice));
+        }
+    }
+
+    match verb.as_ref() {
+        "go" => {
+            if rest.is_empty() {
+                Err(ParseError::MissingArgument {
+                    verb,
+                    prompt: "go_where",
+                })
+            } else {
+                Ok(Command::Go(rest.to_string()))
+            }
+        }
+        "attack" | "kill" => {
+            if rest.is_empty() {
+                Err(ParseError::MissingArgument {
+                    verb,
+                    prompt: "attack_whom",
+                })
+            } else {
+                Ok(Command::Attack(rest.to_string()))
+            }
+        }
+        "say" | "shout" if rest.is_empty() => Err(ParseError::MissingArgument {
+            verb,
+            prompt: "say_what",
+        }),
+        "say" => Ok(Command::Say(rest.to_string())),
+        "shout" => Ok(Command::Shout(rest.to_string())),
+        "emote" | "me" if rest.is_empty() => Err(ParseError::MissingArgument {
+            verb,
+            prompt: "emote_what",
+        }),
+        "emote" | "me" => Ok(Command::Emote(rest.to_string())),
+        "follow" if rest.is_empty() => Err(ParseError::MissingArgument {
+            verb,
+            prompt: "follow_whom",
+        }),
+        "follow" => Ok(Command::Follow(rest.to_string())),
+        "unfollow" if rest.is_empty() => Ok(Command::Unfollow),
+        "party" if rest.is_empty() => Ok(Command::Party),
+        "travel" if rest.is_empty() => Err(ParseError::MissingArgument {
+            verb,
+            prompt: "travel_where",
+        }),
+        "travel" => Ok(Command::Travel(rest.to_string())),
+        "search" if rest.is_empty() => Ok(Command::Search),
+        "exits" if rest.is_empty() => Ok(Command::Exits),
+        "where" if rest.is_empty() => Ok(Command::Where),
+        "help" if rest.is_empty() => Ok(Command::Help(None)),
+        "help" => Ok(Command::Help(Some(rest.to_string()))),
+        "prompt" if rest.is_empty() => Ok(Command::Prompt(None)),
+        "prompt" => Ok(Command::Prompt(Some(rest.to_string()))),
+        "pagesize" if rest.is_empty() => Ok(Command::PageSize(None)),
+        "pagesize" => Ok(Command::PageSize(Some(rest.to_string()))),
+        "width" if rest.is_empty() => Ok(Command::Width(None)),
+        "width" => Ok(Command::Width(Some(rest.to_string()))),
+        "language" if rest.is_empty() => Ok(Command::Language(None)),
+        "language" => Ok(Command::Language(Some(rest.to_string()))),
+        "map" if rest.is_empty() => Ok(Command::Map),
+        "rest" if rest.is_empty() => Ok(Command::Rest),
+        "skills" if rest.is_empty() => Ok(Command::Skills),
+        "achievements" if rest.is_empty() => Ok(Command::Achievements),
+        "who" if rest.is_empty() => Ok(Command::Who),
+        "friend" => match split_verb(rest) {
+            None => Ok(Command::Friends),
+            Some((sub, name)) if sub == "add" && !name.is_empty() => {
+                Ok(Command::FriendAdd(name.to_string()))
+            }
+            Some((sub, name)) if sub == "remove" && !name.is_empty() => {
+                Ok(Command::FriendRemove(name.to_string()))
+            }
+            _ => Err(ParseError::MissingArgument {
+                verb,
+                prompt: "friend_add_or_remove_whom",
+            }),
+        },
+        "ignore" => match split_verb(rest) {
+            None => Ok(Command::Ignored),
+            Some((sub, name)) if sub == "add" && !name.is_empty() => {
+                Ok(Command::IgnoreAdd(name.to_string()))
+            }
+            Some((sub, name)) if sub == "remove" && !name.is_empty() => {
+                Ok(Command::IgnoreRemove(name.to_string()))
+            }
+            _ => Err(ParseError::MissingArgument {
+                verb,
+                prompt: "ignore_add_or_remove_whom",
+            }),
+        },
+        "guild" => {
+            let (sub, arg) = split_verb(rest).unwrap_or_default();
+            let arg = arg.to_string();
+            match (sub.as_str(), arg.is_empty()) {
+                ("", _) => Ok(Command::GuildInfo),
+                ("leave", _) => Ok(Command::GuildLeave),
+                ("create", false) => Ok(Command::GuildCreate(arg)),
+                ("invite", false) => Ok(Command::GuildInvite(arg)),
+                ("join", false) => Ok(Command::GuildJoin(arg)),
+                ("kick", false) => Ok(Command::GuildKick(arg)),
+                ("promote", false) => Ok(Command::GuildPromote(arg)),
+                ("demote", false) => Ok(Command::GuildDemote(arg)),
+                ("deposit", false) => Ok(Command::GuildDeposit(arg)),
+                ("withdraw", false) => Ok(Command::GuildWithdraw(arg)),
+                ("create", true) | ("join", true) => Err(ParseError::MissingArgument {
+                    verb,
+                    prompt: "which_guild",
+                }),
+                ("deposit", true) | ("withdraw", true) => Err(ParseError::MissingArgument {
+                    verb,
+                    prompt: "how_much_gold",
+                }),
+                ("invite", true) | ("kick", true) | ("promote", true) | ("demote", true) => {
+                    Err(ParseError::MissingArgument {
+                        verb,
+                        prompt: "guild_whom",
+                    })
+                }
+                _ => Err(ParseError::MissingArgument {
+                    verb,
+                    prompt: "guild_what",
+                }),
+            }
+        }
+        "chat" => match split_verb(rest) {
+            None => Ok(Command::Channels),
+            Some((sub, channel_name)) if sub == "join" && !channel_name.is_empty() => {
+                Ok(Command::JoinChannel(channel_name.to_string()))
+            }
+            Some((sub, channel_name)) if sub == "leave" && !channel_name.is_empty() => {
+                Ok(Command::LeaveChannel(channel_name.to_string()))
+            }
+            Some((sub, args)) if sub == "mute" => match split_verb(args) {
+                Some((channel_name, target_name)) if !target_name.is_empty() => {
+                    Ok(Command::MuteOnChannel {
+                        channel_name,
+                        target_name: target_name.to_string(),
+                    })
+                }
+                _ => Err(ParseError::MissingArgument {
+                    verb,
+                    prompt: "mute_whom_on_which_channel",
+                }),
+            },
+            Some((channel_name, text)) if !text.is_empty() => Ok(Command::Chat {
+                channel_name,
+                text: text.to_string(),
+            }),
+            Some(_) => Err(ParseError::MissingArgument {
+                verb,
+                prompt: "say_what_on_that_channel",
+            }),
+        },
+        "mail" => match split_verb(rest) {
+            Some((sub, args)) if sub == "send" => match args.split_once(char::is_whitespace) {
+                Some((recipient_name, text)) => Ok(Command::MailSend {
+                    recipient_name: recipient_name.to_string(),
+                    text: text.trim().to_string(),
+                }),
+                None => Err(ParseError::MissingArgument {
+                    verb,
+                    prompt: "send_what_to_whom",
+                }),
+            },
+            Some((sub, "")) if sub == "read" => Ok(Command::MailRead),
+            Some((sub, "")) if sub == "delete" => Err(ParseError::MissingArgument {
+                verb,
+                prompt: "delete_which_letter",
+            }),
+            Some((sub, number)) if sub == "delete" => Ok(Command::MailDelete(number.to_string())),
+            _ => Err(ParseError::MissingArgument {
+                verb,
+                prompt: "mail_send_read_or_delete",
+            }),
+        },
+        "title" if rest.is_empty() => Ok(Command::Title(None)),
+        "title" => Ok(Command::Title(Some(rest.to_string()))),
+        "bio" => Ok(Command::Bio(rest.to_string())),
+        "pick" if rest.is_empty() => Err(ParseError::MissingArgument {
+            verb,
+            prompt: "pick_the_lock_which_way",
+        }),
+        "pick" => Ok(Command::Pick(rest.to_string())),
+        "sneak" if rest.is_empty() => Err(ParseError::MissingArgument {
+            verb,
+            prompt: "sneak_where",
+        }),
+        "sneak" => Ok(Command::Sneak(rest.to_string())),
+        "talk" if rest.is_empty() => Err(ParseError::MissingArgument {
+            verb,
+            prompt: "talk_to_whom",
+        }),
+        "talk" => Ok(Command::Talk(rest.trim_start_matches("to ").to_string())),
+        "whisper" => match split_verb(rest) {
+            Some((target, text)) if !text.is_empty() => Ok(Command::Whisper {
+                target,
+                text: text.to_string(),
+            }),
+            _ => Err(ParseError::MissingArgument {
+                verb,
+                prompt: "whisper_what_to_whom",
+            }),
+        },
+        "look" | "l" if rest.is_empty() => Ok(Command::Look(None)),
+        "look" | "l" => Ok(Command::Look(Some(rest.to_string()))),
+        "examine" | "x" if rest.is_empty() => Err(ParseError::MissingArgument {
+            verb,
+            prompt: "examine_what",
+        }),
+        "examine" | "x" => Ok(Command::Examine(rest.to_string())),
+        "lock" | "unlock" if rest.is_empty() => Err(ParseError::MissingArgument {
+            prompt: if verb == "lock" {
+                "lock_which_way"
+            } else {
+                "unlock_which_way"
+            },
+            verb,
+        }),
+        "lock" => Ok(Command::Lock(rest.to_string())),
+        "unlock" => Ok(Command::Unlock(rest.to_string())),
+        "quests" if rest.is_empty() => Ok(Command::Quests),
+        "journal" if rest.is_empty() => Ok(Command::Journal),
+        "wield" | "wear" | "remove" if rest.is_empty() => Err(ParseError::MissingArgument {
+            prompt: match verb.as_ref() {
+                "wield" => "wield_what",
+                "wear" => "wear_what",
+                _ => "remove_what",
+            },
+            verb,
+        }),
+        "wield" => Ok(Command::Wield(rest.to_string())),
+        "wear" => Ok(Command::Wear(rest.to_string())),
+        "remove" => Ok(Command::Remove(rest.to_string())),
+        "score" if rest.is_empty() => Ok(Command::Score),
+        "list" if rest.is_empty() => Ok(Command::List),
+        "buy" | "sell" if rest.is_empty() => Err(ParseError::MissingArgument {
+            prompt: if verb == "buy" {
+                "buy_what"
+            } else {
+                "sell_what"
+            },
+            verb,
+        }),
+        "buy" => Ok(Command::Buy(rest.to_string())),
+        "sell" => Ok(Command::Sell(rest.to_string())),
+        "trade" if rest.is_empty() => Ok(Command::ShowTrade),
+        "trade" if rest.eq_ignore_ascii_case("cancel") => Ok(Command::CancelTrade),
+        "trade" => Ok(Command::Trade(rest.to_string())),
+        "offer" if rest.is_empty() => Err(ParseError::MissingArgument {
+            verb,
+            prompt: "offer_what",
+        }),
+        "offer" => Ok(Command::Offer(rest.to_string())),
+        "confirm" if rest.is_empty() => Ok(Command::ConfirmTrade),
+        "swing" | "chatter" if rest.is_empty() => Err(ParseError::MissingArgument {
+            verb,
+            prompt: "at_whom",
+        }),
+        "swing" | "infuse" | "chatter" => Ok(Command::UseAbility(
+            Ability::from_verb(&verb).unwrap(),
+            Some(rest.to_string()).filter(|r| !r.is_empty()),
+        )),
+        "roll" if rest.is_empty() => Err(ParseError::MissingArgument {
+            verb,
+            prompt: "roll_what",
+        }),
+        "roll" => Ok(Command::Roll(rest.to_string())),
+        "take" | "get" if rest.is_empty() => Err(ParseError::MissingArgument {
+            verb,
+            prompt: "take_what",
+        }),
+        "take" | "get" => match rest.split_once(" from ") {
+            Some((item_name, container_name)) => Ok(Command::TakeFrom {
+                item_name: item_name.trim().to_string(),
+                container_name: container_name.trim().to_string(),
+            }),
+            None => Ok(Command::Take(rest.to_string())),
+        },
+        "put" => match rest.split_once(" in ") {
+            Some((item_name, container_name)) => Ok(Command::Put {
+                item_name: item_name.trim().to_string(),
+                container_name: container_name.trim().to_string(),
+            }),
+            None => Err(ParseError::MissingArgument {
+                verb,
+                prompt: "put_what_in_what",
+            }),
+        },
+        "loot" => Ok(Command::Loot(
+            Some(rest.to_string()).filter(|r| !r.is_empty()),
+        )),
+        "teleport" | "goto" | "createroom" | "kick" if rest.is_empty() => {
+            Err(ParseError::MissingArgument {
+                prompt: match verb.as_ref() {
+                    "teleport" | "goto" => "teleport_where",
+                    "createroom" => "create_a_room_called_what",
+                    _ => "kick_whom",
+                },
+                verb,
+            })
+        }
+        "teleport" | "goto" => Ok(Command::Teleport(rest.to_string())),
+        "transfer" => match rest.split_once(char::is_whitespace) {
+            Some((user_name, room_name)) => Ok(Command::Transfer {
+                user_name: user_name.to_string(),
+                room_name: room_name.trim().to_string(),
+            }),
+            _ => Err(ParseError::MissingArgument {
+                verb,
+                prompt: "transfer_whom_where",
+            }),
+        },
+        "createroom" => Ok(Command::CreateRoom(rest.to_string())),
+        "kick" => Ok(Command::Kick(rest.to_string())),
+        "ban" | "unban" | "mute" if rest.is_empty() => Err(ParseError::MissingArgument {
+            prompt: match verb.as_ref() {
+                "ban" => "ban_whom",
+                "unban" => "unban_whom",
+                _ => "mute_whom",
+            },
+            verb,
+        }),
+        "ban" => Ok(Command::Ban(rest.to_string())),
+        "announce" if rest.is_empty() => Err(ParseError::MissingArgument {
+            verb,
+            prompt: "announce_what",
+        }),
+        "announce" => Ok(Command::Announce(rest.to_string())),
+        "unban" => Ok(Command::Unban(rest.to_string())),
+        "mute" => Ok(Command::Mute(rest.to_string())),
+        "dig" => match split_verb(rest) {
+            Some((direction, room_name)) if !room_name.is_empty() => Ok(Command::Dig {
+                direction,
+                room_name: room_name.to_string(),
+            }),
+            _ => Err(ParseError::MissingArgument {
+                verb,
+                prompt: "dig_which_way_to_which_room",
+            }),
+        },
+        "describe" | "rename" | "delpath" if rest.is_empty() => Err(ParseError::MissingArgument {
+            prompt: match verb.as_ref() {
+                "describe" => "describe_this_room_how",
+                "rename" => "rename_this_room_to_what",
+                _ => "delete_the_path_which_way",
+            },
+            verb,
+        }),
+        "describe" => Ok(Command::Describe(rest.to_string())),
+        "rename" => Ok(Command::Rename(rest.to_string())),
+        "delpath" => Ok(Command::DelPath(rest.to_string())),
+        "color" | "colour" => match rest.to_lowercase().as_ref() {
+            "" => Ok(Command::Color(None)),
+            "on" => Ok(Command::Color(Some(true))),
+            "off" => Ok(Command::Color(Some(false))),
+            _ => Err(ParseError::MissingArgument {
+                verb,
+                prompt: "color_on_or_off",
+            }),
+        },
+        // Anything else is taken as the name of a path, which may contain spaces.
+        _ => Ok(Command::Bare(input.trim().to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_verb_from_arguments() {
+        assert_eq!(
+            split_verb("  Say hello   there "),
+            Some(("say".to_string(), "hello   there"))
+        );
+        assert_eq!(split_verb("look"), Some(("look".to_string(), "")));
+        assert_eq!(split_verb("   "), None);
+    }
+
+    #[test]
+    fn sanitizes_control_characters_and_length() {
+        assert_eq!(sanitize_input("say\thi\u{0}\u{7f}\r\n"), "say hi  ");
+        let long = "a".repeat(MAX_INPUT_LEN * 10);
+        assert_eq!(sanitize_input(&long).chars().count(), MAX_INPUT_LEN + 1);
+        assert_eq!(sanitize_input(""), "");
+
+        // Dropped control characters don't count towards the limit, so they can't pull
+        // overlong input back under it.
+        let padded = format!(
+            "{}{}",
+            "\u{0}".repeat(MAX_INPUT_LEN),
+            "a".repeat(MAX_INPUT_LEN * 2)
+        );
+        assert_eq!(sanitize_input(&padded).chars().count(), MAX_INPUT_LEN + 1);
+        let mixed = "a\u{7}".repeat(MAX_INPUT_LEN);
+        assert_eq!(sanitize_input(&mixed).chars().count(), MAX_INPUT_LEN);
+    }
+
+    #[test]
+    fn parses_go_and_bare_paths() {
+        assert_eq!(parse("go north"), Ok(Command::Go("north".to_string())));
+        assert_eq!(parse("GO north"), Ok(Command::Go("north".to_string())));
+        assert_eq!(parse(" n "), Ok(Command::Bare("n".to_string())));
+        assert_eq!(
+            parse("secret door"),
+            Ok(Command::Bare("secret door".to_string()))
+        );
+    }
+
+    #[test]
+    fn expands_unambiguous_verb_abbreviations() {
+        assert_eq!(
+            parse("exa guard"),
+            Ok(Command::Examine("guard".to_string()))
+        );
+        assert_eq!(parse("att bob"), Ok(Command::Attack("bob".to_string())));
+        // Too short, a lone word, or more than one verb starting that way.
+        assert_eq!(parse("ex guard"), Ok(Command::Bare("ex guard".to_string())));
+        assert_eq!(parse("exa"), Ok(Command::Bare("exa".to_string())));
+        assert_eq!(parse("un door"), Ok(Command::Bare("un door".to_string())));
+    }
+
+    #[test]
+    fn parses_attack() {
+        assert_eq!(parse("attack bob"), Ok(Command::Attack("bob".to_string())));
+        assert_eq!(parse("kill bob"), Ok(Command::Attack("bob".to_string())));
+    }
+
+    #[test]
+    fn parses_chat() {
+        assert_eq!(
+            parse("say hello there"),
+            Ok(Command::Say("hello there".to_string()))
+        );
+        assert_eq!(
+            parse("whisper bob psst, over here"),
+            Ok(Command::Whisper {
+                target: "bob".to_string(),
+                text: "psst, over here".to_string(),
+            })
+        );
+        assert_eq!(parse("shout HEY"), Ok(Command::Shout("HEY".to_string())));
+        assert!(parse("say").is_err());
+        assert!(parse("whisper bob").is_err());
+    }
+
+    #[test]
+    fn parses_look_and_examine() {
+        assert_eq!(parse("look"), Ok(Command::Look(None)));
+        assert_eq!(
+            parse("l north"),
+            Ok(Command::Look(Some("north".to_string())))
+        );
+        assert_eq!(parse("x bob"), Ok(Command::Examine("bob".to_string())));
+        assert!(parse("examine").is_err());
+    }
+
+    #[test]
+    fn parses_lock_and_unlock() {
+        assert_eq!(parse("lock north"), Ok(Command::Lock("north".to_string())));
+        assert_eq!(parse("unlock n"), Ok(Command::Unlock("n".to_string())));
+        assert!(parse("unlock").is_err());
+    }
+
+    #[test]
+    fn parses_global_actions() {
+        assert_eq!(
+            parse("list_users"),
+            Ok(Command::Global(GlobalActions::ListOnlineUsers))
+        );
+        assert_eq!(
+            parse("buildundo"),
+            Ok(Command::Global(GlobalActions::BuildUndo))
+        );
+    }
+
+    #[test]
+    fn reports_empty_and_incomplete_input() {
+        assert_eq!(parse(""), Err(ParseError::Empty));
+        assert_eq!(
+            parse("go"),
+            Err(ParseError::MissingArgument {
+                verb: "go".to_string(),
+                prompt: "go_where",
+            })
+        );
+    }
+}
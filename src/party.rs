@@ -0,0 +1,280 @@
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::{RoomName, UserName};
+use crate::GameState;
+
+// Following is one level at a time: each follower has a leader, who may in turn be following
+// someone else. A party is everyone linked that way to the same leader at the top.
+impl GameState {
+    pub fn follow(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let room_name = self.get_user_location(user_name)?;
+        let leader_name = match self.users.get_user(target_name) {
+            Ok(target) if target.room_name == room_name => target.name,
+            _ => {
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "nobody_here", &[("name", target_name)])],
+                    FailReason::InvalidTarget,
+                ))
+            }
+        };
+        if &leader_name == user_name {
+            return self.unfollow(user_name);
+        }
+        if self.party_leader(&leader_name) == *user_name {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "already_following", &[("name", &leader_name)])],
+                FailReason::InvalidTarget,
+            ));
+        }
+
+        self.leaders.insert(*user_name, leader_name);
+        self.tell(&leader_name, "follows_you", &[("name", user_name)]);
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_follow",
+            &[("name", &leader_name)],
+        )]))
+    }
+
+    pub fn unfollow(&mut self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        match self.leaders.remove(user_name) {
+            Some(leader_name) => {
+                self.tell(&leader_name, "unfollows_you", &[("name", user_name)]);
+                Ok(ActionSuccess::new(vec![self.text(
+                    user_name,
+                    "you_unfollow",
+                    &[("name", &leader_name)],
+                )]))
+            }
+            None => Err(ActionFailure::new(
+                vec![self.text(user_name, "not_following", &[])],
+                FailReason::InvalidTarget,
+            )),
+        }
+    }
+
+    pub fn leader_of(&self, user_name: &UserName) -> Option<&UserName> {
+        self.leaders.get(user_name)
+    }
+
+    // The leader at the top of the user's party, which is the user themselves if they aren't
+    // following anyone.
+    pub fn party_leader(&self, user_name: &UserName) -> UserName {
+        let mut leader_name = user_name;
+        while let Some(next) = self.leaders.get(leader_name) {
+            leader_name = next;
+        }
+        *leader_name
+    }
+
+    // The leader first, then everyone else in name order.
+    pub fn party_members(&self, user_name: &UserName) -> Vec<UserName> {
+        let leader_name = self.party_leader(user_name);
+        let mut members: Vec<UserName> = self
+            .leaders
+            .keys()
+            .filter(|u| **u != leader_name && self.party_leader(u) == leader_name)
+            .cloned()
+            .collect();
+        members.sort();
+        members.insert(0, leader_name);
+        members
+    }
+
+    pub fn show_party(&self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        self.users.get_user(user_name)?;
+        let members = self.party_members(user_name);
+        if members.len() == 1 {
+            return Ok(ActionSuccess::new(vec![self.text(
+                user_name,
+                "no_party",
+                &[],
+            )]));
+        }
+        let mut messages = vec![self.text(user_name, "party_header", &[("name", &members[0])])];
+        for member in &members[1..] {
+            let args = [("name", member.as_str()), ("leader", &self.leaders[member])];
+            messages.push(self.text(user_name, "party_member", &args));
+        }
+        Ok(ActionSuccess::new(messages))
+    }
+
+    fn tell_party(&mut self, user_name: &UserName, id: &str, args: &[(&str, &str)]) {
+        for member in self.party_members(user_name) {
+            self.tell(&member, id, args);
+        }
+    }
+
+    // Called after a user has moved. Followers who were in the room they left take the same
+    // path, and are stopped by the same conditions as anyone else.
+    pub(crate) fn move_followers(
+        &mut self,
+        leader_name: &UserName,
+        from_room_name: &RoomName,
+        path_name: &str,
+    ) {
+        let mut followers: Vec<UserName> = self
+            .leaders
+            .iter()
+            .filter(|(_, l)| *l == leader_name)
+            .map(|(f, _)| *f)
+            .filter(|f| {
+                self.users
+                    .get_user(f)
+                    .is_ok_and(|u| &u.room_name == from_room_name)
+            })
+            .collect();
+        followers.sort();
+
+        for follower_name in followers {
+            let args = [("name", leader_name.as_str()), ("exit", path_name)];
+            self.tell(&follower_name, "you_follow_them", &args);
+            match self.process_input_impl(&follower_name, &format!("go {}", path_name)) {
+                Ok(succ) => {
+                    let messages = self.render_events(&follower_name, &succ.events);
+                    self.send_all(&follower_name, &messages);
+                }
+                Err(failure) => {
+                    let reason = failure.messages.join(" ");
+                    let args = [
+                        ("name", follower_name.as_str()),
+                        ("leader", leader_name),
+                        ("reason", &reason),
+                    ];
+                    self.tell_party(&follower_name, "could_not_follow", &args);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::lambda::{mk_action_callback, ActionFailure, FailReason};
+    use crate::room::Direction;
+    use crate::testing::TestWorld;
+    use crate::type_aliases::{RoomName, UserName};
+    use crate::user::User;
+
+    fn make_party_world() -> TestWorld {
+        TestWorld::build(
+            GameStateBuilder::new()
+                .room("camp", "A campfire.")
+                .room("forest", "Tall pines.")
+                .path("camp", "forest", Direction::North)
+                .user("alice", "camp")
+                .user("bob", "camp")
+                .user("carol", "camp"),
+        )
+    }
+
+    #[test]
+    fn followers_move_with_their_leader() {
+        let mut world = make_party_world();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        let carol = UserName::new("carol").unwrap();
+
+        world
+            .game_state
+            .process_input_impl(&bob, "follow alice")
+            .unwrap();
+        world
+            .game_state
+            .process_input_impl(&carol, "follow bob")
+            .unwrap();
+        assert_eq!(
+            world.game_state.party_members(&carol),
+            vec!["alice", "bob", "carol"]
+        );
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "follow carol")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::InvalidTarget);
+        world.take_messages();
+
+        world
+            .game_state
+            .process_input_impl(&alice, "north")
+            .unwrap();
+        for user_name in &[&alice, &bob, &carol] {
+            assert_eq!(
+                world.game_state.get_user_location(user_name).unwrap(),
+                "forest"
+            );
+        }
+        let bob_saw = world.messages_for("bob");
+        assert_eq!(bob_saw[0], "alice leaves north.");
+        assert_eq!(bob_saw[1], "You follow alice north.");
+
+        world
+            .game_state
+            .process_input_impl(&bob, "unfollow")
+            .unwrap();
+        world
+            .game_state
+            .process_input_impl(&alice, "south")
+            .unwrap();
+        assert_eq!(world.game_state.get_user_location(&bob).unwrap(), "forest");
+        assert_eq!(
+            world.game_state.get_user_location(&carol).unwrap(),
+            "forest"
+        );
+    }
+
+    #[test]
+    fn blocked_followers_are_reported_to_the_party() {
+        let mut world = make_party_world();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        let carol = UserName::new("carol").unwrap();
+        world
+            .game_state
+            .get_path_mut(&RoomName::new("camp").unwrap(), "north")
+            .unwrap()
+            .exit_cond = mk_action_callback(|user: &mut User| {
+            if user.name == "bob" {
+                Err(ActionFailure::new(
+                    vec!["Roots trip you.".to_string()],
+                    FailReason::ExitBlocked,
+                ))
+            } else {
+                Ok(crate::lambda::ActionSuccess::new(vec![]))
+            }
+        });
+
+        world.game_state.follow(&bob, "alice").unwrap();
+        world.game_state.follow(&carol, "alice").unwrap();
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "party")
+            .unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "Party led by alice:",
+                "  bob (following alice)",
+                "  carol (following alice)"
+            ]
+        );
+        world.take_messages();
+
+        world
+            .game_state
+            .process_input_impl(&alice, "north")
+            .unwrap();
+        assert_eq!(world.game_state.get_user_location(&bob).unwrap(), "camp");
+        assert_eq!(
+            world.game_state.get_user_location(&carol).unwrap(),
+            "forest"
+        );
+        let report = "bob couldn't follow alice: Roots trip you.";
+        assert!(world.messages_for("alice").contains(&report.to_string()));
+        assert!(world.messages_for("carol").contains(&report.to_string()));
+    }
+}
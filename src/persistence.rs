@@ -0,0 +1,137 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path as FsPath;
+
+use crate::error::FaerieError;
+use crate::GameState;
+
+// Rooms, paths, users and their positions are saved. Painful paths are rebuilt on load, but
+// PathType::Custom exit conditions, entry conditions and room hooks are closures and come back
+// empty. Per-session state like the builder undo stack and the output formatter is not saved.
+impl GameState {
+    pub fn to_json(&self) -> Result<String, FaerieError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<GameState, FaerieError> {
+        let mut game_state: GameState = serde_json::from_str(json)?;
+        game_state.rooms.reindex();
+        game_state.users.reindex();
+        for room in game_state.rooms.rooms.values_mut() {
+            for path in room.paths.values_mut() {
+                path.restore_exit_cond();
+            }
+        }
+        Ok(game_state)
+    }
+
+    pub fn save_to_file<P: AsRef<FsPath>>(&self, file_path: P) -> Result<(), FaerieError> {
+        write_atomically(file_path.as_ref(), &self.to_json()?)
+    }
+
+    pub fn load_from_file<P: AsRef<FsPath>>(file_path: P) -> Result<GameState, FaerieError> {
+        let json = fs::read_to_string(file_path)?;
+        GameState::from_json(&json)
+    }
+}
+
+// Writes next to the file and renames over it, so a crash mid-write leaves the old save whole.
+// The new contents are synced before the rename and, on Unix, the directory after it, so a
+// power loss can't leave the rename pointing at a file that never reached the disk.
+pub(crate) fn write_atomically(file_path: &FsPath, contents: &str) -> Result<(), FaerieError> {
+    let mut temp_path = file_path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let mut temp_file = File::create(&temp_path)?;
+    temp_file.write_all(contents.as_bytes())?;
+    temp_file.sync_all()?;
+    drop(temp_file);
+    fs::rename(&temp_path, file_path)?;
+    #[cfg(unix)]
+    {
+        let dir = match file_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => FsPath::new("."),
+        };
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::room::{Direction, PathKind, PathType};
+    use crate::type_aliases::{PathName, RoomName, UserName};
+    use crate::user::UserType;
+    use crate::GameState;
+
+    fn make_saved_world() -> GameState {
+        let mut game_state = GameStateBuilder::new()
+            .room("room1", "description")
+            .room("room2", "description2")
+            .path("room1", "room2", Direction::North)
+            .user_of_type("user1", "room1", UserType::Viking)
+            .build()
+            .unwrap();
+        let room2 = game_state.rooms.get_room_mut("room2").unwrap();
+        room2
+            .add_path_special(
+                &RoomName::new("room1").unwrap(),
+                &PathName::new("thorns").unwrap(),
+                PathType::Painful,
+            )
+            .unwrap();
+        game_state
+    }
+
+    #[test]
+    fn round_trip_keeps_rooms_and_user_positions() {
+        let user1name = UserName::new("user1").unwrap();
+        let mut game_state = make_saved_world();
+        game_state.attempt_move(&user1name, "north").unwrap();
+        game_state.tick_n(4);
+
+        let mut loaded = GameState::from_json(&game_state.to_json().unwrap()).unwrap();
+
+        let user = loaded.users.get_user(&user1name).unwrap();
+        assert_eq!(user.room_name, "room2");
+        assert_eq!(user.basic_attributes.hp, 220);
+        let room2 = loaded.rooms.get_room("room2").unwrap();
+        assert!(room2.users.contains(&user1name));
+        assert_eq!(loaded.tick_count(), 4);
+
+        loaded.attempt_move(&user1name, "s").unwrap();
+        let user = loaded.users.get_user(&user1name).unwrap();
+        assert_eq!(user.room_name, "room1");
+    }
+
+    #[test]
+    fn painful_paths_still_hurt_after_load() {
+        let user1name = UserName::new("user1").unwrap();
+        let game_state = make_saved_world();
+        let mut loaded = GameState::from_json(&game_state.to_json().unwrap()).unwrap();
+
+        let room2 = loaded.rooms.get_room("room2").unwrap();
+        assert_eq!(room2.get_path("thorns").unwrap().kind, PathKind::Painful);
+
+        loaded.attempt_move(&user1name, "north").unwrap();
+        loaded.attempt_move(&user1name, "thorns").unwrap();
+        let user = loaded.users.get_user(&user1name).unwrap();
+        assert_eq!(user.basic_attributes.hp, 219);
+    }
+
+    #[test]
+    fn save_and_load_file() {
+        let file_path = std::env::temp_dir().join(format!(
+            "faerie_save_and_load_file_{}.json",
+            std::process::id()
+        ));
+        make_saved_world().save_to_file(&file_path).unwrap();
+
+        let loaded = GameState::load_from_file(&file_path).unwrap();
+        std::fs::remove_file(&file_path).unwrap();
+
+        assert_eq!(loaded.rooms.rooms.len(), 2);
+        assert!(loaded.users.get_user("user1").is_ok());
+    }
+}
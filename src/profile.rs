@@ -0,0 +1,247 @@
+use crate::achievements::Achievement;
+use crate::error::FaerieError;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::UserName;
+use crate::user::User;
+use crate::GameState;
+
+pub const MAX_TITLE_LEN: usize = 30;
+pub const MAX_USER_DESCRIPTION_LEN: usize = 200;
+
+// Checks text a user wants others to see, like a title or description, returning why it's
+// refused. Games can add their own, e.g. a profanity filter; lengths are always checked.
+pub type TextValidator = Box<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+// The title that comes with each achievement.
+impl Achievement {
+    pub fn title(self) -> &'static str {
+        match self {
+            Achievement::FirstBlood => "the Bloodied",
+            Achievement::Wanderer => "the Wanderer",
+            Achievement::Undying => "the Undying",
+        }
+    }
+}
+
+impl User {
+    // Their name with their chosen title, if any, e.g. "alice the Wanderer".
+    pub fn titled_name(&self) -> String {
+        match &self.title {
+            Some(title) => format!("{} {}", self.name, title),
+            None => self.name.to_string(),
+        }
+    }
+}
+
+impl GameState {
+    pub fn add_text_validator<F>(&mut self, validator: F)
+    where
+        F: 'static + Send + Sync + Fn(&str) -> Result<(), String>,
+    {
+        self.text_validators.push(Box::new(validator));
+    }
+
+    fn validate_text(
+        &self,
+        user_name: &UserName,
+        text: &str,
+        max_len: usize,
+    ) -> Result<(), ActionFailure> {
+        if text.chars().count() > max_len {
+            let max = max_len.to_string();
+            return Err(profile_failure(self.text(
+                user_name,
+                "text_too_long",
+                &[("max", &max)],
+            )));
+        }
+        for validator in &self.text_validators {
+            validator(text).map_err(profile_failure)?;
+        }
+        Ok(())
+    }
+
+    // Lets the user choose the title. Titles are checked when given, not when chosen.
+    pub fn grant_title(&mut self, user_name: &UserName, title: &str) -> Result<(), FaerieError> {
+        let title = title.trim();
+        if self.validate_text(user_name, title, MAX_TITLE_LEN).is_err() {
+            return Err(FaerieError::InvalidTitle(title.to_string()));
+        }
+        self.users
+            .get_user_mut(user_name)?
+            .titles
+            .insert(title.to_string());
+        Ok(())
+    }
+
+    // With no title, lists those the user has earned. "none" goes back to no title.
+    pub fn choose_title(
+        &mut self,
+        user_name: &UserName,
+        title: Option<&str>,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let user = self.users.get_user(user_name)?;
+        let title = match title {
+            None if user.titles.is_empty() => {
+                return Ok(ActionSuccess::new(vec![self.text(
+                    user_name,
+                    "no_titles",
+                    &[],
+                )]))
+            }
+            None => {
+                let mut messages = vec![self.text(user_name, "titles_header", &[])];
+                for title in &user.titles {
+                    let mark = if user.title.as_ref() == Some(title) {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    messages.push(format!("{} {}", mark, title));
+                }
+                return Ok(ActionSuccess::new(messages));
+            }
+            Some(title) => title,
+        };
+        if title == "none" {
+            self.users.get_user_mut(user_name)?.title = None;
+            return Ok(ActionSuccess::new(vec![self.text(
+                user_name,
+                "no_title",
+                &[],
+            )]));
+        }
+        let title = match user.titles.iter().find(|t| t.eq_ignore_ascii_case(title)) {
+            Some(title) => title.clone(),
+            None => {
+                return Err(profile_failure(self.text(
+                    user_name,
+                    "title_not_earned",
+                    &[("title", title)],
+                )))
+            }
+        };
+        let user = self.users.get_user_mut(user_name)?;
+        user.title = Some(title);
+        let name = user.titled_name();
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "known_as",
+            &[("name", &name)],
+        )]))
+    }
+
+    // What others see when they look at the user. An empty description clears it.
+    pub fn set_user_description(
+        &mut self,
+        user_name: &UserName,
+        description: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let description = description.trim();
+        self.validate_text(user_name, description, MAX_USER_DESCRIPTION_LEN)?;
+        let user = self.users.get_user_mut(user_name)?;
+        if description.is_empty() {
+            user.description = None;
+            return Ok(ActionSuccess::new(vec![self.text(
+                user_name,
+                "description_cleared",
+                &[],
+            )]));
+        }
+        user.description = Some(description.to_string());
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "description_updated",
+            &[],
+        )]))
+    }
+}
+
+fn profile_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::events::GameEvent;
+
+    fn make_tavern() -> GameState {
+        GameStateBuilder::new()
+            .room("tavern", "Smoky.")
+            .user("alice", "tavern")
+            .user("bob", "tavern")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn titles_are_earned_then_chosen() {
+        let mut game_state = make_tavern();
+        let alice = UserName::new("alice").unwrap();
+        let failure = game_state
+            .process_input_impl(&alice, "title the Bloodied")
+            .unwrap_err();
+        assert_eq!(
+            failure.messages,
+            vec!["You haven't earned the title \"the Bloodied\"."]
+        );
+
+        game_state.publish(GameEvent::Killed {
+            user_name: alice,
+            victim_name: UserName::new("bob").unwrap(),
+        });
+        let succ = game_state
+            .process_input_impl(&alice, "title the bloodied")
+            .unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["You are now known as alice the Bloodied."]
+        );
+        let succ = game_state
+            .process_input_impl(&UserName::new("bob").unwrap(), "look alice")
+            .unwrap();
+        assert_eq!(succ.messages()[0], "alice the Bloodied is a civilian.");
+        let succ = game_state.process_input_impl(&alice, "who").unwrap();
+        assert!(succ.messages()[2].contains("the Bloodied"));
+    }
+
+    #[test]
+    fn descriptions_are_validated() {
+        let mut game_state = make_tavern();
+        game_state.add_text_validator(|text| {
+            if text.contains("darn") {
+                Err("Mind your language.".to_string())
+            } else {
+                Ok(())
+            }
+        });
+        let alice = UserName::new("alice").unwrap();
+        let failure = game_state
+            .process_input_impl(&alice, "bio A darn tall figure.")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["Mind your language."]);
+        let long = format!("bio {}", "a".repeat(MAX_USER_DESCRIPTION_LEN + 1));
+        assert!(game_state.process_input_impl(&alice, &long).is_err());
+        assert_eq!(
+            game_state.grant_title(&alice, "the darned"),
+            Err(FaerieError::InvalidTitle("the darned".to_string()))
+        );
+
+        game_state
+            .process_input_impl(&alice, "bio A tall figure in a green cloak.")
+            .unwrap();
+        let succ = game_state
+            .process_input_impl(&UserName::new("bob").unwrap(), "examine alice")
+            .unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "alice is a civilian.",
+                "A tall figure in a green cloak.",
+                "They look healthy.",
+            ]
+        );
+    }
+}
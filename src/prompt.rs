@@ -0,0 +1,172 @@
+use crate::error::FaerieError;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::UserName;
+use crate::user::User;
+use crate::GameState;
+
+pub const DEFAULT_PROMPT: &str = ">>>";
+pub const MAX_PROMPT_LEN: usize = 80;
+
+// What each token in a prompt template stands for. Anything else after a % is left as it is,
+// and %% is a lone %.
+type TokenValue = fn(&User) -> String;
+
+const TOKENS: &[(&str, TokenValue)] = &[
+    ("maxhp", |user| user.max_hp().to_string()),
+    ("maxmp", |user| user.max_mp().to_string()),
+    ("maxsp", |user| user.max_stamina().to_string()),
+    ("hp", |user| user.basic_attributes.hp.to_string()),
+    ("mp", |user| user.basic_attributes.mp.to_string()),
+    ("sp", |user| user.stamina().to_string()),
+    ("gold", |user| user.basic_attributes.gold.to_string()),
+    ("room", |user| user.room_name.to_string()),
+    ("name", |user| user.name.to_string()),
+];
+
+// Fills in a template's tokens for the user, e.g. "%hp/%maxhp hp>" becomes "20/20 hp>".
+fn render(template: &str, user: &User) -> String {
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(at) = rest.find('%') {
+        rendered.push_str(&rest[..at]);
+        rest = &rest[at + 1..];
+        if let Some(after) = rest.strip_prefix('%') {
+            rendered.push('%');
+            rest = after;
+            continue;
+        }
+        match TOKENS.iter().find(|(token, _)| rest.starts_with(token)) {
+            Some((token, value)) => {
+                rendered.push_str(&value(user));
+                rest = &rest[token.len()..];
+            }
+            None => rendered.push('%'),
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+impl GameState {
+    // The prompt to show the user when asking for their next line of input. Input loses its
+    // trailing spaces on the way in, so one is always added here.
+    pub fn render_prompt(&self, user_name: &UserName) -> Result<String, FaerieError> {
+        let user = self.users.get_user(user_name)?;
+        let template = user.prompt.as_deref().unwrap_or(DEFAULT_PROMPT);
+        let rendered = render(template, user);
+        Ok(format!("{} ", rendered.trim_end()))
+    }
+
+    // Hands the user's prompt to the output sink. Hosts call this when they're ready for
+    // the user's next line.
+    pub fn send_prompt(&mut self, user_name: &UserName) -> Result<(), FaerieError> {
+        let prompt = self.render_prompt(user_name)?;
+        self.output.send_prompt(user_name, &prompt);
+        Ok(())
+    }
+
+    // Shows the user's template, or sets it. `prompt default` goes back to the default.
+    pub(crate) fn set_prompt(
+        &mut self,
+        user_name: &UserName,
+        template: Option<&str>,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let template = match template {
+            None => {
+                let user = self.users.get_user(user_name)?;
+                let template = user.prompt.as_deref().unwrap_or(DEFAULT_PROMPT);
+                return Ok(ActionSuccess::new(vec![
+                    self.text(user_name, "prompt_is", &[("prompt", template)]),
+                    self.text(user_name, "prompt_tokens", &[]),
+                ]));
+            }
+            Some(template) => template,
+        };
+        if template.chars().count() > MAX_PROMPT_LEN {
+            let max = MAX_PROMPT_LEN.to_string();
+            return Err(prompt_failure(self.text(
+                user_name,
+                "text_too_long",
+                &[("max", &max)],
+            )));
+        }
+        let user = self.users.get_user_mut(user_name)?;
+        user.prompt = if template.eq_ignore_ascii_case("default") {
+            None
+        } else {
+            Some(template.to_string())
+        };
+        let prompt = self.render_prompt(user_name)?;
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "prompt_now",
+            &[("prompt", &prompt)],
+        )]))
+    }
+}
+
+fn prompt_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::output::BufferedSink;
+
+    #[test]
+    fn tokens_are_filled_in() {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .user("alice", "hall")
+            .build()
+            .unwrap();
+        let alice = UserName::new("alice").unwrap();
+        assert_eq!(game_state.render_prompt(&alice).unwrap(), ">>> ");
+
+        game_state
+            .process_input_impl(&alice, "prompt [%hp/%maxhp %sp %room] 100%% %x>")
+            .unwrap();
+        assert_eq!(
+            game_state.render_prompt(&alice).unwrap(),
+            "[20/20 40 hall] 100% %x> "
+        );
+        game_state
+            .users
+            .get_user_mut(&alice)
+            .unwrap()
+            .basic_attributes
+            .hp = 7;
+        assert_eq!(
+            game_state.render_prompt(&alice).unwrap(),
+            "[7/20 40 hall] 100% %x> "
+        );
+
+        game_state
+            .process_input_impl(&alice, "prompt default")
+            .unwrap();
+        assert_eq!(game_state.render_prompt(&alice).unwrap(), ">>> ");
+        let long = format!("prompt {}", "%hp".repeat(MAX_PROMPT_LEN));
+        assert!(game_state.process_input_impl(&alice, &long).is_err());
+    }
+
+    #[test]
+    fn prompts_go_to_the_output_sink() {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .user("alice", "hall")
+            .build()
+            .unwrap();
+        let output = BufferedSink::new();
+        game_state.set_output_sink(Box::new(output.clone()));
+        let alice = UserName::new("alice").unwrap();
+
+        game_state.process_input(&alice, "prompt %mp mp>");
+        game_state.send_prompt(&alice).unwrap();
+        assert_eq!(output.drain_for(&alice), vec!["Your prompt is now: 7 mp> "]);
+        let prompts = output.drain_prompts();
+        assert_eq!(prompts.len(), 1);
+        assert_eq!(prompts[0].text, "7 mp> ");
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::events::GameEvent;
+use crate::lambda::{ActionFailure, ActionSuccess};
+use crate::type_aliases::{ItemName, QuestId, RoomName, UserName};
+use crate::GameState;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Objective {
+    VisitRoom(RoomName),
+    CollectItem(ItemName),
+    Defeat(UserName),
+}
+
+impl Objective {
+    // The catalog ID for the objective, and what it's about.
+    fn describe(&self) -> (&'static str, &str) {
+        match self {
+            Objective::VisitRoom(room_name) => ("objective_visit", room_name),
+            Objective::CollectItem(item_name) => ("objective_collect", item_name),
+            Objective::Defeat(user_name) => ("objective_defeat", user_name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quest {
+    pub id: QuestId,
+    pub name: String,
+    pub description: String,
+    pub objectives: Vec<Objective>,
+}
+
+// One user's progress on one quest, by index into Quest::objectives.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuestProgress {
+    pub completed_objectives: BTreeSet<usize>,
+    pub completed: bool,
+}
+
+impl GameState {
+    pub fn register_quest(&mut self, quest: Quest) -> Result<(), FaerieError> {
+        if self.quests.contains_key(&quest.id) {
+            return Err(FaerieError::DuplicateQuest(quest.id));
+        }
+        self.quests.insert(quest.id.clone(), quest);
+        Ok(())
+    }
+
+    // Objectives the user has already met, like carrying the item or standing in the room,
+    // count straight away.
+    pub fn start_quest(
+        &mut self,
+        user_name: &UserName,
+        quest_id: &QuestId,
+    ) -> Result<Vec<String>, FaerieError> {
+        let quest_name = self
+            .quests
+            .get(quest_id)
+            .ok_or_else(|| FaerieError::QuestNotFound(quest_id.clone()))?
+            .name
+            .clone();
+        let user = self.users.get_user_mut(user_name)?;
+        if user.quest_progress.contains_key(quest_id) {
+            return Ok(vec![]);
+        }
+        user.quest_progress
+            .insert(quest_id.clone(), QuestProgress::default());
+
+        let mut already_met = vec![Objective::VisitRoom(user.room_name)];
+        already_met.extend(
+            user.inventory
+                .iter()
+                .map(|i| Objective::CollectItem(i.name.clone())),
+        );
+        let mut messages = vec![self.text(user_name, "new_quest", &[("quest", &quest_name)])];
+        for objective in already_met {
+            messages.extend(self.advance_quests(user_name, &objective));
+        }
+        Ok(messages)
+    }
+
+    pub fn quest_progress(
+        &self,
+        user_name: &UserName,
+        quest_id: &QuestId,
+    ) -> Option<&QuestProgress> {
+        self.users
+            .get_user(user_name)
+            .ok()?
+            .quest_progress
+            .get(quest_id)
+    }
+
+    // Marks the objective done on every active quest of the user's that has it, and returns
+    // a message for each quest that this completes.
+    pub(crate) fn advance_quests(
+        &mut self,
+        user_name: &UserName,
+        achieved: &Objective,
+    ) -> Vec<String> {
+        let quests = &self.quests;
+        let user = match self.users.get_user_mut(user_name) {
+            Ok(user) => user,
+            Err(_) => return vec![],
+        };
+
+        let mut completed = vec![];
+        for (quest_id, progress) in user.quest_progress.iter_mut() {
+            let quest = match quests.get(quest_id) {
+                Some(quest) if !progress.completed => quest,
+                _ => continue,
+            };
+            for (i, objective) in quest.objectives.iter().enumerate() {
+                if objective == achieved {
+                    progress.completed_objectives.insert(i);
+                }
+            }
+            if progress.completed_objectives.len() == quest.objectives.len() {
+                progress.completed = true;
+                completed.push(quest.name.clone());
+            }
+        }
+        completed
+            .iter()
+            .map(|quest_name| self.text(user_name, "quest_complete", &[("quest", quest_name)]))
+            .collect()
+    }
+
+    // Quests are the first listener on the event bus.
+    pub(crate) fn advance_quests_for(&mut self, event: &GameEvent) -> Vec<String> {
+        let achieved = match event {
+            GameEvent::Moved { to, .. } => Objective::VisitRoom(*to),
+            GameEvent::ItemGained { item_name, .. } => Objective::CollectItem(item_name.clone()),
+            GameEvent::Killed { victim_name, .. } => Objective::Defeat(*victim_name),
+            GameEvent::Spoke { .. }
+            | GameEvent::Hit { .. }
+            | GameEvent::EffectStarted { .. }
+            | GameEvent::EffectEnded { .. }
+            | GameEvent::Died { .. } => return vec![],
+        };
+        self.advance_quests(event.user_name(), &achieved)
+    }
+
+    pub fn list_quests(
+        &self,
+        user_name: &UserName,
+        include_completed: bool,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let user = self.users.get_user(user_name)?;
+        let mut active = vec![];
+        let mut completed = vec![];
+        for (quest_id, progress) in &user.quest_progress {
+            let quest = match self.quests.get(quest_id) {
+                Some(quest) => quest,
+                None => continue,
+            };
+            if progress.completed {
+                completed.push(format!("* {}", quest.name));
+                continue;
+            }
+            let done = progress.completed_objectives.len().to_string();
+            let total = quest.objectives.len().to_string();
+            let args = [
+                ("quest", quest.name.as_str()),
+                ("done", &done),
+                ("total", &total),
+            ];
+            active.push(self.text(user_name, "quest_line", &args));
+            active.push(format!("    {}", quest.description));
+            for (i, objective) in quest.objectives.iter().enumerate() {
+                let mark = if progress.completed_objectives.contains(&i) {
+                    "x"
+                } else {
+                    " "
+                };
+                let (id, what) = objective.describe();
+                let objective = self.text(user_name, id, &[("name", what)]);
+                active.push(format!("    [{}] {}", mark, objective));
+            }
+        }
+
+        let mut messages = vec![];
+        if active.is_empty() {
+            messages.push(self.text(user_name, "no_active_quests", &[]));
+        } else {
+            messages.push(self.text(user_name, "active_quests", &[]));
+            messages.append(&mut active);
+        }
+        if include_completed && !completed.is_empty() {
+            messages.push(self.text(user_name, "completed_quests", &[]));
+            messages.append(&mut completed);
+        }
+
+        Ok(ActionSuccess::new(messages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::item::Item;
+    use crate::room::Direction;
+    use crate::type_aliases::{RoomName, UserName};
+
+    fn make_quest_world() -> GameState {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .room("cellar", "Damp.")
+            .path("hall", "cellar", Direction::North)
+            .user("alice", "hall")
+            .build()
+            .unwrap();
+        game_state
+            .register_quest(Quest {
+                id: "cellar".to_string(),
+                name: "Into the Cellar".to_string(),
+                description: "Something is down there.".to_string(),
+                objectives: vec![
+                    Objective::VisitRoom(RoomName::new("cellar").unwrap()),
+                    Objective::CollectItem("lamp".to_string()),
+                ],
+            })
+            .unwrap();
+        game_state
+    }
+
+    #[test]
+    fn objectives_complete_a_quest() {
+        let mut game_state = make_quest_world();
+        let alice = UserName::new("alice").unwrap();
+        let quest_id = "cellar".to_string();
+        game_state.start_quest(&alice, &quest_id).unwrap();
+
+        let lamp = Item::new("lamp".to_string(), "It's a lamp.".to_string()).unwrap();
+        game_state.give_item(&alice, lamp).unwrap();
+        assert!(
+            !game_state
+                .quest_progress(&alice, &quest_id)
+                .unwrap()
+                .completed
+        );
+
+        let succ = game_state.process_input_impl(&alice, "north").unwrap();
+        assert_eq!(succ.messages(), vec!["Quest complete: Into the Cellar!"]);
+        assert!(
+            game_state
+                .quest_progress(&alice, &quest_id)
+                .unwrap()
+                .completed
+        );
+    }
+
+    #[test]
+    fn quests_and_journal_commands() {
+        let mut game_state = make_quest_world();
+        let alice = UserName::new("alice").unwrap();
+
+        let succ = game_state.process_input_impl(&alice, "quests").unwrap();
+        assert_eq!(succ.messages(), vec!["You have no active quests."]);
+
+        game_state
+            .start_quest(&alice, &"cellar".to_string())
+            .unwrap();
+        game_state.process_input_impl(&alice, "north").unwrap();
+        let succ = game_state.process_input_impl(&alice, "quests").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "Active quests:",
+                "* Into the Cellar (1/2)",
+                "    Something is down there.",
+                "    [x] Visit cellar",
+                "    [ ] Collect the lamp",
+            ]
+        );
+
+        let lamp = Item::new("lamp".to_string(), "It's a lamp.".to_string()).unwrap();
+        game_state.give_item(&alice, lamp).unwrap();
+        let succ = game_state.process_input_impl(&alice, "journal").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "You have no active quests.",
+                "Completed quests:",
+                "* Into the Cellar",
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_and_duplicate_quests_are_errors() {
+        let mut game_state = make_quest_world();
+        let res = game_state.start_quest(&UserName::new("alice").unwrap(), &"dragon".to_string());
+        assert_eq!(res, Err(FaerieError::QuestNotFound("dragon".to_string())));
+
+        let quest = game_state.quests["cellar"].clone();
+        let res = game_state.register_quest(quest);
+        assert_eq!(res, Err(FaerieError::DuplicateQuest("cellar".to_string())));
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeSet;
+
+use crate::flags::RoomFlags;
+use crate::type_aliases::UserName;
+use crate::user::UserType;
+use crate::GameState;
+
+// Resting somewhere safe heals this many times faster.
+pub const SAFE_ROOM_REGEN_MULTIPLIER: i32 = 2;
+
+// Hp and mp restored per tick.
+fn regen_rate(user_type: UserType) -> (i32, i32) {
+    match user_type {
+        UserType::Civilian => (1, 1),
+        UserType::Viking => (5, 1),
+        UserType::ElfLord => (2, 3),
+    }
+}
+
+impl GameState {
+    // Run once a tick. Anyone fighting or being fought doesn't recover, and nobody goes
+    // over their maximum.
+    pub(crate) fn regenerate(&mut self) {
+        let fighting: BTreeSet<&UserName> = self
+            .combat_targets
+            .iter()
+            .flat_map(|(attacker, target)| vec![attacker, target])
+            .collect();
+
+        for user in self.users.users.values_mut() {
+            if user.is_dead()
+                || !user.can_regenerate()
+                || fighting.contains(&user.name)
+                || self.dead_users.contains_key(&user.name)
+            {
+                continue;
+            }
+            let (mut hp, mut mp) = regen_rate(user.user_type());
+            let safe = self
+                .rooms
+                .rooms
+                .get(&user.room_name)
+                .is_some_and(|room| room.flags.contains(RoomFlags::SAFE));
+            if safe {
+                hp *= SAFE_ROOM_REGEN_MULTIPLIER;
+                mp *= SAFE_ROOM_REGEN_MULTIPLIER;
+            }
+            let (max_hp, max_mp) = (user.max_hp(), user.max_mp());
+            let attributes = &mut user.basic_attributes;
+            attributes.hp = (attributes.hp + hp).min(max_hp.max(attributes.hp));
+            attributes.mp = (attributes.mp + mp).min(max_mp.max(attributes.mp));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::flags::RoomFlags;
+    use crate::room::Direction;
+    use crate::type_aliases::{RoomName, UserName};
+    use crate::user::UserType;
+    use crate::GameState;
+
+    fn make_resting_world() -> GameState {
+        let mut game_state = GameStateBuilder::new()
+            .room("road", "A dusty road.")
+            .room("shrine", "Candles.")
+            .path("road", "shrine", Direction::East)
+            .user_of_type("olaf", "road", UserType::Viking)
+            .user_of_type("pat", "road", UserType::Viking)
+            .user_of_type("quinn", "shrine", UserType::Viking)
+            .build()
+            .unwrap();
+        game_state
+            .set_room_flags(&RoomName::new("shrine").unwrap(), RoomFlags::SAFE)
+            .unwrap();
+        for user in game_state.users.users.values_mut() {
+            user.basic_attributes.hp = 100;
+            user.basic_attributes.mp = 0;
+        }
+        game_state
+    }
+
+    fn hp_and_mp(game_state: &GameState, name: &str) -> (i32, i32) {
+        let attributes = &game_state.users.get_user(name).unwrap().basic_attributes;
+        (attributes.hp, attributes.mp)
+    }
+
+    #[test]
+    fn rest_is_faster_somewhere_safe() {
+        let mut game_state = make_resting_world();
+        game_state.tick_n(2);
+        assert_eq!(hp_and_mp(&game_state, "olaf"), (110, 2));
+        assert_eq!(hp_and_mp(&game_state, "quinn"), (120, 4));
+
+        game_state.tick_n(100);
+        assert_eq!(hp_and_mp(&game_state, "quinn"), (220, 9));
+    }
+
+    #[test]
+    fn fighting_stops_regeneration() {
+        let mut game_state = make_resting_world();
+        let olaf = UserName::new("olaf").unwrap();
+        game_state.attack(&olaf, "pat").unwrap();
+
+        game_state.tick();
+        assert_eq!(hp_and_mp(&game_state, "olaf"), (100, 0));
+        assert!(hp_and_mp(&game_state, "pat").0 < 100);
+    }
+}
@@ -0,0 +1,265 @@
+use std::fs;
+use std::path::Path as FsPath;
+
+use crate::error::FaerieError;
+use crate::room::{Path, Room};
+use crate::type_aliases::RoomName;
+use crate::GameState;
+
+// What reload_world changed, and what it left alone because users would have been caught up
+// in it.
+#[derive(Debug, Default, PartialEq)]
+pub struct ReloadReport {
+    pub added_rooms: Vec<RoomName>,
+    pub removed_rooms: Vec<RoomName>,
+    pub changed_rooms: Vec<RoomName>,
+    pub conflicts: Vec<String>,
+}
+
+// Only rooms and their paths are reloaded, plus any zones they need and the start and
+// respawn rooms. Users, spawn points and everything else stay as they are, as does whatever
+// has happened in a room since it was made: who's there, what's on the floor, and whether its
+// doors are locked, as long as the door itself hasn't changed.
+impl GameState {
+    pub fn reload_world<P: AsRef<FsPath>>(
+        &mut self,
+        file_path: P,
+    ) -> Result<ReloadReport, FaerieError> {
+        let contents = fs::read_to_string(file_path)?;
+        self.reload_world_str(&contents)
+    }
+
+    // Nothing is changed if the new world doesn't load.
+    pub fn reload_world_str(&mut self, contents: &str) -> Result<ReloadReport, FaerieError> {
+        let mut world = GameState::from_world_str(contents)?;
+        let mut report = ReloadReport::default();
+
+        let mut gone: Vec<RoomName> = self
+            .rooms
+            .rooms
+            .keys()
+            .filter(|room_name| !world.rooms.rooms.contains_key(*room_name))
+            .cloned()
+            .collect();
+        gone.sort();
+        for room_name in gone {
+            match self.delete_room(&room_name) {
+                Ok(()) => report.removed_rooms.push(room_name),
+                Err(FaerieError::RoomOccupied(_)) => report.conflicts.push(format!(
+                    "{} is no longer in the world file, but there are users in it.",
+                    room_name
+                )),
+                Err(e) => return Err(e),
+            }
+        }
+
+        for (zone_name, zone) in std::mem::take(&mut world.zones) {
+            self.zones.entry(zone_name).or_insert(zone);
+        }
+
+        let mut room_names: Vec<RoomName> = world.rooms.rooms.keys().cloned().collect();
+        room_names.sort();
+        for room_name in room_names {
+            let mut new_room = match world.rooms.rooms.remove(&room_name) {
+                Some(room) => room,
+                None => continue,
+            };
+            match self.rooms.rooms.get_mut(&room_name) {
+                Some(room) => {
+                    if update_room(room, new_room) {
+                        report.changed_rooms.push(room_name);
+                    }
+                }
+                None => {
+                    new_room.users.clear();
+                    match self.rooms.insert_room(new_room) {
+                        Ok(()) => report.added_rooms.push(room_name),
+                        Err(FaerieError::DuplicateRoom(_)) => report.conflicts.push(format!(
+                            "{} can't be added while a room of the same name has users in it.",
+                            room_name
+                        )),
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+        }
+
+        // Changed rooms were updated in place, so anything indexed by what's in them is redone.
+        self.rooms.reindex();
+
+        // Rooms the new world dropped but that had to be kept may still be named here.
+        if let Some(room_name) = world.start_room_name {
+            self.set_start_room(&room_name)?;
+        }
+        if let Some(room_name) = world.respawn_room_name {
+            self.set_respawn_room(&room_name)?;
+        }
+        Ok(report)
+    }
+}
+
+// Brings a room's definition up to date, keeping what's happened in it. Hooks are always
+// replaced, but since they can't be compared, only other changes count.
+fn update_room(room: &mut Room, new_room: Room) -> bool {
+    let mut changed = room.description != new_room.description
+        || room.flags != new_room.flags
+        || room.shop != new_room.shop
+        || room.zone != new_room.zone
+        || room.coords != new_room.coords
+        || room.ambient != new_room.ambient
+        || room.terrain != new_room.terrain;
+    room.description = new_room.description;
+    room.flags = new_room.flags;
+    room.shop = new_room.shop;
+    room.zone = new_room.zone;
+    room.coords = new_room.coords;
+    room.ambient = new_room.ambient;
+    room.terrain = new_room.terrain;
+    room.on_enter = new_room.on_enter;
+    room.on_exit = new_room.on_exit;
+    room.describe = new_room.describe;
+
+    let new_paths = new_room.paths;
+    let path_count = room.paths.len();
+    room.paths
+        .retain(|path_name, _| new_paths.contains_key(path_name));
+    changed |= room.paths.len() != path_count;
+    for (path_name, mut new_path) in new_paths {
+        match room.paths.get_mut(&path_name) {
+            Some(path) if same_path(path, &new_path) => {
+                new_path.lock = path.lock.take();
+                *path = new_path;
+            }
+            _ => {
+                room.paths.insert(path_name, new_path);
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+fn same_path(path: &Path, new_path: &Path) -> bool {
+    path.target_room_name == new_path.target_room_name
+        && path.kind == new_path.kind
+        && path.flags == new_path.flags
+        && path.required_skill == new_path.required_skill
+        && path.lock.as_ref().map(|lock| &lock.key_name)
+            == new_path.lock.as_ref().map(|lock| &lock.key_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::type_aliases::UserName;
+
+    const WORLD: &str = r#"
+        start_room = "Hall"
+
+        [[rooms]]
+        name = "Hall"
+        description = "A long hall."
+
+        [[rooms]]
+        name = "Garden"
+        description = "Roses everywhere."
+
+        [[rooms]]
+        name = "Shed"
+        description = "Rakes."
+
+        [[paths]]
+        from = "Hall"
+        to = "Garden"
+        direction = "north"
+        key = "gate key"
+
+        [[paths]]
+        from = "Garden"
+        to = "Shed"
+        direction = "east"
+
+        [[users]]
+        name = "alice"
+        room = "Shed"
+    "#;
+
+    const NEW_WORLD: &str = r#"
+        start_room = "Hall"
+
+        [[rooms]]
+        name = "Hall"
+        description = "A long hall, freshly swept."
+
+        [[rooms]]
+        name = "Garden"
+        description = "Roses everywhere."
+
+        [[rooms]]
+        name = "Pond"
+        description = "Ducks."
+
+        [[paths]]
+        from = "Hall"
+        to = "Garden"
+        direction = "north"
+        key = "gate key"
+
+        [[paths]]
+        from = "Garden"
+        to = "Pond"
+        direction = "west"
+    "#;
+
+    #[test]
+    fn reloads_apply_changes_around_users() {
+        let mut game_state = GameState::from_world_str(WORLD).unwrap();
+        let hall = RoomName::new("Hall").unwrap();
+        let path = game_state.get_path_mut(&hall, "north").unwrap();
+        path.lock.as_mut().unwrap().locked = false;
+
+        let report = game_state.reload_world_str(NEW_WORLD).unwrap();
+        assert_eq!(
+            report,
+            ReloadReport {
+                added_rooms: vec![RoomName::new("Pond").unwrap()],
+                removed_rooms: vec![],
+                changed_rooms: vec![RoomName::new("Garden").unwrap(), hall],
+                conflicts: vec![
+                    "Shed is no longer in the world file, but there are users in it.".to_string()
+                ],
+            }
+        );
+        let alice = UserName::new("alice").unwrap();
+        assert_eq!(game_state.get_user_location(&alice).unwrap(), "Shed");
+        let room = game_state.rooms.get_room(&hall).unwrap();
+        assert_eq!(room.description, "A long hall, freshly swept.");
+        assert!(
+            !room
+                .get_path("north")
+                .unwrap()
+                .lock
+                .as_ref()
+                .unwrap()
+                .locked
+        );
+        assert!(game_state.rooms.get_room("Pond").unwrap().users.is_empty());
+    }
+
+    #[test]
+    fn empty_rooms_are_removed_and_bad_worlds_change_nothing() {
+        let mut game_state = GameState::from_world_str(WORLD).unwrap();
+        game_state
+            .remove_user(&UserName::new("alice").unwrap())
+            .unwrap();
+        let err = game_state.reload_world_str("[[rooms]]").unwrap_err();
+        assert!(matches!(err, FaerieError::Serialization(_)));
+        assert!(game_state.rooms.get_room("Shed").is_ok());
+
+        let report = game_state.reload_world_str(NEW_WORLD).unwrap();
+        assert_eq!(report.removed_rooms, vec![RoomName::new("Shed").unwrap()]);
+        assert!(report.conflicts.is_empty());
+        let garden = game_state.rooms.get_room("Garden").unwrap();
+        assert!(garden.get_path("east").is_none());
+    }
+}
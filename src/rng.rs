@@ -0,0 +1,119 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::journal::JournalEntry;
+use crate::GameState;
+
+// A small SplitMix64 generator. Everything random in the game draws from the one on
+// GameState, so a game started with the same seed and fed the same input plays out the same
+// way. Its state is saved with the game, so a loaded game carries on the same sequence.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameRng {
+    state: u64,
+}
+
+impl Default for GameRng {
+    // Seeded from the clock, for games that don't ask for a seed.
+    fn default() -> GameRng {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        GameRng::new(nanos)
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> GameRng {
+        GameRng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // A number from 0 up to but not including `n`. Zero gives zero.
+    pub fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            return 0;
+        }
+        self.next_u64() % n
+    }
+
+    // A number from `low` to `high`, both included.
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        if high <= low {
+            return low;
+        }
+        low + self.below((high - low) as u64 + 1) as i64
+    }
+
+    // True `percent` times out of a hundred.
+    pub fn chance(&mut self, percent: u32) -> bool {
+        self.below(100) < u64::from(percent)
+    }
+
+    pub fn choose<'a, T>(&mut self, options: &'a [T]) -> Option<&'a T> {
+        if options.is_empty() {
+            return None;
+        }
+        options.get(self.below(options.len() as u64) as usize)
+    }
+}
+
+impl GameState {
+    pub fn new_with_seed(seed: u64) -> GameState {
+        let mut game_state = GameState::new();
+        game_state.set_seed(seed);
+        game_state
+    }
+
+    // Restarts the game's random sequence, including the one scripts draw from.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.journaled(
+            || JournalEntry::SeedSet { seed },
+            |game_state| {
+                game_state.rng = GameRng::new(seed);
+                #[cfg(feature = "scripting")]
+                {
+                    let script_seed = game_state.rng.next_u64();
+                    game_state.scripts.set_seed(script_seed);
+                }
+            },
+        )
+    }
+
+    pub fn rng(&mut self) -> &mut GameRng {
+        &mut self.rng
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = GameRng::new(42);
+        let mut b = GameRng::new(42);
+        let rolls: Vec<i64> = (0..20).map(|_| a.range(1, 6)).collect();
+        assert_eq!(rolls, (0..20).map(|_| b.range(1, 6)).collect::<Vec<_>>());
+        assert!(rolls.iter().all(|r| (1..=6).contains(r)));
+        assert_ne!(GameRng::new(43).next_u64(), GameRng::new(42).next_u64());
+        assert_eq!(a.choose::<u8>(&[]), None);
+        assert_eq!(a.range(3, 3), 3);
+    }
+
+    #[test]
+    fn saved_games_continue_the_sequence() {
+        let mut game_state = GameState::new_with_seed(7);
+        game_state.rng().next_u64();
+        let mut loaded = GameState::from_json(&game_state.to_json().unwrap()).unwrap();
+        assert_eq!(loaded.rng().next_u64(), game_state.rng().next_u64());
+    }
+}
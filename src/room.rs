@@ -1,39 +1,95 @@
-use crate::type_aliases::{PathName, RoomName, UserName};
-use crate::lambda::{mk_action_callback, ActionFunc, ActionSuccess};
+use crate::ambience::AmbientMessage;
+use crate::descriptions::DescriptionProvider;
+use crate::error::FaerieError;
+use crate::flags::{PathFlags, RoomFlags};
+use crate::item::Item;
+use crate::lambda::{mk_action_callback, ActionEvent, ActionFunc, ActionSuccess};
+use crate::loot::Corpse;
+use crate::map::Coords;
+use crate::shop::Shop;
+use crate::skills::SkillCheck;
+use crate::stamina::Terrain;
+use crate::status::StatusEffect;
+use crate::type_aliases::{name_key, ItemName, PathName, RoomName, UserName};
 use crate::user::User;
+use crate::GameState;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
+use serde::{Deserialize, Serialize};
+
+const BRUISE_TICKS: u64 = 3;
+
+#[derive(Serialize, Deserialize)]
 pub struct Room {
     pub name: RoomName,
     pub description: String,
     pub paths: HashMap<PathName, Path>,
     pub users: HashSet<UserName>,
+    #[serde(default)]
+    pub flags: RoomFlags,
+    #[serde(default)]
+    pub shop: Option<Shop>,
+    // Lying on the floor, for anyone to take.
+    #[serde(default)]
+    pub items: Vec<Item>,
+    #[serde(default)]
+    pub gold: i32,
+    #[serde(default)]
+    pub corpses: Vec<Corpse>,
+    #[serde(default)]
+    pub zone: Option<String>,
+    #[serde(default)]
+    pub coords: Option<Coords>,
+    #[serde(default)]
+    pub ambient: Vec<AmbientMessage>,
+    #[serde(default)]
+    pub terrain: Terrain,
+    // Run after someone arrives or leaves; whatever they return is shown to that user.
+    #[serde(skip)]
+    pub on_enter: RoomHook,
+    #[serde(skip)]
+    pub on_exit: RoomHook,
+    #[serde(skip)]
+    pub describe: DescriptionProvider,
 }
 
+pub type RoomHook = Option<Box<dyn FnMut(&mut GameState, &UserName) -> Vec<String> + Send + Sync>>;
+
 impl Room {
-    pub fn new(name: RoomName, description: String) -> Room {
-        assert!(!name.is_empty(), "Empty room names are not allowed!");
-        assert!(
-            !description.is_empty(),
-            "Empty room descriptions are not allowed!"
-        );
-        Room {
+    pub fn new(name: RoomName, description: String) -> Result<Room, FaerieError> {
+        if description.is_empty() {
+            return Err(FaerieError::EmptyRoomDescription);
+        }
+        Ok(Room {
             name,
             description,
             paths: HashMap::new(),
             users: HashSet::new(),
-        }
+            flags: RoomFlags::empty(),
+            shop: None,
+            items: vec![],
+            gold: 0,
+            corpses: vec![],
+            zone: None,
+            coords: None,
+            ambient: vec![],
+            terrain: Terrain::Normal,
+            on_enter: None,
+            on_exit: None,
+            describe: None,
+        })
     }
 
-    pub fn add_path(&mut self, target_room_name: &RoomName, path_name: &PathName) {
-        self.check_duplicate_path(&path_name);
-        let path = Path::new(
-            target_room_name.clone(),
-            path_name.clone(),
-            PathType::Normal,
-        );
-        self.paths.insert(path_name.clone(), path);
+    pub fn add_path(
+        &mut self,
+        target_room_name: &RoomName,
+        path_name: &PathName,
+    ) -> Result<(), FaerieError> {
+        self.check_duplicate_path(path_name)?;
+        let path = Path::new(*target_room_name, *path_name, PathType::Normal);
+        self.paths.insert(*path_name, path);
+        Ok(())
     }
 
     pub fn add_path_special(
@@ -41,23 +97,75 @@ impl Room {
         target_room_name: &RoomName,
         path_name: &PathName,
         path_type: PathType,
-    ) {
-        let path = Path::new(target_room_name.clone(), path_name.clone(), path_type);
-        self.paths.insert(path_name.clone(), path);
+    ) -> Result<(), FaerieError> {
+        let path = Path::new(*target_room_name, *path_name, path_type);
+        self.paths.insert(*path_name, path);
+        Ok(())
+    }
+
+    pub fn remove_path(&mut self, path_name: &str) -> Option<Path> {
+        let path_name = *self.resolve_path(path_name)?;
+        self.paths.remove(&path_name)
     }
 
-    pub fn check_duplicate_path(&self, path_name: &PathName) {
-        assert!(
-            !self.paths.contains_key(path_name),
-            format!("Path '{}' from {} already exists!", &path_name, &self.name) 
-        );
+    // The path's name as it's stored, found by name_key if there's no exact match. Rooms only
+    // have a handful of paths, so they're searched rather than indexed.
+    pub fn resolve_path(&self, path_name: &str) -> Option<&PathName> {
+        let exact = PathName::lookup(path_name).and_then(|name| self.paths.get_key_value(&name));
+        match exact {
+            Some((path_name, _)) => Some(path_name),
+            None => {
+                let key = name_key(path_name);
+                self.paths.keys().find(|name| name_key(name) == key)
+            }
+        }
+    }
+
+    pub fn get_path(&self, path_name: &str) -> Option<&Path> {
+        self.resolve_path(path_name)
+            .and_then(|path_name| self.paths.get(path_name))
+    }
+
+    pub fn get_path_mut(&mut self, path_name: &str) -> Option<&mut Path> {
+        let path_name = *self.resolve_path(path_name)?;
+        self.paths.get_mut(&path_name)
+    }
+
+    pub fn check_duplicate_path(&self, path_name: &str) -> Result<(), FaerieError> {
+        if self.resolve_path(path_name).is_some() {
+            return Err(FaerieError::DuplicatePath {
+                room_name: self.name.to_string(),
+                path_name: path_name.to_string(),
+            });
+        }
+        Ok(())
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Path {
     pub target_room_name: RoomName,
     pub path_name: PathName,
+    pub kind: PathKind,
+    #[serde(default)]
+    pub lock: Option<Lock>,
+    #[serde(default)]
+    pub flags: PathFlags,
+    #[serde(default)]
+    pub required_skill: Option<SkillCheck>,
+    // Closures can't be serialized; see Path::restore_exit_cond.
+    #[serde(skip)]
     pub exit_cond: ActionFunc<User>,
+    // Checked before exit_cond, so a user turned away at the door pays no exit cost.
+    #[serde(skip)]
+    pub entry_cond: ActionFunc<User>,
+}
+
+// A door on a path. Only someone carrying the named key can lock or unlock it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lock {
+    pub key_name: ItemName,
+    pub locked: bool,
 }
 
 pub enum PathType {
@@ -66,41 +174,82 @@ pub enum PathType {
     Custom(ActionFunc<User>),
 }
 
+// Remembers which PathType a path was built from, since exit_cond can't be inspected.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PathKind {
+    Normal,
+    Painful,
+    Custom,
+}
+
 impl Path {
     pub fn new(target_room_name: RoomName, path_name: PathName, path_type: PathType) -> Path {
-        assert!(!path_name.is_empty(), "Empty path names are not allowed!");
         match path_type {
             PathType::Normal => Path {
                 target_room_name,
                 path_name,
+                kind: PathKind::Normal,
+                lock: None,
+                flags: PathFlags::empty(),
                 exit_cond: None,
+                entry_cond: None,
+                required_skill: None,
             },
             PathType::Painful => Path::new_painful(target_room_name, path_name),
             PathType::Custom(exit_cond) => Path {
                 target_room_name,
                 path_name,
+                kind: PathKind::Custom,
+                lock: None,
+                flags: PathFlags::empty(),
                 exit_cond,
+                entry_cond: None,
+                required_skill: None,
             },
         }
     }
 
+    pub fn is_locked(&self) -> bool {
+        self.lock.as_ref().is_some_and(|l| l.locked)
+    }
+
     pub fn new_painful(target_room_name: RoomName, path_name: PathName) -> Path {
+        Path {
+            target_room_name,
+            path_name,
+            kind: PathKind::Painful,
+            lock: None,
+            flags: PathFlags::empty(),
+            exit_cond: Path::painful_exit_cond(),
+            entry_cond: None,
+            required_skill: None,
+        }
+    }
+
+    // Passing through also leaves a bruise for a few ticks, which stops regeneration. Whoever
+    // moves the user tells them it hurt, since that needs the catalog.
+    fn painful_exit_cond() -> ActionFunc<User> {
         let clos = |user: &mut User| {
             user.basic_attributes.hp -= 1;
+            user.add_effect(StatusEffect::Bruised, BRUISE_TICKS);
 
-            Ok(ActionSuccess::new(vec!["You passed through, but it hurt you.".to_string()]))
+            Ok(ActionSuccess::from_events(vec![ActionEvent::Damaged {
+                amount: 1,
+            }]))
         };
-        let exit_cond = mk_action_callback(clos);
 
-        Path {
-            target_room_name,
-            path_name,
-            exit_cond,
+        mk_action_callback(clos)
+    }
+
+    // Rebuilds exit_cond after deserialization. Custom conditions can't be recovered.
+    pub fn restore_exit_cond(&mut self) {
+        if self.kind == PathKind::Painful {
+            self.exit_cond = Path::painful_exit_cond();
         }
     }
 
     pub fn match_basic_aliases(s: String) -> String {
-        match s.as_ref() {
+        match name_key(&s).as_ref() {
             "n" => "north".to_string(),
             "s" => "south".to_string(),
             "w" => "west".to_string(),
@@ -114,7 +263,7 @@ impl Path {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Direction {
     North,
     East,
@@ -124,12 +273,26 @@ pub enum Direction {
     SouthEast,
     SouthWest,
     NorthWest,
-    CustomOneWay(PathName),
-    Custom(PathName, PathName),
+    CustomOneWay(String),
+    Custom(String, String),
 }
 
 impl Direction {
-    pub fn get_path_name(dir: Direction) -> PathName {
+    pub fn from_name(name: &str) -> Option<Direction> {
+        match name {
+            "north" => Some(Direction::North),
+            "south" => Some(Direction::South),
+            "east" => Some(Direction::East),
+            "west" => Some(Direction::West),
+            "northeast" => Some(Direction::NorthEast),
+            "southeast" => Some(Direction::SouthEast),
+            "southwest" => Some(Direction::SouthWest),
+            "northwest" => Some(Direction::NorthWest),
+            _ => None,
+        }
+    }
+
+    pub fn get_path_name(dir: Direction) -> String {
         match dir {
             Direction::North => "north".to_string(),
             Direction::South => "south".to_string(),
@@ -0,0 +1,769 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError, RwLock};
+use std::thread::{self, JoinHandle};
+
+use crate::catalog::{self, Catalog};
+use crate::error::FaerieError;
+use crate::events::GameEvent;
+use crate::flags::{PathFlags, RoomFlags};
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::output::{self, OutputSink};
+use crate::parser::{self, Command};
+use crate::room::{Direction, Path, PathKind, Room};
+
+use crate::type_aliases::{name_key, PathName, RoomName, UserName};
+use crate::user::User;
+use crate::weight::ENCUMBERED_MOVE_DELAY;
+use crate::wrap;
+use crate::{GameState, RoomStore, UserStore};
+
+// What a room is asked to do. Each room handles its messages one at a time, in the order
+// they were sent.
+enum RoomMessage {
+    Input { user_name: UserName, input: String },
+    // Someone handed over by the room they just left.
+    Arrive { user: Box<User>, from: RoomName },
+}
+
+enum WorkerMessage {
+    // For the room with this name_key.
+    Room(String, RoomMessage),
+    // Hand the rooms back; see RoomActors::stop.
+    Stop,
+}
+
+// A room, and everyone in it. Nobody else can touch either while the actors are running.
+struct RoomActor {
+    room: Room,
+    occupants: HashMap<String, User>,
+}
+
+// What a room needs to know about another before sending someone there.
+struct RoomEntry {
+    worker: usize,
+    move_cost: i32,
+    no_mob: bool,
+    // Hooks need the whole GameState, so nobody can go into a room with one.
+    on_enter: bool,
+}
+
+// What every worker can see. Nothing in here is held for longer than one message.
+struct Shared {
+    // Where each room is run, by name_key.
+    directory: HashMap<String, RoomEntry>,
+    inboxes: Vec<Sender<WorkerMessage>>,
+    // Which room each user is in, by name_key, so input can be sent straight there.
+    locator: RwLock<HashMap<String, RoomName>>,
+    // Messages sent but not yet handled, so callers can wait for the world to settle.
+    in_flight: Mutex<usize>,
+    settled: Condvar,
+    catalog: Catalog,
+    output: Mutex<Box<dyn OutputSink>>,
+    // Taken from the game at the start. Nothing the rooms do can change these.
+    refuse_input: bool,
+    dead: HashSet<UserName>,
+    muted: HashSet<String>,
+    // Users who can't move again until the game ticks, which it doesn't until `stop`.
+    slowed: Mutex<HashSet<UserName>>,
+    // Published, in the order they happened, once the game has its rooms back.
+    events: Mutex<Vec<GameEvent>>,
+}
+
+// A worker that panicked has already lost its rooms; the others carry on.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl Shared {
+    fn post(&self, room_name: &str, message: RoomMessage) -> Result<(), FaerieError> {
+        let key = name_key(room_name);
+        let worker = self
+            .directory
+            .get(&key)
+            .ok_or_else(|| FaerieError::RoomNotFound(room_name.to_string()))?
+            .worker;
+        *lock(&self.in_flight) += 1;
+        if self.inboxes[worker]
+            .send(WorkerMessage::Room(key, message))
+            .is_err()
+        {
+            self.finished();
+        }
+        Ok(())
+    }
+
+    fn finished(&self) {
+        let mut in_flight = lock(&self.in_flight);
+        *in_flight = in_flight.saturating_sub(1);
+        if *in_flight == 0 {
+            self.settled.notify_all();
+        }
+    }
+
+    fn send_all(&self, user_name: &UserName, lines: &[String]) {
+        let mut output = lock(&self.output);
+        for line in lines {
+            output.send(user_name, line);
+        }
+    }
+
+    fn text(&self, user: &User, id: &str, args: &[(&str, &str)]) -> String {
+        catalog::lookup(&self.catalog, user.locale.as_deref(), id, args)
+    }
+}
+
+// An opt-in way to run a large world without one lock around all of it. Each room becomes an
+// actor that owns its paths and the users standing in it, and works through its own queue of
+// messages. Rooms are spread over a few worker threads, so rooms on different workers run at
+// the same time. Moving is a handoff: the room being left sends the user, whole, to the room
+// being entered.
+//
+// Only moving, looking, saying and emoting work while the world runs this way; anything
+// that reaches beyond one room needs the whole GameState, so it waits for `stop`. That
+// includes moves along paths that need a skill, or into or out of rooms with hooks: those are
+// refused. Events, like GameEvent::Moved, are published when the world is handed back.
+//
+//     let actors = RoomActors::start(game_state, 4);
+//     actors.send_input(&alice, "north")?;
+//     let game_state = actors.stop();
+pub struct RoomActors {
+    // The rest of the world, without its rooms and users, until they're handed back.
+    game_state: GameState,
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<Vec<RoomActor>>>,
+}
+
+impl RoomActors {
+    // Moves every room, and every user in one, out of the game and into actors. Output goes
+    // to the game's output sink.
+    pub fn start(mut game_state: GameState, workers: usize) -> RoomActors {
+        let workers = workers.max(1);
+        let rooms = std::mem::replace(&mut game_state.rooms, RoomStore::new());
+        let users = std::mem::replace(&mut game_state.users, UserStore::new());
+
+        let mut directory = HashMap::new();
+        let mut shards: Vec<HashMap<String, RoomActor>> =
+            (0..workers).map(|_| HashMap::new()).collect();
+        for (i, room) in rooms.rooms.into_values().enumerate() {
+            let key = name_key(&room.name);
+            let entry = RoomEntry {
+                worker: i % workers,
+                move_cost: room.terrain.move_cost(),
+                no_mob: room.flags.contains(RoomFlags::NO_MOB),
+                on_enter: room.on_enter.is_some(),
+            };
+            directory.insert(key.clone(), entry);
+            let actor = RoomActor {
+                room,
+                occupants: HashMap::new(),
+            };
+            shards[i % workers].insert(key, actor);
+        }
+        let mut locator = HashMap::new();
+        for user in users.users.into_values() {
+            let room_key = name_key(&user.room_name);
+            match directory.get(&room_key).map(|entry| entry.worker) {
+                Some(worker) => {
+                    let user_key = name_key(&user.name);
+                    locator.insert(user_key.clone(), user.room_name);
+                    if let Some(actor) = shards[worker].get_mut(&room_key) {
+                        actor.occupants.insert(user_key, user);
+                    }
+                }
+                // Nowhere to put them; they wait in the game for the rooms to come back.
+                None => game_state.users.restore_user(user),
+            }
+        }
+
+        let (inboxes, receivers): (Vec<_>, Vec<_>) = (0..workers).map(|_| mpsc::channel()).unzip();
+        let output = std::mem::replace(&mut game_state.output, output::default_sink());
+        let tick_count = game_state.tick_count;
+        let slowed = game_state
+            .move_ready_at
+            .iter()
+            .filter(|(_, ready_at)| **ready_at > tick_count)
+            .map(|(user_name, _)| *user_name)
+            .collect();
+        let shared = Arc::new(Shared {
+            directory,
+            inboxes,
+            locator: RwLock::new(locator),
+            in_flight: Mutex::new(0),
+            settled: Condvar::new(),
+            catalog: game_state.catalog.clone(),
+            output: Mutex::new(output),
+            refuse_input: game_state.paused && game_state.reject_input_while_paused,
+            dead: game_state.dead_users.keys().copied().collect(),
+            muted: game_state
+                .muted_users
+                .iter()
+                .map(|name| name_key(name))
+                .collect(),
+            slowed: Mutex::new(slowed),
+            events: Mutex::new(vec![]),
+        });
+        let workers = shards
+            .into_iter()
+            .zip(receivers)
+            .map(|(rooms, inbox)| {
+                let worker = Worker {
+                    rooms,
+                    shared: shared.clone(),
+                };
+                thread::spawn(move || worker.run(inbox))
+            })
+            .collect();
+        RoomActors {
+            game_state,
+            shared,
+            workers,
+        }
+    }
+
+    // Queues a line of input for the user, in whichever room they're in.
+    pub fn send_input(&self, user_name: &UserName, input: &str) -> Result<(), FaerieError> {
+        let room_name = self
+            .locate(user_name)
+            .ok_or_else(|| FaerieError::UserNotFound(user_name.to_string()))?;
+        self.shared.post(
+            &room_name,
+            RoomMessage::Input {
+                user_name: *user_name,
+                input: input.to_string(),
+            },
+        )
+    }
+
+    pub fn locate(&self, user_name: &UserName) -> Option<RoomName> {
+        self.shared
+            .locator
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&name_key(user_name))
+            .cloned()
+    }
+
+    // Blocks until every message sent so far, and everything those led to, has been handled.
+    pub fn wait_idle(&self) {
+        let mut in_flight = lock(&self.shared.in_flight);
+        while *in_flight > 0 {
+            in_flight = self
+                .shared
+                .settled
+                .wait(in_flight)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    // Waits for the rooms to settle, then puts them and their users back into the game.
+    pub fn stop(self) -> GameState {
+        self.wait_idle();
+        let mut game_state = self.game_state;
+        for inbox in &self.shared.inboxes {
+            let _ = inbox.send(WorkerMessage::Stop);
+        }
+        for worker in self.workers {
+            let actors = match worker.join() {
+                Ok(actors) => actors,
+                Err(panic) => std::panic::resume_unwind(panic),
+            };
+            for actor in actors {
+                for user in actor.occupants.into_values() {
+                    game_state.users.restore_user(user);
+                }
+                game_state.rooms.restore_room(actor.room);
+            }
+        }
+        let output = std::mem::replace(&mut *lock(&self.shared.output), output::default_sink());
+        game_state.output = output;
+        let ready_at = game_state.tick_count + ENCUMBERED_MOVE_DELAY;
+        for user_name in lock(&self.shared.slowed).drain() {
+            game_state.move_ready_at.insert(user_name, ready_at);
+        }
+        for event in std::mem::take(&mut *lock(&self.shared.events)) {
+            let user_name = *event.user_name();
+            let messages = game_state.publish(event);
+            game_state.send_all(&user_name, &messages);
+        }
+        game_state
+    }
+}
+
+struct Worker {
+    rooms: HashMap<String, RoomActor>,
+    shared: Arc<Shared>,
+}
+
+impl Worker {
+    fn run(mut self, inbox: Receiver<WorkerMessage>) -> Vec<RoomActor> {
+        while let Ok(WorkerMessage::Room(room_key, message)) = inbox.recv() {
+            if let Some(actor) = self.rooms.get_mut(&room_key) {
+                actor.handle(&self.shared, message);
+            }
+            self.shared.finished();
+        }
+        self.rooms.into_values().collect()
+    }
+}
+
+impl RoomActor {
+    fn handle(&mut self, shared: &Shared, message: RoomMessage) {
+        match message {
+            RoomMessage::Input { user_name, input } => {
+                if !self.occupants.contains_key(&name_key(&user_name)) {
+                    // They left after the input was sent; it follows them.
+                    let room_name = shared
+                        .locator
+                        .read()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .get(&name_key(&user_name))
+                        .cloned();
+                    if let Some(room_name) = room_name.filter(|name| *name != self.room.name) {
+                        let _ = shared.post(&room_name, RoomMessage::Input { user_name, input });
+                    }
+                    return;
+                }
+                self.handle_input(shared, &user_name, &input);
+            }
+            RoomMessage::Arrive { user, from } => self.arrive(shared, user, &from),
+        }
+    }
+
+    fn handle_input(&mut self, shared: &Shared, user_name: &UserName, input: &str) {
+        let key = name_key(user_name);
+        let user_name = match self.occupants.get(&key) {
+            Some(user) => user.name,
+            None => return,
+        };
+        let reply = self.dispatch(shared, &key, &user_name, &parser::sanitize_input(input));
+        let lines = match reply {
+            Ok(succ) => succ.messages(),
+            Err(fail) => fail.messages,
+        };
+        shared.send_all(&user_name, &lines);
+    }
+
+    // Refuses input the same way GameState::dispatch_input does before handing it on.
+    fn dispatch(
+        &mut self,
+        shared: &Shared,
+        key: &str,
+        user_name: &UserName,
+        input: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        if shared.refuse_input {
+            let message = self.text(shared, key, "world_paused", &[]);
+            return Err(ActionFailure::new(vec![message], FailReason::WorldPaused));
+        }
+        if input.chars().count() > parser::MAX_INPUT_LEN {
+            let message = self.text(shared, key, "input_too_long", &[]);
+            return Err(ActionFailure::new(vec![message], FailReason::InputTooLong));
+        }
+        if shared.dead.contains(user_name) {
+            let message = self.text(shared, key, "dead", &[]);
+            return Err(ActionFailure::new(vec![message], FailReason::Dead));
+        }
+        let command = parser::parse(input);
+        let speaking = matches!(command, Ok(Command::Say(_) | Command::Emote(_)));
+        if speaking && shared.muted.contains(key) {
+            let message = self.text(shared, key, "muted", &[]);
+            return Err(ActionFailure::new(
+                vec![message],
+                FailReason::PermissionDenied,
+            ));
+        }
+        match command {
+            Ok(Command::Go(path_name)) => self.leave(shared, key, &path_name),
+            Ok(Command::Bare(path_name)) => match self.leave(shared, key, &path_name) {
+                Err(_) if !self.has_path(&path_name) => {
+                    Err(self.failure(shared, key, "unknown_input", &[("input", input.trim())]))
+                }
+                result => result,
+            },
+            Ok(Command::Say(text)) => {
+                let args = [("name", user_name.as_str()), ("text", text.as_str())];
+                self.tell_others(shared, user_name, "says", &args);
+                lock(&shared.events).push(GameEvent::Spoke {
+                    user_name: *user_name,
+                    text: text.clone(),
+                });
+                Ok(ActionSuccess::new(vec![
+                    self.text(shared, key, "you_say", &args)
+                ]))
+            }
+            Ok(Command::Emote(text)) => {
+                let seen = format!("{} {}", user_name, text);
+                self.send_to_others(shared, user_name, &seen);
+                Ok(ActionSuccess::new(vec![seen]))
+            }
+            Ok(Command::Look(None)) => Ok(ActionSuccess::new(self.describe(shared, key))),
+            _ => Err(self.failure(shared, key, "room_actors_only", &[])),
+        }
+    }
+
+    fn has_path(&self, path_name: &str) -> bool {
+        let path_name = Path::match_basic_aliases(path_name.to_string());
+        self.room.get_path(&path_name).is_some()
+    }
+
+    fn text(&self, shared: &Shared, key: &str, id: &str, args: &[(&str, &str)]) -> String {
+        match self.occupants.get(key) {
+            Some(user) => shared.text(user, id, args),
+            None => id.to_string(),
+        }
+    }
+
+    fn failure(
+        &self,
+        shared: &Shared,
+        key: &str,
+        id: &str,
+        args: &[(&str, &str)],
+    ) -> ActionFailure {
+        ActionFailure::new(
+            vec![self.text(shared, key, id, args)],
+            FailReason::InvalidTarget,
+        )
+    }
+
+    // GameState::attempt_move's checks, in the same order. Paths that need a skill and rooms
+    // with hooks are refused, since those need the rest of the world.
+    fn leave(
+        &mut self,
+        shared: &Shared,
+        key: &str,
+        path_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let path_name = Path::match_basic_aliases(path_name.to_string());
+        let room_name = self.room.name.to_string();
+        let slowed = self
+            .occupants
+            .get(key)
+            .is_some_and(|user| lock(&shared.slowed).contains(&user.name));
+        if slowed {
+            let message = self.text(shared, key, "slowed", &[]);
+            return Err(ActionFailure::new(vec![message], FailReason::Encumbered));
+        }
+        let has_exit_hook = self.room.on_exit.is_some();
+        let path = match self.room.get_path_mut(&path_name) {
+            Some(path) => path,
+            None => {
+                let args = [("exit", path_name.as_str()), ("room", room_name.as_str())];
+                return Err(self.failure(shared, key, "no_such_path", &args));
+            }
+        };
+        let user = match self.occupants.get_mut(key) {
+            Some(user) => user,
+            None => return Ok(ActionSuccess::default()),
+        };
+        if path.flags.contains(PathFlags::CLOSED) {
+            let message = shared.text(user, "path_closed", &[("exit", &path_name)]);
+            return Err(ActionFailure::new(vec![message], FailReason::PathClosed));
+        }
+        let target = match shared.directory.get(&name_key(&path.target_room_name)) {
+            Some(target) if !(user.npc && target.no_mob) => target,
+            _ => {
+                let message = shared.text(user, "exit_blocked", &[]);
+                return Err(ActionFailure::new(vec![message], FailReason::ExitBlocked));
+            }
+        };
+        if path.required_skill.is_some() || has_exit_hook || target.on_enter {
+            let message = shared.text(user, "way_unavailable", &[("exit", &path_name)]);
+            return Err(ActionFailure::new(vec![message], FailReason::ExitBlocked));
+        }
+        let mut stamina_cost = target.move_cost;
+        if user.is_encumbered() {
+            stamina_cost *= 2;
+        }
+        if user.stamina() < stamina_cost {
+            let message = shared.text(user, "exhausted", &[]);
+            return Err(ActionFailure::new(vec![message], FailReason::Exhausted));
+        }
+        if let Some(lock) = path.lock.as_ref().filter(|lock| lock.locked) {
+            let hint = if user.has_item(&lock.key_name) {
+                shared.text(user, "try_unlocking", &[("exit", &path_name)])
+            } else {
+                shared.text(user, "need_key", &[("key", &lock.key_name)])
+            };
+            let message = shared.text(
+                user,
+                "path_locked",
+                &[("exit", path_name.as_str()), ("hint", &hint)],
+            );
+            return Err(ActionFailure::new(vec![message], FailReason::PathLocked));
+        }
+
+        let mut succ = ActionSuccess::default();
+        if let Some(ref mut entry_lambda) = path.entry_cond {
+            succ.append(&mut entry_lambda(user)?);
+        }
+        if let Some(ref mut exit_lambda) = path.exit_cond {
+            if path.kind == PathKind::Painful {
+                succ.push_messages(vec![shared.text(user, "painful_path", &[])]);
+            }
+            succ.append(&mut exit_lambda(user)?);
+        }
+        let target_room_name = path.target_room_name;
+        let moved_along = path.path_name;
+        let leave_id = match Direction::from_name(&path.path_name) {
+            Some(_) => "leaves",
+            None => "leaves_via",
+        };
+
+        let mut user = match self.occupants.remove(key) {
+            Some(user) => user,
+            None => return Ok(succ),
+        };
+        user.fatigue = (user.fatigue + stamina_cost).min(user.max_stamina());
+        if user.is_encumbered() {
+            lock(&shared.slowed).insert(user.name);
+        }
+        lock(&shared.events).push(GameEvent::Moved {
+            user_name: user.name,
+            from: self.room.name,
+            to: target_room_name,
+            path_name: moved_along,
+        });
+        self.room.users.remove(&user.name);
+        let user_name = user.name;
+        let args = [("name", user_name.as_str()), ("exit", moved_along.as_str())];
+        self.tell_others(shared, &user_name, leave_id, &args);
+        shared.send_all(&user_name, &succ.messages());
+
+        // The locator is updated and the user sent while holding the lock, so any input that
+        // finds them in their new room gets there after they do.
+        let mut locator = shared
+            .locator
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        locator.insert(key.to_string(), target_room_name);
+        let from = self.room.name;
+        let user = Box::new(user);
+        let _ = shared.post(&target_room_name, RoomMessage::Arrive { user, from });
+        Ok(ActionSuccess::default())
+    }
+
+    fn arrive(&mut self, shared: &Shared, mut user: Box<User>, from: &RoomName) {
+        let key = name_key(&user.name);
+        user.room_name = self.room.name;
+        user.visited_rooms.insert(self.room.name);
+        self.room.users.insert(user.name);
+        let user_name = user.name;
+        self.occupants.insert(key.clone(), *user);
+
+        let mut compass_paths: Vec<&PathName> = self
+            .room
+            .paths
+            .iter()
+            .filter(|(name, path)| {
+                path.target_room_name == *from && Direction::from_name(name).is_some()
+            })
+            .map(|(name, _)| name)
+            .collect();
+        compass_paths.sort();
+        match compass_paths.first() {
+            Some(from) => {
+                let args = [("name", user_name.as_str()), ("exit", from.as_str())];
+                self.tell_others(shared, &user_name, "arrives_from", &args);
+            }
+            None => self.tell_others(shared, &user_name, "arrives", &[("name", &user_name)]),
+        }
+        shared.send_all(&user_name, &self.describe(shared, &key));
+    }
+
+    // Everyone else in the room who isn't ignoring whoever it's about, each in their own
+    // locale, like GameState::tell_room_except.
+    fn tell_others(&self, shared: &Shared, user_name: &UserName, id: &str, args: &[(&str, &str)]) {
+        for other in self.occupants.values() {
+            if other.name != *user_name && !other.ignored.contains(user_name) {
+                shared.send_all(&other.name, &[shared.text(other, id, args)]);
+            }
+        }
+    }
+
+    // Raw text for everyone else in the room who isn't ignoring whoever it's about.
+    fn send_to_others(&self, shared: &Shared, user_name: &UserName, text: &str) {
+        for other in self.occupants.values() {
+            if other.name != *user_name && !other.ignored.contains(user_name) {
+                shared.send_all(&other.name, &[text.to_string()]);
+            }
+        }
+    }
+
+    // Like GameState::describe_room_for, without anything that needs the rest of the world.
+    fn describe(&self, shared: &Shared, key: &str) -> Vec<String> {
+        let room = &self.room;
+        let mut lines = vec![room.name.to_string()];
+        if room.flags.contains(RoomFlags::DARK) {
+            lines.push(format!("  {}", self.text(shared, key, "too_dark", &[])));
+            return lines;
+        }
+        for paragraph in wrap::paragraphs(&room.description) {
+            lines.push(format!("  {}", paragraph));
+        }
+        lines.push("".to_string());
+        lines.push(self.text(shared, key, "paths_header", &[]));
+
+        let user = self.occupants.get(key);
+        let mut path_names: Vec<&PathName> = room
+            .paths
+            .iter()
+            .filter(|(name, path)| {
+                !path.flags.contains(PathFlags::HIDDEN)
+                    || user.is_some_and(|user| user.found_paths.contains(&(room.name, *(*name))))
+            })
+            .map(|(name, _)| name)
+            .collect();
+        path_names.sort();
+        for path_name in path_names {
+            lines.push(format!("* {}", path_name));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::output::BufferedSink;
+    use crate::room::Direction;
+    use crate::testing::grid_builder;
+
+    #[test]
+    fn users_move_and_talk_between_room_actors() {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .room("garden", "Roses everywhere.")
+            .path("hall", "garden", Direction::North)
+            .user("alice", "hall")
+            .user("bob", "garden")
+            .build()
+            .unwrap();
+        let output = BufferedSink::new();
+        game_state.set_output_sink(Box::new(output.clone()));
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+
+        let actors = RoomActors::start(game_state, 2);
+        actors.send_input(&alice, "north").unwrap();
+        actors.send_input(&alice, "say hi").unwrap();
+        actors.wait_idle();
+        assert_eq!(
+            actors.locate(&alice),
+            Some(RoomName::new("garden").unwrap())
+        );
+        assert_eq!(
+            output.drain_for("alice"),
+            vec![
+                "garden",
+                "  Roses everywhere.",
+                "",
+                "paths: ",
+                "* south",
+                "You say, \"hi\""
+            ]
+        );
+        assert_eq!(
+            output.drain_for("bob"),
+            vec!["alice arrives from the south.", "alice says, \"hi\""]
+        );
+
+        actors.send_input(&bob, "go west").unwrap();
+        actors.send_input(&bob, "score").unwrap();
+        actors.wait_idle();
+        assert_eq!(
+            output.drain_for("bob"),
+            vec![
+                "What? There's no direction west from garden.",
+                "Only moving, looking and talking work here right now."
+            ]
+        );
+    }
+
+    #[test]
+    fn stopping_hands_back_a_consistent_world() {
+        let mut game_state = grid_builder(4, 4)
+            .user("alice", "0,0")
+            .user("bob", "3,3")
+            .build()
+            .unwrap();
+        game_state.set_output_sink(Box::new(BufferedSink::new()));
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+
+        let actors = RoomActors::start(game_state, 3);
+        for _ in 0..3 {
+            actors.send_input(&alice, "east").unwrap();
+            actors.send_input(&bob, "south").unwrap();
+        }
+        let game_state = actors.stop();
+
+        assert!(game_state.validate_world().is_clean());
+        assert_eq!(game_state.get_user_location(&alice).unwrap(), "3,0");
+        assert_eq!(game_state.get_user_location(&bob).unwrap(), "3,0");
+        let alice = game_state.users.get_user(&alice).unwrap();
+        assert!(alice.has_visited("2,0"));
+    }
+
+    #[test]
+    fn room_actors_refuse_what_they_cannot_check() {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .room("garden", "Roses everywhere.")
+            .room("cellar", "Damp.")
+            .path("hall", "garden", Direction::North)
+            .path("hall", "cellar", Direction::East)
+            .user("alice", "hall")
+            .user("bob", "hall")
+            .user("carol", "hall")
+            .build()
+            .unwrap();
+        let output = BufferedSink::new();
+        game_state.set_output_sink(Box::new(output.clone()));
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        let carol = UserName::new("carol").unwrap();
+        let cellar = RoomName::new("cellar").unwrap();
+        game_state
+            .set_on_enter(&cellar, |_, _| vec!["The stairs creak.".to_string()])
+            .unwrap();
+        game_state.muted_users.insert(carol);
+        game_state.ignore(&bob, "alice").unwrap();
+        let moves = Arc::new(Mutex::new(0));
+        let counted = moves.clone();
+        game_state.subscribe(move |_, event| {
+            if let GameEvent::Moved { .. } = event {
+                *lock(&counted) += 1;
+            }
+            vec![]
+        });
+
+        let actors = RoomActors::start(game_state, 2);
+        actors.send_input(&carol, "say hello").unwrap();
+        actors.send_input(&alice, "say hi").unwrap();
+        actors.send_input(&alice, "east").unwrap();
+        actors.send_input(&alice, "north").unwrap();
+        actors.wait_idle();
+        assert_eq!(
+            output.drain_for("carol"),
+            vec![
+                "You have been muted.",
+                "alice says, \"hi\"",
+                "alice leaves north."
+            ]
+        );
+        // Bob is ignoring alice.
+        assert_eq!(output.drain_for("bob"), Vec::<String>::new());
+        assert_eq!(
+            output.drain_for("alice")[..2],
+            ["You say, \"hi\"", "You can't go east right now."]
+        );
+        assert_eq!(*lock(&moves), 0);
+
+        let game_state = actors.stop();
+        assert_eq!(*lock(&moves), 1);
+        assert_eq!(game_state.get_user_location(&alice).unwrap(), "garden");
+    }
+}
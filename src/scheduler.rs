@@ -0,0 +1,137 @@
+use crate::GameState;
+
+pub type TaskId = u64;
+pub type ScheduledFunc = Box<dyn FnMut(&mut GameState) + Send + Sync>;
+
+struct Task {
+    id: TaskId,
+    due_tick: u64,
+    interval: Option<u64>,
+    func: ScheduledFunc,
+}
+
+// Callbacks waiting for a future tick. Lives on GameState and is driven by GameState::tick.
+#[derive(Default)]
+pub(crate) struct Scheduler {
+    next_id: TaskId,
+    tasks: Vec<Task>,
+}
+
+impl GameState {
+    // Runs f once, `ticks` ticks from now. Zero is treated as one: the next tick.
+    pub fn schedule_after<F>(&mut self, ticks: u64, f: F) -> TaskId
+    where
+        F: 'static + Send + Sync + FnMut(&mut GameState),
+    {
+        self.add_task(ticks, None, Box::new(f))
+    }
+
+    // Runs f every `interval` ticks, starting `interval` ticks from now.
+    pub fn schedule_every<F>(&mut self, interval: u64, f: F) -> TaskId
+    where
+        F: 'static + Send + Sync + FnMut(&mut GameState),
+    {
+        let interval = interval.max(1);
+        self.add_task(interval, Some(interval), Box::new(f))
+    }
+
+    // Returns false if the task already ran (and wasn't repeating) or never existed.
+    // A task can't cancel itself from inside its own callback.
+    pub fn cancel_scheduled(&mut self, task_id: TaskId) -> bool {
+        let tasks = &mut self.scheduler.tasks;
+        let before = tasks.len();
+        tasks.retain(|t| t.id != task_id);
+        tasks.len() != before
+    }
+
+    pub fn scheduled_task_count(&self) -> usize {
+        self.scheduler.tasks.len()
+    }
+
+    fn add_task(&mut self, ticks: u64, interval: Option<u64>, func: ScheduledFunc) -> TaskId {
+        let id = self.scheduler.next_id;
+        self.scheduler.next_id += 1;
+        self.scheduler.tasks.push(Task {
+            id,
+            due_tick: self.tick_count + ticks.max(1),
+            interval,
+            func,
+        });
+        id
+    }
+
+    // Runs everything due by the current tick, oldest first. Tasks scheduled by a callback
+    // wait for a later tick.
+    pub(crate) fn run_scheduled_tasks(&mut self) {
+        let now = self.tick_count;
+        let (mut due, waiting): (Vec<Task>, Vec<Task>) = self
+            .scheduler
+            .tasks
+            .drain(..)
+            .partition(|t| t.due_tick <= now);
+        self.scheduler.tasks = waiting;
+        due.sort_by_key(|t| (t.due_tick, t.id));
+
+        for mut task in due {
+            (task.func)(self);
+            if let Some(interval) = task.interval {
+                task.due_tick = now + interval;
+                self.scheduler.tasks.push(task);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::GameState;
+
+    #[test]
+    fn delayed_task_runs_once_on_its_tick() {
+        let mut game_state = GameState::new();
+        let runs = Arc::new(Mutex::new(vec![]));
+        let seen = runs.clone();
+        game_state.schedule_after(2, move |gs| seen.lock().unwrap().push(gs.tick_count()));
+
+        game_state.tick_n(5);
+        assert_eq!(*runs.lock().unwrap(), vec![2]);
+        assert_eq!(game_state.scheduled_task_count(), 0);
+    }
+
+    #[test]
+    fn repeating_task_runs_until_cancelled() {
+        let mut game_state = GameState::new();
+        let runs = Arc::new(Mutex::new(vec![]));
+        let seen = runs.clone();
+        let id = game_state.schedule_every(3, move |gs| seen.lock().unwrap().push(gs.tick_count()));
+
+        game_state.tick_n(7);
+        assert_eq!(*runs.lock().unwrap(), vec![3, 6]);
+
+        assert!(game_state.cancel_scheduled(id));
+        game_state.tick_n(5);
+        assert_eq!(*runs.lock().unwrap(), vec![3, 6]);
+        assert!(!game_state.cancel_scheduled(id));
+    }
+
+    #[test]
+    fn tasks_wait_while_paused_and_can_schedule_more() {
+        let mut game_state = GameState::new();
+        let runs = Arc::new(Mutex::new(vec![]));
+        let seen = runs.clone();
+        game_state.schedule_after(1, move |gs| {
+            let seen = seen.clone();
+            gs.schedule_after(1, move |gs| seen.lock().unwrap().push(gs.tick_count()));
+        });
+
+        game_state.set_paused(true);
+        game_state.tick_n(3);
+        assert_eq!(game_state.scheduled_task_count(), 1);
+
+        game_state.set_paused(false);
+        game_state.tick_n(2);
+        assert_eq!(*runs.lock().unwrap(), vec![2]);
+    }
+}
@@ -0,0 +1,432 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use rhai::{Array, Dynamic, Engine, Scope, AST};
+
+use crate::dice::Dice;
+use crate::error::FaerieError;
+use crate::lambda::{mk_action_callback, ActionEvent, ActionFailure, ActionSuccess, FailReason};
+use crate::rng::GameRng;
+use crate::room::PathKind;
+use crate::type_aliases::{RoomName, UserName};
+use crate::user::User;
+use crate::weather::Weather;
+use crate::GameState;
+
+// Scripts don't panic while holding these, but a thread that shares the game might.
+fn lock<T>(shared: &Mutex<T>) -> MutexGuard<'_, T> {
+    shared.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// Rhai scripts for path conditions, room hooks and NPCs. Every script sees the acting user as
+// `user` (their name), `room`, `items` (an array of item names) and `hp`, which it may change,
+// as well as `tick` and `weather`, e.g. "raining", or "" where there's none. Path conditions
+// always see a `tick` of 0 and no weather.
+// `tell(text)` shows text to the acting user, `random(n)` gives a number from 0 up to but not
+// including n, and `roll(dice)` rolls dice like "2d6+3", giving 0 for notation it can't read.
+// Both draw from a sequence seeded by the game's own.
+//
+// Path conditions refuse passage by returning false or a string to use as the refusal; any
+// other result lets the user through. Room hooks' results are ignored. A description script
+// runs for whoever is looking, can't change their hp, and gives the room's description as a
+// string. An NPC script runs every tick as that NPC, and a string it returns is processed as
+// the NPC's input.
+//
+// Like the closures they become, scripts are not saved with the game state.
+#[derive(Clone)]
+pub(crate) struct ScriptEngine {
+    engine: Arc<Engine>,
+    told: Arc<Mutex<Vec<String>>>,
+    rng: Arc<Mutex<GameRng>>,
+}
+
+impl Default for ScriptEngine {
+    fn default() -> ScriptEngine {
+        let told = Arc::new(Mutex::new(vec![]));
+        let mut engine = Engine::new();
+        let sink = told.clone();
+        engine.register_fn("tell", move |text: &str| {
+            lock(&sink).push(text.to_string());
+        });
+        let rng = Arc::new(Mutex::new(GameRng::default()));
+        let source = rng.clone();
+        engine.register_fn("random", move |n: i64| {
+            lock(&source).below(n.max(0) as u64) as i64
+        });
+        let source = rng.clone();
+        engine.register_fn("roll", move |notation: &str| {
+            notation
+                .parse::<Dice>()
+                .map(|dice| dice.roll(&mut lock(&source)).total)
+                .unwrap_or(0)
+        });
+        ScriptEngine {
+            engine: Arc::new(engine),
+            told,
+            rng,
+        }
+    }
+}
+
+struct ScriptRun {
+    result: Dynamic,
+    told: Vec<String>,
+}
+
+impl ScriptEngine {
+    pub(crate) fn set_seed(&self, seed: u64) {
+        *lock(&self.rng) = GameRng::new(seed);
+    }
+
+    fn compile(&self, source: &str) -> Result<Arc<AST>, FaerieError> {
+        self.engine
+            .compile(source)
+            .map(Arc::new)
+            .map_err(|e| FaerieError::Script(e.to_string()))
+    }
+
+    fn scope_for(user: &User, tick: u64, weather: Option<Weather>) -> Scope<'static> {
+        let items: Array = user
+            .inventory
+            .iter()
+            .map(|i| Dynamic::from(i.name.clone()))
+            .collect();
+        let mut scope = Scope::new();
+        scope.push_constant("user", user.name.to_string());
+        scope.push_constant("room", user.room_name.to_string());
+        scope.push_constant("items", items);
+        scope.push_constant("tick", tick as i64);
+        scope.push_constant("weather", weather.map_or("", Weather::display_name));
+        scope.push("hp", i64::from(user.basic_attributes.hp));
+        scope
+    }
+
+    fn eval(&self, ast: &AST, scope: &mut Scope) -> Result<Dynamic, FaerieError> {
+        lock(&self.told).clear();
+        self.engine
+            .eval_ast_with_scope::<Dynamic>(scope, ast)
+            .map_err(|e| FaerieError::Script(e.to_string()))
+    }
+
+    fn run(
+        &self,
+        ast: &AST,
+        user: &mut User,
+        tick: u64,
+        weather: Option<Weather>,
+    ) -> Result<ScriptRun, FaerieError> {
+        let mut scope = Self::scope_for(user, tick, weather);
+        let result = self.eval(ast, &mut scope)?;
+        if let Some(hp) = scope.get_value::<i64>("hp") {
+            user.basic_attributes.hp = hp.clamp(i32::MIN.into(), i32::MAX.into()) as i32;
+        }
+        Ok(ScriptRun {
+            result,
+            told: lock(&self.told).drain(..).collect(),
+        })
+    }
+
+    fn make_path_cond(
+        &self,
+        source: &str,
+    ) -> Result<impl FnMut(&mut User) -> Result<ActionSuccess, ActionFailure>, FaerieError> {
+        let scripts = self.clone();
+        let ast = self.compile(source)?;
+        Ok(move |user: &mut User| {
+            let hp_before = user.basic_attributes.hp;
+            let run = scripts.run(&ast, user, 0, None)?;
+            if run.result.as_bool() == Ok(false) {
+                return Err(ActionFailure::new(run.told, FailReason::ExitBlocked));
+            }
+            if run.result.is_string() {
+                let mut messages = run.told;
+                messages.push(run.result.to_string());
+                return Err(ActionFailure::new(messages, FailReason::ExitBlocked));
+            }
+            let mut succ = ActionSuccess::new(run.told);
+            let lost = hp_before - user.basic_attributes.hp;
+            if lost > 0 {
+                succ.push(ActionEvent::Damaged { amount: lost });
+            }
+            Ok(succ)
+        })
+    }
+
+    fn make_room_hook(
+        &self,
+        source: &str,
+    ) -> Result<impl FnMut(&mut GameState, &UserName) -> Vec<String>, FaerieError> {
+        let scripts = self.clone();
+        let ast = self.compile(source)?;
+        Ok(move |game_state: &mut GameState, user_name: &UserName| {
+            let tick = game_state.tick_count;
+            let weather = game_state.weather_for(user_name);
+            let res = game_state
+                .users
+                .get_user_mut(user_name)
+                .and_then(|user| scripts.run(&ast, user, tick, weather));
+            match res {
+                Ok(run) => run.told,
+                Err(e) => vec![e.to_string()],
+            }
+        })
+    }
+}
+
+impl GameState {
+    // Runs a script once for the user, like a room hook, and returns what it told them.
+    pub(crate) fn run_script_for(&mut self, user_name: &UserName, source: &str) -> Vec<String> {
+        let scripts = self.scripts.clone();
+        let tick = self.tick_count;
+        let weather = self.weather_for(user_name);
+        let res = scripts.compile(source).and_then(|ast| {
+            self.users
+                .get_user_mut(user_name)
+                .and_then(|user| scripts.run(&ast, user, tick, weather))
+        });
+        match res {
+            Ok(run) => run.told,
+            Err(e) => vec![e.to_string()],
+        }
+    }
+
+    // Anything but a string leaves the room's own description.
+    pub fn set_description_script(
+        &mut self,
+        room_name: &RoomName,
+        source: &str,
+    ) -> Result<(), FaerieError> {
+        let scripts = self.scripts.clone();
+        let ast = scripts.compile(source)?;
+        let name = *room_name;
+        self.set_description_provider(room_name, move |game_state, user_name| {
+            let res = game_state.users.get_user(user_name).and_then(|user| {
+                let weather = game_state.weather_for(user_name);
+                let mut scope = ScriptEngine::scope_for(user, game_state.tick_count, weather);
+                scripts.eval(&ast, &mut scope)
+            });
+            match res {
+                Ok(result) if result.is_string() => result.to_string(),
+                Ok(_) => game_state
+                    .rooms
+                    .get_room(&name)
+                    .map(|room| room.description.clone())
+                    .unwrap_or_default(),
+                Err(e) => e.to_string(),
+            }
+        })
+    }
+
+    pub fn set_exit_script(
+        &mut self,
+        room_name: &RoomName,
+        path_name: &str,
+        source: &str,
+    ) -> Result<(), FaerieError> {
+        let cond = self.scripts.make_path_cond(source)?;
+        let path = self.get_path_mut(room_name, path_name)?;
+        path.exit_cond = mk_action_callback(cond);
+        path.kind = PathKind::Custom;
+        Ok(())
+    }
+
+    pub fn set_entry_script(
+        &mut self,
+        room_name: &RoomName,
+        path_name: &str,
+        source: &str,
+    ) -> Result<(), FaerieError> {
+        let cond = self.scripts.make_path_cond(source)?;
+        self.set_entry_cond(room_name, path_name, cond)
+    }
+
+    pub fn set_on_enter_script(
+        &mut self,
+        room_name: &RoomName,
+        source: &str,
+    ) -> Result<(), FaerieError> {
+        let hook = self.scripts.make_room_hook(source)?;
+        self.set_on_enter(room_name, hook)
+    }
+
+    pub fn set_on_exit_script(
+        &mut self,
+        room_name: &RoomName,
+        source: &str,
+    ) -> Result<(), FaerieError> {
+        let hook = self.scripts.make_room_hook(source)?;
+        self.set_on_exit(room_name, hook)
+    }
+
+    // Errors are shown to the NPC, like any other output it gets; the script keeps running on
+    // later ticks.
+    pub fn set_npc_script(
+        &mut self,
+        user_name: &UserName,
+        source: &str,
+    ) -> Result<(), FaerieError> {
+        self.users.get_user(user_name)?;
+        let scripts = self.scripts.clone();
+        let ast = scripts.compile(source)?;
+        self.users.get_user_mut(user_name)?.npc = true;
+        let npc_name = *user_name;
+        self.schedule_every(1, move |game_state| {
+            let tick = game_state.tick_count;
+            let weather = game_state.weather_for(&npc_name);
+            let res = game_state
+                .users
+                .get_user_mut(&npc_name)
+                .and_then(|npc| scripts.run(&ast, npc, tick, weather));
+            match res {
+                Ok(run) => {
+                    game_state.send_all(&npc_name, &run.told);
+                    if run.result.is_string() {
+                        game_state.process_input(&npc_name, &run.result.to_string());
+                    }
+                }
+                Err(e) => game_state.send_all(&npc_name, &[e.to_string()]),
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::error::FaerieError;
+    use crate::item::Item;
+    use crate::lambda::{ActionEvent, FailReason};
+    use crate::room::Direction;
+    use crate::testing::TestWorld;
+    use crate::type_aliases::{RoomName, UserName};
+
+    // alice and the guard start in the hall; the vault is to the north.
+    fn make_script_world() -> TestWorld {
+        TestWorld::build(
+            GameStateBuilder::new()
+                .room("hall", "A long hall.")
+                .room("vault", "Gold!")
+                .path("hall", "vault", Direction::North)
+                .user("alice", "hall")
+                .user("guard", "hall"),
+        )
+    }
+
+    #[test]
+    fn path_scripts_can_refuse_and_hurt() {
+        let mut world = make_script_world();
+        let alice = UserName::new("alice").unwrap();
+        let hall = RoomName::new("hall").unwrap();
+        world
+            .game_state
+            .set_entry_script(
+                &hall,
+                "north",
+                r#"if !("amulet" in items) { "You need the amulet to enter." }"#,
+            )
+            .unwrap();
+        world
+            .game_state
+            .set_exit_script(&hall, "north", r#"hp -= 5; tell("The door bites.");"#)
+            .unwrap();
+
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "north")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::ExitBlocked);
+        assert_eq!(failure.messages, vec!["You need the amulet to enter."]);
+
+        let amulet = Item::new("amulet".to_string(), "Warm.".to_string()).unwrap();
+        world.game_state.give_item(&alice, amulet).unwrap();
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "north")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["The door bites."]);
+        assert!(succ.events.contains(&ActionEvent::Damaged { amount: 5 }));
+        let user = world.game_state.users.get_user("alice").unwrap();
+        assert_eq!(user.basic_attributes.hp, 15);
+        assert_eq!(user.room_name, "vault");
+    }
+
+    #[test]
+    fn room_and_npc_scripts_run() {
+        let mut world = make_script_world();
+        let alice = UserName::new("alice").unwrap();
+        let guard = UserName::new("guard").unwrap();
+        world
+            .game_state
+            .set_on_enter_script(
+                &RoomName::new("vault").unwrap(),
+                r#"tell("Welcome to the vault, " + user + ".");"#,
+            )
+            .unwrap();
+        world
+            .game_state
+            .set_npc_script(&guard, r#"if tick % 2 == 0 { "say Halt!" }"#)
+            .unwrap();
+
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "north")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["Welcome to the vault, alice."]);
+        world
+            .game_state
+            .process_input_impl(&alice, "south")
+            .unwrap();
+        world.take_messages();
+
+        world.game_state.tick();
+        assert!(world.messages_for("alice").is_empty());
+        world.game_state.tick();
+        assert_eq!(world.messages_for("alice"), vec!["guard says, \"Halt!\""]);
+
+        world
+            .game_state
+            .set_description_script(
+                &RoomName::new("hall").unwrap(),
+                r#"if tick > 100 { "Dust everywhere." }"#,
+            )
+            .unwrap();
+        let succ = world.game_state.process_input_impl(&alice, "look").unwrap();
+        assert_eq!(succ.messages()[1], "  A long hall.");
+        world.game_state.tick_n(100);
+        let succ = world.game_state.process_input_impl(&alice, "look").unwrap();
+        assert_eq!(succ.messages()[1], "  Dust everywhere.");
+    }
+
+    #[test]
+    fn seeded_games_roll_the_same_for_scripts() {
+        let mut heard = vec![];
+        for _ in 0..2 {
+            let mut world = make_script_world();
+            world.game_state.set_seed(99);
+            world
+                .game_state
+                .set_npc_script(
+                    &UserName::new("guard").unwrap(),
+                    r#""say " + random(1000) + " " + roll("2d1+1")"#,
+                )
+                .unwrap();
+            world.game_state.tick_n(3);
+            heard.push(world.messages_for("alice"));
+        }
+        assert_eq!(heard[0].len(), 3);
+        assert!(heard[0][0].ends_with(" 3\""));
+        assert_eq!(heard[0], heard[1]);
+    }
+
+    #[test]
+    fn bad_scripts_are_rejected() {
+        let mut world = make_script_world();
+        let res = world
+            .game_state
+            .set_on_exit_script(&RoomName::new("hall").unwrap(), "if {");
+        match res {
+            Err(FaerieError::Script(_)) => {}
+            _ => panic!("Malformed script was not rejected!"),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::flags::PathFlags;
+use crate::lambda::{ActionFailure, ActionSuccess};
+use crate::room::Path;
+use crate::type_aliases::{PathName, RoomName, UserName};
+use crate::user::User;
+use crate::GameState;
+
+// Decides whether a search turns up a particular hidden path, e.g. by rolling against a
+// skill. Without one, searching always finds everything.
+pub type SearchCheck = Box<dyn FnMut(&User, &RoomName, &PathName) -> bool + Send + Sync>;
+
+impl GameState {
+    pub fn set_search_check<F>(&mut self, check: F)
+    where
+        F: 'static + Send + Sync + FnMut(&User, &RoomName, &PathName) -> bool,
+    {
+        self.search_check = Some(Box::new(check));
+    }
+
+    // Hidden paths only show up for users who have found them.
+    pub(crate) fn can_see_path(
+        &self,
+        user_name: &UserName,
+        room_name: &RoomName,
+        path: &Path,
+    ) -> bool {
+        if !path.flags.contains(PathFlags::HIDDEN) {
+            return true;
+        }
+        self.users
+            .get_user(user_name)
+            .is_ok_and(|user| user.found_paths.contains(&(*room_name, path.path_name)))
+    }
+
+    pub fn search(&mut self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let room_name = self.get_user_location(user_name)?;
+        let mut hidden: Vec<PathName> = self
+            .rooms
+            .get_room(&room_name)?
+            .paths
+            .values()
+            .filter(|path| !self.can_see_path(user_name, &room_name, path))
+            .map(|path| path.path_name)
+            .collect();
+        hidden.sort();
+
+        let mut check = self.search_check.take();
+        let user = self.users.get_user_mut(user_name)?;
+        let mut found_paths = vec![];
+        for path_name in hidden {
+            let found = match check.as_mut() {
+                Some(check) => check(user, &room_name, &path_name),
+                None => true,
+            };
+            if found {
+                found_paths.push(path_name);
+                user.found_paths.insert((room_name, path_name));
+            }
+        }
+        self.search_check = check;
+
+        let mut messages: Vec<String> = found_paths
+            .iter()
+            .map(|path_name| self.text(user_name, "you_find_way", &[("exit", path_name)]))
+            .collect();
+        if messages.is_empty() {
+            messages.push(self.text(user_name, "found_nothing", &[]));
+        }
+        Ok(ActionSuccess::new(messages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::flags::PathFlags;
+    use crate::room::Direction;
+    use crate::type_aliases::{RoomName, UserName};
+
+    use crate::GameState;
+
+    fn make_search_world() -> GameState {
+        let mut game_state = GameStateBuilder::new()
+            .room("study", "Bookshelves everywhere.")
+            .room("vault", "Gold!")
+            .path(
+                "study",
+                "vault",
+                Direction::CustomOneWay("bookcase".to_string()),
+            )
+            .user("alice", "study")
+            .user("bob", "study")
+            .build()
+            .unwrap();
+        game_state
+            .set_path_flags(
+                &RoomName::new("study").unwrap(),
+                "bookcase",
+                PathFlags::HIDDEN,
+            )
+            .unwrap();
+        game_state
+    }
+
+    #[test]
+    fn found_paths_are_remembered_per_user() {
+        let mut game_state = make_search_world();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        assert_eq!(game_state.describe_room(&alice).unwrap().len(), 4);
+
+        let succ = game_state.process_input_impl(&alice, "search").unwrap();
+        assert_eq!(succ.messages(), vec!["You find a hidden way bookcase!"]);
+        assert_eq!(
+            game_state.describe_room(&alice).unwrap().last().unwrap(),
+            "* bookcase"
+        );
+        assert_eq!(game_state.describe_room(&bob).unwrap().len(), 4);
+
+        let succ = game_state.process_input_impl(&alice, "search").unwrap();
+        assert_eq!(succ.messages(), vec!["You search, but find nothing."]);
+    }
+
+    #[test]
+    fn search_checks_can_fail() {
+        let mut game_state = make_search_world();
+        let alice = UserName::new("alice").unwrap();
+        let mut attempts = 0;
+        game_state.set_search_check(move |_, _, _| {
+            attempts += 1;
+            attempts > 2
+        });
+
+        for _ in 0..2 {
+            let succ = game_state.process_input_impl(&alice, "search").unwrap();
+            assert_eq!(succ.messages(), vec!["You search, but find nothing."]);
+        }
+        let succ = game_state.process_input_impl(&alice, "search").unwrap();
+        assert_eq!(succ.messages(), vec!["You find a hidden way bookcase!"]);
+    }
+}
@@ -0,0 +1,647 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::accounts::{AccountStore, MIN_PASSWORD_LEN};
+use crate::error::FaerieError;
+use crate::output::BufferedSink;
+use crate::type_aliases::{AccountName, UserName, MAX_NAME_LEN};
+use crate::GameState;
+
+pub(crate) type ClientId = u64;
+
+pub(crate) const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+pub(crate) enum ServerEvent<C> {
+    Connected(ClientId, C),
+    Line(ClientId, String),
+    Disconnected(ClientId),
+    Tick,
+}
+
+// How the game thread reaches a client, whether a socket it writes to itself or a channel to
+// a task that does (see async_server.rs).
+pub(crate) trait Connection {
+    // False once the client has gone.
+    fn write_text(&mut self, text: &str) -> bool;
+    fn close(&mut self);
+}
+
+impl Connection for TcpStream {
+    fn write_text(&mut self, text: &str) -> bool {
+        self.write_all(text.as_bytes()).is_ok()
+    }
+
+    fn close(&mut self) {
+        let _ = self.shutdown(Shutdown::Both);
+    }
+}
+
+struct Client<C> {
+    connection: C,
+    user_name: Option<UserName>,
+    login: LoginStep,
+}
+
+// How far a client has got with logging in. Names are kept as typed until they've passed
+// every check, so nothing is interned for names that are refused.
+enum LoginStep {
+    Name,
+    Password(String),
+    NewName,
+    NewPassword(String),
+}
+
+// What a line typed while logging in leads to: another question, or playing as someone.
+enum LoginOutcome {
+    Ask(&'static str, LoginStep),
+    Play(UserName),
+}
+
+// Logins, input, ticks and output for every connected client, however they're connected.
+// Game output is buffered and routed to whichever client is logged in as the addressed user.
+pub(crate) struct Sessions<C> {
+    game_state: GameState,
+    output: BufferedSink,
+    clients: HashMap<ClientId, Client<C>>,
+    accounts: AccountStore,
+    // Where accounts are saved as they're registered, if anywhere.
+    accounts_file: Option<PathBuf>,
+}
+
+impl<C: Connection> Sessions<C> {
+    pub(crate) fn new(mut game_state: GameState) -> Result<Sessions<C>, FaerieError> {
+        game_state.start_room().ok_or(FaerieError::NoStartRoom)?;
+        let output = BufferedSink::new();
+        game_state.set_output_sink(Box::new(output.clone()));
+        Ok(Sessions {
+            game_state,
+            output,
+            clients: HashMap::new(),
+            accounts: AccountStore::new(),
+            accounts_file: None,
+        })
+    }
+
+    pub(crate) fn set_accounts(&mut self, accounts: AccountStore, file_path: Option<PathBuf>) {
+        self.accounts = accounts;
+        self.accounts_file = file_path;
+    }
+
+    pub(crate) fn handle_event(&mut self, event: ServerEvent<C>) {
+        match event {
+            ServerEvent::Connected(client_id, connection) => {
+                let client = Client {
+                    connection,
+                    user_name: None,
+                    login: LoginStep::Name,
+                };
+                self.clients.insert(client_id, client);
+                log::info!("client {} connected", client_id);
+                let greeting = self.game_state.default_text("what_is_your_name", &[]);
+                self.send(client_id, &[greeting]);
+            }
+            ServerEvent::Line(client_id, line) => self.handle_line(client_id, line.trim()),
+            ServerEvent::Disconnected(client_id) => {
+                let client = self.clients.remove(&client_id);
+                log::info!("client {} disconnected", client_id);
+                if let Some(user_name) = client.and_then(|c| c.user_name) {
+                    log::info!("{} logged out", user_name);
+                    self.game_state.notify_logout(&user_name);
+                }
+            }
+            ServerEvent::Tick => self.game_state.tick(),
+        }
+        self.flush_output();
+        self.drop_kicked_clients();
+    }
+
+    fn drop_kicked_clients(&mut self) {
+        for user_name in self.game_state.take_kicked_users() {
+            self.clients.retain(|_, c| {
+                let kicked = c.user_name.as_ref() == Some(&user_name);
+                if kicked {
+                    c.connection.close();
+                }
+                !kicked
+            });
+        }
+    }
+
+    fn flush_output(&mut self) {
+        for message in self.output.drain() {
+            if let Some(client_id) = self.client_of(&message.user_name) {
+                self.send(client_id, &[message.text]);
+            }
+        }
+        // Prompts go last, and without a line break, so input is typed after them.
+        for prompt in self.output.drain_prompts() {
+            if let Some(client_id) = self.client_of(&prompt.user_name) {
+                self.write(client_id, &prompt.text);
+            }
+        }
+    }
+
+    fn client_of(&self, user_name: &UserName) -> Option<ClientId> {
+        self.clients
+            .iter()
+            .find(|(_, c)| c.user_name.as_ref() == Some(user_name))
+            .map(|(id, _)| *id)
+    }
+
+    fn handle_line(&mut self, client_id: ClientId, line: &str) {
+        let user_name = match self.clients.get(&client_id) {
+            Some(client) => client.user_name,
+            None => return,
+        };
+
+        match user_name {
+            Some(user_name) => {
+                self.game_state.process_input(&user_name, line);
+                let _ = self.game_state.send_prompt(&user_name);
+            }
+            None => self.handle_login(client_id, line),
+        }
+    }
+
+    // Returning players give their name, then their password. Typing `new` registers an
+    // account along with a character of the same name. Characters no account owns, like NPCs
+    // or the world's own admins, can't be logged in as at all.
+    fn handle_login(&mut self, client_id: ClientId, line: &str) {
+        let step = match self.clients.get_mut(&client_id) {
+            Some(client) => std::mem::replace(&mut client.login, LoginStep::Name),
+            None => return,
+        };
+        let outcome = match step {
+            LoginStep::Name if line.eq_ignore_ascii_case("new") => {
+                LoginOutcome::Ask("new_name", LoginStep::NewName)
+            }
+            LoginStep::Name if self.game_state.is_banned(line) => {
+                LoginOutcome::Ask("name_banned", LoginStep::Name)
+            }
+            LoginStep::Name => LoginOutcome::Ask("password", LoginStep::Password(line.to_string())),
+            LoginStep::Password(name) => self.log_in(&name, line),
+            LoginStep::NewName => self.choose_name(line),
+            LoginStep::NewPassword(name) => self.register(&name, line),
+        };
+        let (id, next) = match outcome {
+            LoginOutcome::Ask(id, next) => (id, next),
+            LoginOutcome::Play(user_name) => match self.attach(client_id, user_name) {
+                Ok(()) => return,
+                Err(id) => (id, LoginStep::Name),
+            },
+        };
+        if let Some(client) = self.clients.get_mut(&client_id) {
+            client.login = next;
+        }
+        let min = MIN_PASSWORD_LEN.to_string();
+        let text = self.game_state.default_text(id, &[("min", &min)]);
+        self.send(client_id, &[text]);
+    }
+
+    // Unknown names and wrong passwords are refused alike (see AccountStore::authenticate).
+    fn log_in(&self, name: &str, password: &str) -> LoginOutcome {
+        match self.accounts.authenticate(name, password) {
+            // An account plays the character it was registered with.
+            Ok(account) => match account.characters.first() {
+                Some(user_name) => LoginOutcome::Play(*user_name),
+                None => LoginOutcome::Ask("name_unavailable", LoginStep::Name),
+            },
+            Err(_) => LoginOutcome::Ask("login_failed", LoginStep::Name),
+        }
+    }
+
+    fn choose_name(&self, name: &str) -> LoginOutcome {
+        if self.game_state.is_banned(name) {
+            return LoginOutcome::Ask("name_banned", LoginStep::Name);
+        }
+        if !self.name_is_free(name) {
+            return LoginOutcome::Ask("new_name_unavailable", LoginStep::NewName);
+        }
+        LoginOutcome::Ask("new_password", LoginStep::NewPassword(name.to_string()))
+    }
+
+    fn register(&mut self, name: &str, password: &str) -> LoginOutcome {
+        if password.chars().count() < MIN_PASSWORD_LEN {
+            return LoginOutcome::Ask(
+                "password_too_short",
+                LoginStep::NewPassword(name.to_string()),
+            );
+        }
+        // Someone else may have taken the name while this client chose a password.
+        if self.game_state.is_banned(name) || !self.name_is_free(name) {
+            return LoginOutcome::Ask("new_name_unavailable", LoginStep::NewName);
+        }
+        let registered = AccountName::new(name).and_then(|account_name| {
+            let user_name = UserName::new(name)?;
+            self.accounts.register(&account_name, password)?;
+            self.accounts.add_character(name, &user_name)?;
+            Ok(user_name)
+        });
+        let user_name = match registered {
+            Ok(user_name) => user_name,
+            Err(e) => {
+                log::error!("couldn't register {}: {}", name, e);
+                return LoginOutcome::Ask("new_name_unavailable", LoginStep::NewName);
+            }
+        };
+        if let Some(file_path) = &self.accounts_file {
+            if let Err(e) = self.accounts.save_to_file(file_path) {
+                log::error!("couldn't save accounts to {}: {}", file_path.display(), e);
+            }
+        }
+        log::info!("{} registered", user_name);
+        LoginOutcome::Play(user_name)
+    }
+
+    // Whether a new account and character could both have this name, in any case.
+    fn name_is_free(&self, name: &str) -> bool {
+        let name = name.trim();
+        !name.is_empty()
+            && name.chars().count() <= MAX_NAME_LEN
+            && self.game_state.users.resolve(name).is_none()
+            && self.accounts.get_account(name).is_err()
+    }
+
+    // Puts the client in the world as the user, making the character first if the world
+    // doesn't have them, e.g. because it was reset. Fails with the message to send instead.
+    fn attach(&mut self, client_id: ClientId, user_name: UserName) -> Result<(), &'static str> {
+        let already_connected = self
+            .clients
+            .values()
+            .any(|c| c.user_name.as_ref() == Some(&user_name));
+        if already_connected {
+            return Err("name_unavailable");
+        }
+        match self.game_state.users.get_user(&user_name) {
+            Ok(user) if user.npc => return Err("name_unavailable"),
+            Ok(_) => {}
+            Err(_) => {
+                // Builders can rename the start room, so look it up each time.
+                let created = match self.game_state.start_room().cloned() {
+                    Some(start_room_name) => self
+                        .game_state
+                        .create_basic_user_in_room(&user_name, &start_room_name),
+                    None => Err(FaerieError::NoStartRoom),
+                };
+                if let Err(e) = created {
+                    log::error!("couldn't create {}: {}", user_name, e);
+                    return Err("name_unavailable");
+                }
+            }
+        }
+
+        if let Some(client) = self.clients.get_mut(&client_id) {
+            client.user_name = Some(user_name);
+        }
+        log::info!("{} logged in on client {}", user_name, client_id);
+        let welcome = self
+            .game_state
+            .text(&user_name, "welcome", &[("name", &user_name)]);
+        self.send(client_id, &[welcome]);
+        if let Err(e) = self.game_state.print_room(&user_name) {
+            self.send(client_id, &[e.to_string()]);
+        }
+        let notices = self.game_state.login_notices(&user_name);
+        self.send(client_id, &notices);
+        self.game_state.notify_login(&user_name);
+        let _ = self.game_state.send_prompt(&user_name);
+        Ok(())
+    }
+
+    fn send(&mut self, client_id: ClientId, messages: &[String]) {
+        for message in messages {
+            self.write(client_id, &format!("{}\r\n", message));
+        }
+    }
+
+    fn write(&mut self, client_id: ClientId, text: &str) {
+        let failed = match self.clients.get_mut(&client_id) {
+            Some(client) => !client.connection.write_text(text),
+            None => false,
+        };
+        if failed {
+            self.clients.remove(&client_id);
+        }
+    }
+}
+
+// The game runs on the thread that calls run/serve. Each connection gets a reader thread that forwards lines to the game thread over a channel,
+// and the game thread is the only one that writes to sockets.
+pub struct Server {
+    sessions: Sessions<TcpStream>,
+}
+
+impl Server {
+    // New users are placed in the world's start room.
+    pub fn new(game_state: GameState) -> Result<Server, FaerieError> {
+        Ok(Server {
+            sessions: Sessions::new(game_state)?,
+        })
+    }
+
+    // The accounts players log in with, which start out empty. Accounts registered while
+    // serving are saved to the file, if one is given, as soon as they're made.
+    pub fn with_accounts(mut self, accounts: AccountStore, file_path: Option<PathBuf>) -> Server {
+        self.sessions.set_accounts(accounts, file_path);
+        self
+    }
+
+    pub fn run<A: ToSocketAddrs>(self, addr: A) -> Result<(), FaerieError> {
+        let listener = TcpListener::bind(addr)?;
+        self.serve(listener)
+    }
+
+    pub fn serve(mut self, listener: TcpListener) -> Result<(), FaerieError> {
+        let (tx, rx) = mpsc::channel();
+        let ticker_tx = tx.clone();
+        thread::spawn(move || accept_connections(listener, tx));
+        thread::spawn(move || send_ticks(ticker_tx));
+        for event in rx {
+            self.sessions.handle_event(event);
+        }
+        Ok(())
+    }
+}
+
+fn accept_connections(listener: TcpListener, tx: Sender<ServerEvent<TcpStream>>) {
+    let mut next_client_id = 0;
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(_) => continue,
+        };
+        let reader = match stream.try_clone() {
+            Ok(s) => s,
+            Err(_) => continue,
+        };
+
+        let client_id = next_client_id;
+        next_client_id += 1;
+        if tx.send(ServerEvent::Connected(client_id, stream)).is_err() {
+            return;
+        }
+
+        let tx = tx.clone();
+        thread::spawn(move || read_lines(client_id, reader, tx));
+    }
+}
+
+fn send_ticks(tx: Sender<ServerEvent<TcpStream>>) {
+    loop {
+        thread::sleep(TICK_INTERVAL);
+        if tx.send(ServerEvent::Tick).is_err() {
+            return;
+        }
+    }
+}
+
+fn read_lines(client_id: ClientId, stream: TcpStream, tx: Sender<ServerEvent<TcpStream>>) {
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => break,
+        };
+        if tx.send(ServerEvent::Line(client_id, line)).is_err() {
+            return;
+        }
+    }
+    let _ = tx.send(ServerEvent::Disconnected(client_id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORLD: &str = r#"
+        start_room = "Hall"
+
+        [[rooms]]
+        name = "Hall"
+        description = "A long hall."
+
+        [[rooms]]
+        name = "Garden"
+        description = "Roses everywhere."
+
+        [[paths]]
+        from = "Hall"
+        to = "Garden"
+        direction = "north"
+    "#;
+
+    fn start_server() -> std::net::SocketAddr {
+        start_server_with(GameState::from_world_str(WORLD).unwrap())
+    }
+
+    fn start_server_with(game_state: GameState) -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            Server::new(game_state).unwrap().serve(listener).unwrap();
+        });
+        addr
+    }
+
+    const PASSWORD: &str = "correct horse";
+
+    struct TestClient {
+        stream: TcpStream,
+        reader: BufReader<TcpStream>,
+    }
+
+    impl TestClient {
+        fn open(addr: std::net::SocketAddr) -> TestClient {
+            let stream = TcpStream::connect(addr).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_millis(300)))
+                .unwrap();
+            let reader = BufReader::new(stream.try_clone().unwrap());
+            let mut client = TestClient { stream, reader };
+            assert_eq!(
+                client.read_line(),
+                Some("What is your name? Type `new` to make a new character.".to_string())
+            );
+            client
+        }
+
+        // Registers a new character with PASSWORD.
+        fn connect(addr: std::net::SocketAddr, name: &str) -> TestClient {
+            let mut client = TestClient::open(addr);
+            client.send("new");
+            client.read_line();
+            client.send(name);
+            client.read_line();
+            client.send(PASSWORD);
+            client
+        }
+
+        fn log_in(addr: std::net::SocketAddr, name: &str, password: &str) -> TestClient {
+            let mut client = TestClient::open(addr);
+            client.send(name);
+            assert_eq!(client.read_line(), Some("Password?".to_string()));
+            client.send(password);
+            client
+        }
+
+        fn send(&mut self, line: &str) {
+            write!(self.stream, "{}\r\n", line).unwrap();
+        }
+
+        fn read_line(&mut self) -> Option<String> {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Ok(n) if n > 0 => Some(line.trim_end().to_string()),
+                _ => None,
+            }
+        }
+
+        fn read_all(&mut self) -> Vec<String> {
+            let mut lines = vec![];
+            while let Some(line) = self.read_line() {
+                lines.push(line);
+            }
+            lines
+        }
+    }
+
+    #[test]
+    fn output_only_reaches_the_acting_client() {
+        let addr = start_server();
+        let mut alice = TestClient::connect(addr, "alice");
+        let mut bob = TestClient::connect(addr, "bob");
+
+        let greeting = alice.read_all();
+        assert_eq!(greeting[0], "Welcome, alice!");
+        assert_eq!(greeting[1], "Hall");
+        bob.read_all();
+
+        alice.send("north");
+        let lines = alice.read_all();
+        assert_eq!(lines[0], "Garden");
+        assert_eq!(bob.read_all(), vec!["alice leaves north."]);
+
+        bob.send("list_users");
+        let lines = bob.read_all();
+        assert_eq!(lines[0], "Users online:");
+        assert!(alice.read_all().is_empty());
+    }
+
+    #[test]
+    fn name_already_connected_is_refused() {
+        let addr = start_server();
+        let mut alice = TestClient::connect(addr, "alice");
+        alice.read_all();
+
+        let mut imposter = TestClient::log_in(addr, "alice", PASSWORD);
+        assert_eq!(
+            imposter.read_line(),
+            Some("That name isn't available. What is your name?".to_string())
+        );
+    }
+
+    #[test]
+    fn login_text_comes_from_the_catalog() {
+        let mut game_state = GameState::from_world_str(WORLD).unwrap();
+        let reworded = [
+            ("what_is_your_name", "Who goes there?"),
+            ("name_banned", "Begone."),
+            ("welcome", "Hail, {name}!"),
+        ];
+        game_state
+            .add_messages(
+                "en",
+                reworded
+                    .iter()
+                    .map(|(id, text)| (id.to_string(), text.to_string())),
+            )
+            .unwrap();
+        game_state
+            .banned_users
+            .insert(UserName::new("mallory").unwrap());
+        let addr = start_server_with(game_state);
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_millis(300)))
+            .unwrap();
+        let mut reader = BufReader::new(client.try_clone().unwrap());
+        let mut read_line = || {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            line.trim_end().to_string()
+        };
+        assert_eq!(read_line(), "Who goes there?");
+        write!(client, "mallory\r\n").unwrap();
+        assert_eq!(read_line(), "Begone.");
+        for line in &["new", "alice"] {
+            write!(client, "{}\r\n", line).unwrap();
+            read_line();
+        }
+        write!(client, "{}\r\n", PASSWORD).unwrap();
+        assert_eq!(read_line(), "Hail, alice!");
+    }
+
+    #[test]
+    fn only_registered_names_with_the_right_password_get_in() {
+        let mut game_state = GameState::from_world_str(WORLD).unwrap();
+        let hall = crate::RoomName::new("Hall").unwrap();
+        let guard = UserName::new("guard").unwrap();
+        game_state.create_basic_user_in_room(&guard, &hall).unwrap();
+        game_state.set_role(&guard, crate::Role::Admin).unwrap();
+        let accounts_file =
+            std::env::temp_dir().join(format!("faerie_accounts_{}.json", std::process::id()));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = Server::new(game_state)
+            .unwrap()
+            .with_accounts(AccountStore::new(), Some(accounts_file.clone()));
+        thread::spawn(move || server.serve(listener).unwrap());
+
+        let mut alice = TestClient::connect(addr, "alice");
+        assert_eq!(alice.read_line(), Some("Welcome, alice!".to_string()));
+        drop(alice);
+        thread::sleep(Duration::from_millis(100));
+        let saved = AccountStore::load_from_file(&accounts_file).unwrap();
+        assert!(saved.authenticate("alice", PASSWORD).is_ok());
+        std::fs::remove_file(&accounts_file).unwrap();
+
+        // Wrong passwords, unregistered names and characters nobody has an account for, like
+        // the world's own admin, are all refused alike.
+        for (name, password) in &[
+            ("alice", "wrong password"),
+            ("zed", PASSWORD),
+            ("guard", PASSWORD),
+        ] {
+            let mut client = TestClient::log_in(addr, name, password);
+            assert_eq!(
+                client.read_line(),
+                Some("That name and password don't match. What is your name?".to_string())
+            );
+        }
+        let mut client = TestClient::open(addr);
+        client.send("new");
+        client.read_line();
+        for name in &["Guard", "ALICE"] {
+            client.send(name);
+            assert_eq!(
+                client.read_line(),
+                Some("That name isn't available. What will your character be called?".to_string())
+            );
+        }
+        client.send("bob");
+        client.read_line();
+        client.send("short");
+        assert_eq!(
+            client.read_line(),
+            Some("That's too short. Choose a password of at least 8 characters.".to_string())
+        );
+
+        let mut alice = TestClient::log_in(addr, " Alice", PASSWORD);
+        assert_eq!(alice.read_line(), Some("Welcome, alice!".to_string()));
+    }
+}
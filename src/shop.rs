@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::grammar::with_article;
+use crate::item::Item;
+use crate::journal::JournalEntry;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::{ItemName, RoomName, UserName};
+use crate::GameState;
+
+// Something a shop sells, and may buy back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ware {
+    pub price: i32,
+    // What the shop pays for one. Zero means it doesn't buy them.
+    #[serde(default)]
+    pub sell_price: i32,
+    pub description: Option<String>,
+}
+
+// In world files, a table of item names to wares:
+//
+//     [rooms.shop.lamp]
+//     price = 5
+//     sell_price = 2
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Shop {
+    pub wares: BTreeMap<ItemName, Ware>,
+}
+
+impl Shop {
+    pub fn with_ware(mut self, item_name: &str, price: i32, sell_price: i32) -> Shop {
+        self.wares.insert(
+            item_name.to_string(),
+            Ware {
+                price,
+                sell_price,
+                description: None,
+            },
+        );
+        self
+    }
+}
+
+// A shop can belong to a room or to an NPC standing in it. The room's own shop comes first.
+impl GameState {
+    pub fn set_room_shop(
+        &mut self,
+        room_name: &RoomName,
+        shop: Option<Shop>,
+    ) -> Result<(), FaerieError> {
+        self.rooms.get_room_mut(room_name)?.shop = shop;
+        Ok(())
+    }
+
+    pub fn set_user_shop(
+        &mut self,
+        user_name: &UserName,
+        shop: Option<Shop>,
+    ) -> Result<(), FaerieError> {
+        self.users.get_user_mut(user_name)?.shop = shop;
+        Ok(())
+    }
+
+    pub fn give_gold(&mut self, user_name: &UserName, amount: i32) -> Result<(), FaerieError> {
+        let entry = || JournalEntry::GoldGiven {
+            user_name: *user_name,
+            amount,
+        };
+        self.journaled_if_ok(entry, |game_state| {
+            let user = game_state.users.get_user_mut(user_name)?;
+            user.basic_attributes.gold = user
+                .basic_attributes
+                .gold
+                .checked_add(amount)
+                .ok_or_else(|| FaerieError::TooMuchGold(user_name.to_string()))?;
+            Ok(())
+        })
+    }
+
+    fn shop_here(&self, user_name: &UserName) -> Result<&Shop, ActionFailure> {
+        let room_name = self.get_user_location(user_name)?;
+        let room = self.rooms.get_room(&room_name)?;
+        if let Some(shop) = &room.shop {
+            return Ok(shop);
+        }
+        let mut keepers: Vec<&UserName> = room.users.iter().filter(|u| *u != user_name).collect();
+        keepers.sort();
+        keepers
+            .into_iter()
+            .find_map(|keeper| self.users.get_user(keeper).ok()?.shop.as_ref())
+            .ok_or_else(|| shop_failure(self.text(user_name, "no_shop", &[])))
+    }
+
+    pub fn list_wares(&self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let shop = self.shop_here(user_name)?;
+        let mut messages = vec![self.text(user_name, "for_sale", &[])];
+        for (item_name, ware) in &shop.wares {
+            let price = ware.price.to_string();
+            let sell_price = ware.sell_price.to_string();
+            let args = [
+                ("item", item_name.as_str()),
+                ("price", &price),
+                ("sell_price", &sell_price),
+            ];
+            let id = if ware.sell_price > 0 {
+                "ware_bought"
+            } else {
+                "ware"
+            };
+            messages.push(self.text(user_name, id, &args));
+        }
+        Ok(ActionSuccess::new(messages))
+    }
+
+    pub fn buy(
+        &mut self,
+        user_name: &UserName,
+        item_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let ware = match self.shop_here(user_name)?.wares.get(item_name) {
+            Some(ware) => ware.clone(),
+            None => {
+                return Err(shop_failure(self.text(
+                    user_name,
+                    "not_for_sale",
+                    &[("item", item_name)],
+                )))
+            }
+        };
+        let named = with_article(item_name);
+        let price = ware.price.to_string();
+        let gold = self.users.get_user(user_name)?.basic_attributes.gold;
+        if gold < ware.price {
+            let gold = gold.to_string();
+            let args = [("gold", gold.as_str()), ("item", &named), ("price", &price)];
+            return Err(shop_failure(self.text(user_name, "too_poor", &args)));
+        }
+        self.users.get_user_mut(user_name)?.basic_attributes.gold -= ware.price;
+
+        let description = ware
+            .description
+            .unwrap_or_else(|| format!("It's {}.", with_article(item_name)));
+        self.give_item(user_name, Item::new(item_name.to_string(), description)?)?;
+        let args = [("item", named.as_str()), ("price", &price)];
+        Ok(ActionSuccess::new(vec![
+            self.text(user_name, "you_buy", &args)
+        ]))
+    }
+
+    pub fn sell(
+        &mut self,
+        user_name: &UserName,
+        item_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let named = with_article(item_name);
+        let price = match self.shop_here(user_name)?.wares.get(item_name) {
+            Some(ware) if ware.sell_price > 0 => ware.sell_price,
+            _ => {
+                return Err(shop_failure(self.text(
+                    user_name,
+                    "will_not_buy",
+                    &[("item", &named)],
+                )))
+            }
+        };
+        let user = self.users.get_user(user_name)?;
+        let index = match user.inventory.iter().position(|i| i.name == item_name) {
+            Some(index) => index,
+            None => {
+                return Err(shop_failure(self.text(
+                    user_name,
+                    "not_carrying",
+                    &[("item", &named)],
+                )))
+            }
+        };
+        let gold = match user.basic_attributes.gold.checked_add(price) {
+            Some(gold) => gold,
+            None => {
+                return Err(shop_failure(self.text(
+                    user_name,
+                    "you_cannot_hold_gold",
+                    &[],
+                )))
+            }
+        };
+        let user = self.users.get_user_mut(user_name)?;
+        user.inventory.remove(index);
+        user.basic_attributes.gold = gold;
+        let price = price.to_string();
+        let args = [("item", named.as_str()), ("price", &price)];
+        Ok(ActionSuccess::new(vec![
+            self.text(user_name, "you_sell", &args)
+        ]))
+    }
+}
+
+fn shop_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+
+    fn make_market() -> GameState {
+        let mut game_state = GameStateBuilder::new()
+            .room("market", "Stalls and shouting.")
+            .user("alice", "market")
+            .user("grocer", "market")
+            .build()
+            .unwrap();
+        let grocer = UserName::new("grocer").unwrap();
+        game_state
+            .set_user_shop(
+                &grocer,
+                Some(
+                    Shop::default()
+                        .with_ware("apple", 2, 1)
+                        .with_ware("lamp", 5, 0),
+                ),
+            )
+            .unwrap();
+        game_state
+    }
+
+    #[test]
+    fn buying_and_selling_moves_gold_and_items() {
+        let mut game_state = make_market();
+        let alice = UserName::new("alice").unwrap();
+        game_state.give_gold(&alice, 6).unwrap();
+
+        let succ = game_state.process_input_impl(&alice, "list").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "For sale:",
+                "  apple: 2 gold (buys for 1)",
+                "  lamp: 5 gold"
+            ]
+        );
+        game_state.process_input_impl(&alice, "buy lamp").unwrap();
+        let failure = game_state
+            .process_input_impl(&alice, "buy lamp")
+            .unwrap_err();
+        assert_eq!(
+            failure.messages,
+            vec!["You have 1 gold, and a lamp costs 5."]
+        );
+        let failure = game_state
+            .process_input_impl(&alice, "sell lamp")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["Nobody here will buy a lamp."]);
+
+        game_state
+            .process_input_impl(&alice, "buy apple")
+            .unwrap_err();
+        game_state.give_gold(&alice, 1).unwrap();
+        game_state.process_input_impl(&alice, "buy apple").unwrap();
+        let succ = game_state.process_input_impl(&alice, "sell apple").unwrap();
+        assert_eq!(succ.messages(), vec!["You sell an apple for 1 gold."]);
+        let user = game_state.users.get_user(&alice).unwrap();
+        assert_eq!(user.basic_attributes.gold, 1);
+        assert_eq!(user.inventory.len(), 1);
+        assert!(user.has_item("lamp"));
+    }
+
+    #[test]
+    fn room_shops_come_before_npc_shops() {
+        let mut game_state = make_market();
+        let alice = UserName::new("alice").unwrap();
+        let market = RoomName::new("market").unwrap();
+        game_state
+            .set_room_shop(&market, Some(Shop::default().with_ware("rope", 3, 1)))
+            .unwrap();
+
+        let succ = game_state.list_wares(&alice).unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["For sale:", "  rope: 3 gold (buys for 1)"]
+        );
+
+        game_state
+            .users
+            .get_user_mut(&UserName::new("grocer").unwrap())
+            .unwrap()
+            .shop = None;
+        game_state.set_room_shop(&market, None).unwrap();
+        let failure = game_state.list_wares(&alice).unwrap_err();
+        assert_eq!(failure.messages, vec!["There's no shop here."]);
+    }
+
+    #[test]
+    fn selling_never_overflows_gold() {
+        let mut game_state = make_market();
+        let alice = UserName::new("alice").unwrap();
+        let apple = Item::new("apple".to_string(), "Red.".to_string()).unwrap();
+        game_state.give_item(&alice, apple).unwrap();
+        game_state.give_gold(&alice, i32::MAX).unwrap();
+        assert!(game_state.give_gold(&alice, 1).is_err());
+
+        let failure = game_state
+            .process_input_impl(&alice, "sell apple")
+            .unwrap_err();
+        assert_eq!(
+            failure.messages,
+            vec!["You can't hold that much more gold."]
+        );
+        let user = game_state.users.get_user(&alice).unwrap();
+        assert_eq!(user.basic_attributes.gold, i32::MAX);
+        assert!(user.has_item("apple"));
+    }
+}
@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::room::Path;
+use crate::type_aliases::{RoomName, UserName};
+use crate::user::User;
+use crate::GameState;
+
+// Successful uses it takes to go up a level.
+pub const USES_PER_LEVEL: u32 = 5;
+pub const MAX_SKILL_LEVEL: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Skill {
+    Swimming,
+    Lockpicking,
+    Stealth,
+}
+
+impl Skill {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Skill::Swimming => "swimming",
+            Skill::Lockpicking => "lockpicking",
+            Skill::Stealth => "stealth",
+        }
+    }
+
+    pub fn all() -> [Skill; 3] {
+        [Skill::Swimming, Skill::Lockpicking, Skill::Stealth]
+    }
+}
+
+// Only users with at least this level in the skill can take a path. In world files:
+//
+//     required_skill = { skill = "Swimming", level = 2 }
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SkillCheck {
+    pub skill: Skill,
+    pub level: u32,
+}
+
+// Users keep a count of each skill's successful uses, and their level comes from that.
+impl User {
+    pub fn skill_level(&self, skill: Skill) -> u32 {
+        let uses = self.skills.get(&skill).copied().unwrap_or(0);
+        (uses / USES_PER_LEVEL).min(MAX_SKILL_LEVEL)
+    }
+}
+
+impl GameState {
+    // For builders and tests; sets the skill to the start of the level.
+    pub fn set_skill_level(
+        &mut self,
+        user_name: &UserName,
+        skill: Skill,
+        level: u32,
+    ) -> Result<(), FaerieError> {
+        let uses = level.min(MAX_SKILL_LEVEL) * USES_PER_LEVEL;
+        self.users
+            .get_user_mut(user_name)?
+            .skills
+            .insert(skill, uses);
+        Ok(())
+    }
+
+    pub fn set_path_skill(
+        &mut self,
+        room_name: &RoomName,
+        path_name: &str,
+        required_skill: Option<SkillCheck>,
+    ) -> Result<(), FaerieError> {
+        self.get_path_mut(room_name, path_name)?.required_skill = required_skill;
+        Ok(())
+    }
+
+    // Counts a successful use, with a message for the user if it takes them up a level.
+    pub(crate) fn practice_skill(&mut self, user_name: &UserName, skill: Skill) -> Vec<String> {
+        let user = match self.users.get_user_mut(user_name) {
+            Ok(user) => user,
+            Err(_) => return vec![],
+        };
+        let before = user.skill_level(skill);
+        *user.skills.entry(skill).or_insert(0) += 1;
+        let after = user.skill_level(skill);
+        if after > before {
+            let args = [
+                ("skill", skill.display_name()),
+                ("level", &after.to_string()),
+            ];
+            vec![self.text(user_name, "skill_improves", &args)]
+        } else {
+            vec![]
+        }
+    }
+
+    pub(crate) fn refuse_if_unskilled(
+        &self,
+        user_name: &UserName,
+        path: &Path,
+    ) -> Result<(), ActionFailure> {
+        if let Some(check) = path.required_skill {
+            if self.users.get_user(user_name)?.skill_level(check.skill) < check.level {
+                let args = [
+                    ("skill", check.skill.display_name()),
+                    ("exit", &path.path_name),
+                ];
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "too_unskilled", &args)],
+                    FailReason::ExitBlocked,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn skills(&self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let user = self.users.get_user(user_name)?;
+        let messages = Skill::all()
+            .iter()
+            .map(|skill| {
+                let level = user.skill_level(*skill).to_string();
+                let args = [("skill", skill.display_name()), ("level", &level)];
+                self.text(user_name, "skill_line", &args)
+            })
+            .collect();
+        Ok(ActionSuccess::new(messages))
+    }
+
+    // Unlocks a path without its key, more often the better the user is at it.
+    pub fn pick_lock(
+        &mut self,
+        user_name: &UserName,
+        path_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let path_name = Path::match_basic_aliases(path_name.to_string());
+        let room_name = self.get_user_location(user_name)?;
+        let key_name = match self.rooms.get_room(&room_name)?.get_path(&path_name) {
+            Some(path) if path.is_locked() => path.lock.as_ref().map(|l| l.key_name.clone()),
+            Some(_) => {
+                return Err(skill_failure(self.text(
+                    user_name,
+                    "not_locked",
+                    &[("exit", &path_name)],
+                )))
+            }
+            None => {
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "no_direction", &[("exit", &path_name)])],
+                    FailReason::NoSuchPath,
+                ))
+            }
+        };
+
+        let level = self
+            .users
+            .get_user(user_name)?
+            .skill_level(Skill::Lockpicking);
+        if !self.rng.chance(30 + 7 * level) {
+            return Err(skill_failure(self.text(user_name, "pick_failed", &[])));
+        }
+        self.set_path_lock(&room_name, &path_name, key_name, false)?;
+        let mut messages = vec![self.text(user_name, "you_pick", &[("exit", &path_name)])];
+
+        messages.extend(self.practice_skill(user_name, Skill::Lockpicking));
+        Ok(ActionSuccess::new(messages))
+    }
+}
+
+fn skill_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+
+    // A jetty with a lake to the north that takes some swimming, and a locked boathouse.
+    fn make_lakeside() -> GameState {
+        let mut game_state = GameStateBuilder::new()
+            .room("jetty", "Planks over water.")
+            .room("lake", "Wet.")
+            .room("boathouse", "Wet.")
+            .path("jetty", "lake", Direction::North)
+            .path("jetty", "boathouse", Direction::East)
+            .user("alice", "jetty")
+            .build()
+            .unwrap();
+        game_state.set_seed(4);
+        let jetty = RoomName::new("jetty").unwrap();
+        let check = SkillCheck {
+            skill: Skill::Swimming,
+            level: 1,
+        };
+        game_state
+            .set_path_skill(&jetty, "north", Some(check))
+            .unwrap();
+        game_state
+            .set_path_lock(&jetty, "east", Some("oar key".to_string()), true)
+            .unwrap();
+        game_state
+    }
+
+    #[test]
+    fn paths_can_need_a_skill() {
+        let mut game_state = make_lakeside();
+        let alice = UserName::new("alice").unwrap();
+        let failure = game_state.process_input_impl(&alice, "north").unwrap_err();
+        assert_eq!(
+            failure.messages,
+            vec!["You'd need more skill at swimming to go north."]
+        );
+
+        game_state
+            .set_skill_level(&alice, Skill::Swimming, 1)
+            .unwrap();
+        game_state.process_input_impl(&alice, "north").unwrap();
+        let user = game_state.users.get_user(&alice).unwrap();
+        assert_eq!(user.skills[&Skill::Swimming], USES_PER_LEVEL + 1);
+        let succ = game_state.process_input_impl(&alice, "skills").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["swimming: 1", "lockpicking: 0", "stealth: 0"]
+        );
+    }
+
+    #[test]
+    fn picking_locks_gets_easier_with_practice() {
+        let mut game_state = make_lakeside();
+        let alice = UserName::new("alice").unwrap();
+        game_state
+            .set_skill_level(&alice, Skill::Lockpicking, MAX_SKILL_LEVEL)
+            .unwrap();
+        let succ = game_state.process_input_impl(&alice, "pick east").unwrap();
+        assert_eq!(succ.messages(), vec!["You pick the lock on the way east."]);
+        game_state.process_input_impl(&alice, "east").unwrap();
+
+        let failure = game_state
+            .process_input_impl(&alice, "pick west")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["The way west isn't locked."]);
+        let user = game_state.users.get_user(&alice).unwrap();
+        assert_eq!(user.skill_level(Skill::Lockpicking), MAX_SKILL_LEVEL);
+    }
+}
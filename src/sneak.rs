@@ -0,0 +1,188 @@
+use std::collections::BTreeSet;
+
+use crate::error::FaerieError;
+use crate::lambda::{ActionFailure, ActionSuccess};
+use crate::skills::Skill;
+use crate::status::StatusEffect;
+use crate::type_aliases::{RoomName, UserName};
+use crate::user::{User, UserType};
+use crate::GameState;
+
+// How sharp-eyed a user is, or None if they can't see anything at all.
+impl User {
+    pub fn perception(&self) -> Option<i64> {
+        if self.has_effect(StatusEffect::Blinded) {
+            return None;
+        }
+        Some(match self.user_type() {
+            UserType::Civilian => 3,
+            UserType::Viking => 1,
+            UserType::ElfLord => 6,
+        })
+    }
+}
+
+impl GameState {
+    // Moves like `go`, but only those who spot the sneaker hear them leave or arrive. Each
+    // observer's d20 plus their perception is set against the sneaker's d20 plus twice their
+    // stealth, and spots them by beating it. Getting past everyone unseen counts as practice.
+    pub fn sneak(
+        &mut self,
+        user_name: &UserName,
+        possible_path_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.attempt_move_impl(user_name, possible_path_name, true)
+    }
+
+    // Everyone in both rooms, other than the sneaker, who spots them.
+    pub(crate) fn spot_sneaker(
+        &mut self,
+        user_name: &UserName,
+        room_names: &[&RoomName],
+    ) -> Result<BTreeSet<UserName>, FaerieError> {
+        let mut spotted_by = BTreeSet::new();
+        for room_name in room_names {
+            spotted_by.extend(self.spot_sneaker_in(user_name, room_name)?);
+        }
+        Ok(spotted_by)
+    }
+
+    // Tells everyone else in the room about someone coming or going, or only those given.
+    pub(crate) fn announce_movement(
+        &mut self,
+        room_name: &RoomName,
+        user_name: &UserName,
+        id: &str,
+        args: &[(&str, &str)],
+        spotted_by: Option<&BTreeSet<UserName>>,
+    ) -> Result<(), FaerieError> {
+        let spotted_by = match spotted_by {
+            Some(spotted_by) => spotted_by,
+            None => return self.tell_room_except(room_name, user_name, id, args),
+        };
+        let room = self.rooms.get_room(room_name)?;
+        let listeners: Vec<UserName> = spotted_by
+            .iter()
+            .filter(|name| room.users.contains(*name))
+            .cloned()
+            .collect();
+        for listener in listeners {
+            let text = self.text(&listener, id, args);
+            self.output.send(&listener, &text);
+        }
+        Ok(())
+    }
+
+    // After a sneak: a warning if they were seen, or else practice.
+    pub(crate) fn finish_sneaking(
+        &mut self,
+        user_name: &UserName,
+        spotted_by: &BTreeSet<UserName>,
+    ) -> Vec<String> {
+        if spotted_by.is_empty() {
+            self.practice_skill(user_name, Skill::Stealth)
+        } else {
+            vec![self.text(user_name, "spotted", &[])]
+        }
+    }
+
+    fn spot_sneaker_in(
+        &mut self,
+        user_name: &UserName,
+        room_name: &RoomName,
+    ) -> Result<BTreeSet<UserName>, FaerieError> {
+        let stealth = i64::from(self.users.get_user(user_name)?.skill_level(Skill::Stealth));
+        let mut observers: Vec<(UserName, i64)> = vec![];
+        for observer_name in &self.rooms.get_room(room_name)?.users {
+            if observer_name == user_name {
+                continue;
+            }
+            if let Some(perception) = self.users.get_user(observer_name)?.perception() {
+                observers.push((*observer_name, perception));
+            }
+        }
+        observers.sort();
+
+        let mut spotted_by = BTreeSet::new();
+        for (observer_name, perception) in observers {
+            let sneak_roll = self.rng.range(1, 20) + 2 * stealth;
+            if self.rng.range(1, 20) + perception > sneak_roll {
+                spotted_by.insert(observer_name);
+            }
+        }
+        Ok(spotted_by)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+    use crate::testing::TestWorld;
+
+    // A guardroom with a viking on watch, and a corridor to its north with an elf lord in it.
+    fn make_guardroom() -> TestWorld {
+        let mut world = TestWorld::build(
+            GameStateBuilder::new()
+                .room("guardroom", "Dice on a table.")
+                .room("corridor", "Long and echoing.")
+                .path("guardroom", "corridor", Direction::North)
+                .user("alice", "guardroom")
+                .user_of_type("olaf", "guardroom", UserType::Viking)
+                .user_of_type("elrond", "corridor", UserType::ElfLord),
+        );
+        world.game_state.set_seed(21);
+        world
+    }
+
+    #[test]
+    fn skilled_sneakers_go_unseen() {
+        let mut world = make_guardroom();
+        let alice = UserName::new("alice").unwrap();
+        world
+            .game_state
+            .set_skill_level(&alice, Skill::Stealth, 10)
+            .unwrap();
+        let elrond = UserName::new("elrond").unwrap();
+        world
+            .game_state
+            .users
+            .get_user_mut(&elrond)
+            .unwrap()
+            .add_effect(StatusEffect::Blinded, 5);
+
+        world
+            .game_state
+            .process_input_impl(&alice, "sneak north")
+            .unwrap();
+        assert_eq!(
+            world.game_state.users.get_user(&alice).unwrap().room_name,
+            "corridor"
+        );
+        assert!(world.messages_for("olaf").is_empty());
+        assert!(world.messages_for("elrond").is_empty());
+        let user = world.game_state.users.get_user(&alice).unwrap();
+        assert_eq!(user.skills[&Skill::Stealth], 51);
+    }
+
+    #[test]
+    fn clumsy_sneakers_get_spotted() {
+        let mut world = make_guardroom();
+        let alice = UserName::new("alice").unwrap();
+        let mut spotted = false;
+        for path in ["north", "south"].iter().cycle().take(20) {
+            let succ = world
+                .game_state
+                .process_input_impl(&alice, &format!("sneak {}", path))
+                .unwrap();
+            spotted |= succ
+                .messages()
+                .contains(&"You make a noise, and are spotted.".to_string());
+        }
+        assert!(spotted);
+        assert!(world
+            .messages_for("elrond")
+            .contains(&"alice arrives from the south.".to_string()));
+    }
+}
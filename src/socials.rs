@@ -0,0 +1,272 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::catalog;
+use crate::error::FaerieError;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::parser;
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+// A canned emote like `wave`. Messages may use {actor} and {target}. The targeted messages
+// are used for `wave bob`; a social without them can't take a target.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Social {
+    pub to_actor: String,
+    pub to_room: String,
+    pub to_actor_targeted: Option<String>,
+    pub to_target: Option<String>,
+    pub to_room_targeted: Option<String>,
+}
+
+impl Social {
+    pub fn new(to_actor: &str, to_room: &str) -> Social {
+        Social {
+            to_actor: to_actor.to_string(),
+            to_room: to_room.to_string(),
+            to_actor_targeted: None,
+            to_target: None,
+            to_room_targeted: None,
+        }
+    }
+
+    pub fn with_target(mut self, to_actor: &str, to_target: &str, to_room: &str) -> Social {
+        self.to_actor_targeted = Some(to_actor.to_string());
+        self.to_target = Some(to_target.to_string());
+        self.to_room_targeted = Some(to_room.to_string());
+        self
+    }
+}
+
+fn fill(template: &str, actor: &UserName, target: Option<&UserName>) -> String {
+    match target {
+        Some(target) => catalog::fill_in(template, &[("actor", actor), ("target", target)]),
+        None => catalog::fill_in(template, &[("actor", actor)]),
+    }
+}
+
+pub(crate) fn default_socials() -> BTreeMap<String, Social> {
+    let mut socials = BTreeMap::new();
+    socials.insert(
+        "wave".to_string(),
+        Social::new("You wave.", "{actor} waves.").with_target(
+            "You wave at {target}.",
+            "{actor} waves at you.",
+            "{actor} waves at {target}.",
+        ),
+    );
+    socials.insert(
+        "bow".to_string(),
+        Social::new("You bow deeply.", "{actor} bows deeply.").with_target(
+            "You bow before {target}.",
+            "{actor} bows before you.",
+            "{actor} bows before {target}.",
+        ),
+    );
+    socials.insert(
+        "grin".to_string(),
+        Social::new("You grin.", "{actor} grins.").with_target(
+            "You grin at {target}.",
+            "{actor} grins at you.",
+            "{actor} grins at {target}.",
+        ),
+    );
+    socials
+}
+
+impl GameState {
+    // Replaces any existing social with the same name. Built-in verbs can't be socials.
+    pub fn add_social(&mut self, name: &str, social: Social) -> Result<(), FaerieError> {
+        let name = name.trim().to_lowercase();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(FaerieError::InvalidCommandName(name));
+        }
+        if parser::is_builtin_verb(&name) {
+            return Err(FaerieError::DuplicateCommand(name));
+        }
+        self.socials.insert(name, social);
+        Ok(())
+    }
+
+    pub fn remove_social(&mut self, name: &str) -> bool {
+        self.socials.remove(&name.trim().to_lowercase()).is_some()
+    }
+
+    // Free-form: `emote dances a jig` shows "alice dances a jig" to everyone in the room.
+    pub fn emote(
+        &mut self,
+        user_name: &UserName,
+        text: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.refuse_if_muted(user_name)?;
+        let room_name = self.get_user_location(user_name)?;
+        let seen = format!("{} {}", user_name, text);
+        self.send_to_room_except(&room_name, user_name, &seen)?;
+        Ok(ActionSuccess::new(vec![seen]))
+    }
+
+    // None if the input doesn't start with the name of a social.
+    pub(crate) fn run_social(
+        &mut self,
+        user_name: &UserName,
+        user_input: &str,
+    ) -> Option<Result<ActionSuccess, ActionFailure>> {
+        let (verb, rest) = parser::split_verb(user_input)?;
+        let social = self.socials.get(&verb)?.clone();
+        let target = if rest.is_empty() { None } else { Some(rest) };
+        Some(self.perform_social(user_name, &verb, &social, target))
+    }
+
+    fn perform_social(
+        &mut self,
+        user_name: &UserName,
+        verb: &str,
+        social: &Social,
+        target_name: Option<&str>,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.refuse_if_muted(user_name)?;
+        let room_name = self.get_user_location(user_name)?;
+        let target_name = match target_name {
+            None => {
+                let seen = fill(&social.to_room, user_name, None);
+                self.send_to_room_except(&room_name, user_name, &seen)?;
+                return Ok(ActionSuccess::new(vec![fill(
+                    &social.to_actor,
+                    user_name,
+                    None,
+                )]));
+            }
+            Some(target_name) => target_name,
+        };
+
+        let target_name = match self.users.get_user(target_name) {
+            Ok(target) if target.room_name == room_name => target.name,
+            _ => {
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "no_one_here", &[("name", target_name)])],
+                    FailReason::InvalidTarget,
+                ))
+            }
+        };
+        let (to_actor, to_target, to_room) = match (
+            &social.to_actor_targeted,
+            &social.to_target,
+            &social.to_room_targeted,
+        ) {
+            (Some(to_actor), Some(to_target), Some(to_room)) => (to_actor, to_target, to_room),
+            _ => {
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "social_untargeted", &[("verb", verb)])],
+                    FailReason::InvalidTarget,
+                ))
+            }
+        };
+
+        let target = Some(&target_name);
+        if &target_name != user_name && !self.is_ignoring(&target_name, user_name) {
+            self.send_all(&target_name, &[fill(to_target, user_name, target)]);
+        }
+        let seen = fill(to_room, user_name, target);
+        let onlookers = self.room_listeners(&room_name, user_name)?;
+        for onlooker in onlookers.iter().filter(|u| **u != target_name) {
+            self.output.send(onlooker, &seen);
+        }
+        Ok(ActionSuccess::new(vec![fill(to_actor, user_name, target)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::error::FaerieError;
+    use crate::lambda::FailReason;
+    use crate::socials::Social;
+    use crate::testing::TestWorld;
+    use crate::type_aliases::UserName;
+
+    fn make_social_world() -> TestWorld {
+        TestWorld::build(
+            GameStateBuilder::new()
+                .room("hall", "A long hall.")
+                .user("alice", "hall")
+                .user("bob", "hall")
+                .user("carol", "hall"),
+        )
+    }
+
+    #[test]
+    fn emote_is_seen_by_the_room() {
+        let mut world = make_social_world();
+        let alice = UserName::new("alice").unwrap();
+
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "emote dances a jig.")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["alice dances a jig."]);
+        assert_eq!(world.messages_for("bob"), vec!["alice dances a jig."]);
+        assert_eq!(world.messages_for("carol"), vec!["alice dances a jig."]);
+    }
+
+    #[test]
+    fn socials_have_first_and_third_person_forms() {
+        let mut world = make_social_world();
+        let alice = UserName::new("alice").unwrap();
+
+        let succ = world.game_state.process_input_impl(&alice, "wave").unwrap();
+        assert_eq!(succ.messages(), vec!["You wave."]);
+        assert_eq!(world.messages_for("bob"), vec!["alice waves."]);
+        assert_eq!(world.messages_for("carol"), vec!["alice waves."]);
+
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "BOW bob")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You bow before bob."]);
+        assert_eq!(world.messages_for("bob"), vec!["alice bows before you."]);
+        assert_eq!(world.messages_for("carol"), vec!["alice bows before bob."]);
+        assert!(world.take_messages().is_empty());
+
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "grin dave")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::InvalidTarget);
+    }
+
+    #[test]
+    fn socials_can_be_configured() {
+        let mut world = make_social_world();
+        let alice = UserName::new("alice").unwrap();
+        world
+            .game_state
+            .add_social("shrug", Social::new("You shrug.", "{actor} shrugs."))
+            .unwrap();
+        assert!(world.game_state.remove_social("grin"));
+        assert_eq!(
+            world.game_state.add_social("look", Social::new("", "")),
+            Err(FaerieError::DuplicateCommand("look".to_string()))
+        );
+
+        world
+            .game_state
+            .process_input_impl(&alice, "shrug")
+            .unwrap();
+        assert_eq!(world.messages_for("bob"), vec!["alice shrugs."]);
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "shrug bob")
+            .unwrap_err();
+        assert_eq!(
+            failure.messages,
+            vec!["You can't shrug at anyone in particular."]
+        );
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "grin")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::UnknownCommand);
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::grammar::with_article;
+use crate::item::Item;
+use crate::loot::LootTable;
+use crate::type_aliases::{ItemName, RoomName, UserName};
+use crate::user::UserType;
+use crate::GameState;
+
+pub const DEFAULT_SPAWN_DELAY: u64 = 10;
+
+fn default_npc_type() -> UserType {
+    UserType::Civilian
+}
+
+// What a spawn point makes. Each NPC is named after the template plus the lowest number not
+// already taken: "goblin1", "goblin2".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NpcTemplate {
+    pub name: String,
+    #[serde(default = "default_npc_type", rename = "type")]
+    pub user_type: UserType,
+    #[serde(default)]
+    pub items: Vec<ItemName>,
+    #[serde(default)]
+    pub gold: i32,
+    #[serde(default)]
+    pub loot: Option<LootTable>,
+}
+
+impl NpcTemplate {
+    pub fn new(name: &str, user_type: UserType) -> NpcTemplate {
+        NpcTemplate {
+            name: name.to_string(),
+            user_type,
+            items: vec![],
+            gold: 0,
+            loot: None,
+        }
+    }
+}
+
+// Keeps up to `max_population` NPCs from one template alive in a room. One that dies is gone
+// for good, and a new one turns up `respawn_delay` ticks later.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpawnPoint {
+    pub room: RoomName,
+    pub npc: NpcTemplate,
+    pub max_population: usize,
+    pub respawn_delay: u64,
+    // NPCs from this point still alive, and the ticks replacements are due at.
+    population: BTreeSet<UserName>,
+    due: Vec<u64>,
+}
+
+impl SpawnPoint {
+    pub fn new(
+        room: RoomName,
+        npc: NpcTemplate,
+        max_population: usize,
+        respawn_delay: u64,
+    ) -> SpawnPoint {
+        SpawnPoint {
+            room,
+            npc,
+            max_population,
+            respawn_delay,
+            population: BTreeSet::new(),
+            due: vec![],
+        }
+    }
+
+    pub fn population(&self) -> &BTreeSet<UserName> {
+        &self.population
+    }
+}
+
+impl GameState {
+    // The point is filled straight away, rather than on the next tick.
+    pub fn add_spawn_point(&mut self, spawn_point: SpawnPoint) -> Result<(), FaerieError> {
+        self.rooms.check_room_exists(&spawn_point.room)?;
+        UserName::new(&spawn_point.npc.name)?;
+        self.spawn_points.push(spawn_point);
+        self.run_spawn_points();
+        Ok(())
+    }
+
+    pub fn spawn_points(&self) -> &[SpawnPoint] {
+        &self.spawn_points
+    }
+
+    pub(crate) fn run_spawn_points(&mut self) {
+        let now = self.tick_count;
+        for index in 0..self.spawn_points.len() {
+            let point = &mut self.spawn_points[index];
+            point.due.retain(|tick| *tick > now);
+            let wanted = point
+                .max_population
+                .saturating_sub(point.population.len() + point.due.len());
+            for _ in 0..wanted {
+                // The room may have been deleted since; the point just stays empty.
+                if self.spawn_npc(index).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn spawn_npc(&mut self, index: usize) -> Result<(), FaerieError> {
+        let point = &self.spawn_points[index];
+        let (room_name, template) = (point.room, point.npc.clone());
+        let npc_name = (1..)
+            .map(|n| format!("{}{}", template.name, n))
+            .find(|name| self.users.get_user(name).is_err())
+            .unwrap();
+        let npc_name = UserName::new(&npc_name)?;
+
+        self.create_user_in_room(&npc_name, &room_name, template.user_type)?;
+        self.users.get_user_mut(&npc_name)?.npc = true;
+        self.give_gold(&npc_name, template.gold)?;
+        self.set_loot_table(&npc_name, template.loot)?;
+        for item_name in template.items {
+            let description = format!("It's {}.", with_article(&item_name));
+            self.give_item(&npc_name, Item::new(item_name, description)?)?;
+        }
+        self.spawn_points[index].population.insert(npc_name);
+        self.tell_room_except(&room_name, &npc_name, "spawns", &[("name", &npc_name)])?;
+
+        Ok(())
+    }
+
+    // Spawned NPCs don't respawn like users do: they leave the world, and their spawn point
+    // starts counting down to a replacement.
+    pub(crate) fn despawn_if_spawned(&mut self, user_name: &UserName) -> bool {
+        let due = self.tick_count;
+        let point = match self
+            .spawn_points
+            .iter_mut()
+            .find(|p| p.population.contains(user_name))
+        {
+            Some(point) => point,
+            None => return false,
+        };
+        point.population.remove(user_name);
+        point.due.push(due + point.respawn_delay);
+        let _ = self.remove_user(user_name);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::testing::TestWorld;
+
+    fn make_cave() -> TestWorld {
+        TestWorld::build(
+            GameStateBuilder::new()
+                .room("cave", "It smells of goblin.")
+                .user_of_type("alice", "cave", UserType::Viking),
+        )
+    }
+
+    #[test]
+    fn spawn_points_replace_the_dead_after_a_delay() {
+        let mut world = make_cave();
+        let cave = RoomName::new("cave").unwrap();
+        let mut goblin = NpcTemplate::new("goblin", UserType::Civilian);
+        goblin.items.push("club".to_string());
+        world
+            .game_state
+            .add_spawn_point(SpawnPoint::new(cave, goblin, 2, 3))
+            .unwrap();
+
+        let room = world.game_state.rooms.get_room(&cave).unwrap();
+        assert!(
+            room.users.contains(&UserName::new("goblin1").unwrap())
+                && room.users.contains(&UserName::new("goblin2").unwrap())
+        );
+        assert_eq!(
+            world.messages_for("alice"),
+            vec!["goblin1 appears.", "goblin2 appears."]
+        );
+        let npc = world.game_state.users.get_user("goblin1").unwrap();
+        assert!(npc.npc && npc.has_item("club"));
+
+        world
+            .game_state
+            .users
+            .get_user_mut("goblin1")
+            .unwrap()
+            .basic_attributes
+            .hp = 1;
+        world
+            .game_state
+            .process_input_impl(&UserName::new("alice").unwrap(), "attack goblin1")
+            .unwrap();
+        world.game_state.tick();
+        assert!(world.game_state.users.get_user("goblin1").is_err());
+        assert!(!world
+            .game_state
+            .is_awaiting_respawn(&UserName::new("goblin1").unwrap()));
+        assert_eq!(world.game_state.spawn_points()[0].population().len(), 1);
+
+        world.game_state.tick_n(2);
+        assert!(world.game_state.users.get_user("goblin1").is_err());
+        world.game_state.tick();
+        assert!(world.game_state.users.get_user("goblin1").is_ok());
+        assert_eq!(world.game_state.spawn_points()[0].population().len(), 2);
+    }
+
+    #[test]
+    fn spawns_are_loaded_from_world_files() {
+        let game_state = GameState::from_world_str(
+            r#"
+            [[rooms]]
+            name = "Cave"
+            description = "Dripping."
+
+            [[spawns]]
+            room = "Cave"
+            max_population = 3
+            npc = { name = "bat", gold = 2 }
+            "#,
+        )
+        .unwrap();
+        let point = &game_state.spawn_points()[0];
+        assert_eq!(point.respawn_delay, DEFAULT_SPAWN_DELAY);
+        assert_eq!(point.population().len(), 3);
+        let bat = game_state.users.get_user("bat3").unwrap();
+        assert_eq!(bat.basic_attributes.gold, 2);
+        assert_eq!(bat.user_type(), UserType::Civilian);
+    }
+}
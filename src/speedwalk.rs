@@ -0,0 +1,293 @@
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::room::{Direction, Path};
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+// Longer walks than this are almost certainly typos.
+pub const MAX_SPEEDWALK_STEPS: usize = 50;
+
+// Expands condensed movement like `3n2e` or `n;n;e` into single path names. Within a run of
+// letters an n or s followed by an e or w is a diagonal, so `2ne` is two steps northeast.
+// Segments split by `;` may also be full compass names. Returns None unless the input is a
+// walk of more than one step, so a lone `n` is handled like any other input.
+pub(crate) fn expand_speedwalk(input: &str) -> Option<Vec<String>> {
+    let mut steps = vec![];
+    for segment in input.trim().split(';') {
+        let segment = segment.trim().to_lowercase();
+        if Direction::from_name(&segment).is_some() {
+            steps.push(segment);
+        } else {
+            steps.extend(expand_compact(&segment)?);
+        }
+        if steps.len() > MAX_SPEEDWALK_STEPS {
+            return None;
+        }
+    }
+    if steps.len() > 1 {
+        Some(steps)
+    } else {
+        None
+    }
+}
+
+fn expand_compact(segment: &str) -> Option<Vec<String>> {
+    let chars: Vec<char> = segment.chars().collect();
+    let mut steps = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let mut count = 0usize;
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            count = count * 10 + chars[i].to_digit(10)? as usize;
+            if count > MAX_SPEEDWALK_STEPS {
+                return None;
+            }
+            i += 1;
+        }
+        let mut short = chars.get(i)?.to_string();
+        if !matches!(short.as_str(), "n" | "s" | "e" | "w") {
+            return None;
+        }
+        i += 1;
+        if (short == "n" || short == "s") && matches!(chars.get(i), Some('e') | Some('w')) {
+            short.push(chars[i]);
+            i += 1;
+        }
+        let path_name = Path::match_basic_aliases(short);
+        for _ in 0..count.max(1) {
+            steps.push(path_name.clone());
+        }
+    }
+    if steps.is_empty() {
+        None
+    } else {
+        Some(steps)
+    }
+}
+
+impl GameState {
+    // The walk the user's input stands for, if any. Plain letters like `news` or `sew` are
+    // taken as an exit of that name when the room has one; digits and `;` always mean a walk.
+    pub(crate) fn speedwalk_steps(&self, user_name: &UserName, input: &str) -> Option<Vec<String>> {
+        let steps = expand_speedwalk(input)?;
+        if input.contains(|c: char| c.is_ascii_digit() || c == ';') {
+            return Some(steps);
+        }
+        let exit_here = self
+            .get_user_location(user_name)
+            .and_then(|room_name| self.rooms.get_room(&room_name))
+            .is_ok_and(|room| room.get_path(input).is_some());
+        if exit_here {
+            None
+        } else {
+            Some(steps)
+        }
+    }
+
+    // Takes each step in turn and stops at the first one that fails. Rooms passed through on
+    // the way are still shown.
+    pub(crate) fn speedwalk(
+        &mut self,
+        user_name: &UserName,
+        steps: &[String],
+    ) -> Result<ActionSuccess, ActionFailure> {
+        self.refuse_if_blind(user_name)?;
+        let mut succ = ActionSuccess::default();
+        for (taken, step) in steps.iter().enumerate() {
+            match self.attempt_move(user_name, step) {
+                Ok(mut step_succ) => succ.append(&mut step_succ),
+                Err(mut failure) => {
+                    let lines = self.render_events(user_name, &succ.events);
+                    self.send_all(user_name, &lines);
+                    let room_name = self.get_user_location(user_name)?;
+                    let args = [
+                        ("room", room_name.as_str()),
+                        ("taken", &taken.to_string()),
+                        ("steps", &steps.len().to_string()),
+                    ];
+                    failure
+                        .messages
+                        .push(self.text(user_name, "you_stop", &args));
+                    return Err(failure);
+                }
+            }
+        }
+        Ok(succ)
+    }
+
+    // Walks the route find_path picks. Locks and exit conditions aren't considered when
+    // planning, so the walk stops wherever one of them gets in the way.
+    pub fn travel(
+        &mut self,
+        user_name: &UserName,
+        room_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let here = self.get_user_location(user_name)?;
+        let target_room_name = match self.rooms.get_room(room_name) {
+            Ok(room) => room.name,
+            Err(_) => {
+                return Err(ActionFailure::new(
+                    vec![self.text(user_name, "no_such_room", &[("room", room_name)])],
+                    FailReason::InvalidTarget,
+                ))
+            }
+        };
+        if target_room_name == here {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "already_there", &[])],
+                FailReason::InvalidTarget,
+            ));
+        }
+        match self.find_path(&here, &target_room_name) {
+            Some(route) => {
+                let steps: Vec<String> = route.iter().map(|p| p.to_string()).collect();
+                self.speedwalk(user_name, &steps)
+            }
+            None => Err(ActionFailure::new(
+                vec![self.text(user_name, "no_route", &[("room", &target_room_name)])],
+                FailReason::NoSuchPath,
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::lambda::FailReason;
+    use crate::output::BufferedSink;
+    use crate::type_aliases::RoomName;
+
+    #[test]
+    fn expands_condensed_walks() {
+        assert_eq!(
+            expand_speedwalk("3n2e"),
+            Some(
+                vec!["north", "north", "north", "east", "east"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect()
+            )
+        );
+        assert_eq!(
+            expand_speedwalk("n; north ;e"),
+            Some(vec![
+                "north".to_string(),
+                "north".to_string(),
+                "east".to_string()
+            ])
+        );
+        assert_eq!(
+            expand_speedwalk("2sw"),
+            Some(vec!["southwest".to_string(), "southwest".to_string()])
+        );
+        assert_eq!(expand_speedwalk("n"), None);
+        assert_eq!(expand_speedwalk("ne"), None);
+        assert_eq!(expand_speedwalk("north"), None);
+        assert_eq!(expand_speedwalk("say hi; n"), None);
+        assert_eq!(expand_speedwalk("99n"), None);
+    }
+
+    #[test]
+    fn walks_stop_at_the_first_failure() {
+        let mut game_state = GameStateBuilder::new()
+            .room("r0", "Room 0.")
+            .room("r1", "Room 1.")
+            .room("r2", "Room 2.")
+            .path("r0", "r1", Direction::North)
+            .path("r1", "r2", Direction::North)
+            .user("alice", "r0")
+            .build()
+            .unwrap();
+        let alice = UserName::new("alice").unwrap();
+        let output = BufferedSink::new();
+        game_state.set_output_sink(Box::new(output.clone()));
+
+        let succ = game_state.process_input_impl(&alice, "2n").unwrap();
+        assert!(succ.was_room_move());
+        assert_eq!(game_state.get_user_location(&alice).unwrap(), "r2");
+
+        let failure = game_state.process_input_impl(&alice, "s;s;s").unwrap_err();
+        assert_eq!(failure.reason, FailReason::NoSuchPath);
+        assert_eq!(
+            failure.messages.last().unwrap(),
+            "You stop in r0 after 2 of 3 steps."
+        );
+        assert_eq!(game_state.get_user_location(&alice).unwrap(), "r0");
+        assert!(output.drain_for("alice").contains(&"r1".to_string()));
+    }
+
+    #[test]
+    fn travel_follows_the_shortest_route() {
+        let mut game_state = GameStateBuilder::new()
+            .room("gate", "A gate.")
+            .room("yard", "A yard.")
+            .room("keep", "A keep.")
+            .path("gate", "yard", Direction::North)
+            .path("yard", "keep", Direction::East)
+            .user("alice", "gate")
+            .build()
+            .unwrap();
+        let yard = RoomName::new("yard").unwrap();
+        game_state
+            .set_path_lock(&yard, "east", Some("iron key".to_string()), true)
+            .unwrap();
+        let alice = UserName::new("alice").unwrap();
+
+        let failure = game_state
+            .process_input_impl(&alice, "travel keep")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::PathLocked);
+        assert_eq!(
+            failure.messages.last().unwrap(),
+            "You stop in yard after 1 of 2 steps."
+        );
+
+        game_state
+            .set_path_lock(&yard, "east", None, false)
+            .unwrap();
+        let succ = game_state
+            .process_input_impl(&alice, "travel keep")
+            .unwrap();
+        assert!(succ.was_room_move());
+        assert_eq!(game_state.get_user_location(&alice).unwrap(), "keep");
+
+        let failure = game_state
+            .process_input_impl(&alice, "travel atlantis")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::InvalidTarget);
+    }
+
+    #[test]
+    fn exits_named_like_walks_can_still_be_taken() {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .room("newsroom", "Typewriters.")
+            .room("yard", "A yard.")
+            .path(
+                "hall",
+                "newsroom",
+                Direction::Custom("news".to_string(), "out".to_string()),
+            )
+            .path("hall", "yard", Direction::North)
+            .user("alice", "hall")
+            .build()
+            .unwrap();
+        let alice = UserName::new("alice").unwrap();
+
+        let succ = game_state.process_input_impl(&alice, "NEWS").unwrap();
+        assert!(succ.was_room_move());
+        assert_eq!(game_state.get_user_location(&alice).unwrap(), "newsroom");
+
+        // Anywhere else, and with digits or `;` anywhere, the letters are still a walk.
+        game_state.process_input_impl(&alice, "out").unwrap();
+        let failure = game_state.process_input_impl(&alice, "n;ews").unwrap_err();
+        assert_eq!(failure.reason, FailReason::NoSuchPath);
+        assert_eq!(game_state.get_user_location(&alice).unwrap(), "yard");
+        game_state.process_input_impl(&alice, "ss").unwrap_err();
+        assert_eq!(game_state.get_user_location(&alice).unwrap(), "hall");
+        let failure = game_state.process_input_impl(&alice, "1news").unwrap_err();
+        assert_eq!(failure.reason, FailReason::NoSuchPath);
+        assert_eq!(game_state.get_user_location(&alice).unwrap(), "hall");
+    }
+}
@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::{RoomName, UserName};
+use crate::user::{User, UserType};
+use crate::GameState;
+
+// Stamina everyone gets back each tick.
+pub const STAMINA_REGEN: i32 = 2;
+// Stamina the `rest` command gives back at once.
+pub const REST_STAMINA: i32 = 15;
+
+// How hard a room is to get into. Moving costs the stamina of the room being entered, twice
+// over for anyone encumbered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Terrain {
+    #[default]
+    Normal,
+    Rough,
+    Steep,
+    Water,
+}
+
+impl Terrain {
+    pub fn move_cost(self) -> i32 {
+        match self {
+            Terrain::Normal => 1,
+            Terrain::Rough => 2,
+            Terrain::Steep => 3,
+            Terrain::Water => 4,
+        }
+    }
+}
+
+// Users only keep track of the stamina they've used up, so everyone starts out fresh.
+impl User {
+    pub fn max_stamina(&self) -> i32 {
+        match self.user_type() {
+            UserType::Civilian => 40,
+            UserType::Viking => 60,
+            UserType::ElfLord => 50,
+        }
+    }
+
+    pub fn stamina(&self) -> i32 {
+        self.max_stamina() - self.fatigue
+    }
+}
+
+impl GameState {
+    pub fn set_room_terrain(
+        &mut self,
+        room_name: &RoomName,
+        terrain: Terrain,
+    ) -> Result<(), FaerieError> {
+        self.rooms.get_room_mut(room_name)?.terrain = terrain;
+        Ok(())
+    }
+
+    // The stamina it'll cost the user to go into the room, if they have it.
+    pub(crate) fn refuse_if_exhausted(
+        &self,
+        user_name: &UserName,
+        target_room_name: &RoomName,
+    ) -> Result<i32, ActionFailure> {
+        let user = self.users.get_user(user_name)?;
+        let mut cost = self.rooms.get_room(target_room_name)?.terrain.move_cost();
+        if user.is_encumbered() {
+            cost *= 2;
+        }
+        if user.stamina() < cost {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "exhausted", &[])],
+                FailReason::Exhausted,
+            ));
+        }
+        Ok(cost)
+    }
+
+    pub(crate) fn spend_stamina(&mut self, user_name: &UserName, cost: i32) {
+        if let Ok(user) = self.users.get_user_mut(user_name) {
+            user.fatigue = (user.fatigue + cost).min(user.max_stamina());
+        }
+    }
+
+    pub(crate) fn recover_stamina(&mut self) {
+        for user in self.users.users.values_mut() {
+            if !user.is_dead() {
+                user.fatigue = (user.fatigue - STAMINA_REGEN).max(0);
+            }
+        }
+    }
+
+    pub fn rest(&mut self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let fighting = self
+            .combat_targets
+            .iter()
+            .any(|(attacker, target)| attacker == user_name || target == user_name);
+        if fighting {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "fighting_too_hard", &[])],
+                FailReason::InvalidTarget,
+            ));
+        }
+        let user = self.users.get_user_mut(user_name)?;
+        if user.fatigue == 0 {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "not_tired", &[])],
+                FailReason::InvalidTarget,
+            ));
+        }
+        user.fatigue = (user.fatigue - REST_STAMINA).max(0);
+        let room_name = user.room_name;
+        self.tell_room_except(&room_name, user_name, "rests", &[("name", user_name)])?;
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_rest",
+            &[],
+        )]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+
+    // Scree to the north of a hut, steep enough to wear anyone out.
+    fn make_slope() -> GameState {
+        let mut game_state = GameStateBuilder::new()
+            .room("hut", "A shepherd's hut.")
+            .room("scree", "Loose stones.")
+            .path("hut", "scree", Direction::North)
+            .user("alice", "hut")
+            .build()
+            .unwrap();
+        game_state
+            .set_room_terrain(&RoomName::new("scree").unwrap(), Terrain::Steep)
+            .unwrap();
+        game_state
+    }
+
+    #[test]
+    fn moving_costs_stamina_by_terrain() {
+        let mut game_state = make_slope();
+        let alice = UserName::new("alice").unwrap();
+        game_state.process_input_impl(&alice, "north").unwrap();
+        game_state.process_input_impl(&alice, "south").unwrap();
+        assert_eq!(game_state.users.get_user(&alice).unwrap().stamina(), 36);
+
+        game_state.users.get_user_mut(&alice).unwrap().fatigue = 38;
+        let failure = game_state.process_input_impl(&alice, "north").unwrap_err();
+        assert_eq!(failure.reason, FailReason::Exhausted);
+        assert_eq!(game_state.users.get_user(&alice).unwrap().room_name, "hut");
+        game_state.tick();
+        game_state.process_input_impl(&alice, "north").unwrap();
+    }
+
+    #[test]
+    fn resting_restores_stamina() {
+        let mut game_state = make_slope();
+        let alice = UserName::new("alice").unwrap();
+        let failure = game_state.process_input_impl(&alice, "rest").unwrap_err();
+        assert_eq!(failure.messages, vec!["You're not tired."]);
+
+        game_state.users.get_user_mut(&alice).unwrap().fatigue = 40;
+        let succ = game_state.process_input_impl(&alice, "rest").unwrap();
+        assert_eq!(succ.messages(), vec!["You sit down and catch your breath."]);
+        assert_eq!(game_state.users.get_user(&alice).unwrap().stamina(), 15);
+    }
+}
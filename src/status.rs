@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::events::GameEvent;
+use crate::lambda::{ActionFailure, FailReason};
+use crate::type_aliases::UserName;
+use crate::user::User;
+use crate::GameState;
+
+// Something temporarily affecting a user, kept on the user with the number of ticks it has
+// left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum StatusEffect {
+    // Loses 1 hp a tick and doesn't regenerate.
+    Poisoned,
+    // Hits twice as hard.
+    Hasted,
+    // Can't see rooms, can't speedwalk, and hits half as hard.
+    Blinded,
+    // Doesn't regenerate. Painful paths leave users bruised.
+    Bruised,
+}
+
+impl StatusEffect {
+    pub fn name(self) -> &'static str {
+        match self {
+            StatusEffect::Poisoned => "poisoned",
+            StatusEffect::Hasted => "hasted",
+            StatusEffect::Blinded => "blinded",
+            StatusEffect::Bruised => "bruised",
+        }
+    }
+
+    // Catalog IDs for the messages when the effect starts and ends.
+    fn applied_message(self) -> &'static str {
+        match self {
+            StatusEffect::Poisoned => "poisoned",
+            StatusEffect::Hasted => "hasted",
+            StatusEffect::Blinded => "blinded_now",
+            StatusEffect::Bruised => "bruised",
+        }
+    }
+
+    fn removed_message(self) -> &'static str {
+        match self {
+            StatusEffect::Poisoned => "poison_ends",
+            StatusEffect::Hasted => "haste_ends",
+            StatusEffect::Blinded => "blindness_ends",
+            StatusEffect::Bruised => "bruises_fade",
+        }
+    }
+
+    fn stops_regeneration(self) -> bool {
+        matches!(self, StatusEffect::Poisoned | StatusEffect::Bruised)
+    }
+}
+
+impl User {
+    pub fn has_effect(&self, effect: StatusEffect) -> bool {
+        self.status_effects.contains_key(&effect)
+    }
+
+    // Reapplying an effect never shortens it. Returns true if the user didn't already have it.
+    pub(crate) fn add_effect(&mut self, effect: StatusEffect, ticks: u64) -> bool {
+        let remaining = self.status_effects.entry(effect).or_insert(0);
+        let is_new = *remaining == 0;
+        *remaining = (*remaining).max(ticks);
+        is_new
+    }
+
+    pub(crate) fn can_regenerate(&self) -> bool {
+        !self.status_effects.keys().any(|e| e.stops_regeneration())
+    }
+}
+
+// Hooks for effects starting and ending are listeners on the event bus, which hear
+// GameEvent::EffectStarted and GameEvent::EffectEnded.
+impl GameState {
+    pub fn apply_effect(
+        &mut self,
+        user_name: &UserName,
+        effect: StatusEffect,
+        ticks: u64,
+    ) -> Result<(), FaerieError> {
+        if ticks == 0 {
+            return Ok(());
+        }
+        if self
+            .users
+            .get_user_mut(user_name)?
+            .add_effect(effect, ticks)
+        {
+            self.effect_changed(user_name, effect, true);
+        }
+        Ok(())
+    }
+
+    // Returns false if the user didn't have the effect.
+    pub fn remove_effect(
+        &mut self,
+        user_name: &UserName,
+        effect: StatusEffect,
+    ) -> Result<bool, FaerieError> {
+        if self
+            .users
+            .get_user_mut(user_name)?
+            .status_effects
+            .remove(&effect)
+            .is_none()
+        {
+            return Ok(false);
+        }
+        self.effect_changed(user_name, effect, false);
+        Ok(true)
+    }
+
+    fn effect_changed(&mut self, user_name: &UserName, effect: StatusEffect, started: bool) {
+        let id = if started {
+            effect.applied_message()
+        } else {
+            effect.removed_message()
+        };
+        let mut messages = vec![self.text(user_name, id, &[])];
+        let user_name = *user_name;
+        messages.extend(self.publish(if started {
+            GameEvent::EffectStarted { user_name, effect }
+        } else {
+            GameEvent::EffectEnded { user_name, effect }
+        }));
+        self.send_all(&user_name, &messages);
+    }
+
+    pub(crate) fn refuse_if_blind(&self, user_name: &UserName) -> Result<(), ActionFailure> {
+        if self
+            .users
+            .get_user(user_name)?
+            .has_effect(StatusEffect::Blinded)
+        {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "cannot_see_way_ahead", &[])],
+                FailReason::InvalidTarget,
+            ));
+        }
+        Ok(())
+    }
+
+    // Run once a tick: effects do their work, then count down, and end when they reach 0.
+    pub(crate) fn tick_status_effects(&mut self) {
+        let mut ended = vec![];
+        for user in self.users.users.values_mut() {
+            if user.has_effect(StatusEffect::Poisoned) {
+                user.basic_attributes.hp -= 1;
+            }
+            for (effect, remaining) in user.status_effects.iter_mut() {
+                *remaining -= 1;
+                if *remaining == 0 {
+                    ended.push((user.name, *effect));
+                }
+            }
+        }
+        for (user_name, effect) in ended {
+            let _ = self.remove_effect(&user_name, effect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+    use crate::testing::TestWorld;
+
+    use crate::user::UserType;
+
+    fn make_afflicted_world() -> TestWorld {
+        TestWorld::build(
+            GameStateBuilder::new()
+                .room("bog", "Stinking mud.")
+                .room("hut", "A witch's hut.")
+                .path("bog", "hut", Direction::North)
+                .user_of_type("alice", "bog", UserType::Viking)
+                .user_of_type("olaf", "bog", UserType::Viking),
+        )
+    }
+
+    #[test]
+    fn effects_wear_off_and_tell_listeners() {
+        let mut world = make_afflicted_world();
+        let alice = UserName::new("alice").unwrap();
+        let heard = Arc::new(Mutex::new(vec![]));
+        let log = heard.clone();
+        world.game_state.subscribe(move |_, event| {
+            log.lock().unwrap().push(event.clone());
+            vec![]
+        });
+
+        world
+            .game_state
+            .apply_effect(&alice, StatusEffect::Poisoned, 3)
+            .unwrap();
+        assert_eq!(world.messages_for("alice"), vec!["You feel sick."]);
+        world.game_state.tick_n(2);
+        let user = world.game_state.users.get_user(&alice).unwrap();
+        assert_eq!(user.basic_attributes.hp, 218);
+        world.game_state.tick();
+        let user = world.game_state.users.get_user(&alice).unwrap();
+        assert!(!user.has_effect(StatusEffect::Poisoned));
+        assert_eq!(world.messages_for("alice"), vec!["You feel better."]);
+        assert_eq!(
+            *heard.lock().unwrap(),
+            vec![
+                GameEvent::EffectStarted {
+                    user_name: alice,
+                    effect: StatusEffect::Poisoned,
+                },
+                GameEvent::EffectEnded {
+                    user_name: alice,
+                    effect: StatusEffect::Poisoned,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn effects_change_movement_and_combat() {
+        let mut world = make_afflicted_world();
+        let alice = UserName::new("alice").unwrap();
+        let olaf = UserName::new("olaf").unwrap();
+        world
+            .game_state
+            .apply_effect(&alice, StatusEffect::Blinded, 5)
+            .unwrap();
+        world
+            .game_state
+            .apply_effect(&olaf, StatusEffect::Hasted, 5)
+            .unwrap();
+
+        assert_eq!(
+            world.game_state.describe_room(&alice).unwrap(),
+            vec!["bog", "  You can't see a thing."]
+        );
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "n;s")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["You can't see where you're going."]);
+        let user = world.game_state.users.get_user(&alice).unwrap();
+        assert_eq!(user.attack_damage(), 3);
+        let user = world.game_state.users.get_user(&olaf).unwrap();
+        assert_eq!(user.attack_damage(), 12);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::grammar::join_or;
+use crate::lambda::{ActionFailure, FailReason};
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+const MAX_SUGGESTIONS: usize = 3;
+
+impl GameState {
+    // For input that's neither a command nor a way out. Suggests the verbs closest to its first
+    // word and the exits closest to the whole of it.
+    pub(crate) fn unknown_command(&self, user_name: &UserName, input: &str) -> ActionFailure {
+        let input = input.trim();
+        let mut messages = vec![self.text(user_name, "unknown_input", &[("input", input)])];
+        let suggestions = self.suggestions_for(user_name, input);
+        if !suggestions.is_empty() {
+            let suggestions = join_or(&suggestions);
+            messages.push(self.text(user_name, "did_you_mean", &[("suggestions", &suggestions)]));
+        }
+        ActionFailure::new(messages, FailReason::UnknownCommand)
+    }
+
+    fn suggestions_for(&self, user_name: &UserName, input: &str) -> Vec<String> {
+        let first_word = input.split_whitespace().next().unwrap_or("");
+        let verbs = self
+            .known_verbs()
+            .into_iter()
+            .filter_map(|verb| close_to(first_word, verb).map(|d| (d, verb)));
+        let exits = self
+            .visible_exits(user_name)
+            .into_iter()
+            .filter_map(|exit| close_to(input, exit).map(|d| (d, exit)));
+        let mut scored: Vec<(usize, &str)> = verbs.chain(exits).collect();
+        scored.sort_unstable();
+        scored.dedup_by(|a, b| a.1 == b.1);
+        scored
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, word)| word.to_string())
+            .collect()
+    }
+}
+
+// How many edits away the word is, if it's near enough to suggest: one edit for every three
+// characters typed, up to two. Words shorter than three characters are too short to guess at.
+fn close_to(typed: &str, word: &str) -> Option<usize> {
+    let allowed = (typed.chars().count() / 3).min(2);
+    let distance = edit_distance(&typed.to_lowercase(), &word.to_lowercase());
+    if distance > 0 && distance <= allowed {
+        Some(distance)
+    } else {
+        None
+    }
+}
+
+// Levenshtein distance: the fewest single-character insertions, deletions and substitutions
+// that turn one into the other.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("north", "north"), 0);
+        assert_eq!(edit_distance("nrth", "north"), 1);
+        assert_eq!(edit_distance("exmaine", "examine"), 2);
+        assert_eq!(edit_distance("", "say"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn unknown_input_gets_suggestions() {
+        let mut game_state = GameStateBuilder::new()
+            .room("hall", "A long hall.")
+            .room("garden", "Roses everywhere.")
+            .path("hall", "garden", Direction::North)
+            .user("alice", "hall")
+            .build()
+            .unwrap();
+        let alice = UserName::new("alice").unwrap();
+
+        let fail = game_state.process_input_impl(&alice, "nrth").unwrap_err();
+        assert_eq!(fail.reason, FailReason::UnknownCommand);
+        assert_eq!(
+            fail.messages,
+            vec!["I don't understand 'nrth'.", "Did you mean north?"]
+        );
+        let fail = game_state
+            .process_input_impl(&alice, "exmaine alice")
+            .unwrap_err();
+        assert_eq!(fail.messages[1], "Did you mean examine?");
+        let fail = game_state.process_input_impl(&alice, "xyzzy").unwrap_err();
+        assert_eq!(fail.messages, vec!["I don't understand 'xyzzy'."]);
+
+        // Asking for a way out by name still says there's no such way.
+        let fail = game_state
+            .process_input_impl(&alice, "go nrth")
+            .unwrap_err();
+        assert_eq!(fail.reason, FailReason::NoSuchPath);
+    }
+}
@@ -0,0 +1,191 @@
+use std::sync::{Arc, Mutex};
+
+use crate::builder::GameStateBuilder;
+use crate::events::GameEvent;
+use crate::output::{BufferedSink, Message};
+use crate::room::Direction;
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+// Helpers for testing worlds and the code built on them. Nothing here is needed to run a
+// game, and everything panics rather than returning errors, with a message saying what went
+// wrong.
+
+// The name grid_builder gives the room at (x, y), like "2,1".
+pub fn grid_room(x: usize, y: usize) -> String {
+    format!("{},{}", x, y)
+}
+
+// A width by height grid of rooms joined to their neighbours, with (0, 0) in the south west
+// corner as the start room. Add users and anything else before building.
+pub fn grid_builder(width: usize, height: usize) -> GameStateBuilder {
+    let mut builder = GameStateBuilder::new().start_room(&grid_room(0, 0));
+    for y in 0..height {
+        for x in 0..width {
+            let description = format!("Room {} of a {}x{} grid.", grid_room(x, y), width, height);
+            builder = builder.room(&grid_room(x, y), &description);
+        }
+    }
+    for y in 0..height {
+        for x in 0..width {
+            if x + 1 < width {
+                builder = builder.path(&grid_room(x, y), &grid_room(x + 1, y), Direction::East);
+            }
+            if y + 1 < height {
+                builder = builder.path(&grid_room(x, y), &grid_room(x, y + 1), Direction::North);
+            }
+        }
+    }
+    builder
+}
+
+fn user_name(name: &str) -> UserName {
+    UserName::new(name).unwrap_or_else(|e| panic!("{}", e))
+}
+
+// A world wired up for tests: everything it sends is kept until read, and every event it
+// publishes is recorded.
+pub struct TestWorld {
+    pub game_state: GameState,
+    output: BufferedSink,
+    events: Arc<Mutex<Vec<GameEvent>>>,
+}
+
+impl TestWorld {
+    pub fn new(mut game_state: GameState) -> TestWorld {
+        let output = BufferedSink::new();
+        game_state.set_output_sink(Box::new(output.clone()));
+        let events = Arc::new(Mutex::new(vec![]));
+        let recorded = events.clone();
+        game_state.subscribe(move |_, event| {
+            recorded
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(event.clone());
+            vec![]
+        });
+        TestWorld {
+            game_state,
+            output,
+            events,
+        }
+    }
+
+    // Builds the world, panicking if it's invalid.
+    pub fn build(builder: GameStateBuilder) -> TestWorld {
+        match builder.build() {
+            Ok(game_state) => TestWorld::new(game_state),
+            Err(e) => panic!("couldn't build the test world: {}", e),
+        }
+    }
+
+    // Sends one line of input, just as a host would.
+    pub fn input(&mut self, user: &str, input: &str) {
+        self.game_state.process_input(&user_name(user), input);
+    }
+
+    // Sends each (user, input) pair in turn.
+    pub fn script(&mut self, steps: &[(&str, &str)]) {
+        for (user, input) in steps {
+            self.input(user, input);
+        }
+    }
+
+    pub fn tick_n(&mut self, n: u64) {
+        self.game_state.tick_n(n);
+    }
+
+    // Everything sent to the user since their messages were last read.
+    pub fn messages_for(&self, user: &str) -> Vec<String> {
+        self.output.drain_for(user)
+    }
+
+    // Everything sent to anyone since messages were last read.
+    pub fn take_messages(&self) -> Vec<Message> {
+        self.output.drain()
+    }
+
+    // Every event published since events were last read.
+    pub fn take_events(&self) -> Vec<GameEvent> {
+        self.events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .drain(..)
+            .collect()
+    }
+
+    pub fn location_of(&self, user: &str) -> String {
+        match self.game_state.get_user_location(&user_name(user)) {
+            Ok(room_name) => room_name.to_string(),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    // Reads the user's messages, and checks one of them contains `expected`.
+    #[track_caller]
+    pub fn assert_heard(&self, user: &str, expected: &str) {
+        let messages = self.messages_for(user);
+        assert!(
+            messages.iter().any(|message| message.contains(expected)),
+            "{} never heard {:?}, only {:#?}",
+            user,
+            expected,
+            messages
+        );
+    }
+
+    // Reads the user's messages, and checks none of them contains `unexpected`.
+    #[track_caller]
+    pub fn assert_not_heard(&self, user: &str, unexpected: &str) {
+        let messages = self.messages_for(user);
+        assert!(
+            !messages.iter().any(|message| message.contains(unexpected)),
+            "{} heard {:?} in {:#?}",
+            user,
+            unexpected,
+            messages
+        );
+    }
+
+    // Reads the events, and checks one of them matches.
+    #[track_caller]
+    pub fn assert_event(&self, matches: impl Fn(&GameEvent) -> bool) {
+        let events = self.take_events();
+        assert!(
+            events.iter().any(matches),
+            "no matching event in {:#?}",
+            events
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grids_join_every_neighbour() {
+        let mut world = TestWorld::build(grid_builder(3, 2).user("alice", "0,0"));
+        assert_eq!(world.game_state.start_room().unwrap(), "0,0");
+        assert!(world.game_state.validate_world().is_clean());
+
+        world.script(&[("alice", "east"), ("alice", "east"), ("alice", "north")]);
+        assert_eq!(world.location_of("alice"), "2,1");
+        world.input("alice", "north");
+        assert_eq!(world.location_of("alice"), "2,1");
+    }
+
+    #[test]
+    fn scripts_record_messages_and_events() {
+        let mut world =
+            TestWorld::build(grid_builder(2, 1).user("alice", "0,0").user("bob", "0,0"));
+        world.script(&[("alice", "say Hi, bob."), ("bob", "east")]);
+        world.assert_heard("bob", "Hi, bob.");
+        world.assert_not_heard("bob", "Hi, bob.");
+        world.assert_event(|event| match event {
+            GameEvent::Moved { user_name, to, .. } => user_name == "bob" && to == "1,0",
+            _ => false,
+        });
+        assert!(world.take_events().is_empty());
+    }
+}
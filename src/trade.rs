@@ -0,0 +1,563 @@
+use crate::grammar::with_article;
+use crate::item::Item;
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::{ItemName, UserName};
+use crate::user::User;
+use crate::GameState;
+
+// One side of a trade: who it's with, what's on offer and whether they've agreed to it.
+#[derive(Debug)]
+pub(crate) struct TradeSide {
+    partner: UserName,
+    items: Vec<ItemName>,
+    gold: i32,
+    confirmed: bool,
+}
+
+impl TradeSide {
+    fn new(partner: UserName) -> TradeSide {
+        TradeSide {
+            partner,
+            items: vec![],
+            gold: 0,
+            confirmed: false,
+        }
+    }
+
+    // In the language of whoever's reading it.
+    fn describe(&self, game_state: &GameState, reader: &UserName) -> String {
+        let mut parts: Vec<String> = self.items.iter().map(|i| with_article(i)).collect();
+        if self.gold > 0 {
+            let gold = self.gold.to_string();
+            parts.push(game_state.text(reader, "gold_amount", &[("gold", &gold)]));
+        }
+        if parts.is_empty() {
+            game_state.text(reader, "nothing", &[])
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+// Nothing changes hands until both sides confirm, and any change to an offer takes back both
+// confirmations. The swap then happens all at once or not at all.
+impl GameState {
+    pub fn start_trade(
+        &mut self,
+        user_name: &UserName,
+        target_name: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let room_name = self.get_user_location(user_name)?;
+        let partner = match self.users.get_user(target_name) {
+            Ok(target) if target.room_name == room_name && &target.name != user_name => target.name,
+            _ => {
+                let args = [("name", target_name)];
+                return Err(trade_failure(self.text(user_name, "nobody_here", &args)));
+            }
+        };
+        if let Some(side) = self.trades.get(user_name) {
+            let args = [("name", side.partner.as_str())];
+            return Err(trade_failure(self.text(
+                user_name,
+                "already_trading",
+                &args,
+            )));
+        }
+        if let Some(side) = self.trades.get(&partner) {
+            let args = [
+                ("name", partner.as_str()),
+                ("partner", side.partner.as_str()),
+            ];
+            return Err(trade_failure(self.text(user_name, "busy_trading", &args)));
+        }
+
+        self.trades.insert(*user_name, TradeSide::new(partner));
+        self.trades.insert(partner, TradeSide::new(*user_name));
+        self.tell(&partner, "trade_started", &[("name", user_name)]);
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_start_trading",
+            &[("name", &partner)],
+        )]))
+    }
+
+    pub fn show_trade(&self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let side = self.trade_side(user_name)?;
+        let other = &self.trades[&side.partner];
+        let offer = |side: &TradeSide| match side.confirmed {
+            true => format!(
+                "{} {}",
+                side.describe(self, user_name),
+                self.text(user_name, "confirmed", &[])
+            ),
+            false => side.describe(self, user_name),
+        };
+        let (mine, theirs) = (offer(side), offer(other));
+        let partner = [("name", side.partner.as_str())];
+        Ok(ActionSuccess::new(vec![
+            self.text(user_name, "trading_with", &partner),
+            format!(
+                "  {}",
+                self.text(user_name, "you_offer", &[("offer", &mine)])
+            ),
+            format!(
+                "  {}",
+                self.text(
+                    user_name,
+                    "they_offer",
+                    &[("name", &side.partner), ("offer", &theirs)]
+                )
+            ),
+        ]))
+    }
+
+    // Either an item name, or an amount like "5 gold".
+    pub fn offer(
+        &mut self,
+        user_name: &UserName,
+        offered: &str,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let partner = self.trade_side(user_name)?.partner;
+        let user = self.users.get_user(user_name)?;
+        let side = &self.trades[user_name];
+        let gold = offered
+            .strip_suffix(" gold")
+            .and_then(|amount| amount.trim().parse::<i32>().ok());
+
+        let description = match gold {
+            Some(gold) if gold <= 0 => {
+                return Err(trade_failure(self.text(user_name, "offer_nothing", &[])))
+            }
+            Some(gold)
+                if side
+                    .gold
+                    .checked_add(gold)
+                    .is_none_or(|total| total > user.basic_attributes.gold) =>
+            {
+                let gold = user.basic_attributes.gold.to_string();
+                return Err(trade_failure(self.text(
+                    user_name,
+                    "only_have_gold",
+                    &[("gold", &gold)],
+                )));
+            }
+            Some(gold) => self.text(user_name, "gold_amount", &[("gold", &gold.to_string())]),
+            None => {
+                let owned = user.inventory.iter().filter(|i| i.name == offered).count();
+                let already = side.items.iter().filter(|i| *i == offered).count();
+                if owned <= already {
+                    let item = with_article(offered);
+                    let args = [("item", item.as_str())];
+                    return Err(trade_failure(self.text(
+                        user_name,
+                        "nothing_to_offer",
+                        &args,
+                    )));
+                }
+                with_article(offered)
+            }
+        };
+
+        let side = self.trades.get_mut(user_name).unwrap();
+        match gold {
+            Some(gold) => side.gold += gold,
+            None => side.items.push(offered.to_string()),
+        }
+        self.unconfirm(user_name, &partner);
+        let args = [("name", user_name.as_str()), ("offer", &description)];
+        self.tell(&partner, "offers", &args);
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_offer_more",
+            &args,
+        )]))
+    }
+
+    pub fn confirm_trade(&mut self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let partner = self.trade_side(user_name)?.partner;
+        self.trades.get_mut(user_name).unwrap().confirmed = true;
+        if !self.trades[&partner].confirmed {
+            self.tell(&partner, "trade_confirmed", &[("name", user_name)]);
+            return Ok(ActionSuccess::new(vec![self.text(
+                user_name,
+                "you_confirm_trade",
+                &[("name", &partner)],
+            )]));
+        }
+
+        if let Err(failure) = self.check_trade(user_name, &partner) {
+            self.unconfirm(user_name, &partner);
+            return Err(failure);
+        }
+        let mine = self.trades.remove(user_name).unwrap();
+        let theirs = self.trades.remove(&partner).unwrap();
+        self.hand_over(user_name, &partner, &mine)?;
+        self.hand_over(&partner, user_name, &theirs)?;
+        let (gave, got) = (
+            theirs.describe(self, &partner),
+            mine.describe(self, &partner),
+        );
+        let args = [("gave", gave.as_str()), ("name", user_name), ("got", &got)];
+        self.tell(&partner, "you_trade", &args);
+        let (gave, got) = (
+            mine.describe(self, user_name),
+            theirs.describe(self, user_name),
+        );
+        let args = [("gave", gave.as_str()), ("name", &partner), ("got", &got)];
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_trade",
+            &args,
+        )]))
+    }
+
+    pub fn cancel_trade(&mut self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        self.trade_side(user_name)?;
+        self.end_trade(user_name, "trade_cancelled");
+        Ok(ActionSuccess::new(vec![self.text(
+            user_name,
+            "you_cancel_trade",
+            &[],
+        )]))
+    }
+
+    fn trade_side(&self, user_name: &UserName) -> Result<&TradeSide, ActionFailure> {
+        self.users.get_user(user_name)?;
+        self.trades
+            .get(user_name)
+            .ok_or_else(|| trade_failure(self.text(user_name, "not_trading", &[])))
+    }
+
+    fn unconfirm(&mut self, user_name: &UserName, partner: &UserName) {
+        for name in &[user_name, partner] {
+            if let Some(side) = self.trades.get_mut(*name) {
+                side.confirmed = false;
+            }
+        }
+    }
+
+    // Offers were checked when they were made, but things may have moved on since.
+    fn check_trade(&self, user_name: &UserName, partner: &UserName) -> Result<(), ActionFailure> {
+        let user = self.users.get_user(user_name)?;
+        let other = self.users.get_user(partner)?;
+        if user.room_name != other.room_name {
+            let args = [("name", partner.as_str())];
+            return Err(trade_failure(self.text(
+                user_name,
+                "not_here_any_more",
+                &args,
+            )));
+        }
+        for trader in &[user, other] {
+            let side = &self.trades[&trader.name];
+            let mut inventory: Vec<&ItemName> = trader.inventory.iter().map(|i| &i.name).collect();
+            for item_name in &side.items {
+                match inventory.iter().position(|i| *i == item_name) {
+                    Some(index) => {
+                        inventory.remove(index);
+                    }
+                    None => {
+                        let item = with_article(item_name);
+                        let args = [("name", trader.name.as_str()), ("item", &item)];
+                        return Err(trade_failure(self.text(user_name, "no_longer_has", &args)));
+                    }
+                }
+            }
+            if trader.basic_attributes.gold < side.gold {
+                let gold = self.text(
+                    user_name,
+                    "gold_amount",
+                    &[("gold", &side.gold.to_string())],
+                );
+                let args = [("name", trader.name.as_str()), ("item", &gold)];
+                return Err(trade_failure(self.text(user_name, "no_longer_has", &args)));
+            }
+        }
+        // Each side ends up holding what they had, less what they give, plus what they get.
+        for (trader, giver) in &[(user, other), (other, user)] {
+            let kept = trader.basic_attributes.gold - self.trades[&trader.name].gold;
+            if kept.checked_add(self.trades[&giver.name].gold).is_none() {
+                let args = [("name", trader.name.as_str())];
+                return Err(trade_failure(self.text(
+                    user_name,
+                    "cannot_hold_gold",
+                    &args,
+                )));
+            }
+            let carried =
+                trader.carried_weight() - offered_weight(trader, &self.trades[&trader.name]);
+            let received = offered_weight(giver, &self.trades[&giver.name]);
+            if received > 0 && carried + received > trader.carry_capacity() {
+                let args = [("name", trader.name.as_str())];
+                return Err(trade_failure(self.text(user_name, "cannot_carry", &args)));
+            }
+        }
+        Ok(())
+    }
+
+    // Ends any trade the user is in, telling their partner why with the given message.
+    pub(crate) fn end_trade(&mut self, user_name: &UserName, id: &str) {
+        if let Some(side) = self.trades.remove(user_name) {
+            self.trades.remove(&side.partner);
+            self.tell(&side.partner, id, &[("name", user_name)]);
+        }
+    }
+
+    fn hand_over(
+        &mut self,
+        from: &UserName,
+        to: &UserName,
+        side: &TradeSide,
+    ) -> Result<(), ActionFailure> {
+        let giver = self.users.get_user_mut(from)?;
+        giver.basic_attributes.gold -= side.gold;
+        let mut items = vec![];
+        for item_name in &side.items {
+            if let Some(index) = giver.inventory.iter().position(|i| &i.name == item_name) {
+                items.push(giver.inventory.remove(index));
+            }
+        }
+        self.give_gold(to, side.gold)?;
+        for item in items {
+            self.give_item(to, item)?;
+        }
+        Ok(())
+    }
+}
+
+// What the items on offer weigh, taking each from the trader's inventory once.
+fn offered_weight(trader: &User, side: &TradeSide) -> u32 {
+    let mut inventory: Vec<&Item> = trader.inventory.iter().collect();
+    let mut weight = 0;
+    for item_name in &side.items {
+        if let Some(index) = inventory.iter().position(|i| &i.name == item_name) {
+            weight += inventory.remove(index).total_weight();
+        }
+    }
+    weight
+}
+
+fn trade_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::GameStateBuilder;
+    use crate::item::Item;
+    use crate::room::Direction;
+    use crate::testing::TestWorld;
+    use crate::type_aliases::{RoomName, UserName};
+    use crate::user::UserType;
+
+    fn make_trading_world() -> TestWorld {
+        let mut world = TestWorld::build(
+            GameStateBuilder::new()
+                .room("bazaar", "Rugs and spices.")
+                .room("alley", "A dark alley.")
+                .path("bazaar", "alley", Direction::West)
+                .user("alice", "bazaar")
+                .user("bob", "bazaar"),
+        );
+        let alice = UserName::new("alice").unwrap();
+        let game_state = &mut world.game_state;
+        for name in &["alice", "bob"] {
+            game_state
+                .give_gold(&UserName::new(name).unwrap(), 10)
+                .unwrap();
+        }
+        let lamp = Item::new("lamp".to_string(), "Brass.".to_string()).unwrap();
+        game_state.give_item(&alice, lamp).unwrap();
+        world
+    }
+
+    #[test]
+    fn both_sides_confirm_before_the_swap() {
+        let mut world = make_trading_world();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+
+        world
+            .game_state
+            .process_input_impl(&alice, "trade bob")
+            .unwrap();
+        world
+            .game_state
+            .process_input_impl(&alice, "offer lamp")
+            .unwrap();
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "offer lamp")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["You don't have a lamp to offer."]);
+        world
+            .game_state
+            .process_input_impl(&bob, "offer 4 gold")
+            .unwrap();
+        world
+            .game_state
+            .process_input_impl(&alice, "confirm")
+            .unwrap();
+        // Bob changing his offer means alice has to confirm again.
+        world
+            .game_state
+            .process_input_impl(&bob, "offer 2 gold")
+            .unwrap();
+        world
+            .game_state
+            .process_input_impl(&bob, "confirm")
+            .unwrap();
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "trade")
+            .unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "Trading with bob:",
+                "  You offer a lamp",
+                "  bob offers 6 gold (confirmed)"
+            ]
+        );
+        assert!(world
+            .game_state
+            .users
+            .get_user(&bob)
+            .unwrap()
+            .inventory
+            .is_empty());
+
+        world.take_messages();
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "confirm")
+            .unwrap();
+        assert_eq!(succ.messages(), vec!["You trade a lamp to bob for 6 gold."]);
+        assert_eq!(
+            world.messages_for("bob"),
+            vec!["You trade 6 gold to alice for a lamp."]
+        );
+        let alice_user = world.game_state.users.get_user(&alice).unwrap();
+        assert_eq!(alice_user.basic_attributes.gold, 16);
+        assert!(alice_user.inventory.is_empty());
+        let bob_user = world.game_state.users.get_user(&bob).unwrap();
+        assert_eq!(bob_user.basic_attributes.gold, 4);
+        assert!(bob_user.has_item("lamp"));
+    }
+
+    #[test]
+    fn trades_fail_across_rooms_and_can_be_cancelled() {
+        let mut world = make_trading_world();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+
+        world.game_state.start_trade(&alice, "bob").unwrap();
+        world.game_state.offer(&alice, "lamp").unwrap();
+        world.game_state.confirm_trade(&bob).unwrap();
+        world.game_state.process_input_impl(&bob, "west").unwrap();
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, "confirm")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["bob isn't here any more."]);
+        assert!(world
+            .game_state
+            .users
+            .get_user(&alice)
+            .unwrap()
+            .has_item("lamp"));
+
+        world.take_messages();
+        world
+            .game_state
+            .process_input_impl(&alice, "trade cancel")
+            .unwrap();
+        assert_eq!(world.messages_for("bob"), vec!["alice cancels the trade."]);
+        let failure = world
+            .game_state
+            .process_input_impl(&bob, "confirm")
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["You aren't trading with anyone."]);
+    }
+
+    #[test]
+    fn gold_offers_cannot_overflow() {
+        let mut world = make_trading_world();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+
+        world.game_state.start_trade(&alice, "bob").unwrap();
+        world.game_state.offer(&alice, "1 gold").unwrap();
+        let failure = world
+            .game_state
+            .process_input_impl(&alice, &format!("offer {} gold", i32::MAX))
+            .unwrap_err();
+        assert_eq!(failure.messages, vec!["You only have 10 gold."]);
+
+        // Bob is as rich as he can be, so alice's gold has nowhere to go.
+        world.game_state.give_gold(&bob, i32::MAX - 10).unwrap();
+        assert!(world.game_state.give_gold(&bob, 1).is_err());
+        world.game_state.confirm_trade(&bob).unwrap();
+        let failure = world.game_state.confirm_trade(&alice).unwrap_err();
+        assert_eq!(failure.messages, vec!["bob can't hold that much gold."]);
+        let alice_user = world.game_state.users.get_user(&alice).unwrap();
+        assert_eq!(alice_user.basic_attributes.gold, 10);
+    }
+
+    #[test]
+    fn trades_respect_carry_capacity() {
+        let mut world = make_trading_world();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        let anvil = Item::new("anvil".to_string(), "Iron.".to_string())
+            .unwrap()
+            .with_weight(100);
+        world.game_state.give_item(&alice, anvil).unwrap();
+
+        world.game_state.start_trade(&alice, "bob").unwrap();
+        world.game_state.offer(&alice, "anvil").unwrap();
+        world.game_state.confirm_trade(&bob).unwrap();
+        let failure = world.game_state.confirm_trade(&alice).unwrap_err();
+        assert_eq!(failure.messages, vec!["bob can't carry that much."]);
+        assert!(world
+            .game_state
+            .users
+            .get_user(&alice)
+            .unwrap()
+            .has_item("anvil"));
+    }
+
+    #[test]
+    fn leaving_or_dying_ends_the_trade() {
+        let mut world = make_trading_world();
+        let alice = UserName::new("alice").unwrap();
+        let bob = UserName::new("bob").unwrap();
+        let carol = UserName::new("carol").unwrap();
+        world
+            .game_state
+            .create_user_in_room(
+                &carol,
+                &RoomName::new("bazaar").unwrap(),
+                UserType::Civilian,
+            )
+            .unwrap();
+
+        world.game_state.start_trade(&alice, "bob").unwrap();
+        world.take_messages();
+        world.game_state.remove_user(&bob).unwrap();
+        assert_eq!(
+            world.messages_for("alice"),
+            vec!["bob has left, so the trade is off."]
+        );
+        assert!(world.game_state.trades.is_empty());
+
+        world.game_state.start_trade(&alice, "carol").unwrap();
+        world.take_messages();
+        world.game_state.kill_user(&carol);
+        assert_eq!(
+            world.messages_for("alice"),
+            vec!["carol has died, so the trade is off.", "carol dies."]
+        );
+        assert!(world.game_state.trades.is_empty());
+    }
+}
@@ -1,3 +1,137 @@
-pub type RoomName = String;
-pub type UserName = String;
-pub type PathName = String;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::Deref;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::interner::{self, Symbol};
+
+pub const MAX_NAME_LEN: usize = 64;
+
+// What a name is looked up by: trimmed and case-folded, so "North " and "NORTH" both find
+// "north". Names are always shown as they were given.
+pub(crate) fn name_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+// Names are trimmed, must not be empty, and are at most MAX_NAME_LEN characters. Each kind of
+// name is its own type so a user name can't be passed where a room name is expected. All of
+// them deref to str. Names are interned (see interner.rs), so they're Copy, and maps keyed by
+// them hash an id rather than the text. Look a &str up with `lookup` first.
+macro_rules! validated_name {
+    ($name:ident, $empty_error:expr) => {
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+        #[serde(try_from = "String", into = "String")]
+        pub struct $name(Symbol);
+
+        impl $name {
+            pub fn new(name: &str) -> Result<$name, FaerieError> {
+                let name = name.trim();
+                if name.is_empty() {
+                    return Err($empty_error);
+                }
+                if name.chars().count() > MAX_NAME_LEN {
+                    return Err(FaerieError::NameTooLong(name.to_string()));
+                }
+                Ok($name(interner::intern(name)))
+            }
+
+            // The name, if anything has ever been called it. Unlike `new`, this never interns
+            // anything, so it's the way to look up text a player typed.
+            pub(crate) fn lookup(name: &str) -> Option<$name> {
+                interner::lookup(name.trim()).map($name)
+            }
+
+            pub fn as_str(&self) -> &'static str {
+                self.0.as_str()
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.debug_tuple(stringify!($name))
+                    .field(&self.as_str())
+                    .finish()
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = FaerieError;
+
+            fn try_from(name: String) -> Result<$name, FaerieError> {
+                $name::new(&name)
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(name: $name) -> String {
+                name.as_str().to_string()
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.as_str() == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.as_str() == *other
+            }
+        }
+    };
+}
+
+validated_name!(RoomName, FaerieError::EmptyRoomName);
+validated_name!(UserName, FaerieError::EmptyUserName);
+validated_name!(PathName, FaerieError::EmptyPathName);
+validated_name!(AccountName, FaerieError::EmptyAccountName);
+
+pub type ItemName = String;
+pub type QuestId = String;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_trimmed_and_validated() {
+        assert_eq!(RoomName::new("  Hall ").unwrap(), "Hall");
+        assert_eq!(RoomName::new(" "), Err(FaerieError::EmptyRoomName));
+        assert_eq!(UserName::new(""), Err(FaerieError::EmptyUserName));
+        assert_eq!(PathName::new("\t"), Err(FaerieError::EmptyPathName));
+
+        let long = "x".repeat(MAX_NAME_LEN + 1);
+        assert_eq!(UserName::new(&long), Err(FaerieError::NameTooLong(long)));
+        assert!(UserName::new(&"x".repeat(MAX_NAME_LEN)).is_ok());
+    }
+
+    #[test]
+    fn names_are_validated_when_deserialized() {
+        let name: RoomName = serde_json::from_str("\"Hall\"").unwrap();
+        assert_eq!(name, "Hall");
+        assert!(serde_json::from_str::<RoomName>("\"\"").is_err());
+    }
+}
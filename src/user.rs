@@ -1,51 +1,255 @@
-use crate::type_aliases::{RoomName, UserName};
+use std::collections::{BTreeMap, BTreeSet};
 
-#[derive(Debug)]
+use crate::achievements::Achievement;
+use crate::dialogue::Dialogue;
+use crate::equipment::EquipmentSlot;
+use crate::item::Item;
+use crate::loot::LootTable;
+use crate::mail::Mail;
+use crate::quests::QuestProgress;
+use crate::shop::Shop;
+use crate::skills::Skill;
+use crate::status::StatusEffect;
+use crate::type_aliases::{PathName, QuestId, RoomName, UserName};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum UserType {
     Civilian,
     Viking,
     ElfLord,
 }
 
-#[derive(Debug)]
+// What a user is allowed to do outside of playing. Each role can do everything the ones
+// before it can.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Role {
+    #[default]
+    Player,
+    Builder,
+    Admin,
+}
+
+impl UserType {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            UserType::Civilian => "civilian",
+            UserType::Viking => "viking",
+            UserType::ElfLord => "elf lord",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct User {
     pub name: UserName,
     pub room_name: RoomName,
     pub basic_attributes: BasicAttributes,
     pub special_attributes: SpecialAttributes,
+    #[serde(default)]
+    pub inventory: Vec<Item>,
+    #[serde(default)]
+    pub quest_progress: BTreeMap<QuestId, QuestProgress>,
+    // Whether this user wants the game's formatter; off means plain text.
+    #[serde(default = "default_color")]
+    pub color: bool,
+    // Their prompt template (see prompt.rs); None means the default.
+    #[serde(default)]
+    pub prompt: Option<String>,
+    // Lines per page of long replies (see pager.rs); None means the default, and 0 is no
+    // paging at all.
+    #[serde(default)]
+    pub page_size: Option<usize>,
+    // Columns to wrap text at (see wrap.rs); None means the default, and 0 is no wrapping.
+    #[serde(default)]
+    pub width: Option<usize>,
+    // The language they read messages in (see catalog.rs); None means English.
+    #[serde(default)]
+    pub locale: Option<String>,
+    #[serde(default)]
+    pub role: Role,
+    // Driven by the game rather than a person, e.g. by a script.
+    #[serde(default)]
+    pub npc: bool,
+    // Hidden paths this user has searched out, by room.
+    #[serde(default)]
+    pub found_paths: BTreeSet<(RoomName, PathName)>,
+    // Every room this user has been in. The map only shows these.
+    #[serde(default)]
+    pub visited_rooms: BTreeSet<RoomName>,
+    // Stamina used up and not yet recovered (see stamina.rs).
+    #[serde(default)]
+    pub fatigue: i32,
+    // Successful uses of each skill (see skills.rs).
+    #[serde(default)]
+    pub skills: BTreeMap<Skill, u32>,
+    // Tallies for achievements, and those earned (see achievements.rs).
+    #[serde(default)]
+    pub kills: u32,
+    #[serde(default)]
+    pub deaths: u32,
+    #[serde(default)]
+    pub achievements: BTreeSet<Achievement>,
+    // Titles earned, and the one shown with their name (see profile.rs).
+    #[serde(default)]
+    pub titles: BTreeSet<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    // What others see when they look at this user.
+    #[serde(default)]
+    pub description: Option<String>,
+    // Letters from other users (see mail.rs), oldest first.
+    #[serde(default)]
+    pub mailbox: Vec<Mail>,
+    // Chat channels they've joined (see channels.rs).
+    #[serde(default)]
+    pub channels: BTreeSet<String>,
+    // Who they want to hear about logging in, and who they don't want to hear from at all
+    // (see friends.rs).
+    #[serde(default)]
+    pub friends: BTreeSet<UserName>,
+    #[serde(default)]
+    pub ignored: BTreeSet<UserName>,
+    // Each effect on this user, with the ticks it has left.
+    #[serde(default)]
+    pub status_effects: BTreeMap<StatusEffect, u64>,
+    #[serde(default)]
+    pub equipment: BTreeMap<EquipmentSlot, Item>,
+    // Only NPCs keep shops.
+    #[serde(default)]
+    pub shop: Option<Shop>,
+    // What this user leaves behind when they die, if they're an NPC.
+    #[serde(default)]
+    pub loot: Option<LootTable>,
+    // What this user says when talked to, if they're an NPC.
+    #[serde(default)]
+    pub dialogue: Option<Dialogue>,
+}
+
+fn default_color() -> bool {
+    true
 }
 
 impl User {
     pub fn new(name: UserName, starting_room_name: RoomName, user_type: UserType) -> User {
-        assert!(!name.is_empty(), "Empty user names are not allowed!");
         let basic_attributes = BasicAttributes::default(&user_type);
         let special_attributes = SpecialAttributes::default(&user_type);
         User {
             name,
+            visited_rooms: BTreeSet::from([starting_room_name]),
             room_name: starting_room_name,
             basic_attributes,
             special_attributes,
+            inventory: vec![],
+            quest_progress: BTreeMap::new(),
+            color: true,
+            prompt: None,
+            page_size: None,
+            width: None,
+            locale: None,
+            role: Role::Player,
+            npc: false,
+            found_paths: BTreeSet::new(),
+            fatigue: 0,
+            skills: BTreeMap::new(),
+            kills: 0,
+            deaths: 0,
+            achievements: BTreeSet::new(),
+            titles: BTreeSet::new(),
+            title: None,
+            description: None,
+            mailbox: vec![],
+            channels: BTreeSet::new(),
+            friends: BTreeSet::new(),
+            ignored: BTreeSet::new(),
+            status_effects: BTreeMap::new(),
+            equipment: BTreeMap::new(),
+            shop: None,
+            loot: None,
+            dialogue: None,
+        }
+    }
+
+    // The room they're in counts, even for users saved before rooms were tracked.
+    pub fn has_visited(&self, room_name: &str) -> bool {
+        self.room_name == room_name
+            || RoomName::lookup(room_name).is_some_and(|name| self.visited_rooms.contains(&name))
+    }
+
+    pub fn has_item(&self, item_name: &str) -> bool {
+        self.inventory.iter().any(|i| i.name == item_name)
+    }
+
+    pub fn user_type(&self) -> UserType {
+        match self.special_attributes {
+            SpecialAttributes::Civilian { .. } => UserType::Civilian,
+            SpecialAttributes::Viking { .. } => UserType::Viking,
+            SpecialAttributes::ElfLord { .. } => UserType::ElfLord,
         }
     }
+
+    pub fn max_hp(&self) -> i32 {
+        BasicAttributes::default(&self.user_type()).hp
+    }
+
+    pub fn max_mp(&self) -> i32 {
+        BasicAttributes::default(&self.user_type()).mp
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.basic_attributes.hp <= 0
+    }
+
+    // Damage dealt by one combat round, before any defence.
+    pub fn attack_damage(&self) -> i32 {
+        let mut damage = match self.special_attributes {
+            SpecialAttributes::Civilian { .. } => 1,
+            SpecialAttributes::Viking { brutish_swing } => 3 * i32::from(brutish_swing),
+            SpecialAttributes::ElfLord { fuck_infusion } => 2 * i32::from(fuck_infusion),
+        };
+        damage += self.equipment_modifiers().attack;
+        if self.has_effect(StatusEffect::Hasted) {
+            damage *= 2;
+        }
+        if self.has_effect(StatusEffect::Blinded) {
+            damage = (damage / 2).max(1);
+        }
+        damage
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct BasicAttributes {
     pub hp: i32,
     pub mp: i32,
+    #[serde(default)]
+    pub gold: i32,
 }
 
 impl BasicAttributes {
     fn default(user_type: &UserType) -> BasicAttributes {
         match user_type {
-            UserType::Civilian => BasicAttributes { hp: 20, mp: 7 },
-            UserType::Viking => BasicAttributes { hp: 220, mp: 9 },
-            UserType::ElfLord => BasicAttributes { hp: 80, mp: 28 },
+            UserType::Civilian => BasicAttributes {
+                hp: 20,
+                mp: 7,
+                gold: 0,
+            },
+            UserType::Viking => BasicAttributes {
+                hp: 220,
+                mp: 9,
+                gold: 0,
+            },
+            UserType::ElfLord => BasicAttributes {
+                hp: 80,
+                mp: 28,
+                gold: 0,
+            },
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum SpecialAttributes {
     Civilian { needlessly_chatter: usize },
     Viking { brutish_swing: u8 },
@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+
+use crate::map::{compass_offset, path_fits};
+use crate::room::Direction;
+use crate::type_aliases::{PathName, RoomName};
+use crate::GameState;
+
+// Problems found by GameState::validate_world. Every list is sorted.
+#[derive(Debug, Default, PartialEq)]
+pub struct WorldReport {
+    // Rooms that can't be reached from the start room. Empty if no start room is set.
+    pub unreachable_rooms: Vec<RoomName>,
+    // Paths whose target room doesn't exist.
+    pub dangling_paths: Vec<(RoomName, PathName)>,
+    // Compass paths whose target room has no matching path straight back.
+    pub asymmetric_exits: Vec<(RoomName, PathName)>,
+    // Compass paths between rooms with coordinates that don't head the way they say.
+    pub misplaced_exits: Vec<(RoomName, PathName)>,
+    pub rooms_without_exits: Vec<RoomName>,
+}
+
+impl WorldReport {
+    pub fn is_clean(&self) -> bool {
+        *self == WorldReport::default()
+    }
+}
+
+impl GameState {
+    pub fn validate_world(&self) -> WorldReport {
+        let mut report = WorldReport::default();
+
+        for (room_name, room) in &self.rooms.rooms {
+            if room.paths.is_empty() {
+                report.rooms_without_exits.push(*room_name);
+            }
+
+            for (path_name, path) in &room.paths {
+                let target_room = match self.rooms.get_room(&path.target_room_name) {
+                    Ok(target_room) => target_room,
+                    Err(_) => {
+                        report.dangling_paths.push((*room_name, *path_name));
+                        continue;
+                    }
+                };
+
+                if let (Some(from), Some(to), Some(offset)) =
+                    (room.coords, target_room.coords, compass_offset(path_name))
+                {
+                    if !path_fits(from, to, offset) {
+                        report.misplaced_exits.push((*room_name, *path_name));
+                    }
+                }
+
+                let reverse = Direction::from_name(path_name)
+                    .and_then(Direction::get_reverse)
+                    .map(Direction::get_path_name);
+                if let Some(reverse) = reverse {
+                    let leads_back = PathName::lookup(&reverse)
+                        .and_then(|name| target_room.paths.get(&name))
+                        .is_some_and(|p| &p.target_room_name == room_name);
+                    if !leads_back {
+                        report.asymmetric_exits.push((*room_name, *path_name));
+                    }
+                }
+            }
+        }
+
+        if let Some(start_room_name) = &self.start_room_name {
+            let reachable = self
+                .reachable_rooms(start_room_name, true)
+                .unwrap_or_default();
+            let all_rooms: HashSet<RoomName> = self.rooms.rooms.keys().cloned().collect();
+            report.unreachable_rooms = all_rooms.difference(&reachable).cloned().collect();
+        }
+
+        report.unreachable_rooms.sort();
+        report.dangling_paths.sort();
+        report.asymmetric_exits.sort();
+        report.misplaced_exits.sort();
+        report.rooms_without_exits.sort();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::room::{Direction, PathType};
+    use crate::type_aliases::{PathName, RoomName};
+    use crate::GameState;
+
+    #[test]
+    fn starter_world_is_clean() {
+        let game_state = GameState::from_world_str(include_str!("../worlds/starter.toml")).unwrap();
+        assert!(game_state.validate_world().is_clean());
+    }
+
+    #[test]
+    fn reports_each_kind_of_problem() {
+        let mut game_state = GameState::new();
+        let hall = RoomName::new("hall").unwrap();
+        let garden = RoomName::new("garden").unwrap();
+        let attic = RoomName::new("attic").unwrap();
+        game_state
+            .create_room(&hall, "A long hall.".to_string())
+            .unwrap();
+        game_state
+            .create_room_from(&garden, "Roses.".to_string(), &hall, Direction::North)
+            .unwrap();
+        game_state
+            .create_room(&attic, "Dusty.".to_string())
+            .unwrap();
+        game_state
+            .add_path(&hall, &attic, Direction::CustomOneWay("east".to_string()))
+            .unwrap();
+        game_state.set_start_room(&garden).unwrap();
+        game_state
+            .rooms
+            .get_room_mut(&garden)
+            .unwrap()
+            .add_path_special(
+                &RoomName::new("nowhere").unwrap(),
+                &PathName::new("hole").unwrap(),
+                PathType::Normal,
+            )
+            .unwrap();
+        game_state
+            .create_room(&RoomName::new("island").unwrap(), "Alone.".to_string())
+            .unwrap();
+
+        let report = game_state.validate_world();
+        assert!(!report.is_clean());
+        assert_eq!(report.unreachable_rooms, vec!["island"]);
+        assert_eq!(
+            report.dangling_paths,
+            vec![(garden, PathName::new("hole").unwrap())]
+        );
+        assert_eq!(
+            report.asymmetric_exits,
+            vec![(hall, PathName::new("east").unwrap())]
+        );
+        assert_eq!(
+            report.rooms_without_exits,
+            vec![attic, RoomName::new("island").unwrap()]
+        );
+    }
+}
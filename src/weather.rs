@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::flags::RoomFlags;
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+// Chance, out of a hundred, that a zone's weather turns on a tick.
+pub const WEATHER_CHANGE_CHANCE: u32 = 2;
+// Chance, out of a hundred, that anyone outdoors in a zone is reminded of its weather on a tick.
+pub const WEATHER_AMBIENT_CHANCE: u32 = 3;
+
+// Zones with weather start out with `weather = "Clear"` in world files. It only ever turns
+// to the weather next to it here, so clear skies cloud over before it rains, and rain comes
+// before a storm or clears to cloud. Cloud can also lift into fog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Weather {
+    Clear,
+    Cloudy,
+    Rain,
+    Storm,
+    Fog,
+}
+
+impl Weather {
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Weather::Clear => "clear",
+            Weather::Cloudy => "cloudy",
+            Weather::Rain => "raining",
+            Weather::Storm => "stormy",
+            Weather::Fog => "foggy",
+        }
+    }
+
+    fn next(self) -> &'static [Weather] {
+        match self {
+            Weather::Clear => &[Weather::Cloudy],
+            Weather::Cloudy => &[Weather::Clear, Weather::Rain, Weather::Fog],
+            Weather::Rain => &[Weather::Cloudy, Weather::Storm],
+            Weather::Storm => &[Weather::Rain],
+            Weather::Fog => &[Weather::Cloudy],
+        }
+    }
+
+    // The catalog ID of what everyone outdoors is shown when the weather turns to this.
+    fn arrival(self) -> &'static str {
+        match self {
+            Weather::Clear => "sky_clears",
+            Weather::Cloudy => "clouds_gather",
+            Weather::Rain => "rain_starts",
+            Weather::Storm => "storm_breaks",
+            Weather::Fog => "fog_rolls_in",
+        }
+    }
+
+    fn ambient(self) -> Option<&'static str> {
+        match self {
+            Weather::Clear => None,
+            Weather::Cloudy => Some("clouds_drift"),
+            Weather::Rain => Some("rain_patters"),
+            Weather::Storm => Some("lightning"),
+            Weather::Fog => Some("shapes_in_fog"),
+        }
+    }
+}
+
+impl GameState {
+    // None for a zone without weather.
+    pub fn set_zone_weather(
+        &mut self,
+        zone_name: &str,
+        weather: Option<Weather>,
+    ) -> Result<(), FaerieError> {
+        self.zones
+            .get_mut(zone_name)
+            .ok_or_else(|| FaerieError::ZoneNotFound(zone_name.to_string()))?
+            .weather = weather;
+        Ok(())
+    }
+
+    // The weather where the user is, if their zone has any.
+    pub fn weather_for(&self, user_name: &UserName) -> Option<Weather> {
+        self.user_zone(user_name)?.weather
+    }
+
+    fn outdoor_users_in_zone(&self, zone_name: &str) -> Vec<UserName> {
+        let mut users: Vec<UserName> = self
+            .occupied_rooms()
+            .into_iter()
+            .filter(|room| room.in_zone(zone_name) && !room.flags.contains(RoomFlags::INDOORS))
+            .flat_map(|room| room.users.iter().cloned())
+            .collect();
+        users.sort();
+        users
+    }
+
+    pub(crate) fn change_weather(&mut self) {
+        let mut zone_names: Vec<String> = self
+            .zones
+            .values()
+            .filter(|zone| zone.weather.is_some())
+            .map(|zone| zone.name.clone())
+            .collect();
+        zone_names.sort();
+        for zone_name in zone_names {
+            let weather = match self.zones[&zone_name].weather {
+                Some(weather) => weather,
+                None => continue,
+            };
+            let message = if self.rng.chance(WEATHER_CHANGE_CHANCE) {
+                let turned = *self.rng.choose(weather.next()).unwrap_or(&weather);
+                if let Some(zone) = self.zones.get_mut(&zone_name) {
+                    zone.weather = Some(turned);
+                }
+                Some(turned.arrival())
+            } else if self.rng.chance(WEATHER_AMBIENT_CHANCE) {
+                weather.ambient()
+            } else {
+                None
+            };
+            if let Some(id) = message {
+                for user_name in self.outdoor_users_in_zone(&zone_name) {
+                    let message = self.text(&user_name, id, &[]);
+                    self.output.send(&user_name, &message);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::testing::TestWorld;
+    use crate::type_aliases::RoomName;
+
+    use crate::zones::Zone;
+
+    // A moor in a zone with weather, and a cottage on it that keeps the weather out.
+    fn make_moor() -> TestWorld {
+        let mut world = TestWorld::build(
+            GameStateBuilder::new()
+                .room("moor", "Heather. It's {weather}.")
+                .room("cottage", "Snug.")
+                .user("alice", "moor")
+                .user("bob", "cottage"),
+        );
+        let moor = RoomName::new("moor").unwrap();
+        let cottage = RoomName::new("cottage").unwrap();
+        let game_state = &mut world.game_state;
+        game_state.set_seed(5);
+        game_state
+            .set_room_flags(&cottage, RoomFlags::INDOORS)
+            .unwrap();
+        game_state.add_zone(Zone::new("Moorland")).unwrap();
+        for room_name in &[&moor, &cottage] {
+            game_state
+                .set_room_zone(room_name, Some("Moorland"))
+                .unwrap();
+        }
+        game_state
+            .set_zone_weather("Moorland", Some(Weather::Rain))
+            .unwrap();
+        world
+    }
+
+    #[test]
+    fn weather_turns_and_is_felt_outdoors() {
+        let mut world = make_moor();
+        let alice = UserName::new("alice").unwrap();
+        let succ = world.game_state.process_input_impl(&alice, "look").unwrap();
+        assert_eq!(succ.messages()[1], "  Heather. It's raining.");
+
+        world.game_state.tick_n(200);
+        assert!(!world.messages_for("alice").is_empty());
+        assert!(world.messages_for("bob").is_empty());
+        let weather = world.game_state.weather_for(&alice).unwrap();
+        let succ = world.game_state.process_input_impl(&alice, "look").unwrap();
+        assert_eq!(
+            succ.messages()[1],
+            format!("  Heather. It's {}.", weather.display_name())
+        );
+    }
+
+    #[test]
+    fn zones_without_weather_stay_calm() {
+        let mut world = make_moor();
+        world.game_state.set_zone_weather("Moorland", None).unwrap();
+        world.game_state.tick_n(200);
+        assert!(world.messages_for("alice").is_empty());
+        let alice = UserName::new("alice").unwrap();
+        assert_eq!(world.game_state.weather_for(&alice), None);
+        assert_eq!(
+            world
+                .game_state
+                .set_zone_weather("Nowhere", Some(Weather::Fog)),
+            Err(FaerieError::ZoneNotFound("Nowhere".to_string()))
+        );
+    }
+}
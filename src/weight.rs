@@ -0,0 +1,164 @@
+use crate::item::Item;
+use crate::lambda::{ActionFailure, FailReason};
+use crate::type_aliases::UserName;
+use crate::user::User;
+use crate::GameState;
+
+// Ticks an encumbered user has to wait between moves.
+pub const ENCUMBERED_MOVE_DELAY: u64 = 1;
+
+impl Item {
+    pub fn with_weight(mut self, weight: u32) -> Item {
+        self.weight = weight;
+        self
+    }
+
+    // Including anything inside it.
+    pub fn total_weight(&self) -> u32 {
+        let contents = match &self.container {
+            Some(container) => container.contents.iter().map(Item::total_weight).sum(),
+            None => 0,
+        };
+        self.weight + contents
+    }
+}
+
+// Everything carried or worn counts. Users can't pick up more than their capacity, which
+// grows with their maximum hp, and once they're carrying over half of it they move slowly.
+impl User {
+    pub fn carried_weight(&self) -> u32 {
+        self.inventory
+            .iter()
+            .chain(self.equipment.values())
+            .map(Item::total_weight)
+            .sum()
+    }
+
+    pub fn carry_capacity(&self) -> u32 {
+        10 + self.max_hp().max(0) as u32 / 4
+    }
+
+    pub fn is_encumbered(&self) -> bool {
+        self.carried_weight() * 2 > self.carry_capacity()
+    }
+}
+
+impl GameState {
+    pub(crate) fn refuse_if_too_heavy(
+        &self,
+        user_name: &UserName,
+        weight: u32,
+        what: &str,
+    ) -> Result<(), ActionFailure> {
+        let user = self.users.get_user(user_name)?;
+        if weight > 0 && user.carried_weight() + weight > user.carry_capacity() {
+            return Err(ActionFailure::new(
+                vec![self.text(user_name, "too_heavy", &[("item", what)])],
+                FailReason::Encumbered,
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn refuse_if_slowed(&self, user_name: &UserName) -> Result<(), ActionFailure> {
+        match self.move_ready_at.get(user_name) {
+            Some(ready_at) if *ready_at > self.tick_count => Err(ActionFailure::new(
+                vec![self.text(user_name, "slowed", &[])],
+                FailReason::Encumbered,
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn note_move(&mut self, user_name: &UserName) {
+        match self.users.get_user(user_name) {
+            Ok(user) if user.is_encumbered() => {
+                let ready_at = self.tick_count + ENCUMBERED_MOVE_DELAY;
+                self.move_ready_at.insert(*user_name, ready_at);
+            }
+            _ => {
+                self.move_ready_at.remove(user_name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+    use crate::type_aliases::RoomName;
+
+    fn make_quarry() -> GameState {
+        let mut game_state = GameStateBuilder::new()
+            .room("quarry", "Heaps of stone.")
+            .room("road", "A dusty road.")
+            .path("quarry", "road", Direction::North)
+            .user("alice", "quarry")
+            .build()
+            .unwrap();
+        let quarry = RoomName::new("quarry").unwrap();
+        for (name, weight) in &[("boulder", 12), ("pebble", 1)] {
+            let item = Item::new(name.to_string(), "Stone.".to_string())
+                .unwrap()
+                .with_weight(*weight);
+            game_state.place_item(&quarry, item).unwrap();
+        }
+        game_state
+    }
+
+    #[test]
+    fn users_cannot_take_more_than_they_can_carry() {
+        let mut game_state = make_quarry();
+        let alice = UserName::new("alice").unwrap();
+        let user = game_state.users.get_user(&alice).unwrap();
+        assert_eq!(user.carry_capacity(), 15);
+
+        let bag = Item::new("bag".to_string(), "Sturdy.".to_string())
+            .unwrap()
+            .with_weight(1)
+            .container(3, None);
+        game_state.give_item(&alice, bag).unwrap();
+        game_state
+            .process_input_impl(&alice, "take pebble")
+            .unwrap();
+        game_state
+            .process_input_impl(&alice, "put pebble in bag")
+            .unwrap();
+        assert_eq!(
+            game_state.users.get_user(&alice).unwrap().carried_weight(),
+            2
+        );
+
+        game_state
+            .process_input_impl(&alice, "take boulder")
+            .unwrap();
+        let quarry = RoomName::new("quarry").unwrap();
+        let rock = Item::new("rock".to_string(), "Stone.".to_string())
+            .unwrap()
+            .with_weight(2);
+        game_state.place_item(&quarry, rock).unwrap();
+        let failure = game_state
+            .process_input_impl(&alice, "take rock")
+            .unwrap_err();
+        assert_eq!(failure.reason, FailReason::Encumbered);
+        assert_eq!(failure.messages, vec!["You can't carry a rock as well."]);
+    }
+
+    #[test]
+    fn encumbered_users_move_slowly() {
+        let mut game_state = make_quarry();
+        let alice = UserName::new("alice").unwrap();
+        game_state
+            .process_input_impl(&alice, "take boulder")
+            .unwrap();
+        assert!(game_state.users.get_user(&alice).unwrap().is_encumbered());
+
+        game_state.process_input_impl(&alice, "north").unwrap();
+        let failure = game_state.process_input_impl(&alice, "south").unwrap_err();
+        assert_eq!(failure.reason, FailReason::Encumbered);
+        game_state.tick();
+        game_state.process_input_impl(&alice, "south").unwrap();
+    }
+}
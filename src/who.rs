@@ -0,0 +1,121 @@
+use crate::lambda::{ActionFailure, ActionSuccess};
+use crate::type_aliases::UserName;
+use crate::user::User;
+use crate::GameState;
+
+// Users go up a level for every this many they've defeated.
+pub const KILLS_PER_LEVEL: u32 = 5;
+
+// There's no experience yet, so a user's level comes from who they've defeated.
+impl User {
+    pub fn level(&self) -> u32 {
+        1 + self.kills / KILLS_PER_LEVEL
+    }
+}
+
+impl GameState {
+    // Everyone online who isn't an NPC, with their class, level, title, zone and how many
+    // ticks it's been since they last did anything (see afk.rs).
+    pub fn who(&self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        self.users.get_user(user_name)?;
+        let mut users: Vec<&User> = self.users.users.values().filter(|u| !u.npc).collect();
+        users.sort_by_key(|a| a.name);
+
+        let mut rows = vec![["Name", "Class", "Level", "Title", "Zone", "Idle"]
+            .iter()
+            .map(|heading| heading.to_string())
+            .collect()];
+        for user in users {
+            let zone = self.zone_of(&user.room_name).map(|zone| zone.name.clone());
+            let mut idle = self.idle_ticks(&user.name).to_string();
+            if self.is_afk(&user.name) {
+                idle.push_str(" (AFK)");
+            }
+            rows.push(vec![
+                user.name.to_string(),
+                user.user_type().display_name().to_string(),
+                user.level().to_string(),
+                user.title.clone().unwrap_or_else(|| "-".to_string()),
+                zone.unwrap_or_else(|| "-".to_string()),
+                idle,
+            ]);
+        }
+
+        let mut messages = vec![self.text(user_name, "users_online", &[])];
+
+        messages.extend(align_columns(&rows));
+        Ok(ActionSuccess::new(messages))
+    }
+}
+
+// Pads each column to its widest cell, two spaces apart.
+fn align_columns(rows: &[Vec<String>]) -> Vec<String> {
+    let mut widths: Vec<usize> = vec![];
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            let len = cell.chars().count();
+            match widths.get_mut(i) {
+                Some(width) => *width = (*width).max(len),
+                None => widths.push(len),
+            }
+        }
+    }
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect();
+            format!("  {}", cells.join("  ").trim_end())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+    use crate::type_aliases::RoomName;
+    use crate::user::UserType;
+    use crate::zones::Zone;
+
+    #[test]
+    fn who_lines_up_everyone_online() {
+        let mut game_state = GameStateBuilder::new()
+            .room("gate", "A town gate.")
+            .room("woods", "Pines.")
+            .path("gate", "woods", Direction::North)
+            .user("alice", "gate")
+            .user_of_type("bjorn", "gate", UserType::Viking)
+            .user_of_type("guard", "gate", UserType::Viking)
+            .build()
+            .unwrap();
+        let woods = RoomName::new("woods").unwrap();
+        game_state.add_zone(Zone::new("Darkwood")).unwrap();
+        game_state.set_room_zone(&woods, Some("Darkwood")).unwrap();
+        let alice = UserName::new("alice").unwrap();
+        let bjorn = UserName::new("bjorn").unwrap();
+        game_state.users.get_user_mut("guard").unwrap().npc = true;
+        game_state.users.get_user_mut(&bjorn).unwrap().kills = KILLS_PER_LEVEL;
+        game_state.grant_title(&bjorn, "the Bold").unwrap();
+        game_state.choose_title(&bjorn, Some("the Bold")).unwrap();
+
+        game_state.tick_n(3);
+        game_state.process_input_impl(&alice, "north").unwrap();
+        game_state.tick_n(2);
+        let succ = game_state.process_input_impl(&bjorn, "who").unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec![
+                "Users online:",
+                "  Name   Class     Level  Title     Zone      Idle",
+                "  alice  civilian  1      -         Darkwood  2",
+                "  bjorn  viking    2      the Bold  -         0",
+            ]
+        );
+        let succ = game_state.process_input_impl(&alice, "list_users").unwrap();
+        assert_eq!(succ.messages()[0], "Users online:");
+    }
+}
@@ -0,0 +1,644 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path as FsPath;
+
+use serde::Deserialize;
+
+use crate::ambience::AmbientMessage;
+use crate::dialogue::Dialogue;
+use crate::error::FaerieError;
+use crate::flags::{PathFlags, RoomFlags};
+use crate::grammar::with_article;
+use crate::item::Item;
+use crate::loot::LootTable;
+use crate::map::Coords;
+use crate::room::Direction;
+use crate::shop::Shop;
+use crate::skills::SkillCheck;
+use crate::socials::Social;
+use crate::spawn::{NpcTemplate, SpawnPoint, DEFAULT_SPAWN_DELAY};
+use crate::stamina::Terrain;
+use crate::type_aliases::{ItemName, RoomName, UserName};
+use crate::user::{Role, UserType};
+use crate::zones::Zone;
+use crate::GameState;
+
+// The on-disk world format, e.g.:
+//
+//     start_room = "Starting Point"
+//
+//     [[rooms]]
+//     name = "Starting Point"
+//     description = "This seems like a nice place to start an adventure."
+//
+//     [[paths]]
+//     from = "Starting Point"
+//     to = "North of Start"
+//     direction = "north"
+//
+//     [[users]]
+//     name = "glenn"
+//     room = "Starting Point"
+//     items = ["brass key"]
+//     role = "Admin"
+//
+// The top level can also set `respawn_room`, `respawn_delay` (in ticks) and `death_penalty`
+// (hp). Without a respawn room the dead come back in the start room.
+//
+// Compass directions get their reverse path automatically unless `one_way` is set. Any other
+// direction is a custom path name, which is two-way only if `reverse` names the way back.
+// A path with a `key` starts out locked unless `locked = false`. One with a `required_skill`
+// (see skills.rs) can only be taken by users good enough at it.
+//
+// Rooms and paths can have `flags`, written like `flags = "SAFE | DARK"` (see flags.rs). Flags
+// on a path don't apply to the way back.
+//
+// Rooms, paths and users can name scripts from the `scripts` table (see scripting.rs):
+//
+//     [scripts]
+//     needs_amulet = 'if !("amulet" in items) { "You need the amulet to enter." }'
+//
+// with `on_enter`/`on_exit` or `description_script` on a room, `entry_script`/`exit_script` on
+// a path (the way back isn't scripted) and `script` on a user, to make them an NPC. Users
+// can also have a `dialogue` (see dialogue.rs), whose responses' `script`s are names here too.
+//
+// Rooms and users can keep a shop, and users can start with `gold`. NPCs can have a `loot`
+// table (see loot.rs), as can spawn point templates:
+//
+//     [rooms.shop.lamp]
+//     price = 5
+//     sell_price = 2
+//
+// Rooms can have `coords = [x, y, z]` for the map, with x to the east, y to the north and z up.
+// Compass paths between rooms with coords are checked against them by validation.
+//
+// Rooms can have `ambient` lines, each shown to anyone there with its own chance per tick
+// (see ambience.rs).
+//
+// Rooms can have a `terrain` of "Normal", "Rough", "Steep" or "Water", which makes them
+// harder to get into (see stamina.rs).
+//
+// Rooms can belong to a zone, listed under `zones` (see zones.rs), by giving its name as `zone`.
+//
+// Spawn points keep a room stocked with NPCs made from a template (see spawn.rs).
+// `max_population` defaults to 1 and `respawn_delay` to 10 ticks:
+//
+//     [[spawns]]
+//     room = "Cave"
+//     max_population = 3
+//     npc = { name = "goblin", type = "Viking", items = ["club"], gold = 2 }
+//
+// Socials are added to the built-in ones (wave, bow, grin), replacing any of the same name:
+//
+//     [socials.nod]
+//     to_actor = "You nod."
+//     to_room = "{actor} nods."
+//
+// Messages can be translated for users who choose another language (see catalog.rs):
+//
+//     [messages.fr]
+//     unknown_input = "Je ne comprends pas « {input} »."
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorldFile {
+    start_room: Option<RoomName>,
+    respawn_room: Option<RoomName>,
+    respawn_delay: Option<u64>,
+    death_penalty: Option<i32>,
+    #[serde(default)]
+    zones: Vec<Zone>,
+    #[serde(default)]
+    rooms: Vec<RoomDef>,
+    #[serde(default)]
+    paths: Vec<PathDef>,
+    #[serde(default)]
+    users: Vec<UserDef>,
+    #[serde(default)]
+    spawns: Vec<SpawnDef>,
+    #[serde(default)]
+    scripts: BTreeMap<String, String>,
+    #[serde(default)]
+    socials: BTreeMap<String, Social>,
+    #[serde(default)]
+    messages: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RoomDef {
+    name: RoomName,
+    description: String,
+    #[serde(default)]
+    flags: RoomFlags,
+    on_enter: Option<String>,
+    on_exit: Option<String>,
+    description_script: Option<String>,
+    shop: Option<Shop>,
+    zone: Option<String>,
+    coords: Option<Coords>,
+    #[serde(default)]
+    ambient: Vec<AmbientMessage>,
+    #[serde(default)]
+    terrain: Terrain,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PathDef {
+    from: RoomName,
+    to: RoomName,
+    direction: String,
+    reverse: Option<String>,
+    #[serde(default)]
+    one_way: bool,
+    #[serde(default)]
+    painful: bool,
+    key: Option<ItemName>,
+    locked: Option<bool>,
+    #[serde(default)]
+    flags: PathFlags,
+    entry_script: Option<String>,
+    exit_script: Option<String>,
+    required_skill: Option<SkillCheck>,
+}
+
+impl PathDef {
+    fn to_direction(&self) -> Direction {
+        match (Direction::from_name(&self.direction), &self.reverse) {
+            (_, Some(reverse)) => Direction::Custom(self.direction.clone(), reverse.clone()),
+            (Some(dir), None) if !self.one_way => dir,
+            _ => Direction::CustomOneWay(self.direction.clone()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UserDef {
+    name: UserName,
+    room: RoomName,
+    #[serde(default = "default_user_type", rename = "type")]
+    user_type: UserType,
+    #[serde(default)]
+    items: Vec<ItemName>,
+    #[serde(default)]
+    role: Role,
+    #[serde(default)]
+    gold: i32,
+    shop: Option<Shop>,
+    loot: Option<LootTable>,
+    script: Option<String>,
+    dialogue: Option<Dialogue>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpawnDef {
+    room: RoomName,
+    npc: NpcTemplate,
+    #[serde(default = "default_max_population")]
+    max_population: usize,
+    #[serde(default = "default_spawn_delay")]
+    respawn_delay: u64,
+}
+
+fn default_max_population() -> usize {
+    1
+}
+
+fn default_spawn_delay() -> u64 {
+    DEFAULT_SPAWN_DELAY
+}
+
+// Scripts are attached once everything they might refer to exists.
+#[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+enum ScriptTarget {
+    OnEnter(RoomName),
+    OnExit(RoomName),
+    Description(RoomName),
+    Entry(RoomName, String),
+    Exit(RoomName, String),
+    Npc(UserName),
+}
+
+fn default_user_type() -> UserType {
+    UserType::Civilian
+}
+
+impl GameState {
+    pub fn from_world_file<P: AsRef<FsPath>>(file_path: P) -> Result<GameState, FaerieError> {
+        let contents = fs::read_to_string(file_path)?;
+        GameState::from_world_str(&contents)
+    }
+
+    pub fn from_world_str(contents: &str) -> Result<GameState, FaerieError> {
+        let world: WorldFile = toml::from_str(contents)?;
+        let mut game_state = GameState::new();
+        let mut script_targets = vec![];
+
+        let mut seen_rooms = HashSet::new();
+        let mut room_zones = vec![];
+        for room in world.rooms {
+            if !seen_rooms.insert(room.name) {
+                return Err(FaerieError::DuplicateRoom(room.name.to_string()));
+            }
+            game_state.create_room(&room.name, room.description)?;
+            game_state.set_room_flags(&room.name, room.flags)?;
+            game_state.set_room_shop(&room.name, room.shop)?;
+            game_state.set_room_coords(&room.name, room.coords)?;
+            game_state.set_room_ambience(&room.name, room.ambient)?;
+            game_state.set_room_terrain(&room.name, room.terrain)?;
+            if let Some(zone_name) = room.zone {
+                room_zones.push((room.name, zone_name));
+            }
+            if let Some(script_name) = room.on_enter {
+                script_targets.push((ScriptTarget::OnEnter(room.name), script_name));
+            }
+            if let Some(script_name) = room.on_exit {
+                script_targets.push((ScriptTarget::OnExit(room.name), script_name));
+            }
+            if let Some(script_name) = room.description_script {
+                let target = ScriptTarget::Description(room.name);
+                script_targets.push((target, script_name));
+            }
+        }
+
+        // Zones may name a respawn room, so they come after the rooms.
+        for zone in world.zones {
+            game_state.add_zone(zone)?;
+        }
+        for (room_name, zone_name) in room_zones {
+            game_state.set_room_zone(&room_name, Some(&zone_name))?;
+        }
+
+        for path in world.paths {
+            let direction = path.to_direction();
+            let path_name = Direction::get_path_name(direction.clone());
+            if path.painful {
+                game_state.add_painful_path(&path.from, &path.to, direction)?;
+            } else {
+                game_state.add_path(&path.from, &path.to, direction)?;
+            }
+            if !path.flags.is_empty() {
+                game_state.get_path_mut(&path.from, &path_name)?.flags |= path.flags;
+            }
+            if path.key.is_some() {
+                let locked = path.locked.unwrap_or(true);
+                game_state.set_path_lock(&path.from, &path_name, path.key, locked)?;
+            }
+            if path.required_skill.is_some() {
+                game_state.set_path_skill(&path.from, &path_name, path.required_skill)?;
+            }
+            if let Some(script_name) = path.entry_script {
+                let target = ScriptTarget::Entry(path.from, path_name.clone());
+                script_targets.push((target, script_name));
+            }
+            if let Some(script_name) = path.exit_script {
+                script_targets.push((ScriptTarget::Exit(path.from, path_name), script_name));
+            }
+        }
+
+        for user in world.users {
+            game_state.create_user_in_room(&user.name, &user.room, user.user_type)?;
+            game_state.set_role(&user.name, user.role)?;
+            game_state.give_gold(&user.name, user.gold)?;
+            game_state.set_user_shop(&user.name, user.shop)?;
+            game_state.set_loot_table(&user.name, user.loot)?;
+            for item_name in user.items {
+                let description = format!("It's {}.", with_article(&item_name));
+                game_state.give_item(&user.name, Item::new(item_name, description)?)?;
+            }
+            if let Some(mut dialogue) = user.dialogue {
+                for node in dialogue.nodes.values_mut() {
+                    for response in &mut node.responses {
+                        if let Some(script_name) = response.script.take() {
+                            let source = world
+                                .scripts
+                                .get(&script_name)
+                                .ok_or(FaerieError::ScriptNotFound(script_name))?;
+                            response.script = Some(source.clone());
+                        }
+                    }
+                }
+                game_state.set_dialogue(&user.name, Some(dialogue))?;
+            }
+            if let Some(script_name) = user.script {
+                script_targets.push((ScriptTarget::Npc(user.name), script_name));
+            }
+        }
+
+        for (target, script_name) in script_targets {
+            let source = world
+                .scripts
+                .get(&script_name)
+                .ok_or(FaerieError::ScriptNotFound(script_name))?;
+            game_state.attach_script(target, source)?;
+        }
+
+        for spawn in world.spawns {
+            game_state.add_spawn_point(SpawnPoint::new(
+                spawn.room,
+                spawn.npc,
+                spawn.max_population,
+                spawn.respawn_delay,
+            ))?;
+        }
+
+        for (name, social) in world.socials {
+            game_state.add_social(&name, social)?;
+        }
+        for (locale, messages) in world.messages {
+            game_state.add_messages(&locale, messages)?;
+        }
+
+        if let Some(start_room_name) = world.start_room {
+            game_state.set_start_room(&start_room_name)?;
+        }
+        if let Some(respawn_room_name) = world.respawn_room {
+            game_state.set_respawn_room(&respawn_room_name)?;
+        }
+        if let Some(ticks) = world.respawn_delay {
+            game_state.set_respawn_delay(ticks);
+        }
+        if let Some(hp) = world.death_penalty {
+            game_state.set_death_penalty(hp);
+        }
+
+        Ok(game_state)
+    }
+
+    #[cfg(feature = "scripting")]
+    fn attach_script(&mut self, target: ScriptTarget, source: &str) -> Result<(), FaerieError> {
+        match target {
+            ScriptTarget::OnEnter(room_name) => self.set_on_enter_script(&room_name, source),
+            ScriptTarget::OnExit(room_name) => self.set_on_exit_script(&room_name, source),
+            ScriptTarget::Description(room_name) => self.set_description_script(&room_name, source),
+            ScriptTarget::Entry(room_name, path_name) => {
+                self.set_entry_script(&room_name, &path_name, source)
+            }
+            ScriptTarget::Exit(room_name, path_name) => {
+                self.set_exit_script(&room_name, &path_name, source)
+            }
+            ScriptTarget::Npc(user_name) => self.set_npc_script(&user_name, source),
+        }
+    }
+
+    #[cfg(not(feature = "scripting"))]
+    fn attach_script(&mut self, _target: ScriptTarget, _source: &str) -> Result<(), FaerieError> {
+        Err(FaerieError::Script(
+            "faerie was built without the scripting feature".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::FaerieError;
+    use crate::flags::{PathFlags, RoomFlags};
+    use crate::room::PathKind;
+    use crate::user::Role;
+    use crate::GameState;
+
+    const WORLD: &str = r#"
+        start_room = "Hall"
+
+        [[rooms]]
+        name = "Hall"
+        description = "A long hall."
+
+        [[rooms]]
+        name = "Garden"
+        description = "Roses everywhere."
+
+        [[rooms]]
+        name = "Cellar"
+        description = "Damp and dark."
+        flags = "DARK | INDOORS"
+
+        [[paths]]
+        from = "Hall"
+        to = "Garden"
+        direction = "north"
+
+        [[paths]]
+        from = "Hall"
+        to = "Cellar"
+        direction = "trapdoor"
+        reverse = "ladder"
+        painful = true
+
+        [[paths]]
+        from = "Garden"
+        to = "Cellar"
+        direction = "gate"
+        one_way = true
+        key = "iron key"
+        flags = "HIDDEN"
+
+        [[users]]
+        name = "glenn"
+        room = "Hall"
+        type = "Viking"
+        items = ["iron key"]
+        role = "Builder"
+    "#;
+
+    #[test]
+    fn loads_rooms_paths_and_users() {
+        let game_state = GameState::from_world_str(WORLD).unwrap();
+
+        let hall = game_state.rooms.get_room("Hall").unwrap();
+        assert_eq!(hall.get_path("north").unwrap().target_room_name, "Garden");
+        assert_eq!(hall.get_path("trapdoor").unwrap().kind, PathKind::Painful);
+        assert!(hall.users.contains(&crate::UserName::new("glenn").unwrap()));
+
+        let garden = game_state.rooms.get_room("Garden").unwrap();
+        assert_eq!(garden.get_path("south").unwrap().target_room_name, "Hall");
+        let cellar = game_state.rooms.get_room("Cellar").unwrap();
+        assert_eq!(cellar.get_path("ladder").unwrap().target_room_name, "Hall");
+
+        assert!(garden.get_path("gate").unwrap().is_locked());
+        assert_eq!(
+            garden.get_path("gate").unwrap().flags,
+            PathFlags::HIDDEN | PathFlags::ONE_WAY
+        );
+        assert_eq!(cellar.flags, RoomFlags::DARK | RoomFlags::INDOORS);
+
+        let user = game_state.users.get_user("glenn").unwrap();
+        assert_eq!(user.basic_attributes.hp, 220);
+        assert!(user.has_item("iron key"));
+        assert_eq!(user.role, Role::Builder);
+
+        assert_eq!(game_state.start_room().unwrap(), "Hall");
+    }
+
+    #[test]
+    fn path_to_missing_room_is_a_load_error() {
+        let world = r#"
+            [[rooms]]
+            name = "Hall"
+            description = "A long hall."
+
+            [[paths]]
+            from = "Hall"
+            to = "Nowhere"
+            direction = "east"
+        "#;
+
+        let res = GameState::from_world_str(world);
+        assert_eq!(
+            res.err(),
+            Some(FaerieError::RoomNotFound("Nowhere".to_string()))
+        );
+    }
+
+    #[test]
+    fn duplicate_rooms_and_bad_syntax_are_load_errors() {
+        let world = r#"
+            [[rooms]]
+            name = "Hall"
+            description = "A long hall."
+
+            [[rooms]]
+            name = "Hall"
+            description = "Another long hall."
+        "#;
+        let res = GameState::from_world_str(world);
+        assert_eq!(
+            res.err(),
+            Some(FaerieError::DuplicateRoom("Hall".to_string()))
+        );
+
+        let res = GameState::from_world_str("[[rooms]]\nname = 3");
+        match res {
+            Err(FaerieError::Serialization(_)) => {}
+            _ => panic!("Malformed world file was not rejected!"),
+        }
+    }
+
+    #[cfg(feature = "scripting")]
+    #[test]
+    fn scripts_are_attached_by_name() {
+        let world = r#"
+            [scripts]
+            needs_amulet = 'if !("amulet" in items) { "You need the amulet to enter." }'
+
+            [[rooms]]
+            name = "Hall"
+            description = "A long hall."
+
+            [[rooms]]
+            name = "Shrine"
+            description = "Candles."
+
+            [[paths]]
+            from = "Hall"
+            to = "Shrine"
+            direction = "north"
+            entry_script = "needs_amulet"
+
+            [[users]]
+            name = "glenn"
+            room = "Hall"
+        "#;
+        let mut game_state = GameState::from_world_str(world).unwrap();
+        let glenn = crate::type_aliases::UserName::new("glenn").unwrap();
+        let failure = game_state.process_input_impl(&glenn, "north").unwrap_err();
+        assert_eq!(failure.messages, vec!["You need the amulet to enter."]);
+
+        let res = GameState::from_world_str(&world.replace("\"needs_amulet\"", "\"missing\""));
+        assert_eq!(
+            res.err(),
+            Some(FaerieError::ScriptNotFound("missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn socials_are_added_to_the_defaults() {
+        let world = r#"
+            [[rooms]]
+            name = "Hall"
+            description = "A long hall."
+
+            [[users]]
+            name = "glenn"
+            room = "Hall"
+
+            [socials.nod]
+            to_actor = "You nod."
+            to_room = "{actor} nods."
+        "#;
+        let mut game_state = GameState::from_world_str(world).unwrap();
+        let glenn = crate::type_aliases::UserName::new("glenn").unwrap();
+        let succ = game_state.process_input_impl(&glenn, "nod").unwrap();
+        assert_eq!(succ.messages(), vec!["You nod."]);
+        let succ = game_state.process_input_impl(&glenn, "wave").unwrap();
+        assert_eq!(succ.messages(), vec!["You wave."]);
+
+        let res = GameState::from_world_str(&world.replace("socials.nod", "socials.look"));
+        assert_eq!(
+            res.err(),
+            Some(FaerieError::DuplicateCommand("look".to_string()))
+        );
+    }
+
+    #[test]
+    fn messages_are_translated() {
+        let world = r#"
+            [[rooms]]
+            name = "Hall"
+            description = "A long hall."
+
+            [[users]]
+            name = "glenn"
+            room = "Hall"
+
+            [messages.fr]
+            unknown_input = "Je ne comprends pas « {input} »."
+        "#;
+        let mut game_state = GameState::from_world_str(world).unwrap();
+        let glenn = crate::type_aliases::UserName::new("glenn").unwrap();
+        game_state
+            .process_input_impl(&glenn, "language fr")
+            .unwrap();
+        let fail = game_state.process_input_impl(&glenn, "xyzzy").unwrap_err();
+        assert_eq!(fail.messages, vec!["Je ne comprends pas « xyzzy »."]);
+
+        let res = GameState::from_world_str(&world.replace("unknown_input", "unknown"));
+        assert_eq!(
+            res.err(),
+            Some(FaerieError::MessageNotFound("unknown".to_string()))
+        );
+    }
+
+    #[test]
+    fn shops_and_gold_are_loaded() {
+        let world = r#"
+            [[rooms]]
+            name = "Market"
+            description = "Stalls."
+
+            [rooms.shop.lamp]
+            price = 5
+            sell_price = 2
+
+            [[users]]
+            name = "glenn"
+            room = "Market"
+            gold = 7
+        "#;
+        let mut game_state = GameState::from_world_str(world).unwrap();
+        let glenn = crate::type_aliases::UserName::new("glenn").unwrap();
+        let succ = game_state.process_input_impl(&glenn, "buy lamp").unwrap();
+        assert_eq!(succ.messages(), vec!["You buy a lamp for 5 gold."]);
+        let user = game_state.users.get_user(&glenn).unwrap();
+        assert_eq!(user.basic_attributes.gold, 2);
+    }
+
+    #[test]
+    fn starter_world_loads() {
+        let game_state = GameState::from_world_str(include_str!("../worlds/starter.toml")).unwrap();
+        assert_eq!(game_state.rooms.rooms.len(), 5);
+        assert!(game_state.users.get_user("glenn").is_ok());
+    }
+}
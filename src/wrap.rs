@@ -0,0 +1,196 @@
+use crate::lambda::{ActionFailure, ActionSuccess, FailReason};
+use crate::type_aliases::UserName;
+use crate::GameState;
+
+pub const DEFAULT_WIDTH: usize = 80;
+pub const MIN_WIDTH: usize = 20;
+pub const MAX_WIDTH: usize = 250;
+
+// How many columns the text takes up on screen, not counting color codes.
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip to the end of the escape sequence, like \x1b[36m.
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            width += 1;
+        }
+    }
+    width
+}
+
+// Breaks a line between words so no piece is wider than `width`, unless it's one word that
+// is. Each piece keeps the line's indentation.
+pub(crate) fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    if visible_width(line) <= width {
+        return vec![line.to_string()];
+    }
+    let indent = &line[..line.len() - line.trim_start_matches(' ').len()];
+    let mut pieces = vec![];
+    let mut piece = indent.to_string();
+    let mut piece_width = indent.len();
+    for word in line.split(' ').filter(|word| !word.is_empty()) {
+        let word_width = visible_width(word);
+        let started = piece_width > indent.len();
+        if started && piece_width + 1 + word_width > width {
+            pieces.push(piece);
+            piece = indent.to_string();
+            piece_width = indent.len();
+        } else if started {
+            piece.push(' ');
+            piece_width += 1;
+        }
+        piece.push_str(word);
+        piece_width += word_width;
+    }
+    pieces.push(piece);
+    pieces
+}
+
+// The author's line breaks within a paragraph are dropped, so descriptions can be wrapped to
+// fit each reader. Blank lines still separate paragraphs.
+pub(crate) fn paragraphs(text: &str) -> Vec<String> {
+    text.split("\n\n")
+        .map(|paragraph| paragraph.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect()
+}
+
+impl GameState {
+    fn width(&self, user_name: &UserName) -> usize {
+        self.users
+            .get_user(user_name)
+            .ok()
+            .and_then(|user| user.width)
+            .unwrap_or(DEFAULT_WIDTH)
+    }
+
+    // Fits lines of text to the user's screen.
+    pub(crate) fn wrap_for(&self, user_name: &UserName, lines: Vec<String>) -> Vec<String> {
+        let width = self.width(user_name);
+        if width == 0 {
+            return lines;
+        }
+        lines
+            .iter()
+            .flat_map(|line| line.split('\n'))
+            .flat_map(|line| wrap_line(line, width))
+            .collect()
+    }
+
+    pub(crate) fn set_width(
+        &mut self,
+        user_name: &UserName,
+        width: Option<&str>,
+    ) -> Result<ActionSuccess, ActionFailure> {
+        let width = match width {
+            None => {
+                let message = match self.width(user_name) {
+                    0 => self.text(user_name, "wrapping_off", &[]),
+                    n => self.text(user_name, "screen_width", &[("columns", &n.to_string())]),
+                };
+                return Ok(ActionSuccess::new(vec![message]));
+            }
+            Some(off) if off.eq_ignore_ascii_case("off") => 0,
+            Some(columns) => match columns.parse::<usize>() {
+                Ok(n) if (MIN_WIDTH..=MAX_WIDTH).contains(&n) => n,
+                _ => {
+                    let (min, max) = (MIN_WIDTH.to_string(), MAX_WIDTH.to_string());
+                    let args = [("min", min.as_str()), ("max", max.as_str())];
+                    return Err(wrap_failure(self.text(
+                        user_name,
+                        "screen_width_range",
+                        &args,
+                    )));
+                }
+            },
+        };
+        self.users.get_user_mut(user_name)?.width = Some(width);
+        let message = match width {
+            0 => self.text(user_name, "wrapping_now_off", &[]),
+            n => self.text(
+                user_name,
+                "screen_width_now",
+                &[("columns", &n.to_string())],
+            ),
+        };
+
+        Ok(ActionSuccess::new(vec![message]))
+    }
+}
+
+fn wrap_failure(message: String) -> ActionFailure {
+    ActionFailure::new(vec![message], FailReason::InvalidTarget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::testing::TestWorld;
+
+    #[test]
+    fn lines_break_between_words() {
+        assert_eq!(wrap_line("short", 20), vec!["short"]);
+        assert_eq!(
+            wrap_line("  the quick brown fox jumps", 12),
+            vec!["  the quick", "  brown fox", "  jumps"]
+        );
+        assert_eq!(
+            wrap_line("a \x1b[36mnortheast\x1b[0m b", 11),
+            vec!["a \x1b[36mnortheast\x1b[0m", "b"]
+        );
+        assert_eq!(
+            wrap_line("unbreakable words", 5),
+            vec!["unbreakable", "words"]
+        );
+        assert_eq!(
+            paragraphs("One line\n  and the next.\n\nAnother."),
+            vec!["One line and the next.", "Another."]
+        );
+    }
+
+    #[test]
+    fn descriptions_wrap_to_each_users_width() {
+        let description = "Shelves of old books\nline the walls, floor to ceiling.";
+        let mut world = TestWorld::build(
+            GameStateBuilder::new()
+                .room("hall", "A long hall.")
+                .room("library", description)
+                .path("hall", "library", crate::room::Direction::North)
+                .user("alice", "hall"),
+        );
+        world.input("alice", "width 30");
+        assert_eq!(
+            world.messages_for("alice"),
+            vec!["Your screen is now 30 columns", "wide."]
+        );
+        world.input("alice", "north");
+        assert_eq!(
+            world.messages_for("alice")[1..3],
+            [
+                "  Shelves of old books line",
+                "  the walls, floor to ceiling."
+            ]
+        );
+
+        world.input("alice", "width off");
+        world.messages_for("alice");
+        world.input("alice", "look");
+        assert_eq!(
+            world.messages_for("alice")[1],
+            "  Shelves of old books line the walls, floor to ceiling."
+        );
+        world.input("alice", "width 5");
+        assert_eq!(
+            world.messages_for("alice"),
+            vec!["Screens can be 20 to 250 columns wide, or off."]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::FaerieError;
+use crate::lambda::{ActionFailure, ActionSuccess};
+use crate::room::Room;
+use crate::type_aliases::{RoomName, UserName};
+use crate::weather::Weather;
+use crate::GameState;
+
+// Chance, out of a hundred, that an occupied zone with ambient lines shows one on a tick.
+pub const ZONE_AMBIENT_CHANCE: u32 = 5;
+
+// A named group of rooms, like a forest or a town. In world files:
+//
+//     [[zones]]
+//     name = "Darkwood"
+//     level_range = [3, 8]
+//     respawn_room = "Woodcutter's Hut"
+//     ambient = ["An owl hoots somewhere above you."]
+//     weather = "Fog"
+//
+// with `zone = "Darkwood"` on each of its rooms.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Zone {
+    pub name: String,
+    // The levels it's meant for, lowest and highest.
+    pub level_range: Option<(u32, u32)>,
+    // Used instead of the game's own for anyone who dies in the zone.
+    pub respawn_delay: Option<u64>,
+    pub respawn_room: Option<RoomName>,
+    // Shown now and then to everyone in the zone.
+    #[serde(default)]
+    pub ambient: Vec<String>,
+    // What it's doing now, for zones that have weather (see weather.rs).
+    #[serde(default)]
+    pub weather: Option<Weather>,
+}
+
+impl Zone {
+    pub fn new(name: &str) -> Zone {
+        Zone {
+            name: name.to_string(),
+            ..Zone::default()
+        }
+    }
+}
+
+impl GameState {
+    // Replaces any zone of the same name.
+    pub fn add_zone(&mut self, zone: Zone) -> Result<(), FaerieError> {
+        if let Some(room_name) = &zone.respawn_room {
+            self.rooms.check_room_exists(room_name)?;
+        }
+        self.zones.insert(zone.name.clone(), zone);
+        Ok(())
+    }
+
+    pub fn set_room_zone(
+        &mut self,
+        room_name: &RoomName,
+        zone_name: Option<&str>,
+    ) -> Result<(), FaerieError> {
+        if let Some(zone_name) = zone_name {
+            if !self.zones.contains_key(zone_name) {
+                return Err(FaerieError::ZoneNotFound(zone_name.to_string()));
+            }
+        }
+        self.rooms.get_room_mut(room_name)?.zone = zone_name.map(str::to_string);
+        Ok(())
+    }
+
+    pub fn zone_of(&self, room_name: &str) -> Option<&Zone> {
+        let zone_name = self.rooms.get_room(room_name).ok()?.zone.as_ref()?;
+        self.zones.get(zone_name)
+    }
+
+    pub fn rooms_in_zone(&self, zone_name: &str) -> Vec<&RoomName> {
+        let mut room_names: Vec<&RoomName> = self
+            .rooms
+            .rooms_in_zone(zone_name)
+            .map(|room| &room.name)
+            .collect();
+        room_names.sort();
+        room_names
+    }
+
+    pub(crate) fn user_zone(&self, user_name: &UserName) -> Option<&Zone> {
+        let user = self.users.get_user(user_name).ok()?;
+        self.zone_of(&user.room_name)
+    }
+
+    pub fn where_am_i(&self, user_name: &UserName) -> Result<ActionSuccess, ActionFailure> {
+        let room_name = self.get_user_location(user_name)?;
+        let message = match self.zone_of(&room_name) {
+            Some(zone) => match zone.level_range {
+                Some((low, high)) => {
+                    let (low, high) = (low.to_string(), high.to_string());
+                    let args = [
+                        ("room", room_name.as_str()),
+                        ("zone", &zone.name),
+                        ("low", &low),
+                        ("high", &high),
+                    ];
+                    self.text(user_name, "you_are_in_levels", &args)
+                }
+                None => {
+                    let args = [("room", room_name.as_str()), ("zone", &zone.name)];
+                    self.text(user_name, "you_are_in_zone", &args)
+                }
+            },
+            None => self.text(user_name, "you_are_in", &[("room", &room_name)]),
+        };
+        Ok(ActionSuccess::new(vec![message]))
+    }
+
+    pub(crate) fn play_zone_ambience(&mut self) {
+        let mut zone_names: Vec<String> = self
+            .zones
+            .values()
+            .filter(|zone| !zone.ambient.is_empty())
+            .map(|zone| zone.name.clone())
+            .collect();
+        zone_names.sort();
+        for zone_name in zone_names {
+            let mut listeners: Vec<UserName> = self
+                .occupied_rooms()
+                .into_iter()
+                .filter(|room| room.in_zone(&zone_name))
+                .flat_map(|room| room.users.iter().cloned())
+                .collect();
+            if listeners.is_empty() || !self.rng.chance(ZONE_AMBIENT_CHANCE) {
+                continue;
+            }
+            listeners.sort();
+            let line = match self.rng.choose(&self.zones[&zone_name].ambient) {
+                Some(line) => line.clone(),
+                None => continue,
+            };
+            for listener in listeners {
+                self.output.send(&listener, &line);
+            }
+        }
+    }
+}
+
+impl Room {
+    pub fn in_zone(&self, zone_name: &str) -> bool {
+        self.zone.as_deref() == Some(zone_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GameStateBuilder;
+    use crate::room::Direction;
+    use crate::testing::TestWorld;
+
+    // A hut outside the wood, with two rooms of the wood to its north.
+    fn make_wood() -> TestWorld {
+        let mut world = TestWorld::build(
+            GameStateBuilder::new()
+                .room("hut", "Warm and dry.")
+                .room("wood edge", "Trees begin.")
+                .room("deep wood", "Trees everywhere.")
+                .path("hut", "wood edge", Direction::North)
+                .path("wood edge", "deep wood", Direction::North)
+                .user("alice", "deep wood"),
+        );
+        let mut zone = Zone::new("Darkwood");
+        zone.level_range = Some((3, 8));
+        zone.respawn_room = Some(RoomName::new("hut").unwrap());
+        zone.respawn_delay = Some(1);
+        zone.ambient = vec!["An owl hoots.".to_string()];
+        let game_state = &mut world.game_state;
+        game_state.set_seed(11);
+        game_state.add_zone(zone).unwrap();
+        for room_name in &["wood edge", "deep wood"] {
+            game_state
+                .set_room_zone(&RoomName::new(room_name).unwrap(), Some("Darkwood"))
+                .unwrap();
+        }
+        world
+    }
+
+    #[test]
+    fn zones_group_rooms() {
+        let mut world = make_wood();
+        let alice = UserName::new("alice").unwrap();
+        assert_eq!(
+            world.game_state.rooms_in_zone("Darkwood"),
+            vec!["deep wood", "wood edge"]
+        );
+        assert!(world.game_state.zone_of("hut").is_none());
+        assert_eq!(
+            world
+                .game_state
+                .set_room_zone(&RoomName::new("hut").unwrap(), Some("Nowhere")),
+            Err(FaerieError::ZoneNotFound("Nowhere".to_string()))
+        );
+
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "where")
+            .unwrap();
+        assert_eq!(
+            succ.messages(),
+            vec!["You are in deep wood, in Darkwood (levels 3-8)."]
+        );
+        let succ = world
+            .game_state
+            .process_input_impl(&alice, "score")
+            .unwrap();
+        assert!(succ.messages().contains(&"  zone: Darkwood".to_string()));
+
+        world.game_state.tick_n(100);
+        assert!(world
+            .messages_for("alice")
+            .contains(&"An owl hoots.".to_string()));
+    }
+
+    #[test]
+    fn zones_have_their_own_respawn_rules() {
+        let mut world = make_wood();
+        let alice = UserName::new("alice").unwrap();
+        world.game_state.set_respawn_delay(50);
+        world
+            .game_state
+            .users
+            .get_user_mut(&alice)
+            .unwrap()
+            .basic_attributes
+            .hp = 0;
+
+        world.game_state.tick();
+        assert!(world.game_state.is_awaiting_respawn(&alice));
+        world.game_state.tick();
+        assert!(!world.game_state.is_awaiting_respawn(&alice));
+        assert_eq!(
+            world.game_state.users.get_user(&alice).unwrap().room_name,
+            "hut"
+        );
+    }
+}